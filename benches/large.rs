@@ -0,0 +1,64 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{black_box, BenchmarkId, Criterion};
+
+use annotate_snippets::{
+    display_list::DisplayList,
+    snippet::{AnnotationType, Slice, Snippet, SourceAnnotation},
+};
+
+/// Generates a source of `lines` lines, annotated every 100 lines, with a
+/// multiline annotation over 100 lines every 1000 lines.
+fn create_source(lines: usize) -> (String, Vec<(usize, usize)>) {
+    let mut source = String::new();
+    let mut ranges = vec![];
+    let mut start = 0;
+    for i in 0..lines {
+        let line = format!("let value_{} = {};\n", i, i);
+        source.push_str(&line);
+        if i % 100 == 0 {
+            ranges.push((start, start + 3));
+        }
+        if i % 1000 == 10 && i + 100 < lines {
+            ranges.push((start + 4, start + 4 + 100 * 20));
+        }
+        start += line.chars().count();
+    }
+    (source, ranges)
+}
+
+fn render(source: &str, ranges: &[(usize, usize)], fold: bool) -> String {
+    let slice = ranges.iter().fold(
+        Slice::new(source, 1).origin("src/generated.rs").fold(fold),
+        |slice, &range| {
+            slice.annotation(SourceAnnotation {
+                label: "annotated",
+                annotation_type: AnnotationType::Error,
+                range,
+                ..Default::default()
+            })
+        },
+    );
+    let snippet = Snippet::new().slice(slice);
+
+    DisplayList::from(snippet).to_string()
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large");
+    group.sample_size(10);
+    for &lines in &[10_000, 100_000] {
+        let (source, ranges) = create_source(lines);
+        group.bench_with_input(BenchmarkId::new("unfolded", lines), &lines, |b, _| {
+            b.iter(|| black_box(render(&source, &ranges, false)))
+        });
+        group.bench_with_input(BenchmarkId::new("folded", lines), &lines, |b, _| {
+            b.iter(|| black_box(render(&source, &ranges, true)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,20 @@
+use std::env;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(annotate_snippets_nightly)");
+    println!("cargo:rerun-if-changed=build.rs");
+    // `proc_macro::Diagnostic` is only available on nightly compilers.
+    if env::var_os("CARGO_FEATURE_PROC_MACRO").is_some() && is_nightly() {
+        println!("cargo:rustc-cfg=annotate_snippets_nightly");
+    }
+}
+
+fn is_nightly() -> bool {
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    Command::new(rustc)
+        .arg("--version")
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains("nightly"))
+        .unwrap_or(false)
+}
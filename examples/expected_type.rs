@@ -4,39 +4,36 @@ use annotate_snippets::{
 };
 
 fn main() {
-    let snippet = Snippet {
-        title: Some(Annotation {
+    let snippet = Snippet::new()
+        .title(Annotation {
             label: Some("expected type, found `22`"),
             id: None,
             annotation_type: AnnotationType::Error,
-        }),
-        footer: vec![],
-        slices: vec![Slice {
-            source: r#"                annotations: vec![SourceAnnotation {
+        })
+        .slice(
+            Slice::new(
+                r#"                annotations: vec![SourceAnnotation {
                 label: "expected struct `annotate_snippets::snippet::Slice`, found reference"
                     ,
                 range: <22, 25>,"#,
-            line_start: 26,
-            origin: Some("examples/footer.rs"),
-            fold: true,
-            annotations: vec![
-                SourceAnnotation {
-                    label: "",
-                    annotation_type: AnnotationType::Error,
-                    range: (193, 195),
-                },
-                SourceAnnotation {
-                    label: "while parsing this struct",
-                    annotation_type: AnnotationType::Info,
-                    range: (34, 50),
-                },
-            ],
-        }],
-        opt: FormatOptions {
-            color: true,
-            ..Default::default()
-        },
-    };
+                26,
+            )
+            .origin("examples/footer.rs")
+            .fold(true)
+            .annotation(SourceAnnotation {
+                label: "",
+                annotation_type: AnnotationType::Error,
+                range: (193, 195),
+                ..Default::default()
+            })
+            .annotation(SourceAnnotation {
+                label: "while parsing this struct",
+                annotation_type: AnnotationType::Info,
+                range: (34, 50),
+                ..Default::default()
+            }),
+        )
+        .opt(FormatOptions::new().color(true));
 
     let dl = DisplayList::from(snippet);
     println!("{}", dl);
@@ -4,35 +4,30 @@ use annotate_snippets::{
 };
 
 fn main() {
-    let snippet = Snippet {
-        title: Some(Annotation {
+    let snippet = Snippet::new()
+        .title(Annotation {
             label: Some("mismatched types"),
             id: Some("E0308"),
             annotation_type: AnnotationType::Error,
-        }),
-        footer: vec![Annotation {
+        })
+        .footer(Annotation {
             label: Some(
                 "expected type: `snippet::Annotation`\n   found type: `__&__snippet::Annotation`",
             ),
             id: None,
             annotation_type: AnnotationType::Note,
-        }],
-        slices: vec![Slice {
-            source: "        slices: vec![\"A\",",
-            line_start: 13,
-            origin: Some("src/multislice.rs"),
-            fold: false,
-            annotations: vec![SourceAnnotation {
-                label: "expected struct `annotate_snippets::snippet::Slice`, found reference",
-                range: (21, 24),
-                annotation_type: AnnotationType::Error,
-            }],
-        }],
-        opt: FormatOptions {
-            color: true,
-            ..Default::default()
-        },
-    };
+        })
+        .slice(
+            Slice::new("        slices: vec![\"A\",", 13)
+                .origin("src/multislice.rs")
+                .annotation(SourceAnnotation {
+                    label: "expected struct `annotate_snippets::snippet::Slice`, found reference",
+                    range: (21, 24),
+                    annotation_type: AnnotationType::Error,
+                    ..Default::default()
+                }),
+        )
+        .opt(FormatOptions::new().color(true));
 
     let dl = DisplayList::from(snippet);
     println!("{}", dl);
@@ -4,9 +4,10 @@ use annotate_snippets::{
 };
 
 fn main() {
-    let snippet = Snippet {
-        slices: vec![Slice {
-            source: r#") -> Option<String> {
+    let snippet = Snippet::new()
+        .slice(
+            Slice::new(
+                r#") -> Option<String> {
     for ann in annotations {
         match (ann.range.0, ann.range.1) {
             (None, None) => continue,
@@ -28,33 +29,28 @@ fn main() {
             _ => continue,
         }
     }"#,
-            line_start: 51,
-            origin: Some("src/format.rs"),
-            fold: false,
-            annotations: vec![
-                SourceAnnotation {
-                    label: "expected `Option<String>` because of return type",
-                    annotation_type: AnnotationType::Warning,
-                    range: (5, 19),
-                },
-                SourceAnnotation {
-                    label: "expected enum `std::option::Option`",
-                    annotation_type: AnnotationType::Error,
-                    range: (26, 724),
-                },
-            ],
-        }],
-        title: Some(Annotation {
+                51,
+            )
+            .origin("src/format.rs")
+            .annotation(SourceAnnotation {
+                label: "expected `Option<String>` because of return type",
+                annotation_type: AnnotationType::Warning,
+                range: (5, 19),
+                ..Default::default()
+            })
+            .annotation(SourceAnnotation {
+                label: "expected enum `std::option::Option`",
+                annotation_type: AnnotationType::Error,
+                range: (26, 724),
+                ..Default::default()
+            }),
+        )
+        .title(Annotation {
             label: Some("mismatched types"),
             id: Some("E0308"),
             annotation_type: AnnotationType::Error,
-        }),
-        footer: vec![],
-        opt: FormatOptions {
-            color: true,
-            ..Default::default()
-        },
-    };
+        })
+        .opt(FormatOptions::new().color(true));
 
     let dl = DisplayList::from(snippet);
     println!("{}", dl);
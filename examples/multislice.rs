@@ -4,34 +4,15 @@ use annotate_snippets::{
 };
 
 fn main() {
-    let snippet = Snippet {
-        title: Some(Annotation {
+    let snippet = Snippet::new()
+        .title(Annotation {
             label: Some("mismatched types"),
             id: None,
             annotation_type: AnnotationType::Error,
-        }),
-        footer: vec![],
-        slices: vec![
-            Slice {
-                source: "Foo",
-                line_start: 51,
-                origin: Some("src/format.rs"),
-                fold: false,
-                annotations: vec![],
-            },
-            Slice {
-                source: "Faa",
-                line_start: 129,
-                origin: Some("src/display.rs"),
-                fold: false,
-                annotations: vec![],
-            },
-        ],
-        opt: FormatOptions {
-            color: true,
-            ..Default::default()
-        },
-    };
+        })
+        .slice(Slice::new("Foo", 51).origin("src/format.rs"))
+        .slice(Slice::new("Faa", 129).origin("src/display.rs"))
+        .opt(FormatOptions::new().color(true));
 
     let dl = DisplayList::from(snippet);
     println!("{}", dl);
@@ -0,0 +1,39 @@
+/* The C API of annotate-snippets, built with the `ffi` feature. */
+#ifndef ANNOTATE_SNIPPETS_H
+#define ANNOTATE_SNIPPETS_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+enum annotate_snippets_annotation_type {
+    ANNOTATE_SNIPPETS_ERROR = 0,
+    ANNOTATE_SNIPPETS_WARNING = 1,
+    ANNOTATE_SNIPPETS_INFO = 2,
+    ANNOTATE_SNIPPETS_NOTE = 3,
+    ANNOTATE_SNIPPETS_HELP = 4,
+};
+
+typedef struct SnippetHandle SnippetHandle;
+
+SnippetHandle *annotate_snippets_snippet_new(int annotation_type, const char *id,
+                                             const char *label);
+void annotate_snippets_snippet_free(SnippetHandle *snippet);
+int annotate_snippets_snippet_add_slice(SnippetHandle *snippet, const char *source,
+                                        size_t line_start, const char *origin, bool fold);
+int annotate_snippets_snippet_add_annotation(SnippetHandle *snippet, size_t slice,
+                                             size_t start, size_t end, int annotation_type,
+                                             const char *label);
+int annotate_snippets_snippet_add_footer(SnippetHandle *snippet, int annotation_type,
+                                         const char *label);
+size_t annotate_snippets_snippet_render(const SnippetHandle *snippet, bool color, char *buffer,
+                                        size_t size);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,118 @@
+//! Renders snippets read from stdin.
+//!
+//! The input is either a JSON or TOML description of snippets, with the
+//! fields of `Snippet`, or the JSON diagnostics of rustc, one per line.
+
+mod rustc;
+
+use std::io::{self, Read, Write};
+use std::process;
+
+use annotate_snippets::description::{Document, SnippetDesc};
+use annotate_snippets::display_list::FormatOptions;
+use annotate_snippets::formatter::renderer::Renderer;
+use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+use serde::Deserialize;
+
+const USAGE: &str = "\
+Usage: annotate-snippets [--color <always|never>]
+
+Renders the snippets described by the JSON or TOML read from stdin, or the
+diagnostics printed by `rustc --error-format=json`.
+
+Colors are used unless `--color never` is given or NO_COLOR is set.";
+
+fn parse_json(input: &str) -> Result<Vec<SnippetDesc>, String> {
+    let mut snippets = vec![];
+    for value in serde_json::Deserializer::from_str(input).into_iter::<serde_json::Value>() {
+        let value = value.map_err(|e| e.to_string())?;
+        if rustc::is_rustc_message(&value) {
+            if let Some(diagnostic) = rustc::parse(value).map_err(|e| e.to_string())? {
+                snippets.push(diagnostic.into_snippet());
+            }
+        } else {
+            let document = Document::deserialize(value).map_err(|e| e.to_string())?;
+            snippets.extend(document.into_snippets());
+        }
+    }
+    Ok(snippets)
+}
+
+/// Returns `true` if `input` starts with a JSON object or array rather than
+/// a TOML table header.
+fn is_json(input: &str) -> bool {
+    let input = input.trim_start();
+    match input.strip_prefix('[') {
+        Some(rest) => rest.trim_start().starts_with(['{', ']']),
+        None => input.starts_with('{'),
+    }
+}
+
+fn parse(input: &str) -> Result<Vec<SnippetDesc>, String> {
+    if is_json(input) {
+        parse_json(input)
+    } else {
+        toml::from_str::<Document>(input)
+            .map(Document::into_snippets)
+            .map_err(|e| e.to_string())
+    }
+}
+
+fn run(color: bool) -> Result<(), String> {
+    let mut input = String::new();
+    io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| e.to_string())?;
+    let snippets = parse(&input)?;
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut renderer = Renderer::new();
+    for (i, snippet) in snippets.iter().enumerate() {
+        if i > 0 {
+            writeln!(out).map_err(|e| e.to_string())?;
+        }
+        writeln!(out, "{}", renderer.render(&snippet.to_snippet(color)))
+            .map_err(|e| e.to_string())?;
+    }
+    out.flush().map_err(|e| e.to_string())
+}
+
+fn main() {
+    let mut color = std::env::var_os("NO_COLOR").is_none();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--color" => match args.next().as_deref() {
+                Some("always") => color = true,
+                Some("never") => color = false,
+                _ => exit_with_usage(),
+            },
+            "--color=always" => color = true,
+            "--color=never" => color = false,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => exit_with_usage(),
+        }
+    }
+
+    if let Err(message) = run(color) {
+        let snippet = Snippet::new()
+            .title(Annotation {
+                id: None,
+                label: Some(&message),
+                annotation_type: AnnotationType::Error,
+            })
+            .opt(FormatOptions::new().color(color));
+        let mut renderer = Renderer::new();
+        eprintln!("{}", renderer.render(&snippet));
+        process::exit(1);
+    }
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
@@ -0,0 +1,168 @@
+//! Diagnostics in the JSON format of rustc, as printed with
+//! `--error-format=json`.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use annotate_snippets::description::{
+    AnnotationDesc, AnnotationTypeDesc, OptionsDesc, SliceDesc, SnippetDesc, SourceAnnotationDesc,
+};
+
+#[derive(Deserialize)]
+pub struct Diagnostic {
+    message: String,
+    #[serde(default)]
+    code: Option<Code>,
+    level: String,
+    #[serde(default)]
+    spans: Vec<Span>,
+    #[serde(default)]
+    children: Vec<Diagnostic>,
+}
+
+#[derive(Deserialize)]
+struct Code {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct Span {
+    file_name: String,
+    line_start: usize,
+    is_primary: bool,
+    #[serde(default)]
+    text: Vec<SpanLine>,
+    #[serde(default)]
+    label: Option<String>,
+}
+
+/// A line of a span, with the columns of the span in it starting at 1.
+#[derive(Deserialize)]
+struct SpanLine {
+    text: String,
+    highlight_start: usize,
+    highlight_end: usize,
+}
+
+/// Returns `true` if `value` looks like a message of rustc rather than a
+/// snippet description.
+pub fn is_rustc_message(value: &Value) -> bool {
+    value.get("$message_type").is_some()
+        || (value.get("level").is_some() && value.get("spans").is_some())
+}
+
+/// Parses a message of rustc, returning `None` for messages which are not
+/// diagnostics, like artifact notifications.
+pub fn parse(value: Value) -> serde_json::Result<Option<Diagnostic>> {
+    match value.get("$message_type").and_then(Value::as_str) {
+        Some(message_type) if message_type != "diagnostic" => Ok(None),
+        _ => serde_json::from_value(value).map(Some),
+    }
+}
+
+fn level_type(level: &str) -> AnnotationTypeDesc {
+    match level {
+        "warning" => AnnotationTypeDesc::Warning,
+        "note" | "failure-note" => AnnotationTypeDesc::Note,
+        "help" => AnnotationTypeDesc::Help,
+        level if level.starts_with("error") => AnnotationTypeDesc::Error,
+        _ => AnnotationTypeDesc::Info,
+    }
+}
+
+impl Diagnostic {
+    /// Converts the diagnostic to a snippet. The lines of the spans of each
+    /// file make its slices, and the children become footers.
+    pub fn into_snippet(self) -> SnippetDesc {
+        let annotation_type = level_type(&self.level);
+
+        // The lines of every file, in the order the files are first seen.
+        let mut files: Vec<(&str, BTreeMap<usize, &str>)> = vec![];
+        for span in &self.spans {
+            let lines = match files.iter_mut().find(|(name, _)| *name == span.file_name) {
+                Some((_, lines)) => lines,
+                None => {
+                    files.push((&span.file_name, BTreeMap::new()));
+                    &mut files.last_mut().unwrap().1
+                }
+            };
+            for (i, line) in span.text.iter().enumerate() {
+                lines.insert(span.line_start + i, line.text.as_str());
+            }
+        }
+
+        let mut slices = vec![];
+        for (name, lines) in files {
+            // A slice per run of consecutive lines, with the offset of each line.
+            let mut runs: Vec<(SliceDesc, BTreeMap<usize, usize>)> = vec![];
+            for (lineno, text) in lines {
+                match runs.last_mut() {
+                    Some((slice, offsets)) if slice.line_start + offsets.len() == lineno => {
+                        offsets.insert(lineno, slice.source.chars().count());
+                        slice.source.push_str(text);
+                        slice.source.push('\n');
+                    }
+                    _ => runs.push((
+                        SliceDesc {
+                            source: format!("{}\n", text),
+                            line_start: lineno,
+                            origin: Some(name.to_string()),
+                            annotations: vec![],
+                            fold: false,
+                        },
+                        std::iter::once((lineno, 0)).collect(),
+                    )),
+                }
+            }
+
+            for span in self.spans.iter().filter(|span| span.file_name == name) {
+                let (first, last) = match (span.text.first(), span.text.last()) {
+                    (Some(first), Some(last)) => (first, last),
+                    _ => continue,
+                };
+                let last_lineno = span.line_start + span.text.len() - 1;
+                if let Some((slice, offsets)) = runs
+                    .iter_mut()
+                    .find(|(_, offsets)| offsets.contains_key(&span.line_start))
+                {
+                    slice.annotations.push(SourceAnnotationDesc {
+                        range: (
+                            offsets[&span.line_start] + first.highlight_start - 1,
+                            offsets[&last_lineno] + last.highlight_end - 1,
+                        ),
+                        label: span.label.clone().unwrap_or_default(),
+                        // Like rustc, secondary spans are underlined with `-`
+                        // and their labels have no prefix.
+                        annotation_type: if span.is_primary {
+                            annotation_type
+                        } else {
+                            AnnotationTypeDesc::Warning
+                        },
+                    });
+                }
+            }
+            slices.extend(runs.into_iter().map(|(slice, _)| slice));
+        }
+
+        SnippetDesc {
+            title: Some(AnnotationDesc {
+                id: self.code.map(|code| code.code),
+                label: Some(self.message),
+                annotation_type,
+            }),
+            footer: self
+                .children
+                .into_iter()
+                .map(|child| AnnotationDesc {
+                    id: None,
+                    label: Some(child.message),
+                    annotation_type: level_type(&child.level),
+                })
+                .collect(),
+            slices,
+            opt: OptionsDesc::default(),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Slices of the files of a `codespan-reporting` file database.
+//!
+//! With the `codespan-reporting` feature, a project keeping its files in an
+//! implementor of `codespan_reporting::files::Files` can build the slices of
+//! its annotations from it, rather than through a `SourceMap`.
+//!
+//! The ranges of the annotations are byte ranges of the files, as in
+//! `codespan-reporting`. The slices own the extracted lines of the files, and
+//! are borrowed with `OwnedSlice::to_slice`.
+//!
+//! ```
+//! use annotate_snippets::codespan::{self, FileAnnotation};
+//! use annotate_snippets::snippet::AnnotationType;
+//! use codespan_reporting::files::SimpleFiles;
+//!
+//! let mut files = SimpleFiles::new();
+//! let file = files.add("src/main.rs", "fn main() {\n    let x = 1;\n}\n");
+//!
+//! let slices = codespan::slices(
+//!     &files,
+//!     vec![FileAnnotation {
+//!         file,
+//!         range: (20, 21),
+//!         label: "unused variable",
+//!         annotation_type: AnnotationType::Warning,
+//!     }],
+//!     0,
+//! )
+//! .unwrap();
+//! assert_eq!(slices[0].source, "    let x = 1;\n");
+//! assert_eq!(slices[0].origin.as_deref(), Some("src/main.rs"));
+//! assert_eq!(slices[0].annotations[0].range, (8, 9));
+//! ```
+use codespan_reporting::files::{Error, Files};
+
+use crate::display_list::OwnedSlice;
+use crate::snippet::{AnnotationType, Slice, SourceAnnotation};
+
+/// An annotation of a byte range of a file of a `Files` database.
+#[derive(Debug)]
+pub struct FileAnnotation<'a, Id> {
+    pub file: Id,
+    pub range: (usize, usize),
+    pub label: &'a str,
+    pub annotation_type: AnnotationType,
+}
+
+/// Builds the slices displaying `annotations`, with `context` lines around
+/// the annotated lines, as `SourceMap::slices` does.
+///
+/// The slices of each file are grouped, in the order the files are first
+/// referenced by the annotations, and are numbered with the line numbers of
+/// the database.
+pub fn slices<'a, F: Files<'a>>(
+    files: &'a F,
+    annotations: Vec<FileAnnotation<'_, F::FileId>>,
+    context: usize,
+) -> Result<Vec<OwnedSlice>, Error> {
+    let mut grouped: Vec<(F::FileId, Vec<_>)> = vec![];
+    for annotation in annotations {
+        match grouped.iter_mut().find(|(id, _)| *id == annotation.file) {
+            Some((_, file_annotations)) => file_annotations.push(annotation),
+            None => grouped.push((annotation.file, vec![annotation])),
+        }
+    }
+
+    let mut slices = vec![];
+    for (id, annotations) in grouped {
+        let name = files.name(id)?.to_string();
+        let source = files.source(id)?;
+        let source = source.as_ref();
+        let slice = Slice {
+            source,
+            line_start: files.line_number(id, 0)?,
+            origin: Some(&name),
+            annotations: annotations
+                .iter()
+                .map(|annotation| SourceAnnotation {
+                    range: (
+                        char_offset(source, annotation.range.0),
+                        char_offset(source, annotation.range.1),
+                    ),
+                    label: annotation.label,
+                    annotation_type: annotation.annotation_type,
+                    ..Default::default()
+                })
+                .collect(),
+            fold: false,
+            line_highlights: vec![],
+            origin_note: None,
+        };
+        slices.extend(slice.extract(context).iter().map(OwnedSlice::from));
+    }
+    Ok(slices)
+}
+
+/// The char offset of a byte offset of `source`, rounded down to a char
+/// boundary.
+fn char_offset(source: &str, byte: usize) -> usize {
+    let mut byte = std::cmp::min(byte, source.len());
+    while !source.is_char_boundary(byte) {
+        byte -= 1;
+    }
+    source[..byte].chars().count()
+}
@@ -0,0 +1,196 @@
+//! Snippets described in JSON or TOML, with the fields of the crate's types.
+//!
+//! With the `serde` feature, the descriptions read by the `annotate-snippets`
+//! binary are deserializable by any tool, and borrowed as snippets with
+//! `SnippetDesc::to_snippet`.
+
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::display_list::{FormatOptions, Margin};
+use crate::snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation};
+
+/// A document holding either a single snippet or a list of them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Document {
+    List(Vec<SnippetDesc>),
+    Many { snippets: Vec<SnippetDesc> },
+    One(Box<SnippetDesc>),
+}
+
+impl Document {
+    pub fn into_snippets(self) -> Vec<SnippetDesc> {
+        match self {
+            Document::List(snippets) | Document::Many { snippets } => snippets,
+            Document::One(snippet) => vec![*snippet],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SnippetDesc {
+    #[serde(default)]
+    pub title: Option<AnnotationDesc>,
+    #[serde(default)]
+    pub footer: Vec<AnnotationDesc>,
+    #[serde(default)]
+    pub slices: Vec<SliceDesc>,
+    #[serde(default)]
+    pub opt: OptionsDesc,
+}
+
+#[derive(Deserialize)]
+pub struct AnnotationDesc {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+    pub annotation_type: AnnotationTypeDesc,
+}
+
+#[derive(Deserialize)]
+pub struct SliceDesc {
+    pub source: String,
+    pub line_start: usize,
+    #[serde(default)]
+    pub origin: Option<String>,
+    #[serde(default)]
+    pub annotations: Vec<SourceAnnotationDesc>,
+    #[serde(default)]
+    pub fold: bool,
+}
+
+#[derive(Deserialize)]
+pub struct SourceAnnotationDesc {
+    pub range: (usize, usize),
+    pub label: String,
+    pub annotation_type: AnnotationTypeDesc,
+}
+
+#[derive(Deserialize, Default)]
+pub struct OptionsDesc {
+    #[serde(default)]
+    pub anonymized_line_numbers: bool,
+    #[serde(default)]
+    pub indent: usize,
+    #[serde(default)]
+    pub margin: Option<MarginDesc>,
+    #[serde(default)]
+    pub footnotes: bool,
+    #[serde(default)]
+    pub full_width_marks: bool,
+    #[serde(default)]
+    pub tab_width: Option<usize>,
+    #[serde(default)]
+    pub max_width: Option<usize>,
+    #[serde(default)]
+    pub remap_path_prefix: Vec<(String, String)>,
+    #[serde(default)]
+    pub base_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub max_body_lines: Option<usize>,
+}
+
+/// The arguments of `Margin::new`.
+#[derive(Deserialize)]
+pub struct MarginDesc {
+    pub whitespace_left: usize,
+    pub span_left: usize,
+    pub span_right: usize,
+    pub label_right: usize,
+    pub column_width: usize,
+    pub max_line_len: usize,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub enum AnnotationTypeDesc {
+    Error,
+    Warning,
+    Info,
+    Note,
+    Help,
+}
+
+impl From<AnnotationTypeDesc> for AnnotationType {
+    fn from(desc: AnnotationTypeDesc) -> Self {
+        match desc {
+            AnnotationTypeDesc::Error => AnnotationType::Error,
+            AnnotationTypeDesc::Warning => AnnotationType::Warning,
+            AnnotationTypeDesc::Info => AnnotationType::Info,
+            AnnotationTypeDesc::Note => AnnotationType::Note,
+            AnnotationTypeDesc::Help => AnnotationType::Help,
+        }
+    }
+}
+
+impl AnnotationDesc {
+    fn to_annotation(&self) -> Annotation<'_> {
+        Annotation {
+            id: self.id.as_deref(),
+            label: self.label.as_deref(),
+            annotation_type: self.annotation_type.into(),
+        }
+    }
+}
+
+impl SnippetDesc {
+    /// Borrows the description as a snippet.
+    pub fn to_snippet(&self, color: bool) -> Snippet<'_> {
+        Snippet {
+            title: self.title.as_ref().map(AnnotationDesc::to_annotation),
+            footer: self
+                .footer
+                .iter()
+                .map(AnnotationDesc::to_annotation)
+                .collect(),
+            slices: self
+                .slices
+                .iter()
+                .map(|slice| Slice {
+                    source: &slice.source,
+                    line_start: slice.line_start,
+                    origin: slice.origin.as_deref(),
+                    annotations: slice
+                        .annotations
+                        .iter()
+                        .map(|annotation| SourceAnnotation {
+                            range: annotation.range,
+                            label: &annotation.label,
+                            annotation_type: annotation.annotation_type.into(),
+                            ..Default::default()
+                        })
+                        .collect(),
+                    fold: slice.fold,
+                    line_highlights: vec![],
+                    origin_note: None,
+                })
+                .collect(),
+            opt: FormatOptions {
+                color,
+                anonymized_line_numbers: self.opt.anonymized_line_numbers,
+                indent: self.opt.indent,
+                footnotes: self.opt.footnotes,
+                full_width_marks: self.opt.full_width_marks,
+                tab_width: self.opt.tab_width,
+                max_width: self.opt.max_width,
+                remap_path_prefix: self.opt.remap_path_prefix.clone(),
+                base_dir: self.opt.base_dir.clone(),
+                max_body_lines: self.opt.max_body_lines,
+                margin: self.opt.margin.as_ref().map(|margin| {
+                    Margin::new(
+                        margin.whitespace_left,
+                        margin.span_left,
+                        margin.span_right,
+                        margin.label_right,
+                        margin.column_width,
+                        margin.max_line_len,
+                    )
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
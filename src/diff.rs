@@ -0,0 +1,184 @@
+//! Expected versus actual text, rendered as a line diff.
+//!
+//! A `DiffSnippet` is converted into a `DisplayList` like a `Snippet`: the
+//! lines of the diff are source lines prefixed with `-` when only expected,
+//! `+` when only actual, and colored accordingly when colors are enabled.
+//! Annotations point to a line of either side.
+//!
+//! ```
+//! use annotate_snippets::diff::{DiffAnnotation, DiffSide, DiffSnippet};
+//! use annotate_snippets::display_list::DisplayList;
+//! use annotate_snippets::snippet::AnnotationType;
+//!
+//! let snippet = DiffSnippet {
+//!     expected: "a\nb\n",
+//!     actual: "a\nc\n",
+//!     annotations: vec![DiffAnnotation {
+//!         side: DiffSide::Actual,
+//!         line: 2,
+//!         range: (0, 1),
+//!         label: "unexpected",
+//!         annotation_type: AnnotationType::Error,
+//!     }],
+//!     ..Default::default()
+//! };
+//!
+//! assert_eq!(
+//!     DisplayList::from(snippet).to_string(),
+//!     "  |\n1 |   a\n2 | - b\n2 | + c\n  |   ^ unexpected\n  |"
+//! );
+//! ```
+use crate::display_list::FormatOptions;
+use crate::snippet::{Annotation, AnnotationType};
+
+/// Whether a line of a diff is in both texts or only in one of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffTag {
+    Equal,
+    /// A line only in the expected text.
+    Delete,
+    /// A line only in the actual text.
+    Insert,
+}
+
+/// A line of a diff, with its line numbers in the texts it belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine<'a> {
+    pub tag: DiffTag,
+    pub text: &'a str,
+    pub expected_lineno: Option<usize>,
+    pub actual_lineno: Option<usize>,
+}
+
+/// A side of a diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffSide {
+    Expected,
+    Actual,
+}
+
+/// An annotation of the `line` of a side of a diff. Lines start at 1, and
+/// `range` counts chars of the line.
+#[derive(Debug)]
+pub struct DiffAnnotation<'a> {
+    pub side: DiffSide,
+    pub line: usize,
+    pub range: (usize, usize),
+    pub label: &'a str,
+    pub annotation_type: AnnotationType,
+}
+
+/// A diff between an expected and an actual text, to be displayed.
+#[derive(Debug, Default)]
+pub struct DiffSnippet<'a> {
+    pub title: Option<Annotation<'a>>,
+    pub expected: &'a str,
+    pub actual: &'a str,
+    pub annotations: Vec<DiffAnnotation<'a>>,
+    pub opt: FormatOptions,
+}
+
+/// A text before and after a change, such as the source a suggestion fixes,
+/// to be displayed side by side.
+///
+/// The lines of both versions are on the same row when they are equal, or
+/// when they replace each other, numbered with the lines of the version
+/// before. Between the columns, `|` marks a changed row, `<` a deleted line
+/// and `>` an inserted line.
+///
+/// If the columns are wider than `width`, the text is displayed as a
+/// `DiffSnippet` rather, the lines after the change following the ones
+/// before it.
+///
+/// ```
+/// use annotate_snippets::diff::SideBySideSnippet;
+/// use annotate_snippets::display_list::DisplayList;
+///
+/// let snippet = SideBySideSnippet {
+///     before: "let x = 1;\nlet y = x;\n",
+///     after: "let x = 1;\nlet y = &x;\n",
+///     width: 80,
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     DisplayList::from(snippet).to_string(),
+///     "  |\n1 | let x = 1;   let x = 1;\n2 | let y = x; | let y = &x;\n  |"
+/// );
+/// ```
+#[derive(Debug, Default)]
+pub struct SideBySideSnippet<'a> {
+    pub title: Option<Annotation<'a>>,
+    pub before: &'a str,
+    pub after: &'a str,
+    /// Columns available for the rendered lines.
+    pub width: usize,
+    pub opt: FormatOptions,
+}
+
+/// Computes the line diff of two texts, from their longest common
+/// subsequence of lines.
+///
+/// Unchanged lines at the start and end are matched directly, the middle
+/// takes time and memory proportional to the product of the remaining lines.
+pub fn diff_lines<'a>(expected: &'a str, actual: &'a str) -> Vec<DiffLine<'a>> {
+    let old = expected.lines().collect::<Vec<_>>();
+    let new = actual.lines().collect::<Vec<_>>();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_mid, new_mid) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    // lcs[i][j] is the length of the common subsequence of old_mid[i..] and new_mid[j..].
+    let width = new_mid.len() + 1;
+    let mut lcs = vec![0u32; (old_mid.len() + 1) * width];
+    for i in (0..old_mid.len()).rev() {
+        for j in (0..new_mid.len()).rev() {
+            lcs[i * width + j] = if old_mid[i] == new_mid[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                std::cmp::max(lcs[(i + 1) * width + j], lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::with_capacity(old.len() + new.len());
+    let mut push = |tag, text, i: usize, j: usize| {
+        result.push(DiffLine {
+            tag,
+            text,
+            expected_lineno: Some(i + 1).filter(|_| tag != DiffTag::Insert),
+            actual_lineno: Some(j + 1).filter(|_| tag != DiffTag::Delete),
+        })
+    };
+    for (i, line) in old[..prefix].iter().enumerate() {
+        push(DiffTag::Equal, line, i, i);
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < old_mid.len() || j < new_mid.len() {
+        if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+            push(DiffTag::Equal, old_mid[i], prefix + i, prefix + j);
+            i += 1;
+            j += 1;
+        } else if i < old_mid.len()
+            && (j == new_mid.len() || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+        {
+            push(DiffTag::Delete, old_mid[i], prefix + i, prefix + j);
+            i += 1;
+        } else {
+            push(DiffTag::Insert, new_mid[j], prefix + i, prefix + j);
+            j += 1;
+        }
+    }
+    for k in 0..suffix {
+        let (i, j) = (old.len() - suffix + k, new.len() - suffix + k);
+        push(DiffTag::Equal, old[i], i, j);
+    }
+    result
+}
@@ -0,0 +1,231 @@
+//! Trait for converting `DiffSnippet` to `DisplayList`.
+
+use super::from_snippet::{empty_source_line, format_label, format_title, text_width};
+use super::*;
+use crate::diff::{self, DiffSide, DiffTag};
+use crate::formatter::highlight::{HighlightSegment, Highlighter};
+use crate::formatter::style::{Color, StyleSpec};
+use crate::snippet;
+
+/// Colors the deleted and inserted lines of a diff, from their prefix.
+struct DiffHighlighter;
+
+impl Highlighter for DiffHighlighter {
+    fn highlight_line(
+        &self,
+        _origin: Option<&str>,
+        _lineno: Option<usize>,
+        line: &str,
+    ) -> Vec<HighlightSegment> {
+        let color = match line.as_bytes().first() {
+            Some(b'-') => Color::Red,
+            Some(b'+') => Color::Green,
+            _ => return vec![],
+        };
+        vec![HighlightSegment {
+            range: 0..line.len(),
+            style: StyleSpec::new().fg(color),
+        }]
+    }
+}
+
+fn format_diff_annotation<'a>(
+    annotation: &diff::DiffAnnotation<'a>,
+    text: &str,
+) -> DisplayLine<'a> {
+    // Display column of the char `index` of the line, after its prefix.
+    let col = |index: usize| {
+        let end = text
+            .char_indices()
+            .nth(index)
+            .map_or(text.len(), |(i, _)| i);
+        2 + text_width(&text[..end])
+    };
+    let (start, end) = annotation.range;
+    let annotation_type = match annotation.annotation_type {
+        snippet::AnnotationType::Error => DisplayAnnotationType::None,
+        snippet::AnnotationType::Warning => DisplayAnnotationType::None,
+        _ => DisplayAnnotationType::from(annotation.annotation_type),
+    };
+    DisplayLine::Source {
+        lineno: None,
+        inline_marks: InlineMarks::new(),
+        line: DisplaySourceLine::Annotation {
+            annotation: Annotation {
+                annotation_type,
+                id: None,
+                label: format_label(Some(annotation.label), None),
+            },
+            range: (col(start), col(end)),
+            annotation_type: DisplayAnnotationType::from(annotation.annotation_type),
+            annotation_part: DisplayAnnotationPart::Standalone,
+            wide_marks: false,
+        },
+    }
+}
+
+impl<'a> From<diff::DiffSnippet<'a>> for DisplayList<'a> {
+    fn from(snippet: diff::DiffSnippet<'a>) -> DisplayList<'a> {
+        let mut body = vec![];
+        if let Some(title) = &snippet.title {
+            body.push(format_title(title));
+        }
+        if !snippet.opt.vertical_compact {
+            body.push(empty_source_line());
+        }
+        for line in diff::diff_lines(snippet.expected, snippet.actual) {
+            let (prefix, lineno) = match line.tag {
+                DiffTag::Equal => (' ', line.actual_lineno),
+                DiffTag::Delete => ('-', line.expected_lineno),
+                DiffTag::Insert => ('+', line.actual_lineno),
+            };
+            let text = format!("{} {}", prefix, line.text);
+            let len = text.chars().count();
+            body.push(DisplayLine::Source {
+                lineno,
+                inline_marks: InlineMarks::new(),
+                line: DisplaySourceLine::Content {
+                    text: text.into(),
+                    range: (0, len),
+                },
+            });
+            for annotation in &snippet.annotations {
+                let annotated = match annotation.side {
+                    DiffSide::Expected => line.expected_lineno,
+                    DiffSide::Actual => line.actual_lineno,
+                };
+                if annotated == Some(annotation.line) {
+                    body.push(format_diff_annotation(annotation, line.text));
+                }
+            }
+        }
+        if !snippet.opt.vertical_compact {
+            body.push(empty_source_line());
+        }
+
+        let mut dl = DisplayList::with_options(body, snippet.opt);
+        dl.highlighter = Some(Box::new(DiffHighlighter));
+        dl
+    }
+}
+
+/// Colors the changed sides of the rows of a side by side diff, whose left
+/// column is `column` wide.
+struct SideBySideHighlighter {
+    column: usize,
+}
+
+impl Highlighter for SideBySideHighlighter {
+    fn highlight_line(
+        &self,
+        _origin: Option<&str>,
+        _lineno: Option<usize>,
+        line: &str,
+    ) -> Vec<HighlightSegment> {
+        // The end of the left column.
+        let split = line
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(line.len()))
+            .find(|&i| text_width(&line[..i]) >= self.column)
+            .unwrap_or(line.len());
+        let marker = line[split..].chars().nth(1);
+        let mut segments = vec![];
+        if let Some('|' | '<') = marker {
+            segments.push(HighlightSegment {
+                range: 0..split,
+                style: StyleSpec::new().fg(Color::Red),
+            });
+        }
+        if let Some('|' | '>') = marker {
+            segments.push(HighlightSegment {
+                range: std::cmp::min(split + 3, line.len())..line.len(),
+                style: StyleSpec::new().fg(Color::Green),
+            });
+        }
+        segments
+    }
+}
+
+impl<'a> From<diff::SideBySideSnippet<'a>> for DisplayList<'a> {
+    fn from(snippet: diff::SideBySideSnippet<'a>) -> DisplayList<'a> {
+        // The rows: a line before, a line after and the marker between them.
+        let mut rows: Vec<(Option<usize>, &str, char, &str)> = vec![];
+        let lines = diff::diff_lines(snippet.before, snippet.after);
+        let mut lines = lines.iter().peekable();
+        while let Some(line) = lines.next() {
+            match line.tag {
+                DiffTag::Equal => rows.push((line.expected_lineno, line.text, ' ', line.text)),
+                DiffTag::Insert => rows.push((None, "", '>', line.text)),
+                DiffTag::Delete => {
+                    let mut deleted = vec![line];
+                    while let Some(line) = lines.next_if(|line| line.tag == DiffTag::Delete) {
+                        deleted.push(line);
+                    }
+                    let mut deleted = deleted.into_iter();
+                    while let Some(line) = lines.next_if(|line| line.tag == DiffTag::Insert) {
+                        match deleted.next() {
+                            Some(before) => {
+                                rows.push((before.expected_lineno, before.text, '|', line.text))
+                            }
+                            None => rows.push((None, "", '>', line.text)),
+                        }
+                    }
+                    rows.extend(
+                        deleted.map(|before| (before.expected_lineno, before.text, '<', "")),
+                    );
+                }
+            }
+        }
+
+        let column = rows.iter().map(|row| text_width(row.1)).max().unwrap_or(0);
+        let after = rows.iter().map(|row| text_width(row.3)).max().unwrap_or(0);
+        let max_lineno = rows.iter().filter_map(|row| row.0).max().unwrap_or(0);
+        let gutter = max_lineno.to_string().len() + 3;
+        if snippet.opt.indent + gutter + column + 3 + after > snippet.width {
+            return DisplayList::from(diff::DiffSnippet {
+                title: snippet.title,
+                expected: snippet.before,
+                actual: snippet.after,
+                annotations: vec![],
+                opt: snippet.opt,
+            });
+        }
+
+        let mut body = vec![];
+        if let Some(title) = &snippet.title {
+            body.push(format_title(title));
+        }
+        if !snippet.opt.vertical_compact {
+            body.push(empty_source_line());
+        }
+        for (lineno, before, marker, after) in rows {
+            let padding = column - text_width(before);
+            let text = format!(
+                "{}{:padding$} {} {}",
+                before,
+                "",
+                marker,
+                after,
+                padding = padding
+            );
+            let text = text.trim_end().to_string();
+            let len = text.chars().count();
+            body.push(DisplayLine::Source {
+                lineno,
+                inline_marks: InlineMarks::new(),
+                line: DisplaySourceLine::Content {
+                    text: text.into(),
+                    range: (0, len),
+                },
+            });
+        }
+        if !snippet.opt.vertical_compact {
+            body.push(empty_source_line());
+        }
+
+        let mut dl = DisplayList::with_options(body, snippet.opt);
+        dl.highlighter = Some(Box::new(SideBySideHighlighter { column }));
+        dl
+    }
+}
@@ -1,6 +1,10 @@
 //! Trait for converting `Snippet` to `DisplayList`.
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::VecDeque;
+
 use super::*;
-use crate::{formatter::get_term_style, snippet};
+use crate::{formatter::get_theme_style, snippet};
 
 struct CursorLines<'a>(&'a str);
 
@@ -11,9 +15,9 @@ impl<'a> CursorLines<'a> {
 }
 
 enum EndLine {
-    EOF = 0,
-    CRLF = 1,
-    LF = 2,
+    Eof = 0,
+    Lf = 1,
+    Crlf = 2,
 }
 
 impl<'a> Iterator for CursorLines<'a> {
@@ -28,18 +32,18 @@ impl<'a> Iterator for CursorLines<'a> {
                 .map(|x| {
                     let ret = if 0 < x {
                         if self.0.as_bytes()[x - 1] == b'\r' {
-                            (&self.0[..x - 1], EndLine::LF)
+                            (&self.0[..x - 1], EndLine::Crlf)
                         } else {
-                            (&self.0[..x], EndLine::CRLF)
+                            (&self.0[..x], EndLine::Lf)
                         }
                     } else {
-                        ("", EndLine::CRLF)
+                        ("", EndLine::Lf)
                     };
                     self.0 = &self.0[x + 1..];
                     ret
                 })
                 .or_else(|| {
-                    let ret = Some((self.0, EndLine::EOF));
+                    let ret = Some((self.0, EndLine::Eof));
                     self.0 = "";
                     ret
                 })
@@ -47,7 +51,7 @@ impl<'a> Iterator for CursorLines<'a> {
     }
 }
 
-fn format_label(
+pub(super) fn format_label(
     label: Option<&str>,
     style: Option<DisplayTextStyle>,
 ) -> Vec<DisplayTextFragment<'_>> {
@@ -65,7 +69,7 @@ fn format_label(
                 }
             };
             result.push(DisplayTextFragment {
-                content: element,
+                content: element.into(),
                 style: element_style,
             });
         }
@@ -73,7 +77,43 @@ fn format_label(
     result
 }
 
-fn format_title(annotation: snippet::Annotation<'_>) -> DisplayLine<'_> {
+/// Cuts `label` to `max` chars, the last being `…`, if it is longer.
+fn truncate_label(
+    label: Vec<DisplayTextFragment<'_>>,
+    max: Option<usize>,
+) -> Vec<DisplayTextFragment<'_>> {
+    let max = match max {
+        Some(max)
+            if label
+                .iter()
+                .map(|f| f.content.chars().count())
+                .sum::<usize>()
+                > max =>
+        {
+            max
+        }
+        _ => return label,
+    };
+    let mut left = max.saturating_sub(1);
+    let mut result = vec![];
+    for fragment in label {
+        let len = fragment.content.chars().count();
+        if len < left {
+            left -= len;
+            result.push(fragment);
+            continue;
+        }
+        let content: String = fragment.content.chars().take(left).collect();
+        result.push(DisplayTextFragment {
+            content: (content + "…").into(),
+            style: fragment.style,
+        });
+        break;
+    }
+    result
+}
+
+pub(super) fn format_title<'a>(annotation: &snippet::Annotation<'a>) -> DisplayLine<'a> {
     let label = annotation.label.unwrap_or_default();
     DisplayLine::Raw(DisplayRawLine::Annotation {
         annotation: Annotation {
@@ -86,7 +126,19 @@ fn format_title(annotation: snippet::Annotation<'_>) -> DisplayLine<'_> {
     })
 }
 
-fn format_annotation(annotation: snippet::Annotation<'_>) -> Vec<DisplayLine<'_>> {
+fn format_expansion_title<'a>(frame: &snippet::ExpansionFrame<'a>) -> DisplayLine<'a> {
+    DisplayLine::Raw(DisplayRawLine::Annotation {
+        annotation: Annotation {
+            annotation_type: DisplayAnnotationType::Note,
+            id: None,
+            label: format_label(Some(frame.label), Some(DisplayTextStyle::Emphasis)),
+        },
+        source_aligned: false,
+        continuation: false,
+    })
+}
+
+fn format_annotation<'a>(annotation: &snippet::Annotation<'a>) -> Vec<DisplayLine<'a>> {
     let mut result = vec![];
     let label = annotation.label.unwrap_or_default();
     for (i, line) in label.lines().enumerate() {
@@ -103,39 +155,501 @@ fn format_annotation(annotation: snippet::Annotation<'_>) -> Vec<DisplayLine<'_>
     result
 }
 
-fn format_slice(
-    slice: snippet::Slice<'_>,
-    is_first: bool,
-    has_footer: bool,
+/// The annotations of a slice listed as footnotes: the labeled ones but the
+/// first.
+fn footnote_annotations<'s, 'a>(
+    slice: &'s snippet::Slice<'a>,
+) -> impl Iterator<Item = &'s snippet::SourceAnnotation<'a>> {
+    slice
+        .annotations
+        .iter()
+        .skip(1)
+        .filter(|annotation| !annotation.label.is_empty())
+}
+
+/// The footnotes of the slices, numbered in order.
+fn format_footnotes<'a>(
+    slices: &[snippet::Slice<'a>],
+    max_label_length: Option<usize>,
+) -> Vec<DisplayLine<'a>> {
+    let annotations = slices.iter().flat_map(footnote_annotations);
+    annotations
+        .enumerate()
+        .map(|(i, annotation)| {
+            let mut label = vec![DisplayTextFragment {
+                content: format!("[{}]: ", i + 1).into(),
+                style: DisplayTextStyle::Regular,
+            }];
+            label.extend(truncate_label(
+                format_label(Some(annotation.label), None),
+                max_label_length,
+            ));
+            DisplayLine::Raw(DisplayRawLine::Annotation {
+                annotation: Annotation {
+                    annotation_type: DisplayAnnotationType::None,
+                    id: None,
+                    label,
+                },
+                source_aligned: true,
+                continuation: false,
+            })
+        })
+        .collect()
+}
+
+/// The options of `FormatOptions` the lines of slices are laid out with.
+#[derive(Clone, Copy)]
+struct SliceLayout {
     margin: Option<Margin>,
-) -> Vec<DisplayLine<'_>> {
-    let main_range = slice.annotations.get(0).map(|x| x.range.0);
-    let origin = slice.origin;
-    let line_start = slice.line_start;
-    let need_empty_header = origin.is_some() || is_first;
-    let mut body = format_body(slice, need_empty_header, has_footer, margin);
-    let header = format_header(origin, main_range, line_start, &body, is_first);
-    let mut result = vec![];
+    escape: EscapeMode,
+    column_unit: ColumnUnit,
+    header_position: HeaderPosition,
+    header_placement: HeaderPlacement,
+    full_width_marks: bool,
+    tab_width: Option<usize>,
+    compact_annotations: bool,
+    vertical_compact: bool,
+    max_label_length: Option<usize>,
+    screen_reader: bool,
+    column_ruler: bool,
+}
+
+/// The margin of `opt`, or, when it has none but a maximal width that some
+/// source lines don't fit in, one keeping the annotated spans of the slices
+/// and their labels in the window of these lines.
+pub(crate) fn fitted_margin(snippet: &snippet::Snippet<'_>, opt: &FormatOptions) -> Option<Margin> {
+    if opt.margin.is_some() {
+        return opt.margin;
+    }
+    let max_width = opt.max_width?;
+    let mut spans: Option<(usize, usize, usize, usize)> = None;
+    let mut max_line_len = 0;
+    let mut max_lineno = 0;
+    let mut inline_marks = 0;
+    for slice in &snippet.slices {
+        let mut line_start = 0;
+        for (i, line) in slice.source.split('\n').enumerate() {
+            let line_len = line.chars().count();
+            let line_end = line_start + line_len;
+            max_line_len = cmp::max(max_line_len, line_len);
+            max_lineno = cmp::max(max_lineno, slice.line_start + i);
+            let whitespace = line.chars().take_while(|c| c.is_whitespace()).count();
+            for annotation in &slice.annotations {
+                let (start, end) = annotation.range;
+                if start < line_start || end > line_end {
+                    continue;
+                }
+                let (start, end) = (start - line_start, end - line_start);
+                let label_end = end + 1 + text_width(annotation.label);
+                spans = Some(match spans {
+                    Some((whitespace_left, span_left, span_right, label_right)) => (
+                        cmp::min(whitespace_left, whitespace),
+                        cmp::min(span_left, start),
+                        cmp::max(span_right, end),
+                        cmp::max(label_right, label_end),
+                    ),
+                    None => (whitespace, start, end, label_end),
+                });
+            }
+            line_start = line_end + 1;
+        }
+        // Multiline annotations are marked left of the lines.
+        let multiline = slice
+            .annotations
+            .iter()
+            .filter(|annotation| {
+                slice
+                    .source
+                    .chars()
+                    .skip(annotation.range.0)
+                    .take(annotation.range.1.saturating_sub(annotation.range.0))
+                    .any(|c| c == '\n')
+            })
+            .count();
+        inline_marks = cmp::max(inline_marks, multiline);
+    }
+    let (whitespace_left, span_left, span_right, label_right) = spans?;
+    let lineno_width = if opt.anonymized_line_numbers {
+        2
+    } else {
+        max_lineno.to_string().len()
+    };
+    let gutter = opt.indent
+        + lineno_width
+        + 3
+        + if inline_marks > 0 {
+            inline_marks + 1
+        } else {
+            0
+        };
+    if gutter + max_line_len <= max_width {
+        return None;
+    }
+    Some(Margin::new(
+        whitespace_left,
+        span_left,
+        span_right,
+        label_right,
+        max_width.saturating_sub(gutter),
+        max_line_len,
+    ))
+}
+
+/// Iterator over all lines of a `Snippet`, in display order.
+///
+/// The lines are produced lazily, one source line at a time, which allows
+/// rendering a snippet without materializing its `DisplayList`.
+pub(crate) struct SnippetLines<'s, 'a> {
+    snippet: &'s snippet::Snippet<'a>,
+    includes: std::iter::Enumerate<std::slice::Iter<'s, snippet::IncludeFrame<'a>>>,
+    title: Option<&'s snippet::Annotation<'a>>,
+    extra_titles: std::slice::Iter<'s, snippet::Annotation<'a>>,
+    slices: std::iter::Enumerate<std::slice::Iter<'s, snippet::Slice<'a>>>,
+    slice: Option<SliceLines<'s, 'a>>,
+    buffers: LayoutBuffers,
+    layout: SliceLayout,
+    /// The options the paths are displayed with.
+    opt: &'s FormatOptions,
+    /// The maximal number of body lines, until the lines are elided.
+    max_body_lines: Option<usize>,
+    /// The lines left after elision.
+    elided: Option<std::vec::IntoIter<DisplayLine<'a>>>,
+    /// Number of the last footnote, if the snippet has footnotes.
+    footnotes: Option<usize>,
+    has_footer: bool,
+    footer: std::slice::Iter<'s, snippet::Annotation<'a>>,
+    lint: Option<&'s snippet::Lint<'a>>,
+    footer_lines: std::vec::IntoIter<DisplayLine<'a>>,
+    expansion: std::slice::Iter<'s, snippet::ExpansionFrame<'a>>,
+}
+
+impl<'s, 'a> SnippetLines<'s, 'a> {
+    /// Creates the iterator, laying out the slices with the given buffers.
+    pub(crate) fn with_buffers(snippet: &'s snippet::Snippet<'a>, buffers: LayoutBuffers) -> Self {
+        Self::with_options(snippet, &snippet.opt, buffers)
+    }
+
+    /// Creates the iterator, laying out the slices according to `opt` rather
+    /// than the options of the snippet.
+    pub(crate) fn with_options(
+        snippet: &'s snippet::Snippet<'a>,
+        opt: &'s FormatOptions,
+        buffers: LayoutBuffers,
+    ) -> Self {
+        let footnotes = Some(0).filter(|_| {
+            opt.footnotes
+                && snippet
+                    .slices
+                    .iter()
+                    .any(|slice| footnote_annotations(slice).next().is_some())
+        });
+        Self {
+            snippet,
+            includes: snippet.includes.iter().enumerate(),
+            title: snippet.title.as_ref(),
+            extra_titles: snippet.extra_titles.iter(),
+            slices: snippet.slices.iter().enumerate(),
+            slice: None,
+            buffers,
+            layout: SliceLayout {
+                margin: fitted_margin(snippet, opt),
+                escape: opt.escape,
+                column_unit: opt.column_unit,
+                header_position: opt.header_position,
+                header_placement: opt.header_placement,
+                full_width_marks: opt.full_width_marks,
+                tab_width: opt.tab_width,
+                compact_annotations: opt.compact_annotations,
+                vertical_compact: opt.vertical_compact,
+                max_label_length: opt.max_label_length,
+                screen_reader: opt.screen_reader,
+                column_ruler: opt.column_ruler,
+            },
+            opt,
+            max_body_lines: opt.max_body_lines,
+            elided: None,
+            footnotes,
+            has_footer: !snippet.footer.is_empty() || footnotes.is_some() || snippet.lint.is_some(),
+            footer: snippet.footer.iter(),
+            lint: snippet.lint.as_ref(),
+            footer_lines: vec![].into_iter(),
+            expansion: snippet.expansion.iter(),
+        }
+    }
+
+    /// The line of the title, then those of the extra titles.
+    fn next_title(&mut self) -> Option<DisplayLine<'a>> {
+        let title = self.title.take().or_else(|| self.extra_titles.next())?;
+        Some(format_title(title))
+    }
+
+    /// Gives back the buffers, to be reused for another snippet.
+    pub(crate) fn into_buffers(self) -> LayoutBuffers {
+        match self.slice {
+            Some(slice) => slice.into_buffers(),
+            None => self.buffers,
+        }
+    }
+}
+
+impl<'s, 'a> Iterator for SnippetLines<'s, 'a> {
+    type Item = DisplayLine<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(max) = self.max_body_lines.take() {
+            let lines = self.by_ref().collect();
+            self.elided = Some(elide_body_lines(lines, max).into_iter());
+        }
+        if let Some(elided) = &mut self.elided {
+            return elided.next();
+        }
+        if let Some((depth, include)) = self.includes.next() {
+            return Some(DisplayLine::Raw(DisplayRawLine::Include {
+                path: self.opt.normalize_path(include.path),
+                line: include.line,
+                depth,
+            }));
+        }
+        if self.layout.header_placement == HeaderPlacement::Above {
+            if let Some(title) = self.next_title() {
+                return Some(title);
+            }
+        }
+        loop {
+            if let Some(slice) = &mut self.slice {
+                if let Some(line) = slice.next() {
+                    return Some(line);
+                }
+            }
+            if let Some(slice) = self.slice.take() {
+                self.buffers = slice.into_buffers();
+            }
+            match self.slices.next() {
+                Some((idx, slice)) => {
+                    self.slice = Some(SliceLines::new(
+                        slice,
+                        self.opt,
+                        idx == 0,
+                        self.has_footer,
+                        self.layout,
+                        self.footnotes,
+                        std::mem::take(&mut self.buffers),
+                    ));
+                    if let Some(footnotes) = &mut self.footnotes {
+                        *footnotes += footnote_annotations(slice).count();
+                    }
+                }
+                None => break,
+            }
+        }
+        if let Some(title) = self.next_title() {
+            return Some(title);
+        }
+        if self.footnotes.take().is_some() {
+            self.footer_lines =
+                format_footnotes(&self.snippet.slices, self.layout.max_label_length).into_iter();
+        }
+        loop {
+            if let Some(line) = self.footer_lines.next() {
+                return Some(line);
+            }
+            match self.footer.next() {
+                Some(footer) => self.footer_lines = format_annotation(footer).into_iter(),
+                None => break,
+            }
+        }
+        if let Some(lint) = self.lint.take() {
+            return Some(format_lint(lint));
+        }
+        // The slice of a frame is displayed by the first loop, after its title.
+        let frame = self.expansion.next()?;
+        self.slice = Some(SliceLines::new(
+            &frame.slice,
+            self.opt,
+            true,
+            false,
+            self.layout,
+            None,
+            std::mem::take(&mut self.buffers),
+        ));
+        Some(format_expansion_title(frame))
+    }
+}
+
+/// Whether `line` is a source line highlighted by a `LineHighlight`.
+fn is_highlighted(line: &DisplayLine<'_>) -> bool {
+    match line {
+        DisplayLine::Source {
+            inline_marks,
+            line: DisplaySourceLine::Content { .. } | DisplaySourceLine::Compact { .. },
+            ..
+        } => inline_marks
+            .iter()
+            .any(|mark| matches!(mark.mark_type, DisplayMarkType::LineHighlight { .. })),
+        _ => false,
+    }
+}
+
+/// Keeps at most `max` of the source, annotation and fold lines of the
+/// slices: the annotations and the lines they annotate first, then the lines
+/// closest to them. Each run of elided lines is replaced with a marker
+/// counting its source lines.
+fn elide_body_lines(lines: Vec<DisplayLine<'_>>, max: usize) -> Vec<DisplayLine<'_>> {
+    let is_annotation = |line: &DisplayLine<'_>| {
+        matches!(
+            line,
+            DisplayLine::Source {
+                line: DisplaySourceLine::Annotation { .. },
+                ..
+            }
+        )
+    };
+    let is_compact = |line: &DisplayLine<'_>| {
+        matches!(
+            line,
+            DisplayLine::Source {
+                line: DisplaySourceLine::Compact { .. },
+                ..
+            }
+        )
+    };
+    let body: Vec<usize> = (0..lines.len())
+        .filter(|&i| match &lines[i] {
+            DisplayLine::Source { line, .. } => *line != DisplaySourceLine::Empty,
+            DisplayLine::Fold { .. } | DisplayLine::Elided { .. } => true,
+            DisplayLine::Raw(_) => false,
+        })
+        .collect();
+    if body.len() <= max {
+        return lines;
+    }
+
+    // The annotations, with the source lines above them, and the compact and
+    // highlighted lines.
+    let mut kept = vec![false; body.len()];
+    for (k, &i) in body.iter().enumerate() {
+        kept[k] = is_annotation(&lines[i])
+            || is_compact(&lines[i])
+            || is_highlighted(&lines[i])
+            || body
+                .get(k + 1)
+                .is_some_and(|&next| next == i + 1 && is_annotation(&lines[next]));
+    }
+    let annotated: Vec<usize> = (0..body.len()).filter(|&k| kept[k]).collect();
+    let mut budget = max.saturating_sub(annotated.len());
+    if annotated.is_empty() {
+        kept[..budget].iter_mut().for_each(|kept| *kept = true);
+        budget = 0;
+    }
+    let mut distance = 1;
+    while budget > 0 && distance < body.len() {
+        for &k in &annotated {
+            for near in [k.checked_sub(distance), Some(k + distance)] {
+                match near {
+                    Some(near) if budget > 0 && near < body.len() && !kept[near] => {
+                        kept[near] = true;
+                        budget -= 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        distance += 1;
+    }
 
-    if let Some(header) = header {
-        result.push(header);
+    let mut elided = vec![false; lines.len()];
+    for (k, &i) in body.iter().enumerate() {
+        elided[i] = !kept[k];
+    }
+
+    let mut result = Vec::with_capacity(max + 1);
+    let mut run: Option<(InlineMarks, usize)> = None;
+    for (line, elided) in lines.into_iter().zip(elided) {
+        if elided {
+            let (_, count) = run.get_or_insert_with(|| match &line {
+                DisplayLine::Source { inline_marks, .. }
+                | DisplayLine::Fold { inline_marks }
+                | DisplayLine::Elided { inline_marks, .. } => (inline_marks.clone(), 0),
+                DisplayLine::Raw(_) => (InlineMarks::new(), 0),
+            });
+            if let DisplayLine::Source {
+                line: DisplaySourceLine::Content { .. },
+                ..
+            } = line
+            {
+                *count += 1;
+            }
+            continue;
+        }
+        if let Some((inline_marks, count)) = run.take() {
+            result.push(DisplayLine::Elided {
+                inline_marks,
+                count,
+            });
+        }
+        result.push(line);
+    }
+    if let Some((inline_marks, count)) = run {
+        result.push(DisplayLine::Elided {
+            inline_marks,
+            count,
+        });
     }
-    result.append(&mut body);
     result
 }
 
-#[inline]
-// TODO: option_zip
-fn zip_opt<A, B>(a: Option<A>, b: Option<B>) -> Option<(A, B)> {
-    a.and_then(|a| b.map(|b| (a, b)))
+/// Iterator over all lines of a `Snippet`, in display order.
+pub(crate) fn display_lines<'s, 'a>(snippet: &'s snippet::Snippet<'a>) -> SnippetLines<'s, 'a> {
+    SnippetLines::with_buffers(snippet, LayoutBuffers::default())
+}
+
+/// Line and column, in `column_unit`, of the char `index` of `source`, whose
+/// first line is `line_start`.
+fn position(
+    source: &str,
+    line_start: usize,
+    index: usize,
+    column_unit: ColumnUnit,
+) -> (usize, usize) {
+    let mut row = line_start;
+    let mut col = 1;
+    for line in SourceLines::new(source, line_start) {
+        let index = line.clamp(index);
+        if index >= line.line_start_index && index <= line.line_end_index {
+            let prefix = match line.text.char_indices().nth(index - line.line_start_index) {
+                Some((end, _)) => &line.text[..end],
+                None => line.text,
+            };
+            col = column_unit.measure(prefix) + 1;
+            break;
+        }
+        row += 1;
+    }
+    (row, col)
+}
+
+/// Lines and columns, in `column_unit`, of the start and end of the first
+/// annotation of a slice, the start being displayed in its header.
+#[cfg(feature = "emitter")]
+pub(crate) fn slice_span(
+    slice: &snippet::Slice<'_>,
+    column_unit: ColumnUnit,
+) -> Option<((usize, usize), (usize, usize))> {
+    let (start, end) = slice.annotations.first()?.range;
+    Some((
+        position(slice.source, slice.line_start, start, column_unit),
+        position(slice.source, slice.line_start, end, column_unit),
+    ))
 }
 
 fn format_header<'a>(
-    origin: Option<&'a str>,
+    origin: Option<Cow<'a, str>>,
     main_range: Option<usize>,
-    mut row: usize,
-    body: &[DisplayLine<'_>],
+    source: &str,
+    line_start: usize,
     is_first: bool,
+    column_unit: ColumnUnit,
+    header_position: HeaderPosition,
 ) -> Option<DisplayLine<'a>> {
     let display_header = if is_first {
         DisplayHeaderType::Initial
@@ -143,440 +657,1148 @@ fn format_header<'a>(
         DisplayHeaderType::Continuation
     };
 
-    if let Some((main_range, path)) = zip_opt(main_range, origin) {
-        let mut col = 1;
-
-        for item in body {
-            if let DisplayLine::Source {
-                line: DisplaySourceLine::Content { range, .. },
-                ..
-            } = item
-            {
-                if main_range >= range.0 && main_range <= range.1 {
-                    col = main_range - range.0 + 1;
-                    break;
-                }
-                row += 1;
+    origin.map(|path| {
+        let (pos, offset) = match (main_range, header_position) {
+            (None, _) => (None, None),
+            (Some(main_range), HeaderPosition::LineColumn) => (
+                Some(position(source, line_start, main_range, column_unit)),
+                None,
+            ),
+            (Some(main_range), HeaderPosition::Offset) => {
+                let offset = source
+                    .char_indices()
+                    .nth(main_range)
+                    .map_or(source.len(), |(offset, _)| offset);
+                (None, Some(offset))
             }
-        }
-
-        return Some(DisplayLine::Raw(DisplayRawLine::Origin {
+        };
+        DisplayLine::Raw(DisplayRawLine::Origin {
             path,
-            pos: Some((row, col)),
+            pos,
+            offset,
             header_type: display_header,
-        }));
+        })
+    })
+}
+
+/// The note explaining the level of the lint of a snippet.
+fn format_lint<'a>(lint: &snippet::Lint<'_>) -> DisplayLine<'a> {
+    DisplayLine::Raw(DisplayRawLine::Annotation {
+        annotation: Annotation {
+            annotation_type: DisplayAnnotationType::Note,
+            id: None,
+            label: vec![DisplayTextFragment {
+                content: lint.note().into(),
+                style: DisplayTextStyle::Regular,
+            }],
+        },
+        source_aligned: true,
+        continuation: false,
+    })
+}
+
+/// The note displayed under the header of a slice.
+fn format_origin_note(note: &str) -> DisplayLine<'_> {
+    DisplayLine::Raw(DisplayRawLine::Annotation {
+        annotation: Annotation {
+            annotation_type: DisplayAnnotationType::Note,
+            id: None,
+            label: format_label(Some(note), None),
+        },
+        source_aligned: true,
+        continuation: false,
+    })
+}
+
+pub(super) fn empty_source_line<'a>() -> DisplayLine<'a> {
+    DisplayLine::Source {
+        lineno: None,
+        inline_marks: InlineMarks::new(),
+        line: DisplaySourceLine::Empty,
     }
+}
 
-    if let Some(path) = origin {
-        return Some(DisplayLine::Raw(DisplayRawLine::Origin {
-            path,
-            pos: None,
-            header_type: display_header,
-        }));
+/// The prose of the annotations of `slice`, like `line 52, columns 5-9:
+/// expected `;``, with the lines they end on, in order.
+fn format_prose<'a>(
+    slice: &snippet::Slice<'a>,
+    layout: SliceLayout,
+    translations: Option<&Translations>,
+) -> VecDeque<(usize, DisplayLine<'a>)> {
+    let default = Translations::default();
+    let translations = translations.unwrap_or(&default);
+    let mut prose = slice
+        .annotations
+        .iter()
+        .map(|annotation| {
+            let (start, end) = annotation.range;
+            let at = |index| position(slice.source, slice.line_start, index, layout.column_unit);
+            let (start_line, start_col) = at(start);
+            let (end_line, end_col) = if end > start {
+                at(end - 1)
+            } else {
+                (start_line, start_col)
+            };
+            let mut place = if (start_line, start_col) == (end_line, end_col) {
+                Translations::fill(
+                    &translations.column_place,
+                    &[("line", &start_line), ("column", &start_col)],
+                )
+            } else if start_line == end_line {
+                Translations::fill(
+                    &translations.columns_place,
+                    &[
+                        ("line", &start_line),
+                        ("start", &start_col),
+                        ("end", &end_col),
+                    ],
+                )
+            } else {
+                Translations::fill(
+                    &translations.lines_place,
+                    &[
+                        ("line", &start_line),
+                        ("column", &start_col),
+                        ("end_line", &end_line),
+                        ("end_column", &end_col),
+                    ],
+                )
+            };
+            if !annotation.label.is_empty() {
+                place.push_str(": ");
+            }
+            let mut label = vec![DisplayTextFragment {
+                content: place.into(),
+                style: DisplayTextStyle::Regular,
+            }];
+            label.extend(truncate_label(
+                format_label(Some(annotation.label), None),
+                layout.max_label_length,
+            ));
+            let line = DisplayLine::Raw(DisplayRawLine::Annotation {
+                annotation: Annotation {
+                    annotation_type: DisplayAnnotationType::from(annotation.annotation_type),
+                    id: None,
+                    label,
+                },
+                source_aligned: true,
+                continuation: false,
+            });
+            (end_line, line)
+        })
+        .collect::<Vec<_>>();
+    prose.sort_by_key(|(end_line, _)| *end_line);
+    prose.into()
+}
+
+/// The rows of the column ruler of `source`, as wide as its widest line.
+fn format_ruler<'a>(source: &str) -> VecDeque<DisplayLine<'a>> {
+    let width = source.split('\n').map(text_width).max().unwrap_or(0);
+    [true, false]
+        .iter()
+        .map(|&tens| DisplayLine::Source {
+            lineno: None,
+            inline_marks: InlineMarks::new(),
+            line: DisplaySourceLine::Ruler { width, tens },
+        })
+        .collect()
+}
+
+/// A single line of the source of a slice.
+struct LineInfo<'a> {
+    text: &'a str,
+    lineno: usize,
+    line_start_index: usize,
+    line_end_index: usize,
+    /// Number of chars of the line ending: 2 for CRLF.
+    ending_len: usize,
+}
+
+impl<'a> LineInfo<'a> {
+    /// The char `index`, or the end of the line if `index` is between the
+    /// `\r` and the `\n` of its CRLF ending.
+    fn clamp(&self, index: usize) -> usize {
+        if index > self.line_end_index && index < self.line_end_index + self.ending_len {
+            self.line_end_index
+        } else {
+            index
+        }
     }
+}
 
-    None
+/// Iterator over the lines of the source of a slice.
+struct SourceLines<'a> {
+    lines: CursorLines<'a>,
+    lineno: usize,
+    current_index: usize,
 }
 
-fn fold_body(mut body: Vec<DisplayLine<'_>>) -> Vec<DisplayLine<'_>> {
-    enum Line {
-        Fold(usize),
-        Source(usize),
+impl<'a> SourceLines<'a> {
+    fn new(source: &'a str, line_start: usize) -> Self {
+        Self {
+            lines: CursorLines::new(source),
+            lineno: line_start,
+            current_index: 0,
+        }
     }
+}
 
-    let mut lines = vec![];
-    let mut no_annotation_lines_counter = 0;
+impl<'a> Iterator for SourceLines<'a> {
+    type Item = LineInfo<'a>;
 
-    for (idx, line) in body.iter().enumerate() {
-        match line {
-            DisplayLine::Source {
-                line: DisplaySourceLine::Annotation { .. },
-                ..
-            } => {
-                let fold_start = idx - no_annotation_lines_counter;
-                if no_annotation_lines_counter > 2 {
-                    let fold_end = idx;
-                    let pre_len = if no_annotation_lines_counter > 8 {
-                        4
-                    } else {
-                        0
-                    };
-                    let post_len = if no_annotation_lines_counter > 8 {
-                        2
-                    } else {
-                        1
-                    };
-                    for (i, _) in body
-                        .iter()
-                        .enumerate()
-                        .take(fold_start + pre_len)
-                        .skip(fold_start)
-                    {
-                        lines.push(Line::Source(i));
-                    }
-                    lines.push(Line::Fold(idx));
-                    for (i, _) in body
+    fn next(&mut self) -> Option<Self::Item> {
+        let (line, end_line) = self.lines.next()?;
+        let line_length = line.chars().count();
+        let info = LineInfo {
+            text: line,
+            lineno: self.lineno,
+            line_start_index: self.current_index,
+            line_end_index: self.current_index + line_length,
+            ending_len: end_line as usize,
+        };
+        self.lineno += 1;
+        self.current_index += line_length + info.ending_len;
+        Some(info)
+    }
+}
+
+/// Appends the source line and the annotation lines placed on it to `rows`.
+///
+/// `active` are the indices in `annotations` of the annotations starting
+/// before the end of the line, in slice order. Marks are added to the last row
+/// of the line, and annotations continuing on the following lines are kept in
+/// `active`. `char_cols` are the columns of `char_columns`. The annotations
+/// with a number in `footnotes` are labeled with it.
+fn format_source_line<'a>(
+    line: LineInfo<'a>,
+    annotations: &[snippet::SourceAnnotation<'a>],
+    active: &mut Vec<usize>,
+    char_cols: &[(usize, usize)],
+    footnotes: &[usize],
+    layout: SliceLayout,
+    rows: &mut VecDeque<DisplayLine<'a>>,
+) {
+    let clamp = |index| line.clamp(index);
+    let LineInfo {
+        text,
+        lineno,
+        line_start_index,
+        line_end_index,
+        ..
+    } = line;
+    let last = char_cols.len() - 1;
+    // Annotations start at the start of the cluster of their first character,
+    // and end at the end of the cluster of their last one
+    let col_of = |index: usize| char_cols[std::cmp::min(index - line_start_index, last)].0;
+    let end_col_of = |index: usize| match index.checked_sub(line_start_index + 1) {
+        Some(last_index) => char_cols[std::cmp::min(last_index, last)].1,
+        None => 0,
+    };
+    rows.push_back(DisplayLine::Source {
+        lineno: Some(lineno),
+        inline_marks: InlineMarks::new(),
+        line: DisplaySourceLine::Content {
+            text: text.into(),
+            range: (line_start_index, line_end_index),
+        },
+    });
+
+    let margin_left = layout
+        .margin
+        .map(|m| m.left(line_end_index - line_start_index))
+        .unwrap_or_default();
+    let push_mark = |rows: &mut VecDeque<DisplayLine<'a>>, mark: DisplayMark| {
+        if let Some(DisplayLine::Source {
+            ref mut inline_marks,
+            ..
+        }) = rows.back_mut()
+        {
+            inline_marks.push(mark);
+        }
+    };
+    active.retain(|&idx| {
+        let annotation = &annotations[idx];
+        let (annotation_type, label) = match footnotes.get(idx) {
+            Some(&number) if number > 0 => (
+                DisplayAnnotationType::None,
+                vec![DisplayTextFragment {
+                    content: format!("[{}]", number).into(),
+                    style: DisplayTextStyle::Regular,
+                }],
+            ),
+            _ => (
+                match annotation.annotation_type {
+                    snippet::AnnotationType::Error => DisplayAnnotationType::None,
+                    snippet::AnnotationType::Warning => DisplayAnnotationType::None,
+                    _ => DisplayAnnotationType::from(annotation.annotation_type),
+                },
+                truncate_label(
+                    format_label(Some(annotation.label), None),
+                    layout.max_label_length,
+                ),
+            ),
+        };
+        let mut label = Some(label);
+        match (clamp(annotation.range.0), clamp(annotation.range.1)) {
+            (start, _) if start > line_end_index => true,
+            (start, end)
+                if start >= line_start_index && end <= line_end_index
+                    || start == line_end_index && end - start <= 1 =>
+            {
+                let annotation_start_col = col_of(start).saturating_sub(margin_left);
+                let annotation_end_col = end_col_of(end).saturating_sub(margin_left);
+                let range = (annotation_start_col, annotation_end_col);
+                // Every cluster of the span is two columns wide
+                let wide_marks = layout.full_width_marks
+                    && start < end
+                    && char_cols[start - line_start_index..end - line_start_index]
                         .iter()
-                        .enumerate()
-                        .take(fold_end)
-                        .skip(fold_end - post_len)
-                    {
-                        lines.push(Line::Source(i));
-                    }
+                        .all(|&(start_col, end_col)| end_col - start_col == 2);
+                rows.push_back(DisplayLine::Source {
+                    lineno: None,
+                    inline_marks: InlineMarks::new(),
+                    line: DisplaySourceLine::Annotation {
+                        annotation: Annotation {
+                            annotation_type,
+                            id: None,
+                            label: label.take().unwrap_or_default(),
+                        },
+                        range,
+                        annotation_type: DisplayAnnotationType::from(annotation.annotation_type),
+                        annotation_part: DisplayAnnotationPart::Standalone,
+                        wide_marks,
+                    },
+                });
+                false
+            }
+            (start, end)
+                if start >= line_start_index && start <= line_end_index && end > line_end_index =>
+            {
+                if start - line_start_index == 0 {
+                    push_mark(
+                        rows,
+                        DisplayMark {
+                            mark_type: DisplayMarkType::AnnotationStart,
+                            annotation_type: DisplayAnnotationType::from(
+                                annotation.annotation_type,
+                            ),
+                        },
+                    );
                 } else {
-                    for (i, _) in body.iter().enumerate().take(idx).skip(fold_start) {
-                        lines.push(Line::Source(i));
-                    }
+                    let annotation_start_col = col_of(start);
+                    let range = (annotation_start_col, annotation_start_col + 1);
+                    rows.push_back(DisplayLine::Source {
+                        lineno: None,
+                        inline_marks: InlineMarks::new(),
+                        line: DisplaySourceLine::Annotation {
+                            annotation: Annotation {
+                                annotation_type: DisplayAnnotationType::None,
+                                id: None,
+                                label: vec![],
+                            },
+                            range,
+                            annotation_type: DisplayAnnotationType::from(
+                                annotation.annotation_type,
+                            ),
+                            annotation_part: DisplayAnnotationPart::MultilineStart,
+                            wide_marks: false,
+                        },
+                    });
                 }
-                no_annotation_lines_counter = 0;
+                true
             }
-            DisplayLine::Source { .. } => {
-                no_annotation_lines_counter += 1;
-                continue;
+            (start, end) if start < line_start_index && end > line_end_index => {
+                push_mark(
+                    rows,
+                    DisplayMark {
+                        mark_type: DisplayMarkType::AnnotationThrough,
+                        annotation_type: DisplayAnnotationType::from(annotation.annotation_type),
+                    },
+                );
+                true
             }
-            _ => {
-                no_annotation_lines_counter += 1;
+            (start, end)
+                if start < line_start_index && end >= line_start_index && end <= line_end_index =>
+            {
+                push_mark(
+                    rows,
+                    DisplayMark {
+                        mark_type: DisplayMarkType::AnnotationThrough,
+                        annotation_type: DisplayAnnotationType::from(annotation.annotation_type),
+                    },
+                );
+
+                let end_mark = end_col_of(end).saturating_sub(1);
+                let range = (
+                    end_mark.saturating_sub(margin_left),
+                    (end_mark + 1).saturating_sub(margin_left),
+                );
+                rows.push_back(DisplayLine::Source {
+                    lineno: None,
+                    inline_marks: smallvec::smallvec![DisplayMark {
+                        mark_type: DisplayMarkType::AnnotationThrough,
+                        annotation_type: DisplayAnnotationType::from(annotation.annotation_type),
+                    }],
+                    line: DisplaySourceLine::Annotation {
+                        annotation: Annotation {
+                            annotation_type,
+                            id: None,
+                            label: label.take().unwrap_or_default(),
+                        },
+                        range,
+                        annotation_type: DisplayAnnotationType::from(annotation.annotation_type),
+                        annotation_part: DisplayAnnotationPart::MultilineEnd,
+                        wide_marks: false,
+                    },
+                });
+                false
+            }
+            // Ended before this line, so it can't be displayed anymore.
+            _ => false,
+        }
+    });
+}
+
+/// Fills `char_cols` with the display columns at which the cluster of each
+/// character of `text` starts and ends, from how many spaces each cluster
+/// before it takes up when displayed, followed by the end of the line.
+///
+/// A cluster is a character followed by the characters displayed with it:
+/// combining marks, variation selectors, emoji modifiers and the characters
+/// joined to it by a zero width joiner. The width of a cluster is the width of
+/// its first character, but emoji sequences, joined or with an emoji
+/// presentation selector, are displayed as a single emoji two columns wide.
+/// With a `tab_width`, tabs take up the columns up to the next tab stop.
+pub(super) fn char_columns(
+    text: &str,
+    escape: EscapeMode,
+    tab_width: Option<usize>,
+    char_cols: &mut Vec<(usize, usize)>,
+) {
+    char_cols.clear();
+    let mut cluster = (0, 0);
+    let mut first = 0;
+    let mut prev = None;
+    for (i, c) in text.chars().enumerate() {
+        if !continues_cluster(prev, c, escape) {
+            let width = match tab_width {
+                Some(tab_width) if c == '\t' => {
+                    let tab_width = tab_width.max(1);
+                    tab_width - cluster.1 % tab_width
+                }
+                _ => escape.width(c).unwrap_or(0),
+            };
+            cluster = (cluster.1, cluster.1 + width);
+            first = i;
+        } else if (prev == Some('\u{200D}') || c == '\u{FE0F}') && cluster.1 - cluster.0 < 2 {
+            cluster.1 = cluster.0 + 2;
+            for cols in &mut char_cols[first..] {
+                cols.1 = cluster.1;
             }
         }
-        lines.push(Line::Source(idx));
+        char_cols.push(cluster);
+        prev = Some(c);
+    }
+    // treat the end of line as signle-width
+    char_cols.push((cluster.1, cluster.1 + 1));
+}
+
+/// The number of columns `text` takes up when displayed, its clusters being
+/// as wide as `char_columns` lays them out.
+pub(crate) fn text_width(text: &str) -> usize {
+    let mut char_cols = vec![];
+    char_columns(text, EscapeMode::PassThrough, None, &mut char_cols);
+    char_cols.last().map_or(0, |cols| cols.0)
+}
+
+/// Returns `true` if `c`, following `prev`, is displayed in the same cluster.
+fn continues_cluster(prev: Option<char>, c: char, escape: EscapeMode) -> bool {
+    match prev {
+        Some(prev) if !escape.substitutes(prev) && !escape.substitutes(c) => {
+            prev == '\u{200D}'
+                || matches!(c, '\u{1F3FB}'..='\u{1F3FF}')
+                || !EscapeMode::is_non_printable(c)
+                    && unicode_width::UnicodeWidthChar::width(c) == Some(0)
+        }
+        _ => false,
+    }
+}
+
+/// Scratch buffers used to lay out the lines of slices.
+///
+/// They are moved from one slice to the next, and can be kept between
+/// snippets to avoid allocating them again.
+#[derive(Default)]
+pub(crate) struct LayoutBuffers {
+    /// Indices of the annotations of the slice, sorted by start.
+    pending: Vec<usize>,
+    /// Indices of the annotations started and still open, in slice order.
+    active: Vec<usize>,
+    char_cols: Vec<(usize, usize)>,
+    /// Footnote number of every annotation of the slice, 0 for none.
+    footnotes: Vec<usize>,
+}
+
+/// Iterator over the source and annotation lines of a slice.
+struct BodyLines<'s, 'a> {
+    lines: SourceLines<'a>,
+    annotations: &'s [snippet::SourceAnnotation<'a>],
+    line_highlights: &'s [snippet::LineHighlight],
+    next_pending: usize,
+    buffers: LayoutBuffers,
+    layout: SliceLayout,
+    rows: VecDeque<DisplayLine<'a>>,
+}
+
+impl<'s, 'a> BodyLines<'s, 'a> {
+    /// With `footnote_base`, the annotations of `footnote_annotations` are
+    /// numbered from the one after it.
+    fn new(
+        slice: &'s snippet::Slice<'a>,
+        layout: SliceLayout,
+        footnote_base: Option<usize>,
+        mut buffers: LayoutBuffers,
+    ) -> Self {
+        let annotations = &slice.annotations[..];
+        buffers.footnotes.clear();
+        if let Some(mut number) = footnote_base {
+            buffers.footnotes.push(0);
+            buffers
+                .footnotes
+                .extend(annotations.iter().skip(1).map(|annotation| {
+                    if annotation.label.is_empty() {
+                        0
+                    } else {
+                        number += 1;
+                        number
+                    }
+                }));
+        }
+        buffers.pending.clear();
+        buffers.pending.extend(0..annotations.len());
+        buffers.pending.sort_by_key(|&idx| annotations[idx].range.0);
+        buffers.active.clear();
+        Self {
+            lines: SourceLines::new(slice.source, slice.line_start),
+            annotations,
+            line_highlights: &slice.line_highlights,
+            next_pending: 0,
+            buffers,
+            layout,
+            rows: VecDeque::new(),
+        }
     }
 
-    let mut new_body = vec![];
-    let mut removed = 0;
-    for line in lines {
-        match line {
-            Line::Source(i) => {
-                new_body.push(body.remove(i - removed));
-                removed += 1;
+    /// Moves the annotations starting before the end of `line` to the active
+    /// ones, keeping them in slice order so that they are displayed in the
+    /// order they were given.
+    fn start_annotations(&mut self, line: &LineInfo<'_>) {
+        let annotations = self.annotations;
+        let starting = self.buffers.pending[self.next_pending..]
+            .iter()
+            .take_while(|&&idx| line.clamp(annotations[idx].range.0) <= line.line_end_index);
+        let active_len = self.buffers.active.len();
+        self.buffers.active.extend(starting);
+        let count = self.buffers.active.len() - active_len;
+        if count > 0 {
+            self.buffers.active.sort_unstable();
+            self.next_pending += count;
+        }
+    }
+}
+
+impl<'s, 'a> Iterator for BodyLines<'s, 'a> {
+    type Item = DisplayLine<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.rows.pop_front() {
+                return Some(row);
             }
-            Line::Fold(i) => {
-                if let DisplayLine::Source {
-                    line: DisplaySourceLine::Annotation { .. },
-                    ref inline_marks,
-                    ..
-                } = body.get(i - removed).unwrap()
-                {
-                    new_body.push(DisplayLine::Fold {
-                        inline_marks: inline_marks.clone(),
-                    })
-                } else {
-                    unreachable!()
-                }
+            let line = self.lines.next()?;
+            let highlight = self
+                .line_highlights
+                .iter()
+                .find(|highlight| highlight.line == line.lineno);
+            self.start_annotations(&line);
+            char_columns(
+                line.text,
+                self.layout.escape,
+                self.layout.tab_width,
+                &mut self.buffers.char_cols,
+            );
+            format_source_line(
+                line,
+                self.annotations,
+                &mut self.buffers.active,
+                &self.buffers.char_cols,
+                &self.buffers.footnotes,
+                self.layout,
+                &mut self.rows,
+            );
+            if let (Some(highlight), Some(DisplayLine::Source { inline_marks, .. })) =
+                (highlight, self.rows.front_mut())
+            {
+                inline_marks.insert(
+                    0,
+                    DisplayMark {
+                        mark_type: DisplayMarkType::LineHighlight {
+                            change: highlight.change,
+                            background: highlight.background,
+                        },
+                        annotation_type: DisplayAnnotationType::None,
+                    },
+                );
             }
         }
     }
+}
 
-    new_body
+/// Folds the runs of source lines without annotations of a `BodyLines`.
+///
+/// Only the first and last lines of a run are kept in memory, as at most those
+/// are displayed around the `Fold` line. Lines after the last annotation are
+/// not displayed.
+///
+/// How many lines of a run are displayed depends on the priorities of the
+/// annotations of the lines around it, as given by `fold_context`.
+struct FoldedLines<'s, 'a> {
+    rows: BodyLines<'s, 'a>,
+    out: VecDeque<DisplayLine<'a>>,
+    run_head: Vec<DisplayLine<'a>>,
+    run_tail: VecDeque<DisplayLine<'a>>,
+    run_len: usize,
+    /// Char range of the last source line of the run.
+    last_range: (usize, usize),
+    /// Priority of the last annotated line, if any.
+    last_priority: Option<snippet::Priority>,
 }
 
-fn format_body(
-    slice: snippet::Slice<'_>,
-    need_empty_header: bool,
-    has_footer: bool,
-    margin: Option<Margin>,
-) -> Vec<DisplayLine<'_>> {
-    let source_len = slice.source.chars().count();
-    if let Some(bigger) = slice.annotations.iter().find_map(|x| {
-        if source_len < x.range.1 {
-            Some(x.range)
+/// Lines of context kept by folds after and before the lines annotated with
+/// the given priority, the annotated line included in the latter.
+fn fold_context(priority: snippet::Priority) -> (usize, usize) {
+    match priority {
+        snippet::Priority::Low => (0, 1),
+        snippet::Priority::Normal => (4, 2),
+        snippet::Priority::High => (8, 5),
+    }
+}
+
+impl<'s, 'a> FoldedLines<'s, 'a> {
+    /// Most lines kept around a fold, and up to which a run is not folded
+    /// next to a high-priority annotation.
+    const RUN_HEAD: usize = 8;
+    const RUN_TAIL: usize = 7;
+
+    fn new(rows: BodyLines<'s, 'a>) -> Self {
+        Self {
+            rows,
+            out: VecDeque::new(),
+            run_head: vec![],
+            run_tail: VecDeque::new(),
+            run_len: 0,
+            last_range: (0, 0),
+            last_priority: None,
+        }
+    }
+
+    fn push_run(&mut self, line: DisplayLine<'a>) {
+        if let DisplayLine::Source {
+            line: DisplaySourceLine::Content { range, .. },
+            ..
+        } = line
+        {
+            self.last_range = range;
+        }
+        self.run_len += 1;
+        if self.run_head.len() < Self::RUN_HEAD {
+            self.run_head.push(line);
         } else {
-            None
+            if self.run_tail.len() == Self::RUN_TAIL {
+                self.run_tail.pop_front();
+            }
+            self.run_tail.push_back(line);
         }
-    }) {
-        panic!(
-            "SourceAnnotation range `{:?}` is bigger than source length `{}`",
-            bigger, source_len
-        )
     }
 
-    let mut body = vec![];
-    let mut current_line = slice.line_start;
-    let mut current_index = 0;
-    let mut line_info = vec![];
+    /// The highest priority of the annotations starting or ending on the last
+    /// source line.
+    fn line_priority(&self) -> snippet::Priority {
+        let (start, end) = self.last_range;
+        let on_line = |offset: usize| start <= offset && offset <= end;
+        self.rows
+            .annotations
+            .iter()
+            .filter(|annotation| on_line(annotation.range.0) || on_line(annotation.range.1))
+            .map(|annotation| annotation.priority)
+            .max()
+            .unwrap_or_default()
+    }
 
-    struct LineInfo {
-        line_start_index: usize,
-        line_end_index: usize,
-        // How many spaces each character in the line take up when displayed
-        char_widths: Vec<usize>,
+    /// Moves the current run to the output, folded in front of an annotation
+    /// line with the given marks.
+    fn flush_run(&mut self, inline_marks: &[DisplayMark]) {
+        let priority = self.line_priority();
+        // Before the first annotation, the run is the start of the slice.
+        let previous = self
+            .last_priority
+            .unwrap_or_else(|| cmp::min(priority, snippet::Priority::Normal));
+        self.last_priority = Some(priority);
+        if self.run_len == 0 {
+            return;
+        }
+
+        let mut run = std::mem::take(&mut self.run_head);
+        run.extend(self.run_tail.drain(..));
+        let (pre_len, _) = fold_context(previous);
+        let (_, post_len) = fold_context(priority);
+        let high = cmp::max(previous, priority) == snippet::Priority::High;
+        let lens = if self.run_len > pre_len + post_len + 2 {
+            Some((pre_len, post_len))
+        } else if self.run_len > 2 && !high {
+            Some((0, 1))
+        } else {
+            None
+        };
+        if let Some((pre_len, post_len)) = lens {
+            let post_start = run.len() - post_len;
+            let mut run = run.into_iter();
+            self.out.extend(run.by_ref().take(pre_len));
+            self.out.push_back(DisplayLine::Fold {
+                inline_marks: inline_marks.into(),
+            });
+            self.out.extend(run.skip(post_start - pre_len));
+        } else {
+            self.out.extend(run);
+        }
+        self.run_len = 0;
     }
+}
 
-    for (line, end_line) in CursorLines::new(slice.source) {
-        let line_length = line.chars().count();
-        let line_range = (current_index, current_index + line_length);
-        let char_widths = line
-            .chars()
-            .map(|c| unicode_width::UnicodeWidthChar::width(c).unwrap_or(0))
-            .chain(std::iter::once(1)) // treat the end of line as signle-width
-            .collect::<Vec<_>>();
-        body.push(DisplayLine::Source {
-            lineno: Some(current_line),
-            inline_marks: vec![],
-            line: DisplaySourceLine::Content {
-                text: line,
-                range: line_range,
-            },
-        });
-        line_info.push(LineInfo {
-            line_start_index: line_range.0,
-            line_end_index: line_range.1,
-            char_widths,
-        });
-        current_line += 1;
-        current_index += line_length + end_line as usize;
-    }
-
-    let mut annotation_line_count = 0;
-    let mut annotations = slice.annotations;
-    for (
-        idx,
-        LineInfo {
-            line_start_index,
-            line_end_index,
-            char_widths,
-        },
-    ) in line_info.into_iter().enumerate()
-    {
-        let margin_left = margin
-            .map(|m| m.left(line_end_index - line_start_index))
-            .unwrap_or_default();
-        // It would be nice to use filter_drain here once it's stable.
-        annotations = annotations
-            .into_iter()
-            .filter(|annotation| {
-                let body_idx = idx + annotation_line_count;
-                let annotation_type = match annotation.annotation_type {
-                    snippet::AnnotationType::Error => DisplayAnnotationType::None,
-                    snippet::AnnotationType::Warning => DisplayAnnotationType::None,
-                    _ => DisplayAnnotationType::from(annotation.annotation_type),
-                };
-                match annotation.range {
-                    (start, _) if start > line_end_index => true,
-                    (start, end)
-                        if start >= line_start_index && end <= line_end_index
-                            || start == line_end_index && end - start <= 1 =>
-                    {
-                        let annotation_start_col = char_widths
-                            .iter()
-                            .take(start - line_start_index)
-                            .sum::<usize>()
-                            - margin_left;
-                        let annotation_end_col = char_widths
-                            .iter()
-                            .take(end - line_start_index)
-                            .sum::<usize>()
-                            - margin_left;
-                        let range = (annotation_start_col, annotation_end_col);
-                        body.insert(
-                            body_idx + 1,
-                            DisplayLine::Source {
-                                lineno: None,
-                                inline_marks: vec![],
-                                line: DisplaySourceLine::Annotation {
-                                    annotation: Annotation {
-                                        annotation_type,
-                                        id: None,
-                                        label: format_label(Some(annotation.label), None),
-                                    },
-                                    range,
-                                    annotation_type: DisplayAnnotationType::from(
-                                        annotation.annotation_type,
-                                    ),
-                                    annotation_part: DisplayAnnotationPart::Standalone,
-                                },
-                            },
-                        );
-                        annotation_line_count += 1;
-                        false
+impl<'s, 'a> Iterator for FoldedLines<'s, 'a> {
+    type Item = DisplayLine<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(line) = self.out.pop_front() {
+                return Some(line);
+            }
+            match self.rows.next()? {
+                line @ DisplayLine::Source {
+                    line: DisplaySourceLine::Annotation { .. },
+                    ..
+                } => {
+                    if let DisplayLine::Source { inline_marks, .. } = &line {
+                        self.flush_run(inline_marks);
                     }
-                    (start, end)
-                        if start >= line_start_index
-                            && start <= line_end_index
-                            && end > line_end_index =>
-                    {
-                        if start - line_start_index == 0 {
-                            if let DisplayLine::Source {
-                                ref mut inline_marks,
-                                ..
-                            } = body[body_idx]
-                            {
-                                inline_marks.push(DisplayMark {
-                                    mark_type: DisplayMarkType::AnnotationStart,
-                                    annotation_type: DisplayAnnotationType::from(
-                                        annotation.annotation_type,
-                                    ),
-                                });
-                            }
-                        } else {
-                            let annotation_start_col = char_widths
+                    self.out.push_back(line);
+                }
+                line if is_highlighted(&line) => {
+                    let mut marks = InlineMarks::new();
+                    if let DisplayLine::Source { inline_marks, .. } = &line {
+                        marks.extend(
+                            inline_marks
                                 .iter()
-                                .take(start - line_start_index)
-                                .sum::<usize>();
-                            let range = (annotation_start_col, annotation_start_col + 1);
-                            body.insert(
-                                body_idx + 1,
-                                DisplayLine::Source {
-                                    lineno: None,
-                                    inline_marks: vec![],
-                                    line: DisplaySourceLine::Annotation {
-                                        annotation: Annotation {
-                                            annotation_type: DisplayAnnotationType::None,
-                                            id: None,
-                                            label: vec![],
-                                        },
-                                        range,
-                                        annotation_type: DisplayAnnotationType::from(
-                                            annotation.annotation_type,
-                                        ),
-                                        annotation_part: DisplayAnnotationPart::MultilineStart,
-                                    },
-                                },
-                            );
-                            annotation_line_count += 1;
-                        }
-                        true
-                    }
-                    (start, end) if start < line_start_index && end > line_end_index => {
-                        if let DisplayLine::Source {
-                            ref mut inline_marks,
-                            ..
-                        } = body[body_idx]
-                        {
-                            inline_marks.push(DisplayMark {
-                                mark_type: DisplayMarkType::AnnotationThrough,
-                                annotation_type: DisplayAnnotationType::from(
-                                    annotation.annotation_type,
-                                ),
-                            });
-                        }
-                        true
-                    }
-                    (start, end)
-                        if start < line_start_index
-                            && end >= line_start_index
-                            && end <= line_end_index =>
-                    {
-                        if let DisplayLine::Source {
-                            ref mut inline_marks,
-                            ..
-                        } = body[body_idx]
-                        {
-                            inline_marks.push(DisplayMark {
-                                mark_type: DisplayMarkType::AnnotationThrough,
-                                annotation_type: DisplayAnnotationType::from(
-                                    annotation.annotation_type,
-                                ),
-                            });
-                        }
-
-                        let end_mark = char_widths
-                            .iter()
-                            .take(end - line_start_index)
-                            .sum::<usize>()
-                            .saturating_sub(1);
-                        let range = (end_mark - margin_left, (end_mark + 1) - margin_left);
-                        body.insert(
-                            body_idx + 1,
-                            DisplayLine::Source {
-                                lineno: None,
-                                inline_marks: vec![DisplayMark {
-                                    mark_type: DisplayMarkType::AnnotationThrough,
-                                    annotation_type: DisplayAnnotationType::from(
-                                        annotation.annotation_type,
-                                    ),
-                                }],
-                                line: DisplaySourceLine::Annotation {
-                                    annotation: Annotation {
-                                        annotation_type,
-                                        id: None,
-                                        label: format_label(Some(annotation.label), None),
-                                    },
-                                    range,
-                                    annotation_type: DisplayAnnotationType::from(
-                                        annotation.annotation_type,
-                                    ),
-                                    annotation_part: DisplayAnnotationPart::MultilineEnd,
-                                },
-                            },
+                                .filter(|mark| {
+                                    !matches!(mark.mark_type, DisplayMarkType::LineHighlight { .. })
+                                })
+                                .cloned(),
                         );
-                        annotation_line_count += 1;
-                        false
                     }
-                    _ => true,
+                    self.push_run(line);
+                    self.flush_run(&marks);
                 }
-            })
-            .collect();
+                line => self.push_run(line),
+            }
+        }
     }
+}
 
-    if slice.fold {
-        body = fold_body(body);
+enum Body<'s, 'a> {
+    Unfolded(BodyLines<'s, 'a>),
+    Folded(FoldedLines<'s, 'a>),
+}
+
+impl<'s, 'a> Body<'s, 'a> {
+    fn into_buffers(self) -> LayoutBuffers {
+        match self {
+            Body::Unfolded(lines) => lines.buffers,
+            Body::Folded(lines) => lines.rows.buffers,
+        }
     }
+}
 
-    if need_empty_header {
-        body.insert(
-            0,
-            DisplayLine::Source {
-                lineno: None,
-                inline_marks: vec![],
-                line: DisplaySourceLine::Empty,
-            },
-        );
+impl<'s, 'a> Iterator for Body<'s, 'a> {
+    type Item = DisplayLine<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Body::Unfolded(lines) => lines.next(),
+            Body::Folded(lines) => lines.next(),
+        }
     }
+}
 
-    if has_footer {
-        body.push(DisplayLine::Source {
-            lineno: None,
-            inline_marks: vec![],
-            line: DisplaySourceLine::Empty,
-        });
-    } else if let Some(DisplayLine::Source { .. }) = body.last() {
-        body.push(DisplayLine::Source {
-            lineno: None,
-            inline_marks: vec![],
-            line: DisplaySourceLine::Empty,
-        });
+/// Iterator over the lines of a slice: its header, and its body framed by
+/// empty source lines.
+struct SliceLines<'s, 'a> {
+    header: Option<DisplayLine<'a>>,
+    /// The line of the note about the origin, following the header.
+    origin_note: Option<DisplayLine<'a>>,
+    /// Whether the header follows the body rather than preceding it.
+    header_below: bool,
+    need_empty_header: bool,
+    /// The rows of the column ruler not displayed yet.
+    ruler: VecDeque<DisplayLine<'a>>,
+    body: Body<'s, 'a>,
+    /// Whether single-line annotations are moved into their source lines.
+    compact: bool,
+    /// Line of the body read ahead of the current one.
+    peeked: Option<DisplayLine<'a>>,
+    /// Annotation lines read ahead and not moved into their source line.
+    pending: VecDeque<DisplayLine<'a>>,
+    /// Whether the annotations are written as prose rather than drawn.
+    screen_reader: bool,
+    /// The prose of the annotations not written yet, with the lines they
+    /// end on, in order.
+    prose: VecDeque<(usize, DisplayLine<'a>)>,
+    /// Number of the last source line written.
+    prose_line: usize,
+    need_trailer: bool,
+    has_footer: bool,
+    last_is_source: bool,
+}
+
+impl<'s, 'a> SliceLines<'s, 'a> {
+    fn new(
+        slice: &'s snippet::Slice<'a>,
+        opt: &FormatOptions,
+        is_first: bool,
+        has_footer: bool,
+        layout: SliceLayout,
+        footnote_base: Option<usize>,
+        buffers: LayoutBuffers,
+    ) -> Self {
+        let source_len = slice.source.chars().count();
+        if let Some(bigger) = slice.annotations.iter().find_map(|x| {
+            if source_len < x.range.1 {
+                Some(x.range)
+            } else {
+                None
+            }
+        }) {
+            panic!(
+                "SourceAnnotation range `{:?}` is bigger than source length `{}`",
+                bigger, source_len
+            )
+        }
+
+        let main_range = slice.annotations.first().map(|x| x.range.0);
+        let header = format_header(
+            slice.origin.map(|origin| opt.normalize_path(origin)),
+            main_range,
+            slice.source,
+            slice.line_start,
+            is_first,
+            layout.column_unit,
+            layout.header_position,
+        );
+        let lines = BodyLines::new(slice, layout, footnote_base, buffers);
+        let body = if slice.fold || opt.fold {
+            Body::Folded(FoldedLines::new(lines))
+        } else {
+            Body::Unfolded(lines)
+        };
+        let origin_note = match (&header, slice.origin_note) {
+            (Some(_), Some(note)) => Some(format_origin_note(note)),
+            _ => None,
+        };
+        Self {
+            header,
+            origin_note,
+            header_below: layout.header_placement == HeaderPlacement::Below,
+            need_empty_header: !layout.vertical_compact && (slice.origin.is_some() || is_first),
+            ruler: if layout.column_ruler {
+                format_ruler(slice.source)
+            } else {
+                VecDeque::new()
+            },
+            body,
+            compact: layout.compact_annotations && !layout.screen_reader,
+            peeked: None,
+            pending: VecDeque::new(),
+            screen_reader: layout.screen_reader,
+            prose: if layout.screen_reader {
+                format_prose(slice, layout, opt.translations.as_ref())
+            } else {
+                VecDeque::new()
+            },
+            prose_line: 0,
+            need_trailer: !layout.vertical_compact,
+            has_footer,
+            last_is_source: false,
+        }
     }
-    body
 }
 
-impl<'a> From<snippet::Snippet<'a>> for DisplayList<'a> {
-    fn from(
-        snippet::Snippet {
-            title,
-            footer,
-            slices,
-            opt,
-        }: snippet::Snippet<'a>,
-    ) -> DisplayList<'a> {
-        let mut body = vec![];
-        if let Some(annotation) = title {
-            body.push(format_title(annotation));
-        }
-
-        for (idx, slice) in slices.into_iter().enumerate() {
-            body.append(&mut format_slice(
-                slice,
-                idx == 0,
-                !footer.is_empty(),
-                opt.margin,
-            ));
+impl<'s, 'a> SliceLines<'s, 'a> {
+    fn into_buffers(self) -> LayoutBuffers {
+        self.body.into_buffers()
+    }
+
+    /// The next line of the body, in the screen reader mode without the
+    /// annotation lines and marks, and followed by the prose of the
+    /// annotations ending on it.
+    fn next_linear_line(&mut self) -> Option<DisplayLine<'a>> {
+        if !self.screen_reader {
+            return self.next_body_line();
+        }
+        loop {
+            if let Some((end, _)) = self.prose.front() {
+                if *end <= self.prose_line {
+                    return self.prose.pop_front().map(|(_, line)| line);
+                }
+            }
+            let mut line = match self.next_body_line() {
+                Some(line) => line,
+                None if self.prose.is_empty() => return None,
+                None => {
+                    self.prose_line = usize::MAX;
+                    continue;
+                }
+            };
+            match &mut line {
+                DisplayLine::Source {
+                    line: DisplaySourceLine::Annotation { .. },
+                    ..
+                } => continue,
+                DisplayLine::Source {
+                    lineno,
+                    inline_marks,
+                    ..
+                } => {
+                    inline_marks.retain(|mark| {
+                        matches!(mark.mark_type, DisplayMarkType::LineHighlight { .. })
+                    });
+                    if let Some(lineno) = lineno {
+                        self.prose_line = *lineno;
+                    }
+                }
+                DisplayLine::Fold { inline_marks } | DisplayLine::Elided { inline_marks, .. } => {
+                    inline_marks.clear()
+                }
+                DisplayLine::Raw(_) => {}
+            }
+            return Some(line);
         }
+    }
+
+    /// The next line of the body, in the compact mode with the following
+    /// annotation lines moved into it if they are all single-line ones.
+    fn next_body_line(&mut self) -> Option<DisplayLine<'a>> {
+        if let Some(line) = self.pending.pop_front() {
+            return Some(line);
+        }
+        let line = self.peeked.take().or_else(|| self.body.next())?;
+        let (lineno, mut inline_marks, text, range) = match line {
+            DisplayLine::Source {
+                lineno,
+                inline_marks,
+                line: DisplaySourceLine::Content { text, range },
+            } if self.compact => (lineno, inline_marks, text, range),
+            line => return Some(line),
+        };
+
+        let mut annotations = vec![];
+        let mut single_line = true;
+        loop {
+            match self.peeked.take().or_else(|| self.body.next()) {
+                Some(
+                    line @ DisplayLine::Source {
+                        line: DisplaySourceLine::Annotation { .. },
+                        ..
+                    },
+                ) => {
+                    if let DisplayLine::Source {
+                        line:
+                            DisplaySourceLine::Annotation {
+                                annotation,
+                                range,
+                                annotation_type,
+                                annotation_part: DisplayAnnotationPart::Standalone,
+                                ..
+                            },
+                        ..
+                    } = &line
+                    {
+                        annotations.push(DisplayTrailingAnnotation {
+                            annotation: annotation.clone(),
+                            range: *range,
+                            annotation_type: annotation_type.clone(),
+                        });
+                    } else {
+                        single_line = false;
+                    }
+                    self.pending.push_back(line);
+                }
+                line => {
+                    self.peeked = line;
+                    break;
+                }
+            }
+        }
+        let line = if single_line && !annotations.is_empty() {
+            // The marks of multiline annotations going through the line are
+            // on its annotation lines.
+            for line in self.pending.drain(..) {
+                if let DisplayLine::Source {
+                    inline_marks: marks,
+                    ..
+                } = line
+                {
+                    for mark in marks {
+                        if !inline_marks.contains(&mark) {
+                            inline_marks.push(mark);
+                        }
+                    }
+                }
+            }
+            DisplaySourceLine::Compact {
+                text,
+                range,
+                annotations,
+            }
+        } else {
+            DisplaySourceLine::Content { text, range }
+        };
+        Some(DisplayLine::Source {
+            lineno,
+            inline_marks,
+            line,
+        })
+    }
+}
+
+impl<'s, 'a> Iterator for SliceLines<'s, 'a> {
+    type Item = DisplayLine<'a>;
 
-        for annotation in footer {
-            body.append(&mut format_annotation(annotation));
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.header_below {
+            if let Some(header) = self.header.take().or_else(|| self.origin_note.take()) {
+                return Some(header);
+            }
+        }
+        let line = if self.need_empty_header {
+            self.need_empty_header = false;
+            Some(empty_source_line())
+        } else if let Some(ruler) = self.ruler.pop_front() {
+            Some(ruler)
+        } else {
+            self.next_linear_line()
+        };
+        if let Some(line) = line {
+            self.last_is_source = matches!(line, DisplayLine::Source { .. });
+            return Some(line);
+        }
+        if self.need_trailer && (self.has_footer || self.last_is_source) {
+            self.need_trailer = false;
+            return Some(empty_source_line());
         }
+        self.header.take().or_else(|| self.origin_note.take())
+    }
+}
 
+impl<'a> DisplayList<'a> {
+    /// Creates a list of the given lines, displayed according to `opt`.
+    pub(crate) fn with_options(body: Vec<DisplayLine<'a>>, opt: FormatOptions) -> Self {
         let FormatOptions {
             color,
             anonymized_line_numbers,
             margin,
+            icons,
+            markers,
+            theme,
+            fold: _,
+            indent,
+            translations,
+            footnotes: _,
+            bidi,
+            escape,
+            column_unit: _,
+            header_position: _,
+            header_placement: _,
+            full_width_marks: _,
+            tab_width,
+            max_width,
+            assert_max_width,
+            path_normalizer: _,
+            remap_path_prefix: _,
+            base_dir: _,
+            max_body_lines: _,
+            gutter_badges,
+            compact_annotations: _,
+            vertical_compact: _,
+            max_label_length: _,
+            screen_reader: _,
+            column_ruler: _,
         } = opt;
 
         Self {
             body,
-            stylesheet: get_term_style(color),
+            stylesheet: get_theme_style(color, theme.as_ref()),
             anonymized_line_numbers,
             margin,
+            highlighter: None,
+            icons,
+            markers,
+            indent,
+            translations,
+            bidi,
+            escape,
+            tab_width,
+            max_width,
+            assert_max_width,
+            gutter_badges,
+        }
+    }
+}
+
+impl<'a> DisplayList<'a> {
+    /// Creates a list of the given lines of `snippet`, displayed according to
+    /// `opt` with the margin they are laid out with.
+    pub(crate) fn for_snippet(
+        body: Vec<DisplayLine<'a>>,
+        snippet: &snippet::Snippet<'_>,
+        opt: FormatOptions,
+    ) -> Self {
+        let margin = fitted_margin(snippet, &opt);
+        Self {
+            margin,
+            ..Self::with_options(body, opt)
+        }
+    }
+}
+
+impl<'a> DisplayList<'a> {
+    /// Converts a snippet, returning an error instead of panicking when it is
+    /// not valid.
+    ///
+    /// This is not `TryFrom`, which the infallible `From` already provides.
+    pub fn try_from_snippet(
+        snippet: snippet::Snippet<'a>,
+    ) -> Result<DisplayList<'a>, snippet::SnippetError> {
+        snippet.validate()?;
+        Ok(Self::from(snippet))
+    }
+}
+
+impl<'a> From<snippet::Snippet<'a>> for DisplayList<'a> {
+    fn from(snippet: snippet::Snippet<'a>) -> DisplayList<'a> {
+        let body = display_lines(&snippet).collect();
+        let margin = fitted_margin(&snippet, &snippet.opt);
+        Self {
+            margin,
+            ..Self::with_options(body, snippet.opt)
         }
     }
 }
@@ -30,8 +30,40 @@
 //! only calculated by the implementation of `std::fmt::Display` using information such as
 //! styling.
 //!
+//! A `DisplayList` may also be built or edited line by line before being
+//! displayed, to show lines which a `Snippet` can't describe:
+//!
+//! ```
+//! use annotate_snippets::display_list::{DisplayAnnotationType, DisplayLine, DisplayList};
+//! use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+//!
+//! let mut dl = DisplayList::from(Snippet::new().title(Annotation {
+//!     id: None,
+//!     label: Some("unused import"),
+//!     annotation_type: AnnotationType::Warning,
+//! }));
+//! dl.push(DisplayLine::annotation(
+//!     DisplayAnnotationType::Note,
+//!     "run with `--fix` to remove it",
+//! ));
+//! assert_eq!(dl.len(), 2);
+//! assert_eq!(
+//!     dl.to_string(),
+//!     "warning: unused import\n = note: run with `--fix` to remove it"
+//! );
+//! ```
+//!
 //! The above snippet has been built out of the following structure:
+#[cfg(feature = "diff")]
+mod from_diff;
 mod from_snippet;
+#[cfg(feature = "emitter")]
+pub(crate) use self::from_snippet::slice_span;
+pub(crate) use self::from_snippet::{display_lines, text_width, LayoutBuffers, SnippetLines};
+mod pass;
 mod structs;
+mod to_snippet;
 
+pub use self::pass::Pass;
 pub use self::structs::*;
+pub use self::to_snippet::{OwnedAnnotation, OwnedSlice, OwnedSnippet, OwnedSourceAnnotation};
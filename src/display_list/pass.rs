@@ -0,0 +1,48 @@
+//! Transformations of a `DisplayList` between its construction and its
+//! rendering.
+//!
+//! A `Pass` can rewrite the lines of a list, for example to redact parts of
+//! the source, add marks or fold lines differently. Closures taking the list
+//! are passes.
+use super::DisplayList;
+
+/// Trait implemented by the transformations of a `DisplayList`.
+///
+/// ```
+/// use annotate_snippets::display_list::{DisplayLine, DisplayList, DisplaySourceLine};
+/// use annotate_snippets::snippet::{Slice, Snippet};
+///
+/// let snippet = Snippet::new().slice(Slice::new("let token = \"hunter2\";", 1));
+///
+/// let mut dl = DisplayList::from(snippet);
+/// dl.apply(&|dl: &mut DisplayList<'_>| {
+///     for line in &mut dl.body {
+///         if let DisplayLine::Source {
+///             line: DisplaySourceLine::Content { text, .. },
+///             ..
+///         } = line
+///         {
+///             *text = "<redacted>".into();
+///         }
+///     }
+/// });
+/// assert_eq!(dl.to_string(), "  |\n1 | <redacted>\n  |");
+/// ```
+pub trait Pass {
+    /// Transforms the list in place.
+    fn run(&self, dl: &mut DisplayList<'_>);
+}
+
+impl<F: Fn(&mut DisplayList<'_>)> Pass for F {
+    fn run(&self, dl: &mut DisplayList<'_>) {
+        self(dl)
+    }
+}
+
+impl<'a> DisplayList<'a> {
+    /// Runs the pass on the list.
+    pub fn apply<P: Pass + ?Sized>(&mut self, pass: &P) -> &mut Self {
+        pass.run(self);
+        self
+    }
+}
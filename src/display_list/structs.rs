@@ -1,14 +1,56 @@
+use std::borrow::Cow;
 use std::cmp::{max, min};
 use std::fmt;
+use std::iter::FromIterator;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
 
-use crate::formatter::{get_term_style, style::Stylesheet};
+use smallvec::SmallVec;
+
+use crate::formatter::{
+    get_term_style,
+    highlight::Highlighter,
+    style::{Color, Stylesheet},
+};
+use crate::snippet::LineChange;
+use crate::stylesheets::theme::Theme;
 
 /// List of lines to be displayed.
+///
+/// With the `serde` feature, a list can be serialized once laid out, and
+/// rendered by another process with its own stylesheet. The stylesheet, the
+/// highlighter and the gutter badges are not serialized: a deserialized list
+/// is displayed without color until they are set again. The strings of the
+/// list are borrowed from the serialized data when the deserializer allows
+/// it, and the ids of the annotations always are.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DisplayList<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub body: Vec<DisplayLine<'a>>,
+    #[cfg_attr(feature = "serde", serde(skip, default = "no_color_stylesheet"))]
     pub stylesheet: Box<dyn Stylesheet>,
     pub anonymized_line_numbers: bool,
     pub margin: Option<Margin>,
+    /// Provider of syntax highlighting for the source lines.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub highlighter: Option<Box<dyn Highlighter>>,
+    pub icons: Option<SeverityIcons>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub markers: MarkerSet,
+    pub indent: usize,
+    pub translations: Option<Translations>,
+    pub bidi: BidiMode,
+    pub escape: EscapeMode,
+    pub tab_width: Option<usize>,
+    pub max_width: Option<usize>,
+    pub assert_max_width: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub gutter_badges: Option<GutterBadges>,
+}
+
+#[cfg(feature = "serde")]
+fn no_color_stylesheet() -> Box<dyn Stylesheet> {
+    get_term_style(false)
 }
 
 impl<'a> From<Vec<DisplayLine<'a>>> for DisplayList<'a> {
@@ -18,13 +60,157 @@ impl<'a> From<Vec<DisplayLine<'a>>> for DisplayList<'a> {
             anonymized_line_numbers: false,
             stylesheet: get_term_style(false),
             margin: None,
+            highlighter: None,
+            icons: None,
+            markers: MarkerSet::default(),
+            indent: 0,
+            translations: None,
+            bidi: BidiMode::Preserve,
+            escape: EscapeMode::PassThrough,
+            tab_width: None,
+            max_width: None,
+            assert_max_width: false,
+            gutter_badges: None,
+        }
+    }
+}
+
+/// A place of a `DisplayList` where `DisplayList::insert_raw` adds a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertPoint {
+    /// Before the header of the first slice, after the title.
+    BeforeHeader,
+    /// After the lines of the last slice, before the footers.
+    AfterBody,
+    /// After the source line with the given line number and its annotations.
+    AfterSourceLine(usize),
+}
+
+impl<'a> DisplayList<'a> {
+    /// Creates an empty list, displayed without color.
+    pub fn new() -> Self {
+        Self::from(vec![])
+    }
+
+    /// Appends a line.
+    pub fn push(&mut self, line: DisplayLine<'a>) -> &mut Self {
+        self.body.push(line);
+        self
+    }
+
+    /// Inserts a line before the line at `index`.
+    ///
+    /// Panics if `index` is greater than the number of lines.
+    pub fn insert(&mut self, index: usize, line: DisplayLine<'a>) -> &mut Self {
+        self.body.insert(index, line);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.body.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.body.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, DisplayLine<'a>> {
+        self.body.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, DisplayLine<'a>> {
+        self.body.iter_mut()
+    }
+
+    /// Inserts a raw line at `point`, returning `false` without inserting it
+    /// if the list has no such place, like a list without slices.
+    pub fn insert_raw(&mut self, point: InsertPoint, line: DisplayRawLine<'a>) -> bool {
+        let is_source = |line: &DisplayLine<'_>| matches!(line, DisplayLine::Source { .. });
+        let index = match point {
+            InsertPoint::BeforeHeader => self.body.iter().position(|line| {
+                is_source(line) || matches!(line, DisplayLine::Raw(DisplayRawLine::Origin { .. }))
+            }),
+            InsertPoint::AfterBody => self.body.iter().rposition(is_source).map(|index| index + 1),
+            InsertPoint::AfterSourceLine(number) => self
+                .body
+                .iter()
+                .position(|line| {
+                    matches!(
+                        line,
+                        DisplayLine::Source {
+                            lineno: Some(lineno),
+                            line: DisplaySourceLine::Content { .. } | DisplaySourceLine::Compact { .. },
+                            ..
+                        } if *lineno == number
+                    )
+                })
+                .map(|index| {
+                    let annotations = self.body[index + 1..]
+                        .iter()
+                        .take_while(|line| {
+                            matches!(
+                                line,
+                                DisplayLine::Source {
+                                    line: DisplaySourceLine::Annotation { .. },
+                                    ..
+                                }
+                            )
+                        })
+                        .count();
+                    index + 1 + annotations
+                }),
+        };
+        match index {
+            Some(index) => {
+                self.body.insert(index, DisplayLine::Raw(line));
+                true
+            }
+            None => false,
         }
     }
 }
 
+impl<'a> Default for DisplayList<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Extend<DisplayLine<'a>> for DisplayList<'a> {
+    fn extend<I: IntoIterator<Item = DisplayLine<'a>>>(&mut self, iter: I) {
+        self.body.extend(iter);
+    }
+}
+
+impl<'a> FromIterator<DisplayLine<'a>> for DisplayList<'a> {
+    fn from_iter<I: IntoIterator<Item = DisplayLine<'a>>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<'a> IntoIterator for DisplayList<'a> {
+    type Item = DisplayLine<'a>;
+    type IntoIter = std::vec::IntoIter<DisplayLine<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.body.into_iter()
+    }
+}
+
+impl<'l, 'a> IntoIterator for &'l DisplayList<'a> {
+    type Item = &'l DisplayLine<'a>;
+    type IntoIter = std::slice::Iter<'l, DisplayLine<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.body.iter()
+    }
+}
+
 impl<'a> PartialEq for DisplayList<'a> {
     fn eq(&self, other: &Self) -> bool {
-        self.body == other.body && self.anonymized_line_numbers == other.anonymized_line_numbers
+        self.body == other.body
+            && self.anonymized_line_numbers == other.anonymized_line_numbers
+            && self.indent == other.indent
     }
 }
 
@@ -33,18 +219,840 @@ impl<'a> fmt::Debug for DisplayList<'a> {
         f.debug_struct("DisplayList")
             .field("body", &self.body)
             .field("anonymized_line_numbers", &self.anonymized_line_numbers)
+            .field("indent", &self.indent)
             .finish()
     }
 }
 
-#[derive(Debug, Default, Copy, Clone)]
+/// Options of the rendering of a snippet.
+///
+/// Options are built from their defaults with a method per field, so that
+/// adding options doesn't break them:
+///
+/// ```
+/// use annotate_snippets::display_list::{FormatOptions, SeverityIcons};
+///
+/// let opt = FormatOptions::new()
+///     .color(true)
+///     .anonymized_line_numbers(true)
+///     .icons(SeverityIcons::default());
+/// assert!(opt.color && opt.icons.is_some());
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub struct FormatOptions {
     pub color: bool,
     pub anonymized_line_numbers: bool,
     pub margin: Option<Margin>,
+    /// If set, titles are prefixed with the icon of their severity.
+    pub icons: Option<SeverityIcons>,
+    /// The marks underlining the spans of source annotations.
+    pub markers: MarkerSet,
+    /// If set with `color`, the styles of the output are the ones of the
+    /// theme over the default ones, rather than the ones of the theme of the
+    /// environment.
+    pub theme: Option<Theme>,
+    /// If set, every slice is folded, as if its `fold` was set.
+    pub fold: bool,
+    /// Number of spaces every rendered line is indented by.
+    pub indent: usize,
+    /// If set, replaces the English names of the annotation types and the
+    /// fold marker.
+    pub translations: Option<Translations>,
+    /// If set, the labels of the annotations of a slice but its first are
+    /// replaced by numbers, and listed as footnotes after the slices.
+    pub footnotes: bool,
+    /// How the bidirectional formatting characters of the sources are
+    /// displayed.
+    pub bidi: BidiMode,
+    /// How the non-printable characters of the sources are displayed.
+    pub escape: EscapeMode,
+    /// What the columns of the positions of the headers count.
+    pub column_unit: ColumnUnit,
+    /// How the headers display the position of the first annotation.
+    pub header_position: HeaderPosition,
+    /// Whether the title and the headers are displayed above the slices or
+    /// below them.
+    pub header_placement: HeaderPlacement,
+    /// If set, spans of wide characters, like CJK ones, are underlined with
+    /// full-width marks, one per character, rather than with two marks per
+    /// character.
+    pub full_width_marks: bool,
+    /// If set, the tabs of the sources are written as they are, and
+    /// annotations are laid out for the tab stops of terminals, every
+    /// `tab_width` columns. The source lines are then padded to start at a
+    /// tab stop.
+    pub tab_width: Option<usize>,
+    /// If set, no rendered line is wider than `max_width` columns: without
+    /// `margin`, source lines are cut around the annotated spans, as with a
+    /// margin, and trimmed, ending with `...`. Labels are wrapped on lines
+    /// continuing them below the gutter, and headers and footers below their
+    /// text.
+    pub max_width: Option<usize>,
+    /// If set with `max_width`, rendering panics on a line wider than
+    /// `max_width` in debug builds, rather than trimming or wrapping it, so
+    /// tests catch the outputs which rely on it.
+    pub assert_max_width: bool,
+    /// If set, transforms the origins of the slices and the paths of the
+    /// include frames before they are displayed, in the headers and by the
+    /// emitters.
+    pub path_normalizer: Option<PathNormalizer>,
+    /// Pairs of a prefix and its replacement, like the `from=to` of the
+    /// `--remap-path-prefix` of rustc: a path starting with a `from`, up to
+    /// a separator, has it replaced with its `to`, the last matching pair
+    /// winning. Remapping happens before the path normalizer.
+    pub remap_path_prefix: Vec<(String, String)>,
+    /// If set, absolute paths are displayed relative to `base_dir`, with
+    /// `..` for the directories of the base they are not in, after the
+    /// remappings. Paths sharing no directory with the base, like the ones of
+    /// the standard library in a toolchain, stay absolute.
+    pub base_dir: Option<PathBuf>,
+    /// If set, at most `max_body_lines` source, annotation and fold lines of
+    /// the slices are displayed: the annotations and the lines they annotate,
+    /// then the lines closest to them. The other lines are replaced with
+    /// `DisplayLine::Elided` markers like `… 12 lines omitted`.
+    pub max_body_lines: Option<usize>,
+    /// If set, gives the badges displayed before the numbers of the source
+    /// lines.
+    pub gutter_badges: Option<GutterBadges>,
+    /// If set, the single-line annotations of a source line are written
+    /// after it, as a trailing comment like `  // ^ label`, rather than
+    /// underneath it, unless the line has a multiline annotation.
+    pub compact_annotations: bool,
+    /// If set, the empty lines padding the slices, like the `|` line after
+    /// the origin of a slice and the one closing it, are left out.
+    pub vertical_compact: bool,
+    /// If set, the labels of the source annotations longer than
+    /// `max_label_length` characters are cut, and end with `…`. Emitters
+    /// still report the whole labels.
+    pub max_label_length: Option<usize>,
+    /// If set, the annotations of the slices are written as prose after the
+    /// line they end on, like `line 52, columns 5-9: expected `;``, rather
+    /// than drawn under the source, which screen readers read better.
+    pub screen_reader: bool,
+    /// If set, a ruler numbering the columns, like `         1` over
+    /// `1234567890`, is displayed above the lines of each slice, which helps
+    /// debugging the spans computed by tools.
+    pub column_ruler: bool,
+}
+
+impl FormatOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn anonymized_line_numbers(mut self, anonymized_line_numbers: bool) -> Self {
+        self.anonymized_line_numbers = anonymized_line_numbers;
+        self
+    }
+
+    pub fn margin(mut self, margin: Margin) -> Self {
+        self.margin = Some(margin);
+        self
+    }
+
+    pub fn icons(mut self, icons: SeverityIcons) -> Self {
+        self.icons = Some(icons);
+        self
+    }
+
+    pub fn markers(mut self, markers: MarkerSet) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = Some(theme);
+        self
+    }
+
+    pub fn fold(mut self, fold: bool) -> Self {
+        self.fold = fold;
+        self
+    }
+
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    pub fn translations(mut self, translations: Translations) -> Self {
+        self.translations = Some(translations);
+        self
+    }
+
+    pub fn footnotes(mut self, footnotes: bool) -> Self {
+        self.footnotes = footnotes;
+        self
+    }
+
+    pub fn bidi(mut self, bidi: BidiMode) -> Self {
+        self.bidi = bidi;
+        self
+    }
+
+    pub fn escape(mut self, escape: EscapeMode) -> Self {
+        self.escape = escape;
+        self
+    }
+
+    pub fn column_unit(mut self, column_unit: ColumnUnit) -> Self {
+        self.column_unit = column_unit;
+        self
+    }
+
+    pub fn header_position(mut self, header_position: HeaderPosition) -> Self {
+        self.header_position = header_position;
+        self
+    }
+
+    pub fn header_placement(mut self, header_placement: HeaderPlacement) -> Self {
+        self.header_placement = header_placement;
+        self
+    }
+
+    pub fn full_width_marks(mut self, full_width_marks: bool) -> Self {
+        self.full_width_marks = full_width_marks;
+        self
+    }
+
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = Some(tab_width);
+        self
+    }
+
+    pub fn max_width(mut self, max_width: usize) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    pub fn assert_max_width(mut self, assert_max_width: bool) -> Self {
+        self.assert_max_width = assert_max_width;
+        self
+    }
+
+    pub fn path_normalizer<F>(mut self, normalize: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.path_normalizer = Some(PathNormalizer::new(normalize));
+        self
+    }
+
+    /// Adds a remapping of the paths starting with `from` to `to`.
+    pub fn remap_path_prefix(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.remap_path_prefix.push((from.into(), to.into()));
+        self
+    }
+
+    pub fn base_dir(mut self, base_dir: impl Into<PathBuf>) -> Self {
+        self.base_dir = Some(base_dir.into());
+        self
+    }
+
+    /// Displays the paths relative to the current directory, if it can be
+    /// read.
+    pub fn relative_to_current_dir(mut self) -> Self {
+        self.base_dir = std::env::current_dir().ok();
+        self
+    }
+
+    pub fn max_body_lines(mut self, max_body_lines: usize) -> Self {
+        self.max_body_lines = Some(max_body_lines);
+        self
+    }
+
+    pub fn gutter_badges<F>(mut self, badge: F) -> Self
+    where
+        F: Fn(Option<&str>, usize) -> Option<String> + Send + Sync + 'static,
+    {
+        self.gutter_badges = Some(GutterBadges::new(badge));
+        self
+    }
+
+    pub fn compact_annotations(mut self, compact_annotations: bool) -> Self {
+        self.compact_annotations = compact_annotations;
+        self
+    }
+
+    pub fn vertical_compact(mut self, vertical_compact: bool) -> Self {
+        self.vertical_compact = vertical_compact;
+        self
+    }
+
+    pub fn max_label_length(mut self, max_label_length: usize) -> Self {
+        self.max_label_length = Some(max_label_length);
+        self
+    }
+
+    pub fn screen_reader(mut self, screen_reader: bool) -> Self {
+        self.screen_reader = screen_reader;
+        self
+    }
+
+    pub fn column_ruler(mut self, column_ruler: bool) -> Self {
+        self.column_ruler = column_ruler;
+        self
+    }
+
+    /// The path displayed for `path`, after the remappings, the base
+    /// directory and the path normalizer.
+    ///
+    /// ```
+    /// use annotate_snippets::display_list::FormatOptions;
+    ///
+    /// let opt = FormatOptions::new().remap_path_prefix("/build/1234", "/rustc");
+    /// assert_eq!(opt.normalize_path("/build/1234/src/lib.rs"), "/rustc/src/lib.rs");
+    /// assert_eq!(opt.normalize_path("/build/12345/src/lib.rs"), "/build/12345/src/lib.rs");
+    /// ```
+    pub fn normalize_path<'p>(&self, path: &'p str) -> Cow<'p, str> {
+        let remapped = self.remap_path_prefix.iter().rev().find_map(|(from, to)| {
+            let rest = path.strip_prefix(from.as_str())?;
+            let at_separator =
+                rest.is_empty() || from.ends_with(['/', '\\']) || rest.starts_with(['/', '\\']);
+            Some(format!("{}{}", to, rest)).filter(|_| at_separator)
+        });
+        let mut path = match remapped {
+            Some(remapped) => Cow::Owned(remapped),
+            None => Cow::Borrowed(path),
+        };
+        if let Some(relative) = self
+            .base_dir
+            .as_deref()
+            .and_then(|base| relative_path(&path, base))
+        {
+            path = Cow::Owned(relative);
+        }
+        match &self.path_normalizer {
+            Some(normalizer) => normalizer.normalize(&path).into(),
+            None => path,
+        }
+    }
+}
+
+/// `path` relative to `base`, if both are absolute and share a directory.
+///
+/// The `.` and `..` of the paths are resolved without reading the file
+/// system.
+fn relative_path(path: &str, base: &Path) -> Option<String> {
+    /// The root of a path, and its normal components.
+    fn normalize(path: &Path) -> (Vec<Component<'_>>, Vec<Component<'_>>) {
+        let mut root = vec![];
+        let mut normal = vec![];
+        for component in path.components() {
+            match component {
+                Component::Prefix(_) | Component::RootDir => root.push(component),
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    normal.pop();
+                }
+                Component::Normal(_) => normal.push(component),
+            }
+        }
+        (root, normal)
+    }
+
+    let path = Path::new(path);
+    if !path.is_absolute() || !base.is_absolute() {
+        return None;
+    }
+    let (path_root, path) = normalize(path);
+    let (base_root, base) = normalize(base);
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    if path_root != base_root || common == 0 {
+        return None;
+    }
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    relative.extend(&path[common..]);
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative.into_os_string().into_string().ok()
 }
 
-#[derive(Clone, Copy, Debug)]
+/// A function transforming the paths of a snippet before they are displayed,
+/// to strip the prefix of a workspace, turn them into URIs or redact user
+/// names, for instance.
+///
+/// ```
+/// use annotate_snippets::display_list::FormatOptions;
+///
+/// let opt = FormatOptions::new().path_normalizer(|path| {
+///     path.strip_prefix("/home/user/project/").unwrap_or(path).to_string()
+/// });
+/// assert_eq!(opt.normalize_path("/home/user/project/src/lib.rs"), "src/lib.rs");
+/// ```
+///
+/// Two normalizers are equal if they are clones of each other.
+#[derive(Clone)]
+pub struct PathNormalizer(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl PathNormalizer {
+    pub fn new<F>(normalize: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        Self(Arc::new(normalize))
+    }
+
+    /// Transforms `path`.
+    pub fn normalize(&self, path: &str) -> String {
+        (self.0)(path)
+    }
+}
+
+impl fmt::Debug for PathNormalizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PathNormalizer(..)")
+    }
+}
+
+impl PartialEq for PathNormalizer {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for PathNormalizer {}
+
+impl std::hash::Hash for PathNormalizer {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as *const ()).hash(state);
+    }
+}
+
+// Keeps the options, which are often caught across panics in tests, unwind
+// safe; a normalizer only maps a path to another.
+impl std::panic::UnwindSafe for PathNormalizer {}
+impl std::panic::RefUnwindSafe for PathNormalizer {}
+
+/// A function giving the short badge of a source line, from the origin of
+/// its slice and its number: its coverage, the initials of its author or a
+/// breakpoint, for instance.
+///
+/// The badges are displayed left-aligned in a column before the line
+/// numbers, as wide as the widest badge of the snippet. The function may be
+/// called more than once for a line.
+///
+/// ```
+/// use annotate_snippets::display_list::{DisplayList, FormatOptions};
+/// use annotate_snippets::snippet::{Slice, Snippet};
+///
+/// let snippet = Snippet::new()
+///     .slice(Slice::new("let x = 1;\nlet y = x;", 1))
+///     .opt(FormatOptions::new().gutter_badges(|_, lineno| {
+///         Some("●".to_string()).filter(|_| lineno == 2)
+///     }));
+/// assert_eq!(
+///     DisplayList::from(snippet).to_string(),
+///     "    |\n  1 | let x = 1;\n● 2 | let y = x;\n    |",
+/// );
+/// ```
+///
+/// Two badge functions are equal if they are clones of each other.
+#[derive(Clone)]
+pub struct GutterBadges(Arc<BadgeFn>);
+
+type BadgeFn = dyn Fn(Option<&str>, usize) -> Option<String> + Send + Sync;
+
+impl GutterBadges {
+    pub fn new<F>(badge: F) -> Self
+    where
+        F: Fn(Option<&str>, usize) -> Option<String> + Send + Sync + 'static,
+    {
+        Self(Arc::new(badge))
+    }
+
+    /// The badge of the line `lineno` of a slice from `origin`.
+    pub fn badge(&self, origin: Option<&str>, lineno: usize) -> Option<String> {
+        (self.0)(origin, lineno)
+    }
+}
+
+impl fmt::Debug for GutterBadges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("GutterBadges(..)")
+    }
+}
+
+impl PartialEq for GutterBadges {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for GutterBadges {}
+
+impl std::hash::Hash for GutterBadges {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as *const ()).hash(state);
+    }
+}
+
+impl std::panic::UnwindSafe for GutterBadges {}
+impl std::panic::RefUnwindSafe for GutterBadges {}
+
+/// How the headers display the position of the first annotation of a
+/// slice.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeaderPosition {
+    /// Line and column, like `--> src/main.rs:2:8`.
+    #[default]
+    LineColumn,
+    /// Offset in UTF-8 bytes from the start of the source of the slice, in
+    /// hexadecimal, like `--> file.bin:+0x1A2`, for binary formats.
+    Offset,
+}
+
+/// Where the title of a snippet and the headers of its slices are
+/// displayed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeaderPlacement {
+    /// The title first, and each header above the lines of its slice.
+    #[default]
+    Above,
+    /// Each header below the lines of its slice, and the title after the
+    /// slices, before the footers, like the summaries of clang-tidy.
+    Below,
+}
+
+/// What the columns of positions count, from 1 at the start of the line.
+///
+/// Offsets in a snippet always count chars; this only changes how positions
+/// are reported, in headers and by the emitters, to match what the tools
+/// reading them expect. Language servers, for instance, count UTF-16 code
+/// units.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColumnUnit {
+    /// UTF-8 bytes.
+    Bytes,
+    /// Unicode scalar values, the unit of the offsets of snippets.
+    #[default]
+    Chars,
+    /// UTF-16 code units.
+    Utf16,
+    /// Cells of a terminal, as wide as characters are displayed.
+    Cells,
+}
+
+impl ColumnUnit {
+    /// The number of units of `text`.
+    pub fn measure(self, text: &str) -> usize {
+        match self {
+            ColumnUnit::Bytes => text.len(),
+            ColumnUnit::Chars => text.chars().count(),
+            ColumnUnit::Utf16 => text.chars().map(char::len_utf16).sum(),
+            ColumnUnit::Cells => super::from_snippet::text_width(text),
+        }
+    }
+}
+
+/// How the bidirectional formatting characters of sources are displayed.
+///
+/// Those characters, invisible, change the order in which a terminal displays
+/// the text around them, so a source line may look different from what it
+/// is, and may even reorder the gutter and the markers of its line.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BidiMode {
+    /// Displays the sources as they are.
+    #[default]
+    Preserve,
+    /// Removes the embeddings, overrides, isolates and direction marks from
+    /// the sources, which are then displayed in their logical order.
+    Strip,
+    /// Displays every source line in an isolate, which keeps the characters of
+    /// the line from reordering the rest of the output.
+    Isolate,
+}
+
+impl BidiMode {
+    /// Returns `true` for the characters `BidiMode::Strip` removes.
+    pub fn is_control(c: char) -> bool {
+        matches!(
+            c,
+            '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+        )
+    }
+}
+
+/// How the non-printable characters of sources are displayed.
+///
+/// Annotations are laid out with the width of what is displayed, so they stay
+/// under the characters they point to whatever the mode.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EscapeMode {
+    /// Writes the characters as they are, for the terminal to display.
+    #[default]
+    PassThrough,
+    /// Displays the characters as escapes, like `\u{1b}`.
+    Unicode,
+    /// Displays the characters as `U+FFFD REPLACEMENT CHARACTER`.
+    Replacement,
+}
+
+impl EscapeMode {
+    /// Returns `true` for the characters which are escaped or replaced: the
+    /// control characters but tabs, and the invisible formatting characters.
+    pub fn is_non_printable(c: char) -> bool {
+        c.is_control() && c != '\t'
+            || BidiMode::is_control(c)
+            || matches!(c, '\u{200B}' | '\u{2060}' | '\u{FEFF}')
+    }
+
+    /// Returns `true` if `c` is not displayed as it is.
+    pub fn substitutes(self, c: char) -> bool {
+        self != EscapeMode::PassThrough && Self::is_non_printable(c)
+    }
+
+    /// Appends what is displayed for `c` to `text`.
+    pub(crate) fn push_char(self, c: char, text: &mut String) {
+        if !self.substitutes(c) {
+            text.push(c);
+        } else if self == EscapeMode::Unicode {
+            text.push_str(&c.escape_unicode().to_string());
+        } else {
+            text.push('\u{FFFD}');
+        }
+    }
+
+    /// The number of columns taken up by what is displayed for `c`, `None`
+    /// for control characters displayed as they are.
+    pub(crate) fn width(self, c: char) -> Option<usize> {
+        if !self.substitutes(c) {
+            unicode_width::UnicodeWidthChar::width(c)
+        } else if self == EscapeMode::Unicode {
+            Some(c.escape_unicode().count())
+        } else {
+            Some(1)
+        }
+    }
+}
+
+/// Icons displayed in front of titles, one per annotation type.
+///
+/// The `Default` implementation provides a common set of symbols, but any
+/// string may be used:
+///
+/// ```
+/// use annotate_snippets::display_list::SeverityIcons;
+///
+/// let icons = SeverityIcons {
+///     error: "[E]".into(),
+///     warning: "[W]".into(),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SeverityIcons {
+    pub error: Cow<'static, str>,
+    pub warning: Cow<'static, str>,
+    pub info: Cow<'static, str>,
+    pub note: Cow<'static, str>,
+    pub help: Cow<'static, str>,
+}
+
+impl Default for SeverityIcons {
+    fn default() -> Self {
+        Self {
+            error: "✖".into(),
+            warning: "⚠".into(),
+            info: "ℹ".into(),
+            note: "ℹ".into(),
+            help: "➤".into(),
+        }
+    }
+}
+
+impl SeverityIcons {
+    /// Returns the icon for the given annotation type, if it has one.
+    pub fn get(&self, annotation_type: &DisplayAnnotationType) -> Option<&str> {
+        match annotation_type {
+            DisplayAnnotationType::Error => Some(&self.error),
+            DisplayAnnotationType::Warning => Some(&self.warning),
+            DisplayAnnotationType::Info => Some(&self.info),
+            DisplayAnnotationType::Note => Some(&self.note),
+            DisplayAnnotationType::Help => Some(&self.help),
+            DisplayAnnotationType::None => None,
+        }
+    }
+}
+
+/// The marks underlining the spans of source annotations, by annotation
+/// type.
+///
+/// ```
+/// use annotate_snippets::display_list::MarkerSet;
+///
+/// let markers = MarkerSet {
+///     error: '~',
+///     ..Default::default()
+/// };
+/// ```
+///
+/// Printable ASCII marks are drawn with their full-width forms under wide
+/// characters, with `FormatOptions::full_width_marks`, and the other marks
+/// twice per wide character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MarkerSet {
+    pub error: char,
+    pub warning: char,
+    pub info: char,
+    pub note: char,
+    pub help: char,
+}
+
+impl Default for MarkerSet {
+    fn default() -> Self {
+        Self {
+            error: '^',
+            warning: '-',
+            info: '-',
+            note: '-',
+            help: '-',
+        }
+    }
+}
+
+impl MarkerSet {
+    /// Returns the mark for the given annotation type, a space for none.
+    pub fn get(&self, annotation_type: &DisplayAnnotationType) -> char {
+        match annotation_type {
+            DisplayAnnotationType::Error => self.error,
+            DisplayAnnotationType::Warning => self.warning,
+            DisplayAnnotationType::Info => self.info,
+            DisplayAnnotationType::Note => self.note,
+            DisplayAnnotationType::Help => self.help,
+            DisplayAnnotationType::None => ' ',
+        }
+    }
+}
+
+/// The fixed strings of the rendered output, for tools displaying
+/// diagnostics in the language of the user.
+///
+/// The `Default` implementation provides the English strings:
+///
+/// ```
+/// use annotate_snippets::display_list::Translations;
+///
+/// let french = Translations {
+///     error: "erreur".into(),
+///     warning: "avertissement".into(),
+///     ..Default::default()
+/// };
+/// ```
+///
+/// The fold marker replaces the line numbers of the lines left out of folded
+/// slices, the gutter is not widened for it.
+///
+/// The places of annotations read by screen readers and the notes of
+/// includes are templates, whose `{name}` placeholders are replaced by their
+/// values.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Translations {
+    pub error: Cow<'static, str>,
+    pub warning: Cow<'static, str>,
+    pub info: Cow<'static, str>,
+    pub note: Cow<'static, str>,
+    pub help: Cow<'static, str>,
+    pub fold: Cow<'static, str>,
+    /// The place of an annotation of a single column, with `{line}` and
+    /// `{column}`.
+    pub column_place: Cow<'static, str>,
+    /// The place of an annotation of a line, with `{line}`, `{start}` and
+    /// `{end}`.
+    pub columns_place: Cow<'static, str>,
+    /// The place of a multiline annotation, with `{line}`, `{column}`,
+    /// `{end_line}` and `{end_column}`.
+    pub lines_place: Cow<'static, str>,
+    /// The note about a file including the file of the snippet, with
+    /// `{path}` and `{line}`.
+    pub included_from: Cow<'static, str>,
+    /// The marker of a single source line left out of a long body.
+    pub omitted_line: Cow<'static, str>,
+    /// The marker of several source lines left out of a long body, with
+    /// `{count}`.
+    pub omitted_lines: Cow<'static, str>,
+}
+
+impl Default for Translations {
+    fn default() -> Self {
+        Self {
+            error: "error".into(),
+            warning: "warning".into(),
+            info: "info".into(),
+            note: "note".into(),
+            help: "help".into(),
+            fold: "...".into(),
+            column_place: "line {line}, column {column}".into(),
+            columns_place: "line {line}, columns {start}-{end}".into(),
+            lines_place: "line {line}, column {column} to line {end_line}, column {end_column}"
+                .into(),
+            included_from: "included from {path}:{line}".into(),
+            omitted_line: "… 1 line omitted".into(),
+            omitted_lines: "… {count} lines omitted".into(),
+        }
+    }
+}
+
+impl Translations {
+    /// Returns the name of the given annotation type, if it has one.
+    pub fn get(&self, annotation_type: &DisplayAnnotationType) -> Option<&str> {
+        match annotation_type {
+            DisplayAnnotationType::Error => Some(&self.error),
+            DisplayAnnotationType::Warning => Some(&self.warning),
+            DisplayAnnotationType::Info => Some(&self.info),
+            DisplayAnnotationType::Note => Some(&self.note),
+            DisplayAnnotationType::Help => Some(&self.help),
+            DisplayAnnotationType::None => None,
+        }
+    }
+
+    /// Replaces the `{name}` placeholders of `template` by their values in
+    /// `args`. Other text, unknown placeholders included, is kept.
+    pub fn fill(template: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let mut filled = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            filled.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let value = rest.find('}').and_then(|end| {
+                let (_, value) = args.iter().find(|(name, _)| *name == &rest[1..end])?;
+                Some((end, value))
+            });
+            match value {
+                Some((end, value)) => {
+                    filled.push_str(&value.to_string());
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    filled.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        filled.push_str(rest);
+        filled
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Margin {
     /// The available whitespace in the left that can be consumed when centering.
     whitespace_left: usize,
@@ -103,7 +1111,7 @@ impl Margin {
             if self.computed_right == self.span_right || self.computed_right == self.label_right {
                 // Account for the "..." padding given above. Otherwise we end up with code lines that
                 // do fit but end in "..." as if they were trimmed.
-                self.computed_right - 6
+                self.computed_right.saturating_sub(6)
             } else {
                 self.computed_right
             };
@@ -123,24 +1131,26 @@ impl Margin {
 
         if self.computed_right - self.computed_left > self.column_width {
             // Trimming only whitespace isn't enough, let's get craftier.
-            if self.label_right - self.whitespace_left <= self.column_width {
+            if self.label_right.saturating_sub(self.whitespace_left) <= self.column_width {
                 // Attempt to fit the code window only trimming whitespace.
                 self.computed_left = self.whitespace_left;
                 self.computed_right = self.computed_left + self.column_width;
-            } else if self.label_right - self.span_left <= self.column_width {
+            } else if self.label_right.saturating_sub(self.span_left) <= self.column_width {
                 // Attempt to fit the code window considering only the spans and labels.
-                let padding_left = (self.column_width - (self.label_right - self.span_left)) / 2;
+                let padding_left =
+                    (self.column_width - self.label_right.saturating_sub(self.span_left)) / 2;
                 self.computed_left = self.span_left.saturating_sub(padding_left);
                 self.computed_right = self.computed_left + self.column_width;
-            } else if self.span_right - self.span_left <= self.column_width {
+            } else if self.span_right.saturating_sub(self.span_left) <= self.column_width {
                 // Attempt to fit the code window considering the spans and labels plus padding.
-                let padding_left = (self.column_width - (self.span_right - self.span_left)) / 5 * 2;
+                let padding_left =
+                    (self.column_width - self.span_right.saturating_sub(self.span_left)) / 5 * 2;
                 self.computed_left = self.span_left.saturating_sub(padding_left);
                 self.computed_right = self.computed_left + self.column_width;
             } else {
                 // Mostly give up but still don't show the full line.
                 self.computed_left = self.span_left;
-                self.computed_right = self.span_right;
+                self.computed_right = max(self.span_right, self.span_left);
             }
         }
     }
@@ -159,65 +1169,140 @@ impl Margin {
 }
 
 /// Inline annotation which can be used in either Raw or Source line.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Annotation<'a> {
     pub annotation_type: DisplayAnnotationType,
     pub id: Option<&'a str>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub label: Vec<DisplayTextFragment<'a>>,
 }
 
 /// A single line used in `DisplayList`.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplayLine<'a> {
     /// A line with `lineno` portion of the slice.
     Source {
         lineno: Option<usize>,
-        inline_marks: Vec<DisplayMark>,
+        inline_marks: InlineMarks,
+        #[cfg_attr(feature = "serde", serde(borrow))]
         line: DisplaySourceLine<'a>,
     },
 
     /// A line indicating a folded part of the slice.
-    Fold { inline_marks: Vec<DisplayMark> },
+    Fold { inline_marks: InlineMarks },
+
+    /// A line standing for `count` source lines left out of a body longer
+    /// than `FormatOptions::max_body_lines`.
+    Elided {
+        inline_marks: InlineMarks,
+        count: usize,
+    },
 
     /// A line which is displayed outside of slices.
-    Raw(DisplayRawLine<'a>),
+    Raw(#[cfg_attr(feature = "serde", serde(borrow))] DisplayRawLine<'a>),
+}
+
+impl<'a> DisplayLine<'a> {
+    /// Creates a line outside of slices, aligned with the source like a
+    /// footer, such as `= note: label`.
+    pub fn annotation(annotation_type: DisplayAnnotationType, label: &'a str) -> Self {
+        DisplayLine::Raw(DisplayRawLine::Annotation {
+            annotation: Annotation {
+                annotation_type,
+                id: None,
+                label: vec![DisplayTextFragment {
+                    content: label.into(),
+                    style: DisplayTextStyle::Regular,
+                }],
+            },
+            source_aligned: true,
+            continuation: false,
+        })
+    }
+
+    /// Creates an empty source line, such as the `|` closing a slice.
+    pub fn empty() -> Self {
+        DisplayLine::Source {
+            lineno: None,
+            inline_marks: InlineMarks::new(),
+            line: DisplaySourceLine::Empty,
+        }
+    }
 }
 
 /// A source line.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplaySourceLine<'a> {
     /// A line with the content of the Slice.
     Content {
-        text: &'a str,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        text: Cow<'a, str>,
         range: (usize, usize), // meta information for annotation placement.
     },
 
     /// An annotation line which is displayed in context of the slice.
     Annotation {
+        #[cfg_attr(feature = "serde", serde(borrow))]
         annotation: Annotation<'a>,
         range: (usize, usize),
         annotation_type: DisplayAnnotationType,
         annotation_part: DisplayAnnotationPart,
+        /// Whether the marks are full-width characters, each taking up two
+        /// columns of `range`.
+        wide_marks: bool,
+    },
+
+    /// A line with the content of the Slice, followed by its single-line
+    /// annotations written as a trailing comment, in the compact mode.
+    Compact {
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        text: Cow<'a, str>,
+        range: (usize, usize),
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        annotations: Vec<DisplayTrailingAnnotation<'a>>,
     },
 
+    /// A row of the column ruler numbering the first `width` columns of the
+    /// source lines: the tens digits, or the units digits.
+    Ruler { width: usize, tens: bool },
+
     /// An empty source line.
     Empty,
 }
 
+/// A single-line annotation of a `DisplaySourceLine::Compact`, with the
+/// columns of the source line it would underline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisplayTrailingAnnotation<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub annotation: Annotation<'a>,
+    pub range: (usize, usize),
+    pub annotation_type: DisplayAnnotationType,
+}
+
 /// Raw line - a line which does not have the `lineno` part and is not considered
 /// a part of the snippet.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplayRawLine<'a> {
     /// A line which provides information about the location of the given
     /// slice in the project structure.
     Origin {
-        path: &'a str,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        path: Cow<'a, str>,
         pos: Option<(usize, usize)>,
+        /// UTF-8 byte offset displayed rather than `pos`, as `+0x1A2`.
+        offset: Option<usize>,
         header_type: DisplayHeaderType,
     },
 
     /// An annotation line which is not part of any snippet.
     Annotation {
+        #[cfg_attr(feature = "serde", serde(borrow))]
         annotation: Annotation<'a>,
 
         /// If set to `true`, the annotation will be aligned to the
@@ -229,26 +1314,39 @@ pub enum DisplayRawLine<'a> {
         /// displayed on each line.
         continuation: bool,
     },
+
+    /// A note naming a file which includes the file of the snippet, indented
+    /// by its `depth` in the chain of includes.
+    Include {
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        path: Cow<'a, str>,
+        line: usize,
+        depth: usize,
+    },
 }
 
 /// An inline text fragment which any label is composed of.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DisplayTextFragment<'a> {
-    pub content: &'a str,
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub content: Cow<'a, str>,
     pub style: DisplayTextStyle,
 }
 
 /// A style for the `DisplayTextFragment` which can be visually formatted.
 ///
 /// This information may be used to emphasis parts of the label.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplayTextStyle {
     Regular,
     Emphasis,
 }
 
 /// An indicator of what part of the annotation a given `Annotation` is.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplayAnnotationPart {
     /// A standalone, single-line annotation.
     Standalone,
@@ -262,20 +1360,32 @@ pub enum DisplayAnnotationPart {
     MultilineEnd,
 }
 
+/// The marks of a `DisplayLine`, stored inline as lines rarely have more
+/// than two.
+pub type InlineMarks = SmallVec<[DisplayMark; 2]>;
+
 /// A visual mark used in `inline_marks` field of the `DisplaySourceLine`.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DisplayMark {
     pub mark_type: DisplayMarkType,
     pub annotation_type: DisplayAnnotationType,
 }
 
 /// A type of the `DisplayMark`.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplayMarkType {
     /// A mark indicating a multiline annotation going through the current line.
     AnnotationThrough,
     /// A mark indicating a multiline annotation starting on the given line.
     AnnotationStart,
+    /// A mark indicating a whole line highlighted as added, removed or
+    /// changed, with the background color of the line.
+    LineHighlight {
+        change: LineChange,
+        background: Option<Color>,
+    },
 }
 
 /// A type of the `Annotation` which may impact the sigils, style or text displayed.
@@ -285,7 +1395,8 @@ pub enum DisplayMarkType {
 /// * An annotation may display the name of the type like `error` or `info`.
 /// * An underline for `Error` may be `^^^` while for `Warning` it coule be `---`.
 /// * `ColorStylesheet` may use different colors for different annotations.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplayAnnotationType {
     None,
     Error,
@@ -298,7 +1409,8 @@ pub enum DisplayAnnotationType {
 /// Information whether the header is the initial one or a consequitive one
 /// for multi-slice cases.
 // TODO: private
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplayHeaderType {
     /// Initial header is the first header in the snippet.
     Initial,
@@ -0,0 +1,493 @@
+//! `DisplayList` to `Snippet` conversion.
+//!
+//! The conversion recovers what the lines of a list show: the titles, the
+//! origins, sources and line numbers of the slices, the annotations with
+//! their ranges, and the footers. Lines the snippet model can't describe,
+//! like includes, are skipped, and folded lines come back empty.
+use super::from_snippet::char_columns;
+use crate::display_list::{
+    DisplayAnnotationPart, DisplayAnnotationType, DisplayLine, DisplayList, DisplayMarkType,
+    DisplayRawLine, DisplaySourceLine, DisplayTextFragment, EscapeMode, FormatOptions, Margin,
+};
+use crate::snippet::{
+    Annotation, AnnotationType, LineHighlight, Priority, Slice, Snippet, SourceAnnotation,
+};
+
+/// A snippet owning its texts, as recovered from a `DisplayList`, which
+/// `to_snippet` borrows as a `Snippet`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OwnedSnippet {
+    pub title: Option<OwnedAnnotation>,
+    pub footer: Vec<OwnedAnnotation>,
+    pub slices: Vec<OwnedSlice>,
+    pub opt: FormatOptions,
+    pub extra_titles: Vec<OwnedAnnotation>,
+}
+
+/// An `Annotation` owning its texts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedAnnotation {
+    pub id: Option<String>,
+    pub label: Option<String>,
+    pub annotation_type: AnnotationType,
+}
+
+/// A `Slice` owning its texts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedSlice {
+    pub source: String,
+    pub line_start: usize,
+    pub origin: Option<String>,
+    pub annotations: Vec<OwnedSourceAnnotation>,
+    pub fold: bool,
+    pub line_highlights: Vec<LineHighlight>,
+    pub origin_note: Option<String>,
+}
+
+/// A `SourceAnnotation` owning its label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedSourceAnnotation {
+    pub range: (usize, usize),
+    pub label: String,
+    pub annotation_type: AnnotationType,
+    pub priority: Priority,
+}
+
+impl OwnedSnippet {
+    /// Borrows the snippet.
+    pub fn to_snippet(&self) -> Snippet<'_> {
+        Snippet {
+            title: self.title.as_ref().map(OwnedAnnotation::to_annotation),
+            footer: self
+                .footer
+                .iter()
+                .map(OwnedAnnotation::to_annotation)
+                .collect(),
+            slices: self.slices.iter().map(OwnedSlice::to_slice).collect(),
+            opt: self.opt.clone(),
+            extra_titles: self
+                .extra_titles
+                .iter()
+                .map(OwnedAnnotation::to_annotation)
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Copies the texts of a snippet, apart from its expansion and include
+/// frames.
+impl From<&Snippet<'_>> for OwnedSnippet {
+    fn from(snippet: &Snippet<'_>) -> Self {
+        Self {
+            title: snippet.title.as_ref().map(OwnedAnnotation::from),
+            footer: snippet.footer.iter().map(OwnedAnnotation::from).collect(),
+            slices: snippet.slices.iter().map(OwnedSlice::from).collect(),
+            opt: snippet.opt.clone(),
+            extra_titles: snippet
+                .extra_titles
+                .iter()
+                .map(OwnedAnnotation::from)
+                .collect(),
+        }
+    }
+}
+
+impl OwnedAnnotation {
+    pub fn to_annotation(&self) -> Annotation<'_> {
+        Annotation {
+            id: self.id.as_deref(),
+            label: self.label.as_deref(),
+            annotation_type: self.annotation_type,
+        }
+    }
+}
+
+impl From<&Annotation<'_>> for OwnedAnnotation {
+    fn from(annotation: &Annotation<'_>) -> Self {
+        Self {
+            id: annotation.id.map(str::to_string),
+            label: annotation.label.map(str::to_string),
+            annotation_type: annotation.annotation_type,
+        }
+    }
+}
+
+impl OwnedSlice {
+    pub fn to_slice(&self) -> Slice<'_> {
+        Slice {
+            source: &self.source,
+            line_start: self.line_start,
+            origin: self.origin.as_deref(),
+            annotations: self
+                .annotations
+                .iter()
+                .map(|annotation| SourceAnnotation {
+                    range: annotation.range,
+                    label: &annotation.label,
+                    annotation_type: annotation.annotation_type,
+                    priority: annotation.priority,
+                })
+                .collect(),
+            fold: self.fold,
+            line_highlights: self.line_highlights.clone(),
+            origin_note: self.origin_note.as_deref(),
+        }
+    }
+}
+
+impl From<&Slice<'_>> for OwnedSlice {
+    fn from(slice: &Slice<'_>) -> Self {
+        Self {
+            source: slice.source.to_string(),
+            line_start: slice.line_start,
+            origin: slice.origin.map(str::to_string),
+            annotations: slice
+                .annotations
+                .iter()
+                .map(|annotation| OwnedSourceAnnotation {
+                    range: annotation.range,
+                    label: annotation.label.to_string(),
+                    annotation_type: annotation.annotation_type,
+                    priority: annotation.priority,
+                })
+                .collect(),
+            fold: slice.fold,
+            line_highlights: slice.line_highlights.clone(),
+            origin_note: slice.origin_note.map(str::to_string),
+        }
+    }
+}
+
+/// The slice being recovered, with the position of its last source line.
+struct SliceState {
+    slice: OwnedSlice,
+    /// Line number, text and offset of the last source line.
+    last_line: Option<(usize, String, usize)>,
+    /// Start offsets of the multiline annotations not ended yet.
+    open: Vec<(AnnotationType, usize)>,
+    /// Line and column of the start of the first annotation, from the header.
+    pos: Option<(usize, usize)>,
+}
+
+impl SliceState {
+    fn new(origin: Option<String>, pos: Option<(usize, usize)>) -> Self {
+        Self {
+            slice: OwnedSlice {
+                source: String::new(),
+                line_start: 1,
+                origin,
+                annotations: vec![],
+                fold: false,
+                line_highlights: vec![],
+                origin_note: None,
+            },
+            last_line: None,
+            open: vec![],
+            pos,
+        }
+    }
+
+    /// Finishes the slice, with the annotation starting at the position of
+    /// the header first, as the header shows the first annotation.
+    fn finish(mut self) -> OwnedSlice {
+        let slice = &mut self.slice;
+        if let Some((line, col)) = self.pos {
+            let line_offset = slice
+                .source
+                .split('\n')
+                .take(line.saturating_sub(slice.line_start))
+                .map(|line| line.chars().count() + 1)
+                .sum::<usize>();
+            let start = line_offset + col.saturating_sub(1);
+            if let Some(i) = slice
+                .annotations
+                .iter()
+                .position(|annotation| annotation.range.0 == start)
+            {
+                let first = slice.annotations.remove(i);
+                slice.annotations.insert(0, first);
+            }
+        }
+        self.slice
+    }
+
+    fn push_line(&mut self, lineno: Option<usize>, text: &str) {
+        let slice = &mut self.slice;
+        let lineno = match (&self.last_line, lineno) {
+            (None, Some(lineno)) => {
+                slice.line_start = lineno;
+                lineno
+            }
+            (None, None) => slice.line_start,
+            (Some((last, ..)), Some(lineno)) => {
+                // Folded lines are restored empty, keeping the line numbers.
+                if lineno > last + 1 {
+                    slice.fold = true;
+                }
+                for _ in last + 1..lineno {
+                    slice.source.push('\n');
+                }
+                lineno
+            }
+            (Some((last, ..)), None) => last + 1,
+        };
+        if self.last_line.is_some() {
+            slice.source.push('\n');
+        }
+        let offset = slice.source.chars().count();
+        slice.source.push_str(text);
+        self.last_line = Some((lineno, text.to_string(), offset));
+    }
+
+    /// The offset in the source of the display column `col` of the last line,
+    /// shifted by the part of the line the margin cuts.
+    fn offset(&self, col: usize, margin: Option<Margin>, tab_width: Option<usize>) -> usize {
+        let (_, text, offset) = match &self.last_line {
+            Some(last_line) => last_line,
+            None => return 0,
+        };
+        let col = col + margin.map_or(0, |margin| margin.left(text.chars().count()));
+        let mut char_cols = vec![];
+        char_columns(text, EscapeMode::PassThrough, tab_width, &mut char_cols);
+        // The last column is the end of the line
+        let (width, _) = char_cols.pop().unwrap_or_default();
+        match char_cols.iter().position(|&(start, _)| start >= col) {
+            Some(i) => offset + i,
+            None => offset + char_cols.len() + col.saturating_sub(width),
+        }
+    }
+
+    fn annotate(&mut self, range: (usize, usize), label: String, annotation_type: AnnotationType) {
+        self.slice.annotations.push(OwnedSourceAnnotation {
+            range,
+            label,
+            annotation_type,
+            priority: Priority::Normal,
+        });
+    }
+}
+
+fn annotation_type(annotation_type: &DisplayAnnotationType) -> Option<AnnotationType> {
+    match annotation_type {
+        DisplayAnnotationType::None => None,
+        DisplayAnnotationType::Error => Some(AnnotationType::Error),
+        DisplayAnnotationType::Warning => Some(AnnotationType::Warning),
+        DisplayAnnotationType::Info => Some(AnnotationType::Info),
+        DisplayAnnotationType::Note => Some(AnnotationType::Note),
+        DisplayAnnotationType::Help => Some(AnnotationType::Help),
+    }
+}
+
+fn text(label: &[DisplayTextFragment<'_>]) -> String {
+    label
+        .iter()
+        .map(|fragment| fragment.content.as_ref())
+        .collect()
+}
+
+impl<'a> DisplayList<'a> {
+    /// Recovers the snippet displayed by the list, as far as its lines allow.
+    ///
+    /// Converting back the list made from a snippet gives the same snippet,
+    /// apart from the lines hidden by folds, the annotations whose labels are
+    /// replaced by footnotes, and the parts of the snippet which are not
+    /// displayed, like the empty lines ending sources.
+    pub fn to_owned_snippet(&self) -> OwnedSnippet {
+        let mut snippet = OwnedSnippet {
+            opt: FormatOptions {
+                anonymized_line_numbers: self.anonymized_line_numbers,
+                margin: self.margin,
+                icons: self.icons.clone(),
+                markers: self.markers,
+                indent: self.indent,
+                translations: self.translations.clone(),
+                bidi: self.bidi,
+                escape: self.escape,
+                tab_width: self.tab_width,
+                max_width: self.max_width,
+                assert_max_width: self.assert_max_width,
+                full_width_marks: self.body.iter().any(|line| {
+                    matches!(
+                        line,
+                        DisplayLine::Source {
+                            line: DisplaySourceLine::Annotation {
+                                wide_marks: true,
+                                ..
+                            },
+                            ..
+                        }
+                    )
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut current: Option<SliceState> = None;
+        // Whether a continuation line belongs to the last footer rather than
+        // the title.
+        let mut continues_footer = false;
+
+        for line in &self.body {
+            match line {
+                DisplayLine::Raw(DisplayRawLine::Annotation {
+                    annotation,
+                    source_aligned,
+                    continuation,
+                }) => {
+                    let label = text(&annotation.label);
+                    if *continuation {
+                        let continued = if continues_footer {
+                            snippet.footer.last_mut()
+                        } else if snippet.extra_titles.is_empty() {
+                            snippet.title.as_mut()
+                        } else {
+                            snippet.extra_titles.last_mut()
+                        };
+                        if let Some(OwnedAnnotation {
+                            label: Some(previous),
+                            ..
+                        }) = continued
+                        {
+                            previous.push('\n');
+                            previous.push_str(&label);
+                        }
+                        continue;
+                    }
+                    let annotation_type = match annotation_type(&annotation.annotation_type) {
+                        Some(annotation_type) => annotation_type,
+                        // Footnotes and untyped lines have no annotation.
+                        None => continue,
+                    };
+                    let owned = OwnedAnnotation {
+                        id: annotation.id.map(str::to_string),
+                        label: Some(label).filter(|label| !label.is_empty()),
+                        annotation_type,
+                    };
+                    if let Some(state) = &mut current {
+                        // The note under the header of a slice.
+                        let under_header = state.last_line.is_none()
+                            && state.slice.origin.is_some()
+                            && state.slice.origin_note.is_none();
+                        if *source_aligned && under_header {
+                            state.slice.origin_note = owned.label;
+                            continue;
+                        }
+                    }
+                    if *source_aligned {
+                        if let Some(state) = current.take() {
+                            snippet.slices.push(state.finish());
+                        }
+                        snippet.footer.push(owned);
+                        continues_footer = true;
+                    } else if snippet.title.is_none() && snippet.slices.is_empty() {
+                        snippet.title = Some(owned);
+                    } else if current.is_none() && snippet.slices.is_empty() {
+                        snippet.extra_titles.push(owned);
+                    }
+                }
+                DisplayLine::Raw(DisplayRawLine::Origin { path, pos, .. }) => {
+                    if let Some(state) = current.take() {
+                        snippet.slices.push(state.finish());
+                    }
+                    current = Some(SliceState::new(Some(path.to_string()), *pos));
+                }
+                DisplayLine::Raw(DisplayRawLine::Include { .. }) => {}
+                // Folded and elided lines are restored empty by `push_line`.
+                DisplayLine::Fold { .. } | DisplayLine::Elided { .. } => {
+                    if let Some(state) = &mut current {
+                        state.slice.fold = true;
+                    }
+                }
+                DisplayLine::Source {
+                    lineno,
+                    inline_marks,
+                    line,
+                } => {
+                    let state = current.get_or_insert_with(|| SliceState::new(None, None));
+                    match line {
+                        DisplaySourceLine::Content { text: source, .. }
+                        | DisplaySourceLine::Compact { text: source, .. } => {
+                            state.push_line(*lineno, source);
+                            if let DisplaySourceLine::Compact { annotations, .. } = line {
+                                for trailing in annotations {
+                                    let annotation_type =
+                                        match annotation_type(&trailing.annotation_type) {
+                                            Some(annotation_type) => annotation_type,
+                                            None => continue,
+                                        };
+                                    let (start, end) = trailing.range;
+                                    let start = state.offset(start, self.margin, self.tab_width);
+                                    let end = state.offset(end, self.margin, self.tab_width);
+                                    let label = text(&trailing.annotation.label);
+                                    state.annotate((start, end), label, annotation_type);
+                                }
+                            }
+                            for mark in inline_marks {
+                                if let (DisplayMarkType::AnnotationStart, Some(annotation_type)) =
+                                    (&mark.mark_type, annotation_type(&mark.annotation_type))
+                                {
+                                    let start = state.offset(0, None, self.tab_width);
+                                    state.open.push((annotation_type, start));
+                                }
+                            }
+                        }
+                        DisplaySourceLine::Annotation {
+                            annotation,
+                            range,
+                            annotation_type: source_type,
+                            annotation_part,
+                            ..
+                        } => {
+                            let annotation_type = match annotation_type(source_type) {
+                                Some(annotation_type) => annotation_type,
+                                None => continue,
+                            };
+                            let label = text(&annotation.label);
+                            match annotation_part {
+                                DisplayAnnotationPart::MultilineStart => {
+                                    let start = state.offset(range.0, None, self.tab_width);
+                                    state.open.push((annotation_type, start));
+                                }
+                                DisplayAnnotationPart::MultilineEnd => {
+                                    let end = state.offset(range.1, self.margin, self.tab_width);
+                                    if let Some(i) =
+                                        state.open.iter().position(|(t, _)| *t == annotation_type)
+                                    {
+                                        let (_, start) = state.open.remove(i);
+                                        state.annotate((start, end), label, annotation_type);
+                                    }
+                                }
+                                DisplayAnnotationPart::LabelContinuation => {
+                                    if let Some(last) = state.slice.annotations.last_mut() {
+                                        last.label.push('\n');
+                                        last.label.push_str(&label);
+                                    }
+                                }
+                                DisplayAnnotationPart::Standalone
+                                | DisplayAnnotationPart::Consequitive => {
+                                    let start = state.offset(range.0, self.margin, self.tab_width);
+                                    let end = state.offset(range.1, self.margin, self.tab_width);
+                                    state.annotate((start, end), label, annotation_type);
+                                }
+                            }
+                        }
+                        // The empty line after the source ends the slice.
+                        DisplaySourceLine::Empty => {
+                            if state.last_line.is_some() {
+                                if let Some(state) = current.take() {
+                                    snippet.slices.push(state.finish());
+                                }
+                            }
+                        }
+                        DisplaySourceLine::Ruler { .. } => {}
+                    }
+                }
+            }
+        }
+        if let Some(state) = current {
+            snippet.slices.push(state.finish());
+        }
+        snippet
+    }
+}
@@ -0,0 +1,532 @@
+//! Collection and output of many snippets.
+//!
+//! An `Emitter` accepts snippets over time, and writes them when flushed:
+//! grouped by origin, in the order each origin was first seen, ordered by
+//! location within an origin, without duplicates and without the snippets
+//! below the minimal severity, after capping their severity.
+//!
+//! ```
+//! use annotate_snippets::emitter::{Backend, Emitter};
+//! use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+//!
+//! let mut emitter = Emitter::new(Backend::Json).min_severity(AnnotationType::Warning);
+//! for annotation_type in &[AnnotationType::Error, AnnotationType::Note] {
+//!     emitter.emit(Snippet::new().title(Annotation {
+//!         label: Some("unexpected token"),
+//!         id: None,
+//!         annotation_type: *annotation_type,
+//!     }));
+//! }
+//!
+//! let mut out = Vec::new();
+//! emitter.flush(&mut out).unwrap();
+//! assert!(String::from_utf8(out).unwrap().contains(r#""level":"error""#));
+//! ```
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+use crate::display_list::{slice_span, ColumnUnit, Pass};
+use crate::formatter::metrics::RenderMetrics;
+use crate::formatter::renderer::Renderer;
+use crate::snippet::{Annotation, AnnotationType, Snippet};
+use crate::summary::Summary;
+
+/// Output format of an `Emitter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// The rendered snippets, separated by empty lines, styled according to
+    /// their options.
+    Tty,
+    /// A JSON array with an object per snippet, holding its level, code,
+    /// message, location, rendered text and lint.
+    Json,
+    /// A SARIF 2.1.0 log with a result per snippet, whose columns are in
+    /// UTF-16 code units whatever the `ColumnUnit` of the snippet.
+    Sarif,
+    /// The lines of the rendered snippets as `cargo:warning=` instructions,
+    /// which cargo displays for build scripts.
+    Cargo,
+    /// A `cargo:warning=` instruction per snippet, with its location, level,
+    /// code and message.
+    CargoCondensed,
+}
+
+/// Collects snippets and writes them to a `Backend`.
+pub struct Emitter<'a> {
+    backend: Backend,
+    min_severity: Option<AnnotationType>,
+    max_severity: Option<AnnotationType>,
+    dedup: bool,
+    report_duplicates: bool,
+    snippets: Vec<Entry<'a>>,
+    /// Index of every origin of the snippets, in the order they were seen.
+    origins: HashMap<Option<&'a str>, usize>,
+    /// Title and slices of every snippet, to drop duplicates.
+    seen: HashSet<(Option<Annotation<'a>>, Vec<SliceKey<'a>>)>,
+    renderer: Renderer,
+    summary: Summary,
+}
+
+/// The first and last positions of a snippet, as `slice_span` gives them.
+type Span = ((usize, usize), (usize, usize));
+
+/// Origin, first line and annotation ranges of a slice, as duplicates have
+/// the same.
+type SliceKey<'a> = (Option<&'a str>, usize, Vec<(usize, usize)>);
+
+/// The message of the title of a snippet, formatted when it is written.
+pub type LazyMessage<'a> = Box<dyn FnOnce() -> String + 'a>;
+
+struct Entry<'a> {
+    origin: usize,
+    span: Option<Span>,
+    snippet: Snippet<'a>,
+    message: Option<LazyMessage<'a>>,
+}
+
+/// A snippet being written, with its message formatted.
+struct Flushed<'s> {
+    span: Option<Span>,
+    snippet: Cow<'s, Snippet<'s>>,
+}
+
+impl<'a> Emitter<'a> {
+    /// Creates an emitter writing to `backend`, deduplicating snippets and
+    /// keeping all severities.
+    pub fn new(backend: Backend) -> Self {
+        Self {
+            backend,
+            min_severity: None,
+            max_severity: None,
+            dedup: true,
+            report_duplicates: false,
+            snippets: vec![],
+            origins: HashMap::new(),
+            seen: HashSet::new(),
+            renderer: Renderer::new(),
+            summary: Summary::new(),
+        }
+    }
+
+    /// Drops the snippets whose title is less severe than `annotation_type`.
+    /// Snippets without a title are always kept.
+    pub fn min_severity(mut self, annotation_type: AnnotationType) -> Self {
+        self.min_severity = Some(annotation_type);
+        self
+    }
+
+    /// Demotes the annotations more severe than `annotation_type` to it, like
+    /// `--cap-lints` does, before the other options apply and the snippets
+    /// are counted.
+    pub fn cap_severity(mut self, annotation_type: AnnotationType) -> Self {
+        self.max_severity = Some(annotation_type);
+        self
+    }
+
+    /// Adds a pass run on the `DisplayList` of every snippet before it is
+    /// rendered, as `Renderer::pass` does.
+    pub fn pass(mut self, pass: impl Pass + 'static) -> Self {
+        self.renderer = self.renderer.pass(pass);
+        self
+    }
+
+    /// Adds a hook called with the metrics of every snippet rendered, as
+    /// `Renderer::on_metrics` does. The SARIF and condensed cargo backends,
+    /// which don't render snippets, don't call it.
+    pub fn on_metrics(mut self, hook: impl FnMut(&RenderMetrics) + 'static) -> Self {
+        self.renderer = self.renderer.on_metrics(hook);
+        self
+    }
+
+    /// Sets whether snippets with the same title as an earlier one of the
+    /// same flush, and slices with the same origins, first lines and
+    /// annotation ranges, are dropped.
+    pub fn dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Sets whether the dropped duplicates are counted in the summary, which
+    /// then mentions them.
+    pub fn report_duplicates(mut self, report_duplicates: bool) -> Self {
+        self.report_duplicates = report_duplicates;
+        self
+    }
+
+    /// Adds a snippet, to be written at the next flush.
+    pub fn emit(&mut self, snippet: Snippet<'a>) {
+        self.add(snippet, None);
+    }
+
+    /// Adds a snippet whose title is labeled by `message`, which is only
+    /// called when the snippet is written at the next flush. Its label in
+    /// `snippet`, if any, is replaced.
+    ///
+    /// Snippets dropped for their severity or as duplicates never format
+    /// their messages. Duplicates are told apart without the messages, by
+    /// the rest of their titles and their slices.
+    ///
+    /// ```
+    /// use annotate_snippets::emitter::{Backend, Emitter};
+    /// use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+    ///
+    /// let name = "x";
+    /// let mut emitter = Emitter::new(Backend::Tty);
+    /// for _ in 0..2 {
+    ///     let title = Annotation::new(AnnotationType::Warning, None);
+    ///     let snippet = Snippet::new().title(title);
+    ///     emitter.emit_deferred(snippet, move || format!("unused variable: `{}`", name));
+    /// }
+    /// assert_eq!(emitter.len(), 1);
+    ///
+    /// let mut out = Vec::new();
+    /// emitter.flush(&mut out).unwrap();
+    /// assert_eq!(String::from_utf8(out).unwrap(), "warning: unused variable: `x`\n");
+    /// ```
+    pub fn emit_deferred(&mut self, snippet: Snippet<'a>, message: impl FnOnce() -> String + 'a) {
+        self.add(snippet, Some(Box::new(message)));
+    }
+
+    fn add(&mut self, mut snippet: Snippet<'a>, message: Option<LazyMessage<'a>>) {
+        if let Some(max) = self.max_severity {
+            cap_severity(&mut snippet, max);
+        }
+        if let (Some(min), Some(title)) = (self.min_severity, &snippet.title) {
+            if severity(title.annotation_type) < severity(min) {
+                return;
+            }
+        }
+
+        let slice = snippet.slices.first();
+        let origin = slice.and_then(|slice| slice.origin);
+        let span = slice.and_then(|slice| slice_span(slice, snippet.opt.column_unit));
+        let next = self.origins.len();
+        let origin = *self.origins.entry(origin).or_insert(next);
+        if self.dedup && !self.seen.insert(dedup_key(&snippet)) {
+            if self.report_duplicates {
+                self.summary.duplicates += 1;
+            }
+            return;
+        }
+
+        self.summary.add(&snippet);
+        self.snippets.push(Entry {
+            origin,
+            span,
+            snippet,
+            message,
+        });
+    }
+
+    /// Adds the snippet made by `build`, which is only called if a snippet
+    /// whose title is an `annotation_type` is not dropped for its severity.
+    ///
+    /// The work of making snippets which are filtered out, like looking up
+    /// their labels, is saved.
+    pub fn emit_with(
+        &mut self,
+        annotation_type: AnnotationType,
+        build: impl FnOnce() -> Snippet<'a>,
+    ) {
+        let mut annotation_type = annotation_type;
+        if let Some(max) = self.max_severity {
+            if severity(annotation_type) > severity(max) {
+                annotation_type = max;
+            }
+        }
+        if let Some(min) = self.min_severity {
+            if severity(annotation_type) < severity(min) {
+                return;
+            }
+        }
+        self.emit(build());
+    }
+
+    /// Number of snippets waiting to be flushed.
+    pub fn len(&self) -> usize {
+        self.snippets.len()
+    }
+
+    /// Returns `true` if no snippets are waiting to be flushed.
+    pub fn is_empty(&self) -> bool {
+        self.snippets.is_empty()
+    }
+
+    /// Counts of the levels of the snippets emitted since the creation of the
+    /// emitter, flushed or not, without the dropped snippets.
+    pub fn summary(&self) -> Summary {
+        self.summary
+    }
+
+    /// Writes the collected snippets into `w`, and forgets them.
+    pub fn flush<W: io::Write>(&mut self, w: &mut W) -> io::Result<()> {
+        let mut snippets = std::mem::take(&mut self.snippets);
+        snippets.sort_by_key(|entry| (entry.origin, entry.span));
+        self.origins.clear();
+        self.seen.clear();
+
+        let messages = snippets
+            .iter_mut()
+            .map(|entry| entry.message.take().map(|message| message()))
+            .collect::<Vec<_>>();
+        let snippets = snippets
+            .iter()
+            .zip(&messages)
+            .map(|(entry, message)| Flushed {
+                span: entry.span,
+                snippet: match message {
+                    Some(message) => {
+                        let mut snippet = entry.snippet.clone();
+                        if let Some(title) = &mut snippet.title {
+                            title.label = Some(message);
+                        }
+                        Cow::Owned(snippet)
+                    }
+                    None => Cow::Borrowed(&entry.snippet),
+                },
+            })
+            .collect::<Vec<_>>();
+
+        match self.backend {
+            Backend::Tty => {
+                for (i, entry) in snippets.iter().enumerate() {
+                    if i > 0 {
+                        w.write_all(b"\n")?;
+                    }
+                    writeln!(w, "{}", self.renderer.render(&entry.snippet))?;
+                }
+            }
+            Backend::Json => {
+                w.write_all(b"[")?;
+                for (i, entry) in snippets.iter().enumerate() {
+                    if i > 0 {
+                        w.write_all(b",")?;
+                    }
+                    let (id, label, level) = title_parts(&entry.snippet);
+                    w.write_all(b"{\"level\":")?;
+                    write_json_str(w, Some(level_name(level)))?;
+                    w.write_all(b",\"code\":")?;
+                    write_json_str(w, id)?;
+                    w.write_all(b",\"message\":")?;
+                    write_json_str(w, Some(label))?;
+                    w.write_all(b",\"location\":")?;
+                    match (origin_of(&entry.snippet), entry.span) {
+                        (Some(origin), Some(((line, column), _))) => {
+                            w.write_all(b"{\"origin\":")?;
+                            write_json_str(w, Some(&origin))?;
+                            write!(w, ",\"line\":{},\"column\":{}}}", line, column)?;
+                        }
+                        _ => w.write_all(b"null")?,
+                    }
+                    w.write_all(b",\"rendered\":")?;
+                    write_json_str(w, Some(self.renderer.render(&entry.snippet)))?;
+                    w.write_all(b",\"lint\":")?;
+                    match &entry.snippet.lint {
+                        Some(lint) => {
+                            w.write_all(b"{\"name\":")?;
+                            write_json_str(w, Some(lint.name))?;
+                            w.write_all(b",\"level\":")?;
+                            write_json_str(w, Some(lint.level.name()))?;
+                            w.write_all(b",\"note\":")?;
+                            write_json_str(w, Some(&lint.note()))?;
+                            w.write_all(b"}")?;
+                        }
+                        None => w.write_all(b"null")?,
+                    }
+                    w.write_all(b"}")?;
+                }
+                w.write_all(b"]\n")?;
+            }
+            Backend::Sarif => {
+                w.write_all(
+                    concat!(
+                        "{\"version\":\"2.1.0\",",
+                        "\"$schema\":\"https://json.schemastore.org/sarif-2.1.0.json\",",
+                        "\"runs\":[{\"tool\":{\"driver\":{\"name\":\"annotate-snippets\"}},",
+                        "\"columnKind\":\"utf16CodeUnits\",",
+                        "\"results\":["
+                    )
+                    .as_bytes(),
+                )?;
+                for (i, entry) in snippets.iter().enumerate() {
+                    if i > 0 {
+                        w.write_all(b",")?;
+                    }
+                    let (id, label, level) = title_parts(&entry.snippet);
+                    let lint = entry.snippet.lint.as_ref();
+                    w.write_all(b"{")?;
+                    // Lints without a code are identified by their name.
+                    if let Some(id) = id.or_else(|| lint.map(|lint| lint.name)) {
+                        w.write_all(b"\"ruleId\":")?;
+                        write_json_str(w, Some(id))?;
+                        w.write_all(b",")?;
+                    }
+                    w.write_all(b"\"level\":")?;
+                    write_json_str(w, Some(sarif_level(level)))?;
+                    w.write_all(b",\"message\":{\"text\":")?;
+                    write_json_str(w, Some(label))?;
+                    w.write_all(b"}")?;
+                    if let Some(origin) = origin_of(&entry.snippet) {
+                        w.write_all(b",\"locations\":[{\"physicalLocation\":{\"artifactLocation\":{\"uri\":")?;
+                        write_json_str(w, Some(&origin))?;
+                        w.write_all(b"}")?;
+                        // The columns are in UTF-16 code units, as the run says.
+                        let slice = entry.snippet.slices.first();
+                        let span = slice.and_then(|slice| slice_span(slice, ColumnUnit::Utf16));
+                        if let Some(((line, column), (end_line, end_column))) = span {
+                            write!(
+                                w,
+                                concat!(
+                                    ",\"region\":{{\"startLine\":{},\"startColumn\":{},",
+                                    "\"endLine\":{},\"endColumn\":{}}}"
+                                ),
+                                line, column, end_line, end_column
+                            )?;
+                        }
+                        w.write_all(b"}}]")?;
+                    }
+                    w.write_all(b"}")?;
+                }
+                w.write_all(b"]}]}\n")?;
+            }
+            Backend::Cargo => {
+                for entry in &snippets {
+                    for line in self.renderer.render(&entry.snippet).lines() {
+                        writeln!(w, "cargo:warning={}", line)?;
+                    }
+                }
+            }
+            Backend::CargoCondensed => {
+                for entry in &snippets {
+                    let (id, label, level) = title_parts(&entry.snippet);
+                    w.write_all(b"cargo:warning=")?;
+                    if let (Some(origin), Some(((line, column), _))) =
+                        (origin_of(&entry.snippet), entry.span)
+                    {
+                        write!(w, "{}:{}:{}: ", origin, line, column)?;
+                    }
+                    if level.is_some() {
+                        w.write_all(level_name(level).as_bytes())?;
+                        if let Some(id) = id {
+                            write!(w, "[{}]", id)?;
+                        }
+                        w.write_all(b": ")?;
+                    }
+                    // An instruction ends with its line.
+                    let mut lines = label.lines();
+                    w.write_all(lines.next().unwrap_or_default().as_bytes())?;
+                    for line in lines {
+                        write!(w, " {}", line)?;
+                    }
+                    w.write_all(b"\n")?;
+                }
+            }
+        }
+        w.flush()
+    }
+}
+
+/// The title and slices of a snippet which tell it from the other snippets.
+fn dedup_key<'a>(snippet: &Snippet<'a>) -> (Option<Annotation<'a>>, Vec<SliceKey<'a>>) {
+    let slices = snippet.slices.iter().map(|slice| {
+        let ranges = slice.annotations.iter().map(|annotation| annotation.range);
+        (slice.origin, slice.line_start, ranges.collect())
+    });
+    (snippet.title.clone(), slices.collect())
+}
+
+/// Rank of a severity, higher being more severe.
+fn severity(annotation_type: AnnotationType) -> u8 {
+    match annotation_type {
+        AnnotationType::Error => 4,
+        AnnotationType::Warning => 3,
+        AnnotationType::Info => 2,
+        AnnotationType::Note => 1,
+        AnnotationType::Help => 0,
+    }
+}
+
+fn cap_severity(snippet: &mut Snippet<'_>, max: AnnotationType) {
+    let cap = |annotation_type: &mut AnnotationType| {
+        if severity(*annotation_type) > severity(max) {
+            *annotation_type = max;
+        }
+    };
+    let titles = snippet.title.iter_mut().chain(&mut snippet.footer);
+    titles.for_each(|annotation| cap(&mut annotation.annotation_type));
+    let slices = snippet.slices.iter_mut();
+    for slice in slices.chain(snippet.expansion.iter_mut().map(|frame| &mut frame.slice)) {
+        for annotation in &mut slice.annotations {
+            cap(&mut annotation.annotation_type);
+        }
+    }
+}
+
+/// Origin of the first slice of a snippet, after its path normalizer.
+fn origin_of<'a>(snippet: &Snippet<'a>) -> Option<Cow<'a, str>> {
+    let origin = snippet.slices.first().and_then(|slice| slice.origin)?;
+    Some(snippet.opt.normalize_path(origin))
+}
+
+/// Code, message and level of a snippet, from its title.
+fn title_parts<'a>(snippet: &Snippet<'a>) -> (Option<&'a str>, &'a str, Option<AnnotationType>) {
+    match &snippet.title {
+        Some(title) => (
+            title.id,
+            title.label.unwrap_or_default(),
+            Some(title.annotation_type),
+        ),
+        None => (None, "", None),
+    }
+}
+
+fn level_name(level: Option<AnnotationType>) -> &'static str {
+    match level {
+        Some(AnnotationType::Error) => "error",
+        Some(AnnotationType::Warning) => "warning",
+        Some(AnnotationType::Info) => "info",
+        Some(AnnotationType::Note) => "note",
+        Some(AnnotationType::Help) => "help",
+        None => "none",
+    }
+}
+
+fn sarif_level(level: Option<AnnotationType>) -> &'static str {
+    match level {
+        Some(AnnotationType::Error) => "error",
+        Some(AnnotationType::Warning) => "warning",
+        Some(_) => "note",
+        None => "none",
+    }
+}
+
+/// Writes `s` as a JSON string, or `null`.
+fn write_json_str<W: io::Write>(w: &mut W, s: Option<&str>) -> io::Result<()> {
+    let s = match s {
+        Some(s) => s,
+        None => return w.write_all(b"null"),
+    };
+    w.write_all(b"\"")?;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        let escaped = match c {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            c if c < ' ' => "",
+            _ => continue,
+        };
+        w.write_all(&s.as_bytes()[start..i])?;
+        if escaped.is_empty() {
+            write!(w, "\\u{:04x}", c as u32)?;
+        } else {
+            w.write_all(escaped.as_bytes())?;
+        }
+        start = i + c.len_utf8();
+    }
+    w.write_all(&s.as_bytes()[start..])?;
+    w.write_all(b"\"")
+}
@@ -0,0 +1,171 @@
+//! A snippet usable as an error.
+//!
+//! A `DiagnosticError` displays as its rendered snippet, so a tool can return
+//! it through `?`, and `main` returning it prints the snippet rather than a
+//! structure dump.
+//!
+//! A `DiagnosticError` borrows the texts of its snippet. A `RenderedDiagnostic`
+//! owns them, so it can end the error chains of applications, and be a
+//! variant of their error enums.
+//!
+//! ```
+//! use annotate_snippets::error::DiagnosticError;
+//! use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+//!
+//! fn check(value: &str) -> Result<(), DiagnosticError<'static>> {
+//!     if value.is_empty() {
+//!         return Err(DiagnosticError::new(Snippet::new().title(Annotation {
+//!             id: None,
+//!             label: Some("empty value"),
+//!             annotation_type: AnnotationType::Error,
+//!         })));
+//!     }
+//!     Ok(())
+//! }
+//!
+//! assert_eq!(check("").unwrap_err().to_string(), "error: empty value");
+//! ```
+use std::error::Error;
+use std::fmt;
+
+use crate::display_list::{FormatOptions, OwnedSnippet};
+use crate::snippet::Snippet;
+
+/// An error rendered as a snippet, with the options of the snippet.
+///
+/// The snippet is boxed, keeping results small.
+pub struct DiagnosticError<'a> {
+    snippet: Box<Snippet<'a>>,
+}
+
+impl<'a> DiagnosticError<'a> {
+    pub fn new(snippet: Snippet<'a>) -> Self {
+        Self {
+            snippet: Box::new(snippet),
+        }
+    }
+
+    pub fn snippet(&self) -> &Snippet<'a> {
+        &self.snippet
+    }
+
+    /// Unwraps the snippet.
+    pub fn into_snippet(self) -> Snippet<'a> {
+        *self.snippet
+    }
+}
+
+impl<'a> From<Snippet<'a>> for DiagnosticError<'a> {
+    fn from(snippet: Snippet<'a>) -> Self {
+        Self::new(snippet)
+    }
+}
+
+impl fmt::Display for DiagnosticError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&crate::format(&self.snippet, &self.snippet.opt))
+    }
+}
+
+/// Renders the snippet too, as this is what `main` prints of its error.
+impl fmt::Debug for DiagnosticError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Error for DiagnosticError<'_> {}
+
+/// An error rendered as a snippet owning its texts, with the options of the
+/// snippet.
+///
+/// The snippet is boxed, and rendered when the error is displayed.
+///
+/// ```
+/// use annotate_snippets::error::RenderedDiagnostic;
+/// use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+///
+/// #[derive(Debug)]
+/// enum ConfigError {
+///     Io(std::io::Error),
+///     Invalid(RenderedDiagnostic),
+/// }
+///
+/// fn check(value: &str) -> Result<(), ConfigError> {
+///     if value.is_empty() {
+///         let label = format!("empty value in {}", "config.toml");
+///         let snippet = Snippet::new().title(Annotation::new(AnnotationType::Error, label.as_str()));
+///         return Err(ConfigError::Invalid(RenderedDiagnostic::new(&snippet)));
+///     }
+///     Ok(())
+/// }
+///
+/// match check("") {
+///     Err(ConfigError::Invalid(error)) => {
+///         assert_eq!(error.to_string(), "error: empty value in config.toml")
+///     }
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct RenderedDiagnostic {
+    snippet: Box<OwnedSnippet>,
+}
+
+impl RenderedDiagnostic {
+    /// Copies the texts of `snippet`, apart from its expansion and include
+    /// frames.
+    pub fn new(snippet: &Snippet<'_>) -> Self {
+        Self {
+            snippet: Box::new(OwnedSnippet::from(snippet)),
+        }
+    }
+
+    /// Replaces the options the snippet is rendered with.
+    pub fn options(mut self, opt: FormatOptions) -> Self {
+        self.snippet.opt = opt;
+        self
+    }
+
+    pub fn snippet(&self) -> &OwnedSnippet {
+        &self.snippet
+    }
+}
+
+impl From<&Snippet<'_>> for RenderedDiagnostic {
+    fn from(snippet: &Snippet<'_>) -> Self {
+        Self::new(snippet)
+    }
+}
+
+impl From<DiagnosticError<'_>> for RenderedDiagnostic {
+    fn from(error: DiagnosticError<'_>) -> Self {
+        Self::new(error.snippet())
+    }
+}
+
+impl From<OwnedSnippet> for RenderedDiagnostic {
+    fn from(snippet: OwnedSnippet) -> Self {
+        Self {
+            snippet: Box::new(snippet),
+        }
+    }
+}
+
+impl fmt::Display for RenderedDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&crate::format(
+            &self.snippet.to_snippet(),
+            &self.snippet.opt,
+        ))
+    }
+}
+
+/// Renders the snippet too, as this is what `main` prints of its error.
+impl fmt::Debug for RenderedDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Error for RenderedDiagnostic {}
@@ -0,0 +1,261 @@
+//! A C API rendering snippets.
+//!
+//! With the `ffi` feature, tools written in C, C++ or any language with a C
+//! foreign function interface can build snippets through handles and render
+//! them. `include/annotate_snippets.h` declares the functions; the crate is
+//! built as a C library with:
+//!
+//! ```text
+//! cargo rustc --lib --release --features ffi --crate-type cdylib
+//! ```
+//!
+//! A snippet is created with `annotate_snippets_snippet_new`, completed with
+//! its slices, annotations and footers, rendered into a buffer of the caller
+//! with `annotate_snippets_snippet_render`, and freed with
+//! `annotate_snippets_snippet_free`. The handle owns copies of the strings it
+//! is given, which are NUL-terminated UTF-8. Annotation types are the values
+//! of `AnnotationType` in declaration order, starting at 0 with `Error`.
+//!
+//! Functions returning an `int` return a negative value on invalid arguments,
+//! and leave the snippet unchanged.
+use std::convert::TryFrom;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::display_list::{
+    DisplayList, FormatOptions, OwnedAnnotation, OwnedSlice, OwnedSnippet, OwnedSourceAnnotation,
+};
+use crate::snippet::{AnnotationType, Priority};
+
+/// A snippet built through the C API.
+pub struct SnippetHandle {
+    snippet: OwnedSnippet,
+}
+
+fn annotation_type(value: c_int) -> Option<AnnotationType> {
+    match value {
+        0 => Some(AnnotationType::Error),
+        1 => Some(AnnotationType::Warning),
+        2 => Some(AnnotationType::Info),
+        3 => Some(AnnotationType::Note),
+        4 => Some(AnnotationType::Help),
+        _ => None,
+    }
+}
+
+/// Copies a string which may be null. `Err` if it is not valid UTF-8.
+unsafe fn string(s: *const c_char) -> Result<Option<String>, ()> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    match CStr::from_ptr(s).to_str() {
+        Ok(s) => Ok(Some(s.to_string())),
+        Err(_) => Err(()),
+    }
+}
+
+/// Creates a snippet titled with `label` and `id`, which may both be null,
+/// or without a title if `annotation_type` is negative. Returns null on
+/// invalid arguments.
+///
+/// # Safety
+///
+/// `id` and `label` are null or NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn annotate_snippets_snippet_new(
+    annotation_type: c_int,
+    id: *const c_char,
+    label: *const c_char,
+) -> *mut SnippetHandle {
+    let title = if annotation_type < 0 {
+        None
+    } else {
+        match (
+            self::annotation_type(annotation_type),
+            string(id),
+            string(label),
+        ) {
+            (Some(annotation_type), Ok(id), Ok(label)) => Some(OwnedAnnotation {
+                id,
+                label,
+                annotation_type,
+            }),
+            _ => return ptr::null_mut(),
+        }
+    };
+    Box::into_raw(Box::new(SnippetHandle {
+        snippet: OwnedSnippet {
+            title,
+            ..Default::default()
+        },
+    }))
+}
+
+/// Frees a snippet.
+///
+/// # Safety
+///
+/// `snippet` is null or a snippet of `annotate_snippets_snippet_new` which
+/// was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn annotate_snippets_snippet_free(snippet: *mut SnippetHandle) {
+    if !snippet.is_null() {
+        drop(Box::from_raw(snippet));
+    }
+}
+
+/// Appends a slice of `source`, whose first line is `line_start`, from
+/// `origin`, which may be null. Returns the index of the slice, or -1 if it
+/// does not fit in an `int`.
+///
+/// # Safety
+///
+/// `snippet` is a snippet of `annotate_snippets_snippet_new`, and `source`
+/// and `origin` are null or NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn annotate_snippets_snippet_add_slice(
+    snippet: *mut SnippetHandle,
+    source: *const c_char,
+    line_start: usize,
+    origin: *const c_char,
+    fold: bool,
+) -> c_int {
+    let snippet = match snippet.as_mut() {
+        Some(handle) => &mut handle.snippet,
+        None => return -1,
+    };
+    let (source, origin) = match (string(source), string(origin)) {
+        (Ok(Some(source)), Ok(origin)) => (source, origin),
+        _ => return -1,
+    };
+    let index = match c_int::try_from(snippet.slices.len()) {
+        Ok(index) => index,
+        Err(_) => return -1,
+    };
+    snippet.slices.push(OwnedSlice {
+        source,
+        line_start,
+        origin,
+        annotations: vec![],
+        fold,
+        line_highlights: vec![],
+        origin_note: None,
+    });
+    index
+}
+
+/// Appends an annotation of the chars `start..end` of the slice `slice`,
+/// which must lie within its source. Returns 0.
+///
+/// # Safety
+///
+/// `snippet` is a snippet of `annotate_snippets_snippet_new`, and `label` is
+/// null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn annotate_snippets_snippet_add_annotation(
+    snippet: *mut SnippetHandle,
+    slice: usize,
+    start: usize,
+    end: usize,
+    annotation_type: c_int,
+    label: *const c_char,
+) -> c_int {
+    let slice = match snippet
+        .as_mut()
+        .and_then(|handle| handle.snippet.slices.get_mut(slice))
+    {
+        Some(slice) => slice,
+        None => return -1,
+    };
+    match (self::annotation_type(annotation_type), string(label)) {
+        (Some(annotation_type), Ok(label))
+            if start <= end && end <= slice.source.chars().count() =>
+        {
+            slice.annotations.push(OwnedSourceAnnotation {
+                range: (start, end),
+                label: label.unwrap_or_default(),
+                annotation_type,
+                priority: Priority::Normal,
+            });
+            0
+        }
+        _ => -1,
+    }
+}
+
+/// Appends a footer. Returns 0.
+///
+/// # Safety
+///
+/// `snippet` is a snippet of `annotate_snippets_snippet_new`, and `label` is
+/// null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn annotate_snippets_snippet_add_footer(
+    snippet: *mut SnippetHandle,
+    annotation_type: c_int,
+    label: *const c_char,
+) -> c_int {
+    let snippet = match snippet.as_mut() {
+        Some(handle) => &mut handle.snippet,
+        None => return -1,
+    };
+    match (self::annotation_type(annotation_type), string(label)) {
+        (Some(annotation_type), Ok(label)) => {
+            snippet.footer.push(OwnedAnnotation {
+                id: None,
+                label,
+                annotation_type,
+            });
+            0
+        }
+        _ => -1,
+    }
+}
+
+/// Renders the snippet, colored if `color` is set, into `buffer` of `size`
+/// bytes as a NUL-terminated string, truncated at a char boundary if it is
+/// too small. Returns the length of the rendered snippet, without the NUL.
+///
+/// `buffer` may be null with a `size` of 0, to get the size to allocate.
+/// A snippet which fails to render, as no panic may unwind into the caller,
+/// renders as an empty string.
+///
+/// # Safety
+///
+/// `snippet` is a snippet of `annotate_snippets_snippet_new`, and `buffer`
+/// holds `size` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn annotate_snippets_snippet_render(
+    snippet: *const SnippetHandle,
+    color: bool,
+    buffer: *mut c_char,
+    size: usize,
+) -> usize {
+    let snippet = match snippet.as_ref() {
+        Some(handle) => &handle.snippet,
+        None => return 0,
+    };
+    let mut snippet = snippet.to_snippet();
+    snippet.opt = FormatOptions {
+        color,
+        ..snippet.opt
+    };
+    let rendered = panic::catch_unwind(AssertUnwindSafe(|| {
+        DisplayList::try_from_snippet(snippet).map(|dl| dl.to_string())
+    }));
+    let rendered = match rendered {
+        Ok(Ok(rendered)) => rendered,
+        _ => String::new(),
+    };
+    if size > 0 && !buffer.is_null() {
+        let mut len = std::cmp::min(rendered.len(), size - 1);
+        while !rendered.is_char_boundary(len) {
+            len -= 1;
+        }
+        ptr::copy_nonoverlapping(rendered.as_ptr(), buffer as *mut u8, len);
+        *buffer.add(len) = 0;
+    }
+    rendered.len()
+}
@@ -0,0 +1,175 @@
+//! Snippets whose texts are Fluent messages.
+//!
+//! With the `fluent-bundle` feature, tools shipping translation bundles write
+//! the titles, footers and labels of their snippets as identifiers of the
+//! messages of a `FluentBundle`, `id` or `id.attribute`, and a `Localizer`
+//! formats them with the arguments of the diagnostic when the snippet is
+//! rendered.
+//!
+//! ```
+//! use annotate_snippets::fluent::Localizer;
+//! use annotate_snippets::snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation};
+//! use fluent_bundle::{FluentArgs, FluentBundle, FluentResource};
+//!
+//! let ftl = "unused-variable = unused variable: `{ $name }`\n    .label = never read\n";
+//! let mut bundle = FluentBundle::default();
+//! bundle
+//!     .add_resource(FluentResource::try_new(ftl.to_string()).unwrap())
+//!     .unwrap();
+//! let localizer = Localizer::new(bundle);
+//!
+//! let snippet = Snippet::new()
+//!     .title(Annotation::new(AnnotationType::Warning, "unused-variable"))
+//!     .slice(Slice::new("let x = 1;", 1).annotation(SourceAnnotation::new(
+//!         (4, 5),
+//!         AnnotationType::Warning,
+//!         "unused-variable.label",
+//!     )));
+//! let mut args = FluentArgs::new();
+//! args.set("name", "x");
+//! assert_eq!(
+//!     localizer.render(&snippet, Some(&args)).unwrap(),
+//!     r#"warning: unused variable: `x`
+//!   |
+//! 1 | let x = 1;
+//!   |     - never read
+//!   |"#
+//! );
+//! ```
+use std::borrow::Borrow;
+use std::error::Error;
+use std::fmt;
+
+use fluent_bundle::{FluentArgs, FluentBundle, FluentError, FluentResource};
+
+use crate::display_list::{DisplayList, OwnedAnnotation, OwnedSnippet};
+use crate::snippet::Snippet;
+
+/// An error resolving a message of a snippet.
+#[derive(Debug)]
+pub enum LocalizeError {
+    /// A message, or an attribute of a message, missing from the bundle.
+    MissingMessage(String),
+    /// A message without a value, used without one of its attributes.
+    MissingValue(String),
+    /// Errors formatting a message, like a missing argument.
+    Format(String, Vec<FluentError>),
+}
+
+impl fmt::Display for LocalizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LocalizeError::MissingMessage(id) => write!(f, "missing message `{}`", id),
+            LocalizeError::MissingValue(id) => write!(f, "message `{}` has no value", id),
+            LocalizeError::Format(id, errors) => {
+                write!(f, "failed to format message `{}`", id)?;
+                for error in errors {
+                    write!(f, ": {}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for LocalizeError {}
+
+/// Resolves the texts of snippets from the messages of a bundle.
+pub struct Localizer<R> {
+    bundle: FluentBundle<R>,
+}
+
+impl<R: Borrow<FluentResource>> Localizer<R> {
+    /// Creates a localizer of the messages of `bundle`.
+    ///
+    /// The Unicode isolation marks around the arguments are turned off, as
+    /// terminals would display them.
+    pub fn new(mut bundle: FluentBundle<R>) -> Self {
+        bundle.set_use_isolating(false);
+        Self { bundle }
+    }
+
+    /// The bundle of the messages.
+    pub fn bundle(&self) -> &FluentBundle<R> {
+        &self.bundle
+    }
+
+    /// Formats the message `id`, or its attribute if `id` is
+    /// `message.attribute`, with `args`.
+    pub fn message(
+        &self,
+        id: &str,
+        args: Option<&FluentArgs<'_>>,
+    ) -> Result<String, LocalizeError> {
+        let (message_id, attribute) = match id.split_once('.') {
+            Some((message_id, attribute)) => (message_id, Some(attribute)),
+            None => (id, None),
+        };
+        let missing = || LocalizeError::MissingMessage(id.to_string());
+        let message = self.bundle.get_message(message_id).ok_or_else(missing)?;
+        let pattern = match attribute {
+            Some(attribute) => message
+                .get_attribute(attribute)
+                .ok_or_else(missing)?
+                .value(),
+            None => message
+                .value()
+                .ok_or_else(|| LocalizeError::MissingValue(id.to_string()))?,
+        };
+        let mut errors = vec![];
+        let text = self.bundle.format_pattern(pattern, args, &mut errors);
+        if errors.is_empty() {
+            Ok(text.into_owned())
+        } else {
+            Err(LocalizeError::Format(id.to_string(), errors))
+        }
+    }
+
+    /// Copies `snippet`, formatting its titles, footers and annotation labels
+    /// as the identifiers of messages, with the arguments `args` of the
+    /// diagnostic. Empty labels stay empty.
+    ///
+    /// As with the `From` conversion to `OwnedSnippet`, the lint, the
+    /// expansion frames and the includes of the snippet are not copied.
+    pub fn localize(
+        &self,
+        snippet: &Snippet<'_>,
+        args: Option<&FluentArgs<'_>>,
+    ) -> Result<OwnedSnippet, LocalizeError> {
+        let resolve = |label: &mut String| -> Result<(), LocalizeError> {
+            if !label.is_empty() {
+                *label = self.message(label, args)?;
+            }
+            Ok(())
+        };
+        let resolve_annotation = |annotation: &mut OwnedAnnotation| match &mut annotation.label {
+            Some(label) => resolve(label),
+            None => Ok(()),
+        };
+
+        let mut owned = OwnedSnippet::from(snippet);
+        if let Some(title) = &mut owned.title {
+            resolve_annotation(title)?;
+        }
+        for annotation in owned.extra_titles.iter_mut().chain(&mut owned.footer) {
+            resolve_annotation(annotation)?;
+        }
+        for slice in &mut owned.slices {
+            for annotation in &mut slice.annotations {
+                resolve(&mut annotation.label)?;
+            }
+        }
+        Ok(owned)
+    }
+
+    /// Renders `snippet` with its messages formatted with `args`, as
+    /// `localize` resolves them.
+    pub fn render(
+        &self,
+        snippet: &Snippet<'_>,
+        args: Option<&FluentArgs<'_>>,
+    ) -> Result<String, LocalizeError> {
+        let owned = self.localize(snippet, args)?;
+        Ok(DisplayList::from(owned.to_snippet()).to_string())
+    }
+}
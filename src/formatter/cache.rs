@@ -0,0 +1,207 @@
+//! Rendering of snippets with a cache of their outputs.
+//!
+//! Tools in watch mode render mostly the same diagnostics on every rebuild.
+//! A `RenderCache` keeps the output of each snippet under the key of the
+//! diagnostic, made of its origins, spans, messages and options, and renders
+//! again only the snippets it has not seen.
+//!
+//! The sources of slices with an origin are not part of the key: when a file
+//! changes, the outputs of the snippets of the file are dropped with
+//! `invalidate_origin`. The sources of slices without an origin, which cannot
+//! be invalidated so, are part of it.
+//!
+//! ```
+//! use annotate_snippets::formatter::cache::RenderCache;
+//! use annotate_snippets::snippet::{AnnotationType, Slice, Snippet, SourceAnnotation};
+//!
+//! let snippet = Snippet::new().slice(
+//!     Slice::new("let x = 1;", 1)
+//!         .origin("src/main.rs")
+//!         .annotation(SourceAnnotation::new((4, 5), AnnotationType::Warning, "unused")),
+//! );
+//!
+//! let mut cache = RenderCache::new();
+//! let first = cache.render(&snippet).to_string();
+//! assert_eq!(cache.render(&snippet), first);
+//! assert_eq!(cache.len(), 1);
+//!
+//! cache.invalidate_origin("src/main.rs");
+//! assert!(cache.is_empty());
+//! ```
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use super::renderer::Renderer;
+use crate::snippet::{Slice, Snippet};
+
+/// Renders snippets through a `Renderer`, keeping their outputs by key.
+#[derive(Default)]
+pub struct RenderCache {
+    renderer: Renderer,
+    entries: HashMap<u64, Entry>,
+}
+
+struct Entry {
+    /// The data hashed into the key, telling snippets with colliding keys
+    /// apart.
+    data: Vec<u8>,
+    origins: Vec<String>,
+    rendered: String,
+}
+
+impl RenderCache {
+    /// Creates an empty cache, rendering with a new `Renderer`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty cache, rendering with `renderer` and its passes.
+    pub fn with_renderer(renderer: Renderer) -> Self {
+        Self {
+            renderer,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Renders the snippet as `Renderer::render` does, or returns its output
+    /// of an earlier call if the key of the snippet is cached.
+    ///
+    /// A snippet whose key collides with the key of another cached snippet
+    /// is rendered again, and replaces its output.
+    pub fn render(&mut self, snippet: &Snippet<'_>) -> &str {
+        let data = key_data(snippet);
+        let key = hash(&data);
+        let cached = matches!(self.entries.get(&key), Some(entry) if entry.data == data);
+        if !cached {
+            let entry = Entry {
+                data,
+                origins: origins(snippet).map(str::to_string).collect(),
+                rendered: self.renderer.render(snippet).to_string(),
+            };
+            self.entries.insert(key, entry);
+        }
+        &self.entries[&key].rendered
+    }
+
+    /// Returns `true` if the output of a snippet with the key of `snippet` is
+    /// cached.
+    pub fn contains(&self, snippet: &Snippet<'_>) -> bool {
+        self.get(snippet).is_some()
+    }
+
+    /// Drops the output of the snippets with the key of `snippet`.
+    pub fn invalidate(&mut self, snippet: &Snippet<'_>) {
+        if self.get(snippet).is_some() {
+            self.entries.remove(&key(snippet));
+        }
+    }
+
+    /// Drops the outputs of the snippets with a slice from `origin`, as they
+    /// are given in the slices, whose source may have changed.
+    pub fn invalidate_origin(&mut self, origin: &str) {
+        self.entries
+            .retain(|_, entry| !entry.origins.iter().any(|o| o == origin));
+    }
+
+    /// The entry of `snippet`, if it is not one of a colliding key.
+    fn get(&self, snippet: &Snippet<'_>) -> Option<&Entry> {
+        let data = key_data(snippet);
+        self.entries
+            .get(&hash(&data))
+            .filter(|entry| entry.data == data)
+    }
+
+    /// Drops all outputs.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Number of cached outputs.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no outputs are cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// The key of the diagnostic of `snippet`: a hash of its titles, lint,
+/// footers, spans, labels, origins, frames and options, and of the sources of
+/// its slices without an origin.
+///
+/// The key is the same from one run of a program to the next, unless the
+/// options have a path normalizer, which is hashed by its address.
+pub fn key(snippet: &Snippet<'_>) -> u64 {
+    hash(&key_data(snippet))
+}
+
+fn hash(data: &[u8]) -> u64 {
+    // `DefaultHasher::new` always hashes with the same keys.
+    let mut state = DefaultHasher::new();
+    state.write(data);
+    state.finish()
+}
+
+/// A `Hasher` keeping the bytes it is given, as the data of a key.
+#[derive(Default)]
+struct KeyData(Vec<u8>);
+
+impl Hasher for KeyData {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        hash(&self.0)
+    }
+}
+
+/// The data hashed into the key of `snippet`. The `Hash` implementations
+/// prefix sequences with their lengths and terminate strings, so two
+/// snippets with the same data have the same key components.
+fn key_data(snippet: &Snippet<'_>) -> Vec<u8> {
+    fn hash_slice<H: Hasher>(slice: &Slice<'_>, state: &mut H) {
+        slice.origin.hash(state);
+        if slice.origin.is_none() {
+            slice.source.hash(state);
+        }
+        slice.origin_note.hash(state);
+        slice.line_start.hash(state);
+        slice.annotations.hash(state);
+        slice.fold.hash(state);
+        slice.line_highlights.hash(state);
+    }
+
+    let mut state = KeyData::default();
+    snippet.title.hash(&mut state);
+    snippet.extra_titles.hash(&mut state);
+    snippet.lint.hash(&mut state);
+    snippet.footer.hash(&mut state);
+    snippet.slices.len().hash(&mut state);
+    for slice in &snippet.slices {
+        hash_slice(slice, &mut state);
+    }
+    snippet.expansion.len().hash(&mut state);
+    for frame in &snippet.expansion {
+        frame.label.hash(&mut state);
+        hash_slice(&frame.slice, &mut state);
+    }
+    snippet.includes.len().hash(&mut state);
+    for include in &snippet.includes {
+        include.path.hash(&mut state);
+        include.line.hash(&mut state);
+    }
+    snippet.opt.hash(&mut state);
+    state.0
+}
+
+/// The origins of the slices of `snippet` and of its expansion frames.
+fn origins<'s>(snippet: &'s Snippet<'_>) -> impl Iterator<Item = &'s str> {
+    let slices = snippet.slices.iter();
+    slices
+        .chain(snippet.expansion.iter().map(|frame| &frame.slice))
+        .filter_map(|slice| slice.origin)
+}
@@ -0,0 +1,62 @@
+//! Hook for syntax highlighting of source lines.
+//!
+//! A `Highlighter` provides styled segments for every source line of a
+//! `DisplayList`, for example from the lexer of the embedding tool. The
+//! segments only add styling, so the columns of annotations are not affected.
+//!
+//! ```
+//! use annotate_snippets::formatter::highlight::{HighlightSegment, Highlighter};
+//! use annotate_snippets::formatter::style::{Color, StyleSpec};
+//!
+//! struct Keywords;
+//!
+//! impl Highlighter for Keywords {
+//!     fn highlight_line(
+//!         &self,
+//!         _origin: Option<&str>,
+//!         _lineno: Option<usize>,
+//!         line: &str,
+//!     ) -> Vec<HighlightSegment> {
+//!         line.match_indices("fn")
+//!             .map(|(idx, kw)| HighlightSegment {
+//!                 range: idx..idx + kw.len(),
+//!                 style: StyleSpec::new().fg(Color::Magenta),
+//!             })
+//!             .collect()
+//!     }
+//! }
+//! ```
+//!
+//! With the `syntect` feature, `SyntectHighlighter` highlights lines based
+//! on the extension of the slice origin.
+use std::ops::Range;
+
+use crate::formatter::style::StyleSpec;
+
+#[cfg(feature = "syntect")]
+mod syntect;
+#[cfg(feature = "syntect")]
+pub use self::syntect::{SyntectHighlighter, DEFAULT_THEME};
+
+/// A styled part of a source line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighlightSegment {
+    /// Byte range of the segment within the line.
+    pub range: Range<usize>,
+    pub style: StyleSpec,
+}
+
+/// Trait implemented by providers of syntax highlighting.
+pub trait Highlighter {
+    /// Returns the styled segments of a single source line.
+    ///
+    /// `origin` is the origin of the slice the line belongs to. Segments are
+    /// expected to be sorted and not to overlap; parts of the line which are
+    /// not covered by any segment are written without styling.
+    fn highlight_line(
+        &self,
+        origin: Option<&str>,
+        lineno: Option<usize>,
+        line: &str,
+    ) -> Vec<HighlightSegment>;
+}
@@ -0,0 +1,127 @@
+//! `Highlighter` backed by `syntect`.
+use std::cell::RefCell;
+use std::path::Path;
+
+use syntect::highlighting::{
+    FontStyle, HighlightState, Highlighter as ThemeHighlighter, RangedHighlightIterator, Theme,
+    ThemeSet,
+};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxSet};
+
+use super::{HighlightSegment, Highlighter};
+use crate::formatter::style::{Color, StyleSpec};
+
+/// Name of the theme of the default `ThemeSet` used by `SyntectHighlighter::new`.
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+/// Highlights source lines with `syntect`, choosing the syntax by the
+/// extension of the slice origin.
+///
+/// Lines of slices whose origin has no known extension are left unstyled.
+/// Backgrounds of the theme are ignored, so the highlighting composes with
+/// the colors of the stylesheet.
+pub struct SyntectHighlighter {
+    syntaxes: SyntaxSet,
+    theme: Theme,
+    state: RefCell<Option<LineState>>,
+}
+
+/// Parser state carried from one line to the next one of the same slice.
+struct LineState {
+    origin: String,
+    lineno: Option<usize>,
+    parse: ParseState,
+    highlight: HighlightState,
+}
+
+impl Default for SyntectHighlighter {
+    fn default() -> Self {
+        let theme = ThemeSet::load_defaults()
+            .themes
+            .remove(DEFAULT_THEME)
+            .unwrap_or_default();
+        Self::with_theme(SyntaxSet::load_defaults_nonewlines(), theme)
+    }
+}
+
+impl SyntectHighlighter {
+    /// Creates a highlighter with the default syntaxes and theme of `syntect`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a highlighter with custom syntaxes and theme.
+    ///
+    /// The syntaxes are expected to be loaded for lines without newlines.
+    pub fn with_theme(syntaxes: SyntaxSet, theme: Theme) -> Self {
+        Self {
+            syntaxes,
+            theme,
+            state: RefCell::new(None),
+        }
+    }
+
+    fn line_state(&self, origin: &str, lineno: Option<usize>) -> Option<LineState> {
+        let mut state = self.state.borrow_mut().take();
+        // Folded lines are skipped, but a new origin, or going back, requires a fresh parser.
+        let reusable = state.as_ref().is_some_and(|state| {
+            state.origin == origin
+                && match (state.lineno, lineno) {
+                    (Some(prev), Some(next)) => prev < next,
+                    _ => false,
+                }
+        });
+        if !reusable {
+            let extension = Path::new(origin).extension()?.to_str()?;
+            let syntax = self.syntaxes.find_syntax_by_extension(extension)?;
+            let highlighter = ThemeHighlighter::new(&self.theme);
+            state = Some(LineState {
+                origin: origin.to_string(),
+                lineno,
+                parse: ParseState::new(syntax),
+                highlight: HighlightState::new(&highlighter, ScopeStack::new()),
+            });
+        }
+        state
+    }
+}
+
+impl Highlighter for SyntectHighlighter {
+    fn highlight_line(
+        &self,
+        origin: Option<&str>,
+        lineno: Option<usize>,
+        line: &str,
+    ) -> Vec<HighlightSegment> {
+        let mut state = match origin.and_then(|origin| self.line_state(origin, lineno)) {
+            Some(state) => state,
+            None => return vec![],
+        };
+        let ops = match state.parse.parse_line(line, &self.syntaxes) {
+            Ok(ops) => ops,
+            Err(_) => return vec![],
+        };
+
+        let highlighter = ThemeHighlighter::new(&self.theme);
+        // Text in the default style keeps the color of the terminal.
+        let plain = highlighter.get_default();
+        let segments = RangedHighlightIterator::new(&mut state.highlight, &ops, line, &highlighter)
+            .filter(|(style, _, _)| *style != plain)
+            .map(|(style, _, range)| {
+                let mut spec = StyleSpec::new().fg(Color::Rgb(
+                    style.foreground.r,
+                    style.foreground.g,
+                    style.foreground.b,
+                ));
+                spec.bold = style.font_style.contains(FontStyle::BOLD);
+                spec.italic = style.font_style.contains(FontStyle::ITALIC);
+                spec.underline = style.font_style.contains(FontStyle::UNDERLINE);
+                HighlightSegment { range, style: spec }
+            })
+            .collect();
+
+        state.lineno = lineno;
+        *self.state.borrow_mut() = Some(state);
+        segments
+    }
+}
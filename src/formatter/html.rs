@@ -0,0 +1,143 @@
+//! Conversion of rendered snippets to HTML.
+//!
+//! Environments without a terminal, like a page of a browser, can display a
+//! snippet rendered with colors as HTML. The ANSI escape sequences become
+//! `<span>` elements with inline styles, and the text is escaped, so the
+//! result can be placed in a `<pre>` element.
+//!
+//! ```
+//! use annotate_snippets::formatter::html::ansi_to_html;
+//!
+//! assert_eq!(
+//!     ansi_to_html("\x1b[1;31merror\x1b[0m: <T>"),
+//!     "<span style=\"color:#cd0000;font-weight:bold\">error</span>: &lt;T&gt;"
+//! );
+//! ```
+use super::style::{Color, StyleSpec};
+use crate::display_list::DisplayList;
+
+impl<'a> DisplayList<'a> {
+    /// Renders the list as HTML, with the colors of its stylesheet.
+    pub fn to_html(&self) -> String {
+        ansi_to_html(&self.to_string())
+    }
+}
+
+/// Converts text holding ANSI escape sequences to HTML.
+///
+/// Sequences setting colors and attributes become styles, other sequences are
+/// removed.
+pub fn ansi_to_html(s: &str) -> String {
+    let mut html = String::with_capacity(s.len());
+    let mut style = StyleSpec::new();
+    let mut open = false;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            if !open && style != StyleSpec::new() {
+                html.push_str("<span style=\"");
+                html.push_str(&css(&style));
+                html.push_str("\">");
+                open = true;
+            }
+            match c {
+                '<' => html.push_str("&lt;"),
+                '>' => html.push_str("&gt;"),
+                '&' => html.push_str("&amp;"),
+                '"' => html.push_str("&quot;"),
+                c => html.push(c),
+            }
+            continue;
+        }
+        if let Some('[') = chars.next() {
+            let mut params = String::new();
+            let mut end = None;
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    end = Some(c);
+                    break;
+                }
+                params.push(c);
+            }
+            if end == Some('m') {
+                let next = apply_sgr(style, &params);
+                if next != style && open {
+                    html.push_str("</span>");
+                    open = false;
+                }
+                style = next;
+            }
+        }
+    }
+    if open {
+        html.push_str("</span>");
+    }
+    html
+}
+
+/// Applies the parameters of a "select graphic rendition" sequence.
+pub(super) fn apply_sgr(mut style: StyleSpec, params: &str) -> StyleSpec {
+    let mut codes = params
+        .split(';')
+        .map(|code| code.parse::<u8>().unwrap_or_default());
+    while let Some(code) = codes.next() {
+        match code {
+            0 => style = StyleSpec::new(),
+            1 => style.bold = true,
+            2 => style.dimmed = true,
+            3 => style.italic = true,
+            4 => style.underline = true,
+            22 => {
+                style.bold = false;
+                style.dimmed = false;
+            }
+            23 => style.italic = false,
+            24 => style.underline = false,
+            30..=37 => style.foreground = Some(Color::Fixed(code - 30)),
+            90..=97 => style.foreground = Some(Color::Fixed(code - 90 + 8)),
+            40..=47 => style.background = Some(Color::Fixed(code - 40)),
+            100..=107 => style.background = Some(Color::Fixed(code - 100 + 8)),
+            38 => style.foreground = extended_color(&mut codes),
+            48 => style.background = extended_color(&mut codes),
+            39 => style.foreground = None,
+            49 => style.background = None,
+            _ => {}
+        }
+    }
+    style
+}
+
+/// Reads the color of a `38` or `48` parameter, from the 256-color palette
+/// or as a 24-bit value.
+fn extended_color(codes: &mut impl Iterator<Item = u8>) -> Option<Color> {
+    match codes.next()? {
+        5 => codes.next().map(Color::Fixed),
+        2 => Some(Color::Rgb(codes.next()?, codes.next()?, codes.next()?)),
+        _ => None,
+    }
+}
+
+fn css(style: &StyleSpec) -> String {
+    let mut css = vec![];
+    if let Some(color) = style.foreground {
+        let (r, g, b) = color.to_rgb();
+        css.push(format!("color:#{:02x}{:02x}{:02x}", r, g, b));
+    }
+    if let Some(color) = style.background {
+        let (r, g, b) = color.to_rgb();
+        css.push(format!("background-color:#{:02x}{:02x}{:02x}", r, g, b));
+    }
+    if style.bold {
+        css.push("font-weight:bold".to_string());
+    }
+    if style.dimmed {
+        css.push("opacity:0.7".to_string());
+    }
+    if style.italic {
+        css.push("font-style:italic".to_string());
+    }
+    if style.underline {
+        css.push("text-decoration:underline".to_string());
+    }
+    css.join(";")
+}
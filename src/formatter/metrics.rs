@@ -0,0 +1,177 @@
+//! Measures of rendered snippets.
+//!
+//! Tools logging telemetry, or warning their users that a diagnostic was
+//! elided, get the `RenderMetrics` of a list with `DisplayList::metrics`, or
+//! of every snippet a `Renderer` renders with `Renderer::on_metrics`. Only
+//! the width of a list is measured by `DisplayList::rendered_width`.
+//!
+//! ```
+//! use annotate_snippets::display_list::{DisplayList, FormatOptions};
+//! use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+//!
+//! let snippet = Snippet::new()
+//!     .title(Annotation::new(AnnotationType::Error, "a rather long title"))
+//!     .opt(FormatOptions::new().max_width(12));
+//! let metrics = DisplayList::from(snippet).metrics();
+//! assert_eq!(metrics.wrapped_lines, 1);
+//! assert_eq!((metrics.width, metrics.height), (11, 3));
+//! ```
+use std::cmp;
+
+use super::{cells, FormattedLine, WithoutMaxWidth};
+use crate::display_list::{DisplayLine, DisplayList, DisplayRawLine, DisplaySourceLine};
+
+/// What rendering did to the lines of a snippet, and the size of its output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RenderMetrics {
+    /// Number of source lines left out by the folds of the slices.
+    pub folded_lines: usize,
+    /// Number of source lines cut by the margin or by the maximal width.
+    pub truncated_lines: usize,
+    /// Number of other lines wrapped to the maximal width.
+    pub wrapped_lines: usize,
+    /// Number of columns of the widest line of the output.
+    pub width: usize,
+    /// Number of lines of the output.
+    pub height: usize,
+}
+
+impl RenderMetrics {
+    /// Returns `true` if some of the source was not displayed.
+    pub fn is_elided(&self) -> bool {
+        self.folded_lines > 0 || self.truncated_lines > 0
+    }
+}
+
+/// Measures lines as they are rendered.
+#[derive(Default)]
+pub(crate) struct MetricsCounter {
+    metrics: RenderMetrics,
+    /// Number of the last source line of the current slice.
+    last_lineno: Option<usize>,
+    /// Whether a fold follows the last source line.
+    folded: bool,
+}
+
+impl MetricsCounter {
+    /// Measures a line, `rendered` as it is.
+    pub(crate) fn count(&mut self, line: &FormattedLine<'_, '_>, rendered: &str) {
+        let dl = line.dl;
+        let mut truncated = false;
+        match line.line {
+            DisplayLine::Source {
+                lineno: Some(lineno),
+                line:
+                    DisplaySourceLine::Content { text, .. } | DisplaySourceLine::Compact { text, .. },
+                ..
+            } => {
+                if let (true, Some(last)) = (self.folded, self.last_lineno) {
+                    self.metrics.folded_lines += lineno.saturating_sub(last + 1);
+                }
+                self.folded = false;
+                self.last_lineno = Some(*lineno);
+                if let Some(margin) = dl.margin {
+                    truncated = margin.was_cut_left() || margin.was_cut_right(text.chars().count());
+                }
+            }
+            DisplayLine::Source { .. } => {}
+            DisplayLine::Fold { .. } => self.folded = true,
+            // Lines of folds elided with the source lines are folded too.
+            DisplayLine::Elided { count, .. } => {
+                self.metrics.folded_lines += count;
+                self.last_lineno = self.last_lineno.map(|last| last + count);
+                self.folded = true;
+            }
+            DisplayLine::Raw(_) => {
+                self.folded = false;
+                self.last_lineno = None;
+            }
+        }
+
+        let tab_width = dl.tab_width.unwrap_or(8);
+        if let Some(max_width) = dl.max_width {
+            let unlimited = WithoutMaxWidth(line).to_string();
+            if width(&unlimited, tab_width) > max_width {
+                match line.line {
+                    DisplayLine::Source {
+                        line: DisplaySourceLine::Annotation { .. },
+                        ..
+                    } => self.metrics.wrapped_lines += 1,
+                    DisplayLine::Source { .. } => truncated = true,
+                    _ => self.metrics.wrapped_lines += 1,
+                }
+            }
+        }
+        if truncated {
+            self.metrics.truncated_lines += 1;
+        }
+
+        for rendered in rendered.split('\n') {
+            self.metrics.height += 1;
+            self.metrics.width = cmp::max(self.metrics.width, width(rendered, tab_width));
+        }
+    }
+
+    pub(crate) fn finish(self) -> RenderMetrics {
+        self.metrics
+    }
+}
+
+/// Number of columns of a rendered line.
+fn width(rendered: &str, tab_width: usize) -> usize {
+    cells(rendered, tab_width)
+        .iter()
+        .map(|cell| cell.width)
+        .sum()
+}
+
+impl<'a> DisplayList<'a> {
+    /// Measures the output of the list.
+    pub fn metrics(&self) -> RenderMetrics {
+        let mut counter = MetricsCounter::default();
+        self.for_each_rendered(|line, rendered| counter.count(line, rendered));
+        counter.finish()
+    }
+
+    /// Number of columns of the widest line of the output, in cells, as
+    /// `metrics` measures it, and without writing the output anywhere.
+    ///
+    /// Callers given the width of a terminal can choose the layout of a
+    /// snippet with it, like a maximal width or the compact mode.
+    ///
+    /// ```
+    /// use annotate_snippets::display_list::DisplayList;
+    /// use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+    ///
+    /// let snippet = Snippet::new().title(Annotation::new(AnnotationType::Error, "ünused"));
+    /// assert_eq!(DisplayList::from(snippet).rendered_width(), 13);
+    /// ```
+    pub fn rendered_width(&self) -> usize {
+        let tab_width = self.tab_width.unwrap_or(8);
+        let mut widest = 0;
+        self.for_each_rendered(|_, rendered| {
+            for rendered in rendered.split('\n') {
+                widest = cmp::max(widest, width(rendered, tab_width));
+            }
+        });
+        widest
+    }
+
+    /// Calls `f` with every line of the list and its output.
+    fn for_each_rendered(&self, mut f: impl FnMut(&FormattedLine<'_, '_>, &str)) {
+        let widths = self.column_widths();
+        let mut origin = None;
+        for line in &self.body {
+            if let DisplayLine::Raw(DisplayRawLine::Origin { path, .. }) = line {
+                origin = Some(path.as_ref());
+            }
+            let line = FormattedLine {
+                dl: self,
+                line,
+                widths,
+                origin,
+            };
+            f(&line, &line.to_string());
+        }
+    }
+}
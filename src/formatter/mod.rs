@@ -1,15 +1,28 @@
+#[cfg(feature = "color")]
+use std::sync::OnceLock;
 use std::{
+    borrow::Cow,
     cmp,
     fmt::{self, Display, Write},
     iter::once,
 };
 
+pub mod cache;
+pub mod highlight;
+pub mod html;
+pub mod metrics;
+#[cfg(feature = "ratatui")]
+pub mod ratatui;
+pub mod renderer;
+pub mod stream;
 pub mod style;
 
-use self::style::{Style, StyleClass, Stylesheet};
+use self::style::{Color, Style, StyleClass, StyleSpec, Stylesheet};
 
+use crate::snippet::LineChange;
 #[cfg(feature = "color")]
 use crate::stylesheets::color::AnsiTermStylesheet;
+use crate::stylesheets::theme::Theme;
 use crate::{display_list::*, stylesheets::no_color::NoColorStylesheet};
 
 fn format_repeat_char(c: char, n: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -19,6 +32,16 @@ fn format_repeat_char(c: char, n: usize, f: &mut fmt::Formatter<'_>) -> fmt::Res
     Ok(())
 }
 
+/// The full-width form of an underline mark, if it has one.
+fn full_width_mark(mark: char) -> Option<char> {
+    match mark {
+        ' ' => Some('\u{3000}'),
+        // The full-width forms of printable ASCII are in the same order.
+        '!'..='~' => char::from_u32(mark as u32 + 0xFEE0),
+        _ => None,
+    }
+}
+
 #[inline]
 fn is_annotation_empty(annotation: &Annotation<'_>) -> bool {
     annotation
@@ -27,56 +50,502 @@ fn is_annotation_empty(annotation: &Annotation<'_>) -> bool {
         .all(|fragment| fragment.content.is_empty())
 }
 
+/// Returns the stylesheet of the output, with colors or without.
+///
+/// The colored stylesheet is read from the terminal and the environment once
+/// per process, by `AnsiTermStylesheet::from_env`; a malformed theme in the
+/// environment is not worth failing the output for, and leaves the default
+/// styles. Tools reporting it make the stylesheet themselves, and render
+/// with `Renderer::stylesheet`.
 #[cfg(feature = "color")]
-#[inline]
 pub fn get_term_style(color: bool) -> Box<dyn Stylesheet> {
+    static TERM_STYLESHEET: OnceLock<AnsiTermStylesheet> = OnceLock::new();
+
     if color {
-        Box::new(AnsiTermStylesheet)
+        let stylesheet =
+            TERM_STYLESHEET.get_or_init(|| AnsiTermStylesheet::from_env().unwrap_or_default());
+        Box::new(stylesheet.clone())
     } else {
         Box::new(NoColorStylesheet)
     }
 }
 
+/// Returns the stylesheet of the output, without colors as the crate is
+/// built without the `color` feature.
 #[cfg(not(feature = "color"))]
-#[inline]
 pub fn get_term_style(_color: bool) -> Box<dyn Stylesheet> {
     Box::new(NoColorStylesheet)
 }
 
-impl<'a> fmt::Display for DisplayList<'a> {
+/// Returns the stylesheet of the output, with the styles of `theme` over the
+/// default ones if it is set.
+#[cfg(feature = "color")]
+pub(crate) fn get_theme_style(color: bool, theme: Option<&Theme>) -> Box<dyn Stylesheet> {
+    match theme {
+        Some(theme) if color => {
+            let mut stylesheet = AnsiTermStylesheet::default();
+            stylesheet.apply_theme(theme);
+            Box::new(stylesheet)
+        }
+        _ => get_term_style(color),
+    }
+}
+
+/// Returns the stylesheet of the output, without colors whatever the theme
+/// as the crate is built without the `color` feature.
+#[cfg(not(feature = "color"))]
+pub(crate) fn get_theme_style(_color: bool, _theme: Option<&Theme>) -> Box<dyn Stylesheet> {
+    Box::new(NoColorStylesheet)
+}
+
+/// Widths of the badge, line number and inline marks columns of a list of
+/// lines.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct ColumnWidths {
+    max_lineno: usize,
+    inline_marks: usize,
+    badges: usize,
+}
+
+impl ColumnWidths {
+    /// Widens the columns to fit the given line.
+    pub(crate) fn measure(self, line: &DisplayLine<'_>) -> Self {
+        match line {
+            DisplayLine::Source {
+                lineno,
+                inline_marks,
+                ..
+            } => Self {
+                // The largest line is the largest width.
+                max_lineno: cmp::max(lineno.unwrap_or_default(), self.max_lineno),
+                inline_marks: cmp::max(inline_marks.len(), self.inline_marks),
+                ..self
+            },
+            DisplayLine::Fold { inline_marks } | DisplayLine::Elided { inline_marks, .. } => Self {
+                inline_marks: cmp::max(inline_marks.len(), self.inline_marks),
+                ..self
+            },
+            DisplayLine::Raw(_) => self,
+        }
+    }
+
+    /// Widens the badge column to fit the badge of the given line, of a slice
+    /// from `origin`.
+    pub(crate) fn measure_badge(
+        self,
+        dl: &DisplayList<'_>,
+        line: &DisplayLine<'_>,
+        origin: Option<&str>,
+    ) -> Self {
+        match (&dl.gutter_badges, line) {
+            (
+                Some(badges),
+                DisplayLine::Source {
+                    lineno: Some(lineno),
+                    ..
+                },
+            ) => {
+                let width = badges
+                    .badge(origin, *lineno)
+                    .map_or(0, |badge| text_width(&badge));
+                Self {
+                    badges: cmp::max(width, self.badges),
+                    ..self
+                }
+            }
+            _ => self,
+        }
+    }
+}
+
+/// A single line formatted with the options of a `DisplayList`.
+pub(crate) struct FormattedLine<'d, 'a> {
+    pub(crate) dl: &'d DisplayList<'a>,
+    pub(crate) line: &'d DisplayLine<'a>,
+    pub(crate) widths: ColumnWidths,
+    pub(crate) origin: Option<&'d str>,
+}
+
+impl<'d, 'a> fmt::Display for FormattedLine<'d, 'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let lineno_width = self.body.iter().fold(0, |max, line| match line {
+        let max_width = match self.dl.max_width {
+            Some(max_width) => max_width,
+            None => {
+                return self
+                    .dl
+                    .format_indented_line(self.line, self.widths, self.origin, f)
+            }
+        };
+        let rendered = WithoutMaxWidth(self).to_string();
+        let cells = cells(&rendered, self.dl.tab_width.unwrap_or(8));
+        let width = cells.iter().map(|cell| cell.width).sum::<usize>();
+        if width <= max_width {
+            return f.write_str(&rendered);
+        }
+        if self.dl.assert_max_width {
+            debug_assert!(
+                false,
+                "rendered line is {} columns wide, more than the maximum of {}: {:?}",
+                width, max_width, rendered
+            );
+        }
+        match self.line {
             DisplayLine::Source {
-                lineno: Some(lineno),
+                line: DisplaySourceLine::Annotation { .. },
                 ..
+            }
+            | DisplayLine::Raw(_) => wrap_cells(&cells, max_width, &self.hang(max_width), f),
+            DisplayLine::Source { .. } => trim_cells(&cells, max_width, f),
+            _ => wrap_cells(&cells, max_width, &Hang::leading(&cells, max_width), f),
+        }
+    }
+}
+
+impl FormattedLine<'_, '_> {
+    /// How the line is continued when wrapped: labels go on as the
+    /// continuations of multiline labels, below the gutter, and the paths of
+    /// headers below the path.
+    fn hang(&self, max_width: usize) -> Hang {
+        let tab_width = self.dl.tab_width.unwrap_or(8);
+        let rendered =
+            |line: &DisplayLine<'_>| WithoutMaxWidth(&FormattedLine { line, ..*self }).to_string();
+        // The continuation of the label, of a single space.
+        let space = vec![DisplayTextFragment {
+            content: " ".into(),
+            style: DisplayTextStyle::Regular,
+        }];
+        let continued = |line: &DisplayLine<'_>| {
+            let mut rendered = rendered(line);
+            // The space is the last one, escape sequences have none.
+            if let Some(i) = rendered.rfind(' ') {
+                rendered.remove(i);
+            }
+            rendered
+        };
+        // The space before the label, the last cell being the escape
+        // sequences ending the line.
+        let label_break = |line: &DisplayLine<'_>| cells(&continued(line), tab_width).len() - 2;
+        let (first_break, continuation, gutter) = match self.line {
+            DisplayLine::Source {
+                lineno,
+                inline_marks,
+                line:
+                    DisplaySourceLine::Annotation {
+                        annotation,
+                        annotation_type,
+                        annotation_part,
+                        range,
+                        wide_marks,
+                    },
             } => {
-                // The largest line is the largest width.
-                cmp::max(*lineno, max)
+                let line = |annotation_part| DisplayLine::Source {
+                    lineno: *lineno,
+                    inline_marks: inline_marks.clone(),
+                    line: DisplaySourceLine::Annotation {
+                        annotation: Annotation {
+                            label: space.clone(),
+                            ..annotation.clone()
+                        },
+                        annotation_type: annotation_type.clone(),
+                        annotation_part,
+                        range: *range,
+                        wide_marks: *wide_marks,
+                    },
+                };
+                let gutter = DisplayLine::Source {
+                    lineno: None,
+                    inline_marks: inline_marks.clone(),
+                    line: DisplaySourceLine::Empty,
+                };
+                (
+                    label_break(&line(annotation_part.clone())),
+                    continued(&line(DisplayAnnotationPart::LabelContinuation)),
+                    rendered(&gutter) + " ",
+                )
             }
-            _ => max,
-        });
-        let lineno_width = if lineno_width == 0 {
-            lineno_width
-        } else if self.anonymized_line_numbers {
-            Self::ANONYMIZED_LINE_NUM.len()
+            DisplayLine::Raw(DisplayRawLine::Annotation {
+                annotation,
+                source_aligned,
+                continuation,
+            }) => {
+                let line = |continuation| {
+                    DisplayLine::Raw(DisplayRawLine::Annotation {
+                        annotation: Annotation {
+                            label: space.clone(),
+                            ..annotation.clone()
+                        },
+                        source_aligned: *source_aligned,
+                        continuation,
+                    })
+                };
+                let first_break = label_break(&line(*continuation));
+                (first_break, continued(&line(true)), String::new())
+            }
+            DisplayLine::Raw(DisplayRawLine::Origin { header_type, .. }) => {
+                let header_sigil = match header_type {
+                    DisplayHeaderType::Initial => "-->",
+                    DisplayHeaderType::Continuation => ":::",
+                };
+                let badges_width = match self.widths.badges {
+                    0 => 0,
+                    width => width + 1,
+                };
+                let indent = self.dl.indent
+                    + badges_width
+                    + self.dl.lineno_width(self.widths.max_lineno)
+                    + header_sigil.len()
+                    + 1;
+                // Paths are cut rather than moved below the sigil.
+                (indent, " ".repeat(indent), String::new())
+            }
+            DisplayLine::Raw(DisplayRawLine::Include { depth, .. }) => {
+                let indent = self.dl.indent
+                    + depth * 2
+                    + self.dl.annotation_type_len(&DisplayAnnotationType::Note)
+                    + 2;
+                (indent - 1, " ".repeat(indent), String::new())
+            }
+            _ => {
+                let rendered = WithoutMaxWidth(self).to_string();
+                return Hang::leading(&cells(&rendered, tab_width), max_width);
+            }
+        };
+        let width = cells(&continuation, tab_width)
+            .iter()
+            .map(|cell| cell.width)
+            .sum::<usize>();
+        // Continuations too far right to leave room for their text start
+        // after the gutter.
+        let continuation = if width < max_width / 2 {
+            continuation
         } else {
-            ((lineno_width as f64).log10().floor() as usize) + 1
+            gutter
         };
-        let inline_marks_width = self.body.iter().fold(0, |max, line| match line {
-            DisplayLine::Source { inline_marks, .. } => cmp::max(inline_marks.len(), max),
-            _ => max,
+        let width = cells(&continuation, tab_width)
+            .iter()
+            .map(|cell| cell.width)
+            .sum();
+        Hang {
+            first_break,
+            continuation,
+            width,
+        }
+    }
+}
+
+/// How a wrapped line is continued.
+struct Hang {
+    /// The first cell the first line may be broken at, the space before its
+    /// text. The cells before are trimmed rather than wrapped, when they don't
+    /// fit in a line.
+    first_break: usize,
+    /// What the following lines start with.
+    continuation: String,
+    /// The number of columns of `continuation`.
+    width: usize,
+}
+
+impl Hang {
+    /// The following lines indented as the first one, when this leaves room
+    /// for their text.
+    fn leading(cells: &[Cell<'_>], max_width: usize) -> Self {
+        let indent = cells.iter().take_while(|cell| cell.text == " ").count();
+        let indent = if indent < max_width / 2 { indent } else { 0 };
+        Self {
+            first_break: indent,
+            continuation: " ".repeat(indent),
+            width: indent,
+        }
+    }
+}
+
+/// A line formatted regardless of the maximal width of the list.
+struct WithoutMaxWidth<'l, 'd, 'a>(&'l FormattedLine<'d, 'a>);
+
+impl fmt::Display for WithoutMaxWidth<'_, '_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line = self.0;
+        line.dl
+            .format_indented_line(line.line, line.widths, line.origin, f)
+    }
+}
+
+/// A char of a rendered line, with the escape sequences before it and the
+/// number of columns it takes.
+struct Cell<'r> {
+    escapes: &'r str,
+    text: &'r str,
+    width: usize,
+}
+
+/// Splits a rendered line into cells, the escape sequences ending it being
+/// the last one, without text. Tabs advance to the next multiple of
+/// `tab_width`.
+fn cells(rendered: &str, tab_width: usize) -> Vec<Cell<'_>> {
+    let mut cells = vec![];
+    let mut column = 0;
+    let mut escapes_start = 0;
+    let mut chars = rendered.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == '\x1b' {
+            if chars.next_if(|&(_, c)| c == '[').is_some() {
+                for (_, c) in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        let width = match c {
+            '\t' => tab_width.max(1) - column % tab_width.max(1),
+            c => unicode_width::UnicodeWidthChar::width(c).unwrap_or(0),
+        };
+        column += width;
+        cells.push(Cell {
+            escapes: &rendered[escapes_start..i],
+            text: &rendered[i..i + c.len_utf8()],
+            width,
         });
+        escapes_start = i + c.len_utf8();
+    }
+    cells.push(Cell {
+        escapes: &rendered[escapes_start..],
+        text: "",
+        width: 0,
+    });
+    cells
+}
 
+/// Writes the cells fitting in `max_width` columns, ending with `...`, and
+/// the escape sequences of the others, which keep styles balanced.
+fn trim_cells(cells: &[Cell<'_>], max_width: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let ellipsis = if max_width >= 3 { "..." } else { "" };
+    let mut width = ellipsis.len();
+    let mut trimmed = false;
+    for cell in cells {
+        f.write_str(cell.escapes)?;
+        if trimmed {
+            continue;
+        }
+        if width + cell.width > max_width {
+            f.write_str(ellipsis)?;
+            trimmed = true;
+            continue;
+        }
+        width += cell.width;
+        f.write_str(cell.text)?;
+    }
+    Ok(())
+}
+
+/// Writes the cells in lines of at most `max_width` columns, broken after
+/// the last space fitting in a line if any, the following lines starting as
+/// `hang` tells.
+fn wrap_cells(
+    cells: &[Cell<'_>],
+    max_width: usize,
+    hang: &Hang,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    let mut start = 0;
+    let mut first = true;
+    let prefix = cmp::min(hang.first_break, cells.len());
+    if cells[..prefix].iter().map(|cell| cell.width).sum::<usize>() > max_width {
+        trim_cells(&cells[..prefix], max_width, f)?;
+        start = match cells.get(prefix) {
+            Some(cell) if cell.text == " " => prefix + 1,
+            _ => prefix,
+        };
+        first = false;
+    }
+    while start < cells.len() {
+        let breakable = |end: usize| end > start && (!first || end >= hang.first_break);
+        let mut width = if first { 0 } else { hang.width };
+        let mut end = start;
+        let mut space = None;
+        while end < cells.len() && width + cells[end].width <= max_width {
+            if cells[end].text == " " && breakable(end) {
+                space = Some(end);
+            }
+            width += cells[end].width;
+            end += 1;
+        }
+        // A cell wider than a line is written alone.
+        if end == start {
+            end += 1;
+        }
+        // A line ending right before a space is broken at that space.
+        if end < cells.len() && cells[end].text == " " && breakable(end) {
+            space = Some(end);
+        }
+        let (line_end, next) = match space {
+            Some(space) if end < cells.len() => (space, space + 1),
+            _ => (end, end),
+        };
+        if !first {
+            f.write_char('\n')?;
+            f.write_str(&hang.continuation)?;
+        }
+        for cell in &cells[start..line_end] {
+            f.write_str(cell.escapes)?;
+            f.write_str(cell.text)?;
+        }
+        if line_end < next {
+            // The escape sequences of the space the line is broken at.
+            f.write_str(cells[line_end].escapes)?;
+        }
+        start = next;
+        first = false;
+    }
+    Ok(())
+}
+
+impl<'a> fmt::Display for DisplayList<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let widths = self.column_widths();
+
+        let mut origin = None;
         for (i, line) in self.body.iter().enumerate() {
-            self.format_line(line, lineno_width, inline_marks_width, f)?;
-            if i + 1 < self.body.len() {
+            if i > 0 {
                 f.write_char('\n')?;
             }
+            if let DisplayLine::Raw(DisplayRawLine::Origin { path, .. }) = line {
+                origin = Some(path.as_ref());
+            }
+            FormattedLine {
+                dl: self,
+                line,
+                widths,
+                origin,
+            }
+            .fmt(f)?;
         }
         Ok(())
     }
 }
 
+impl<'a> DisplayList<'a> {
+    /// Returns the lines of the list formatted one by one, without their
+    /// line breaks, as displaying the list joins them.
+    pub fn rendered_lines(&self) -> impl Iterator<Item = String> + '_ {
+        let widths = self.column_widths();
+        let mut origin = None;
+        self.body.iter().map(move |line| {
+            if let DisplayLine::Raw(DisplayRawLine::Origin { path, .. }) = line {
+                origin = Some(path.as_ref());
+            }
+            FormattedLine {
+                dl: self,
+                line,
+                widths,
+                origin,
+            }
+            .to_string()
+        })
+    }
+}
+
 impl<'a> DisplayList<'a> {
     const ANONYMIZED_LINE_NUM: &'static str = "LL";
     const ERROR_TXT: &'static str = "error";
@@ -85,32 +554,78 @@ impl<'a> DisplayList<'a> {
     const NOTE_TXT: &'static str = "note";
     const WARNING_TXT: &'static str = "warning";
 
-    #[inline]
-    fn format_annotation_type(
-        annotation_type: &DisplayAnnotationType,
+    /// Measures the columns of the lines of the list.
+    pub(crate) fn column_widths(&self) -> ColumnWidths {
+        let mut origin = None;
+        self.body
+            .iter()
+            .fold(ColumnWidths::default(), |widths, line| {
+                if let DisplayLine::Raw(DisplayRawLine::Origin { path, .. }) = line {
+                    origin = Some(path.as_ref());
+                }
+                widths.measure(line).measure_badge(self, line, origin)
+            })
+    }
+
+    fn lineno_width(&self, max_lineno: usize) -> usize {
+        if max_lineno == 0 {
+            0
+        } else if self.anonymized_line_numbers {
+            Self::ANONYMIZED_LINE_NUM.len()
+        } else {
+            ((max_lineno as f64).log10().floor() as usize) + 1
+        }
+    }
+
+    /// Formats a single line, including the indentation of the list.
+    pub(crate) fn format_indented_line(
+        &self,
+        line: &DisplayLine<'_>,
+        widths: ColumnWidths,
+        origin: Option<&str>,
         f: &mut fmt::Formatter<'_>,
     ) -> fmt::Result {
-        match annotation_type {
-            DisplayAnnotationType::Error => f.write_str(Self::ERROR_TXT),
-            DisplayAnnotationType::Help => f.write_str(Self::HELP_TXT),
-            DisplayAnnotationType::Info => f.write_str(Self::INFO_TXT),
-            DisplayAnnotationType::Note => f.write_str(Self::NOTE_TXT),
-            DisplayAnnotationType::Warning => f.write_str(Self::WARNING_TXT),
-            DisplayAnnotationType::None => Ok(()),
+        format_repeat_char(' ', self.indent, f)?;
+        let badges_width = match widths.badges {
+            0 => 0,
+            width => width + 1,
+        };
+        self.format_line(
+            line,
+            badges_width + self.lineno_width(widths.max_lineno),
+            widths.inline_marks,
+            origin,
+            f,
+        )
+    }
+
+    /// The translations, English by default.
+    fn translations(&self) -> Cow<'_, Translations> {
+        match &self.translations {
+            Some(translations) => Cow::Borrowed(translations),
+            None => Cow::Owned(Translations::default()),
         }
     }
 
-    fn annotation_type_len(annotation_type: &DisplayAnnotationType) -> usize {
+    /// Name of the annotation type, from the translations if any.
+    fn annotation_type_str(&self, annotation_type: &DisplayAnnotationType) -> &str {
+        if let Some(translations) = &self.translations {
+            return translations.get(annotation_type).unwrap_or_default();
+        }
         match annotation_type {
-            DisplayAnnotationType::Error => Self::ERROR_TXT.len(),
-            DisplayAnnotationType::Help => Self::HELP_TXT.len(),
-            DisplayAnnotationType::Info => Self::INFO_TXT.len(),
-            DisplayAnnotationType::Note => Self::NOTE_TXT.len(),
-            DisplayAnnotationType::Warning => Self::WARNING_TXT.len(),
-            DisplayAnnotationType::None => 0,
+            DisplayAnnotationType::Error => Self::ERROR_TXT,
+            DisplayAnnotationType::Help => Self::HELP_TXT,
+            DisplayAnnotationType::Info => Self::INFO_TXT,
+            DisplayAnnotationType::Note => Self::NOTE_TXT,
+            DisplayAnnotationType::Warning => Self::WARNING_TXT,
+            DisplayAnnotationType::None => "",
         }
     }
 
+    fn annotation_type_len(&self, annotation_type: &DisplayAnnotationType) -> usize {
+        unicode_width::UnicodeWidthStr::width(self.annotation_type_str(annotation_type))
+    }
+
     fn get_annotation_style(&self, annotation_type: &DisplayAnnotationType) -> Box<dyn Style> {
         self.stylesheet.get_style(match annotation_type {
             DisplayAnnotationType::Error => StyleClass::Error,
@@ -132,7 +647,7 @@ impl<'a> DisplayList<'a> {
         for fragment in label {
             match fragment.style {
                 DisplayTextStyle::Regular => fragment.content.fmt(f)?,
-                DisplayTextStyle::Emphasis => emphasis_style.paint(fragment.content, f)?,
+                DisplayTextStyle::Emphasis => emphasis_style.paint(&fragment.content, f)?,
             }
         }
         Ok(())
@@ -147,9 +662,9 @@ impl<'a> DisplayList<'a> {
     ) -> fmt::Result {
         let color = self.get_annotation_style(&annotation.annotation_type);
         let formatted_len = if let Some(id) = &annotation.id {
-            2 + id.len() + Self::annotation_type_len(&annotation.annotation_type)
+            2 + id.len() + self.annotation_type_len(&annotation.annotation_type)
         } else {
-            Self::annotation_type_len(&annotation.annotation_type)
+            self.annotation_type_len(&annotation.annotation_type)
         };
 
         if continuation {
@@ -159,25 +674,25 @@ impl<'a> DisplayList<'a> {
         if formatted_len == 0 {
             self.format_label(&annotation.label, f)
         } else {
-            color.paint_fn(
-                Box::new(|f| {
-                    Self::format_annotation_type(&annotation.annotation_type, f)?;
+            color.paint_with(
+                &mut |f| {
+                    f.write_str(self.annotation_type_str(&annotation.annotation_type))?;
                     if let Some(id) = &annotation.id {
                         f.write_char('[')?;
                         f.write_str(id)?;
                         f.write_char(']')?;
                     }
                     Ok(())
-                }),
+                },
                 f,
             )?;
             if !is_annotation_empty(annotation) {
                 if in_source {
-                    color.paint_fn(
-                        Box::new(|f| {
+                    color.paint_with(
+                        &mut |f| {
                             f.write_str(": ")?;
                             self.format_label(&annotation.label, f)
-                        }),
+                        },
                         f,
                     )?;
                 } else {
@@ -189,16 +704,137 @@ impl<'a> DisplayList<'a> {
         }
     }
 
+    /// Writes the byte range `visible` of a source line, applying the styles
+    /// provided by the highlighter, the `BidiMode` and the `EscapeMode`.
+    fn format_source_text(
+        &self,
+        text: &str,
+        visible: (usize, usize),
+        lineno: Option<usize>,
+        origin: Option<&str>,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        if self.bidi != BidiMode::Isolate {
+            return self.format_highlighted_text(text, visible, lineno, origin, f);
+        }
+        f.write_char('\u{2068}')?;
+        self.format_highlighted_text(text, visible, lineno, origin, f)?;
+        f.write_char('\u{2069}')
+    }
+
+    /// Writes a part of a source line, without the characters removed by
+    /// `BidiMode::Strip` and with the substitutions of the `EscapeMode`.
+    fn source_part<'t>(&self, part: &'t str) -> Cow<'t, str> {
+        let strip = |c| self.bidi == BidiMode::Strip && BidiMode::is_control(c);
+        if !part.chars().any(|c| strip(c) || self.escape.substitutes(c)) {
+            return Cow::Borrowed(part);
+        }
+        let mut text = String::with_capacity(part.len());
+        for c in part.chars().filter(|&c| !strip(c)) {
+            self.escape.push_char(c, &mut text);
+        }
+        Cow::Owned(text)
+    }
+
+    fn format_highlighted_text(
+        &self,
+        text: &str,
+        visible: (usize, usize),
+        lineno: Option<usize>,
+        origin: Option<&str>,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        let highlighter = match &self.highlighter {
+            Some(highlighter) => highlighter,
+            None => return self.source_part(&text[visible.0..visible.1]).fmt(f),
+        };
+        let mut segments = highlighter.highlight_line(origin, lineno, text);
+        segments.sort_by_key(|segment| segment.range.start);
+
+        let mut pos = visible.0;
+        for segment in segments {
+            let start = cmp::max(segment.range.start, pos);
+            let end = cmp::min(segment.range.end, visible.1);
+            if start >= end || !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+                continue;
+            }
+            self.source_part(&text[pos..start]).fmt(f)?;
+            self.stylesheet
+                .get_spec_style(segment.style)
+                .paint(&self.source_part(&text[start..end]), f)?;
+            pos = end;
+        }
+        self.source_part(&text[pos..visible.1]).fmt(f)
+    }
+
+    /// Writes a source or annotation line, its content starting `pad` spaces
+    /// after the gutter.
     #[inline]
     fn format_source_line(
         &self,
         line: &DisplaySourceLine<'_>,
+        lineno: Option<usize>,
+        origin: Option<&str>,
+        pad: usize,
         f: &mut fmt::Formatter<'_>,
     ) -> fmt::Result {
         match line {
             DisplaySourceLine::Empty => Ok(()),
+            DisplaySourceLine::Ruler { width, tens } => {
+                format_repeat_char(' ', pad + 1, f)?;
+                let lineno_color = self.stylesheet.get_style(StyleClass::LineNo);
+                lineno_color.paint_with(
+                    &mut |f| {
+                        if *tens {
+                            for col in 1..=width / 10 * 10 {
+                                match col % 10 {
+                                    0 => write!(f, "{}", col / 10 % 10)?,
+                                    _ => f.write_char(' ')?,
+                                }
+                            }
+                        } else {
+                            for col in 1..=*width {
+                                write!(f, "{}", col % 10)?;
+                            }
+                        }
+                        Ok(())
+                    },
+                    f,
+                )
+            }
+            DisplaySourceLine::Compact {
+                text,
+                range,
+                annotations,
+            } => {
+                let content = DisplaySourceLine::Content {
+                    text: Cow::Borrowed(text),
+                    range: *range,
+                };
+                self.format_source_line(&content, lineno, origin, pad, f)?;
+                f.write_str("  //")?;
+                for (i, trailing) in annotations.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    f.write_char(' ')?;
+                    let color = self.get_annotation_style(&trailing.annotation_type);
+                    color.paint_with(
+                        &mut |f| f.write_char(self.markers.get(&trailing.annotation_type)),
+                        f,
+                    )?;
+                    if !is_annotation_empty(&trailing.annotation) {
+                        f.write_char(' ')?;
+                        color.paint_with(
+                            &mut |f| self.format_annotation(&trailing.annotation, false, true, f),
+                            f,
+                        )?;
+                    }
+                }
+                Ok(())
+            }
             DisplaySourceLine::Content { text, .. } => {
-                f.write_char(' ')?;
+                format_repeat_char(' ', pad + 1, f)?;
                 if let Some(margin) = self.margin {
                     let line_len = text.chars().count();
                     let mut left = margin.left(line_len);
@@ -235,8 +871,8 @@ impl<'a> DisplayList<'a> {
                             // Make sure that the trimming on the right will fall within the terminal width.
                             // FIXME: `unicode_width` sometimes disagrees with terminals on how wide a `char` is.
                             // For now, just accept that sometimes the code line will be longer than desired.
-                            taken += unicode_width::UnicodeWidthChar::width(*ch).unwrap_or(1);
-                            if taken > right - left {
+                            taken += self.escape.width(*ch).unwrap_or(1);
+                            if taken > right.saturating_sub(left) {
                                 ended = true;
                             }
                             true
@@ -250,8 +886,10 @@ impl<'a> DisplayList<'a> {
                             }
                         });
 
-                    // Format text with margins
-                    text[range.0.expect("One character at line")..range.1].fmt(f)?;
+                    // Format text with margins, which may start past the end of a short line.
+                    let start = range.0.unwrap_or(text.len());
+                    let range = (start, range.1.max(start));
+                    self.format_source_text(text, range, lineno, origin, f)?;
 
                     if cut_right {
                         // We have stripped some code after the right-most span end, make it clear we did so.
@@ -259,7 +897,7 @@ impl<'a> DisplayList<'a> {
                     }
                     Ok(())
                 } else {
-                    text.fmt(f)
+                    self.format_source_text(text, (0, text.len()), lineno, origin, f)
                 }
             }
             DisplaySourceLine::Annotation {
@@ -267,6 +905,7 @@ impl<'a> DisplayList<'a> {
                 annotation,
                 annotation_type,
                 annotation_part,
+                wide_marks,
             } => {
                 let indent_char = match annotation_part {
                     DisplayAnnotationPart::Standalone => ' ',
@@ -275,14 +914,7 @@ impl<'a> DisplayList<'a> {
                     DisplayAnnotationPart::MultilineStart => '_',
                     DisplayAnnotationPart::MultilineEnd => '_',
                 };
-                let mark = match annotation_type {
-                    DisplayAnnotationType::Error => '^',
-                    DisplayAnnotationType::Warning => '-',
-                    DisplayAnnotationType::Info => '-',
-                    DisplayAnnotationType::Note => '-',
-                    DisplayAnnotationType::Help => '-',
-                    DisplayAnnotationType::None => ' ',
-                };
+                let mark = self.markers.get(annotation_type);
                 let color = self.get_annotation_style(annotation_type);
                 let indent_length = match annotation_part {
                     DisplayAnnotationPart::LabelContinuation => range.1,
@@ -290,25 +922,30 @@ impl<'a> DisplayList<'a> {
                     _ => range.0,
                 };
 
-                color.paint_fn(
-                    Box::new(|f| {
-                        format_repeat_char(indent_char, indent_length + 1, f)?;
-                        format_repeat_char(mark, range.1 - indent_length, f)
-                    }),
+                let (mark, mark_count) = match full_width_mark(mark) {
+                    Some(wide_mark) if *wide_marks => (wide_mark, (range.1 - indent_length) / 2),
+                    _ => (mark, range.1 - indent_length),
+                };
+
+                color.paint_with(
+                    &mut |f| {
+                        format_repeat_char(indent_char, pad + indent_length + 1, f)?;
+                        format_repeat_char(mark, mark_count, f)
+                    },
                     f,
                 )?;
 
                 if !is_annotation_empty(annotation) {
                     f.write_char(' ')?;
-                    color.paint_fn(
-                        Box::new(|f| {
+                    color.paint_with(
+                        &mut |f| {
                             self.format_annotation(
                                 annotation,
                                 annotation_part == &DisplayAnnotationPart::LabelContinuation,
                                 true,
                                 f,
                             )
-                        }),
+                        },
                         f,
                     )?;
                 }
@@ -329,6 +966,7 @@ impl<'a> DisplayList<'a> {
             DisplayRawLine::Origin {
                 path,
                 pos,
+                offset,
                 header_type,
             } => {
                 let header_sigil = match header_type {
@@ -346,6 +984,12 @@ impl<'a> DisplayList<'a> {
                     col.fmt(f)?;
                     f.write_char(':')?;
                     row.fmt(f)
+                } else if let Some(offset) = offset {
+                    format_repeat_char(' ', lineno_width, f)?;
+                    lineno_color.paint(header_sigil, f)?;
+                    f.write_char(' ')?;
+                    path.fmt(f)?;
+                    write!(f, ":+0x{:X}", offset)
                 } else {
                     format_repeat_char(' ', lineno_width, f)?;
                     lineno_color.paint(header_sigil, f)?;
@@ -358,6 +1002,17 @@ impl<'a> DisplayList<'a> {
                 source_aligned,
                 continuation,
             } => {
+                if !*source_aligned && !*continuation {
+                    if let Some(icon) = self
+                        .icons
+                        .as_ref()
+                        .and_then(|icons| icons.get(&annotation.annotation_type))
+                    {
+                        self.get_annotation_style(&annotation.annotation_type)
+                            .paint(icon, f)?;
+                        f.write_char(' ')?;
+                    }
+                }
                 if *source_aligned {
                     if *continuation {
                         format_repeat_char(' ', lineno_width + 3, f)?;
@@ -371,6 +1026,16 @@ impl<'a> DisplayList<'a> {
                 }
                 self.format_annotation(annotation, *continuation, false, f)
             }
+            DisplayRawLine::Include { path, line, depth } => {
+                format_repeat_char(' ', depth * 2, f)?;
+                self.get_annotation_style(&DisplayAnnotationType::Note)
+                    .paint(self.annotation_type_str(&DisplayAnnotationType::Note), f)?;
+                f.write_str(": ")?;
+                f.write_str(&Translations::fill(
+                    &self.translations().included_from,
+                    &[("path", path), ("line", line)],
+                ))
+            }
         }
     }
 
@@ -380,6 +1045,7 @@ impl<'a> DisplayList<'a> {
         dl: &DisplayLine<'_>,
         lineno_width: usize,
         inline_marks_width: usize,
+        origin: Option<&str>,
         f: &mut fmt::Formatter<'_>,
     ) -> fmt::Result {
         match dl {
@@ -389,32 +1055,64 @@ impl<'a> DisplayList<'a> {
                 line,
             } => {
                 let lineno_color = self.stylesheet.get_style(StyleClass::LineNo);
+                // The badge, left-aligned, and the number share the width.
+                let badge = match (&self.gutter_badges, lineno) {
+                    (Some(badges), Some(n)) => badges.badge(origin, *n),
+                    _ => None,
+                };
+                let badge = badge.as_deref().unwrap_or_default();
+                let width = lineno_width.saturating_sub(text_width(badge));
                 if self.anonymized_line_numbers && lineno.is_some() {
-                    lineno_color.paint_fn(
-                        Box::new(|f| {
-                            f.write_str(Self::ANONYMIZED_LINE_NUM)?;
+                    lineno_color.paint_with(
+                        &mut |f| {
+                            f.write_str(badge)?;
+                            write!(f, "{:>width$}", Self::ANONYMIZED_LINE_NUM, width = width)?;
                             f.write_str(" |")
-                        }),
+                        },
                         f,
                     )?;
                 } else {
-                    lineno_color.paint_fn(
-                        Box::new(|f| {
+                    lineno_color.paint_with(
+                        &mut |f| {
+                            f.write_str(badge)?;
                             match lineno {
-                                Some(n) => write!(f, "{:>width$}", n, width = lineno_width),
-                                None => format_repeat_char(' ', lineno_width, f),
+                                Some(n) => write!(f, "{:>width$}", n, width = width),
+                                None => format_repeat_char(' ', width, f),
                             }?;
                             f.write_str(" |")
-                        }),
+                        },
                         f,
                     )?;
                 }
                 if *line != DisplaySourceLine::Empty {
+                    let mut gutter_width = self.indent + lineno_width + 3;
                     if !inline_marks.is_empty() || 0 < inline_marks_width {
                         f.write_char(' ')?;
                         self.format_inline_marks(inline_marks, inline_marks_width, f)?;
+                        gutter_width += inline_marks_width + 1;
+                    }
+                    // Tabs are expanded from the start of the terminal line
+                    let pad = match self.tab_width {
+                        Some(tab_width) => {
+                            let tab_width = tab_width.max(1);
+                            (tab_width - gutter_width % tab_width) % tab_width
+                        }
+                        None => 0,
+                    };
+                    let background = inline_marks.iter().find_map(|mark| match mark.mark_type {
+                        DisplayMarkType::LineHighlight { background, .. } => background,
+                        _ => None,
+                    });
+                    match background {
+                        Some(color) => self
+                            .stylesheet
+                            .get_spec_style(StyleSpec::new().bg(color))
+                            .paint_with(
+                            &mut |f| self.format_source_line(line, *lineno, origin, pad, f),
+                            f,
+                        )?,
+                        None => self.format_source_line(line, *lineno, origin, pad, f)?,
                     }
-                    self.format_source_line(line, f)?;
                 } else if !inline_marks.is_empty() {
                     f.write_char(' ')?;
                     self.format_inline_marks(inline_marks, inline_marks_width, f)?;
@@ -422,13 +1120,42 @@ impl<'a> DisplayList<'a> {
                 Ok(())
             }
             DisplayLine::Fold { inline_marks } => {
-                f.write_str("...")?;
+                match &self.translations {
+                    Some(translations) => f.write_str(&translations.fold)?,
+                    None => f.write_str("...")?,
+                }
                 if !inline_marks.is_empty() || 0 < inline_marks_width {
                     format_repeat_char(' ', lineno_width, f)?;
                     self.format_inline_marks(inline_marks, inline_marks_width, f)?;
                 }
                 Ok(())
             }
+            DisplayLine::Elided {
+                inline_marks,
+                count,
+            } => {
+                let lineno_color = self.stylesheet.get_style(StyleClass::LineNo);
+                lineno_color.paint_with(
+                    &mut |f| {
+                        format_repeat_char(' ', lineno_width, f)?;
+                        f.write_str(" |")
+                    },
+                    f,
+                )?;
+                if !inline_marks.is_empty() || 0 < inline_marks_width {
+                    f.write_char(' ')?;
+                    self.format_inline_marks(inline_marks, inline_marks_width, f)?;
+                }
+                f.write_char(' ')?;
+                let translations = self.translations();
+                match count {
+                    1 => f.write_str(&translations.omitted_line),
+                    count => f.write_str(&Translations::fill(
+                        &translations.omitted_lines,
+                        &[("count", count)],
+                    )),
+                }
+            }
             DisplayLine::Raw(line) => self.format_raw_line(line, lineno_width, f),
         }
     }
@@ -439,17 +1166,33 @@ impl<'a> DisplayList<'a> {
         inline_marks_width: usize,
         f: &mut fmt::Formatter<'_>,
     ) -> fmt::Result {
-        format_repeat_char(' ', inline_marks_width - inline_marks.len(), f)?;
+        // Lines built by hand may have more marks than the measured width.
+        format_repeat_char(
+            ' ',
+            inline_marks_width.saturating_sub(inline_marks.len()),
+            f,
+        )?;
         for mark in inline_marks {
-            self.get_annotation_style(&mark.annotation_type).paint_fn(
-                Box::new(|f| {
-                    f.write_char(match mark.mark_type {
-                        DisplayMarkType::AnnotationThrough => '|',
-                        DisplayMarkType::AnnotationStart => '/',
-                    })
-                }),
-                f,
-            )?;
+            let (style, sigil) = match mark.mark_type {
+                DisplayMarkType::AnnotationThrough => {
+                    (self.get_annotation_style(&mark.annotation_type), "|")
+                }
+                DisplayMarkType::AnnotationStart => {
+                    (self.get_annotation_style(&mark.annotation_type), "/")
+                }
+                DisplayMarkType::LineHighlight { change, .. } => {
+                    let (color, sigil) = match change {
+                        LineChange::Added => (Color::Green, "+"),
+                        LineChange::Removed => (Color::Red, "-"),
+                        LineChange::Changed => (Color::Yellow, ">"),
+                    };
+                    (
+                        self.stylesheet.get_spec_style(StyleSpec::new().fg(color)),
+                        sigil,
+                    )
+                }
+            };
+            style.paint(sigil, f)?;
         }
         Ok(())
     }
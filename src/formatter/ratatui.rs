@@ -0,0 +1,132 @@
+//! Conversion of rendered snippets to `ratatui` text.
+//!
+//! With the `ratatui` feature, a terminal user interface can display a
+//! snippet in one of its widgets: the snippet rendered with colors becomes a
+//! `ratatui::text::Text`, a line per rendered line, whose spans have the
+//! styles of the ANSI escape sequences.
+//!
+//! ```
+//! use annotate_snippets::formatter::ratatui::ansi_to_text;
+//! use ratatui::style::{Color, Modifier, Style};
+//!
+//! let text = ansi_to_text("\x1b[1;31merror\x1b[0m: <T>\nnext");
+//! assert_eq!(text.lines.len(), 2);
+//! assert_eq!(text.lines[0].spans[0].content, "error");
+//! assert_eq!(
+//!     text.lines[0].spans[0].style,
+//!     Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+//! );
+//! assert_eq!(text.lines[0].spans[1].content, ": <T>");
+//! ```
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+
+use super::html::apply_sgr;
+use super::style::{Color, StyleSpec};
+use crate::display_list::DisplayList;
+
+impl<'a> DisplayList<'a> {
+    /// Renders the list as `ratatui` text, with the colors of its stylesheet.
+    ///
+    /// The text is only styled when the list is, with the `color` feature
+    /// and colors enabled in its options.
+    pub fn to_text(&self) -> Text<'static> {
+        ansi_to_text(&self.to_string())
+    }
+}
+
+/// Converts text holding ANSI escape sequences to `ratatui` text.
+///
+/// Sequences setting colors and attributes become the styles of spans, other
+/// sequences are removed.
+pub fn ansi_to_text(s: &str) -> Text<'static> {
+    let mut lines = vec![];
+    let mut style = StyleSpec::new();
+    for line in s.split('\n') {
+        let mut spans = vec![];
+        let mut content = String::new();
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            if c != '\x1b' {
+                content.push(c);
+                continue;
+            }
+            if let Some('[') = chars.next() {
+                let mut params = String::new();
+                let mut end = None;
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        end = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+                if end == Some('m') {
+                    let next = apply_sgr(style, &params);
+                    if next != style && !content.is_empty() {
+                        spans.push(Span::styled(std::mem::take(&mut content), to_style(&style)));
+                    }
+                    style = next;
+                }
+            }
+        }
+        if !content.is_empty() {
+            spans.push(Span::styled(content, to_style(&style)));
+        }
+        lines.push(Line::from(spans));
+    }
+    Text::from(lines)
+}
+
+fn to_style(spec: &StyleSpec) -> Style {
+    let mut style = Style::default();
+    if let Some(color) = spec.foreground {
+        style = style.fg(to_color(color));
+    }
+    if let Some(color) = spec.background {
+        style = style.bg(to_color(color));
+    }
+    let modifiers = [
+        (spec.bold, Modifier::BOLD),
+        (spec.dimmed, Modifier::DIM),
+        (spec.italic, Modifier::ITALIC),
+        (spec.underline, Modifier::UNDERLINED),
+    ];
+    for (set, modifier) in modifiers {
+        if set {
+            style = style.add_modifier(modifier);
+        }
+    }
+    style
+}
+
+fn to_color(color: Color) -> ratatui::style::Color {
+    use ratatui::style::Color as C;
+
+    const BASIC: [C; 16] = [
+        C::Black,
+        C::Red,
+        C::Green,
+        C::Yellow,
+        C::Blue,
+        C::Magenta,
+        C::Cyan,
+        C::Gray,
+        C::DarkGray,
+        C::LightRed,
+        C::LightGreen,
+        C::LightYellow,
+        C::LightBlue,
+        C::LightMagenta,
+        C::LightCyan,
+        C::White,
+    ];
+    match color {
+        Color::Rgb(r, g, b) => C::Rgb(r, g, b),
+        color => match color.to_fixed() {
+            Some(n) if n < 16 => BASIC[n as usize],
+            Some(n) => C::Indexed(n),
+            None => C::Reset,
+        },
+    }
+}
@@ -0,0 +1,205 @@
+//! Rendering of many snippets with reused buffers.
+//!
+//! `render_batch` renders a whole batch of snippets, in parallel when the
+//! `rayon` feature is enabled.
+//!
+//! ```
+//! use annotate_snippets::formatter::renderer::Renderer;
+//! use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+//!
+//! let mut renderer = Renderer::new();
+//! for label in &["first", "second"] {
+//!     let snippet = Snippet::new().title(Annotation {
+//!         label: Some(label),
+//!         id: None,
+//!         annotation_type: AnnotationType::Error,
+//!     });
+//!     println!("{}", renderer.render(&snippet));
+//! }
+//! ```
+use std::fmt::Write;
+
+use super::metrics::{MetricsCounter, RenderMetrics};
+use super::style::{strip_styles, Stylesheet};
+use super::{ColumnWidths, FormattedLine};
+use crate::display_list::{
+    display_lines, DisplayLine, DisplayList, DisplayRawLine, LayoutBuffers, Pass, SnippetLines,
+};
+use crate::snippet::Snippet;
+
+type MetricsHook = Box<dyn FnMut(&RenderMetrics)>;
+
+/// Renders snippets to strings, keeping its scratch buffers and output string
+/// from one snippet to the next.
+///
+/// This avoids reallocating them for every snippet when emitting many
+/// diagnostics.
+#[derive(Default)]
+pub struct Renderer {
+    buffers: LayoutBuffers,
+    output: String,
+    passes: Vec<Box<dyn Pass>>,
+    metrics_hooks: Vec<MetricsHook>,
+    stylesheet: Option<Box<dyn Stylesheet>>,
+}
+
+impl Renderer {
+    /// Creates a renderer with empty buffers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a pass run on the `DisplayList` of every snippet before it is
+    /// rendered, after the passes added before.
+    ///
+    /// With passes, the lines of each snippet are collected in a list rather
+    /// than rendered as they are built.
+    pub fn pass(mut self, pass: impl Pass + 'static) -> Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    /// Adds a hook called with the metrics of every snippet rendered, after
+    /// it is.
+    ///
+    /// The metrics are only measured when the renderer has hooks.
+    pub fn on_metrics(mut self, hook: impl FnMut(&RenderMetrics) + 'static) -> Self {
+        self.metrics_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Renders the snippets with colors with `stylesheet`, rather than with
+    /// the one of `get_term_style`.
+    ///
+    /// ```
+    /// use annotate_snippets::formatter::renderer::Renderer;
+    /// use annotate_snippets::stylesheets::no_color::NoColorStylesheet;
+    ///
+    /// let renderer = Renderer::new().stylesheet(Box::new(NoColorStylesheet));
+    /// ```
+    pub fn stylesheet(mut self, stylesheet: Box<dyn Stylesheet>) -> Self {
+        self.stylesheet = Some(stylesheet);
+        self
+    }
+
+    /// Swaps the stylesheet of the renderer with the one of `dl`, if the
+    /// snippet is colored.
+    fn swap_stylesheet(&mut self, dl: &mut DisplayList<'_>, color: bool) {
+        if let (true, Some(stylesheet)) = (color, &mut self.stylesheet) {
+            std::mem::swap(&mut dl.stylesheet, stylesheet);
+        }
+    }
+
+    /// Renders the snippet, without a trailing newline.
+    ///
+    /// The output is the same as the one of the `DisplayList` of the snippet,
+    /// after the passes of the renderer. It is overwritten by the next call.
+    pub fn render(&mut self, snippet: &Snippet<'_>) -> &str {
+        self.output.clear();
+
+        if !self.passes.is_empty() {
+            let mut dl = DisplayList::for_snippet(
+                display_lines(snippet).collect(),
+                snippet,
+                snippet.opt.clone(),
+            );
+            for pass in &self.passes {
+                dl.apply(pass.as_ref());
+            }
+            self.swap_stylesheet(&mut dl, snippet.opt.color);
+            // Writing to a `String` can't fail.
+            let _ = write!(self.output, "{}", dl);
+            self.swap_stylesheet(&mut dl, snippet.opt.color);
+            if !self.metrics_hooks.is_empty() {
+                let metrics = dl.metrics();
+                self.metrics_hooks
+                    .iter_mut()
+                    .for_each(|hook| hook(&metrics));
+            }
+            return &self.output;
+        }
+
+        let mut dl = DisplayList::for_snippet(vec![], snippet, snippet.opt.clone());
+        self.swap_stylesheet(&mut dl, snippet.opt.color);
+        let mut lines = SnippetLines::with_buffers(snippet, std::mem::take(&mut self.buffers));
+        let mut origin = None;
+        let widths = lines
+            .by_ref()
+            .fold(ColumnWidths::default(), |widths, line| {
+                if let DisplayLine::Raw(DisplayRawLine::Origin { path, .. }) = &line {
+                    origin = Some(path.clone());
+                }
+                widths
+                    .measure(&line)
+                    .measure_badge(&dl, &line, origin.as_deref())
+            });
+
+        let mut lines = SnippetLines::with_buffers(snippet, lines.into_buffers());
+        let mut counter =
+            Some(MetricsCounter::default()).filter(|_| !self.metrics_hooks.is_empty());
+        let mut origin = None;
+        for (i, line) in lines.by_ref().enumerate() {
+            if i > 0 {
+                self.output.push('\n');
+            }
+            if let DisplayLine::Raw(DisplayRawLine::Origin { path, .. }) = &line {
+                origin = Some(path.clone());
+            }
+            let line = FormattedLine {
+                dl: &dl,
+                line: &line,
+                widths,
+                origin: origin.as_deref(),
+            };
+            let start = self.output.len();
+            // Writing to a `String` can't fail.
+            let _ = write!(self.output, "{}", line);
+            if let Some(counter) = &mut counter {
+                counter.count(&line, &self.output[start..]);
+            }
+        }
+        self.buffers = lines.into_buffers();
+        self.swap_stylesheet(&mut dl, snippet.opt.color);
+        if let Some(counter) = counter {
+            let metrics = counter.finish();
+            self.metrics_hooks
+                .iter_mut()
+                .for_each(|hook| hook(&metrics));
+        }
+
+        &self.output
+    }
+
+    /// Renders the snippet as `render` does, along with the plain form of
+    /// the output, without the styles of its colors.
+    pub fn render_with_plain(&mut self, snippet: &Snippet<'_>) -> (&str, String) {
+        let output = self.render(snippet);
+        (output, strip_styles(output))
+    }
+}
+
+/// Renders all snippets, returning their outputs in the order of `snippets`.
+///
+/// With the `rayon` feature, the snippets are rendered in parallel, each
+/// thread reusing its own `Renderer`.
+pub fn render_batch(snippets: &[Snippet<'_>]) -> Vec<String> {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+
+        snippets
+            .par_iter()
+            .map_init(Renderer::new, |renderer, snippet| {
+                renderer.render(snippet).to_string()
+            })
+            .collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        let mut renderer = Renderer::new();
+        snippets
+            .iter()
+            .map(|snippet| renderer.render(snippet).to_string())
+            .collect()
+    }
+}
@@ -0,0 +1,101 @@
+//! Rendering of a `Snippet` straight into a writer.
+//!
+//! `write_snippet` produces the same output as formatting the `DisplayList`
+//! of the snippet, but never materializes the list: lines are generated one
+//! source line at a time and written as soon as they are formatted. This keeps
+//! memory use flat when rendering snippets of large files.
+//!
+//! The widths of the gutter depend on every line, so the lines are generated
+//! in two passes: the first one measures them, the second one writes them.
+//!
+//! ```
+//! use annotate_snippets::formatter::stream::write_snippet;
+//! use annotate_snippets::snippet::{AnnotationType, Slice, Snippet, SourceAnnotation};
+//!
+//! let snippet = Snippet::new().slice(
+//!     Slice::new("let x = 1;", 1)
+//!         .origin("src/main.rs")
+//!         .annotation(SourceAnnotation {
+//!             range: (4, 5),
+//!             label: "unused variable",
+//!             annotation_type: AnnotationType::Warning,
+//!             ..Default::default()
+//!         }),
+//! );
+//!
+//! let mut out = Vec::new();
+//! write_snippet(&snippet, &mut out).unwrap();
+//! assert!(String::from_utf8(out).unwrap().starts_with(" --> src/main.rs:1:5"));
+//! ```
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Mutex, PoisonError};
+
+use super::{ColumnWidths, FormattedLine};
+use crate::display_list::{
+    DisplayLine, DisplayList, DisplayRawLine, FormatOptions, GutterBadges, LayoutBuffers,
+    SnippetLines,
+};
+use crate::snippet::Snippet;
+
+/// Writes the rendered snippet into `w`, without a trailing newline.
+///
+/// The column widths are determined by a first walk over the generated lines,
+/// which are discarded right away; the second walk writes them. The gutter
+/// badges are computed once per line by the first walk, and kept for the
+/// second one.
+pub fn write_snippet<W: io::Write>(snippet: &Snippet<'_>, w: &mut W) -> io::Result<()> {
+    write_snippet_with(snippet, &snippet.opt, w)
+}
+
+/// Writes the snippet rendered with `opt`, ignoring the options of the
+/// snippet, into `w`, without a trailing newline.
+pub fn write_snippet_with<W: io::Write>(
+    snippet: &Snippet<'_>,
+    opt: &FormatOptions,
+    w: &mut W,
+) -> io::Result<()> {
+    let lines = || SnippetLines::with_options(snippet, opt, LayoutBuffers::default());
+    let mut dl = DisplayList::for_snippet(vec![], snippet, opt.clone());
+    dl.gutter_badges = dl.gutter_badges.take().map(|badges| {
+        let cache = Mutex::new(HashMap::new());
+        GutterBadges::new(move |origin, lineno| {
+            let mut cache = cache.lock().unwrap_or_else(PoisonError::into_inner);
+            let key = (origin.map(str::to_string), lineno);
+            let badge = cache
+                .entry(key)
+                .or_insert_with(|| badges.badge(origin, lineno));
+            badge.clone()
+        })
+    });
+    let mut origin = None;
+    let widths = lines().fold(ColumnWidths::default(), |widths, line| {
+        if let DisplayLine::Raw(DisplayRawLine::Origin { path, .. }) = &line {
+            origin = Some(path.clone());
+        }
+        widths
+            .measure(&line)
+            .measure_badge(&dl, &line, origin.as_deref())
+    });
+
+    let mut origin = None;
+    for (i, line) in lines().enumerate() {
+        if i > 0 {
+            w.write_all(b"\n")?;
+        }
+        if let DisplayLine::Raw(DisplayRawLine::Origin { path, .. }) = &line {
+            origin = Some(path.clone());
+        }
+        write!(
+            w,
+            "{}",
+            FormattedLine {
+                dl: &dl,
+                line: &line,
+                widths,
+                origin: origin.as_deref(),
+            }
+        )?;
+    }
+    Ok(())
+}
@@ -7,6 +7,7 @@
 use std::fmt;
 
 /// StyleClass is a collection of named variants of style classes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum StyleClass {
     /// Message indicating an error.
     Error,
@@ -39,6 +40,16 @@ pub trait Style {
         c: Box<dyn FnOnce(&mut fmt::Formatter<'_>) -> fmt::Result + 'a>,
         f: &mut fmt::Formatter<'_>,
     ) -> fmt::Result;
+    /// The method used to write a display function with formatter, without
+    /// boxing it. Styles should override it to avoid the allocation done by
+    /// the default implementation.
+    fn paint_with(
+        &self,
+        c: &mut dyn FnMut(&mut fmt::Formatter<'_>) -> fmt::Result,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        self.paint_fn(Box::new(move |f| c(f)), f)
+    }
     /// The method used by the `Formatter` to display the message in bold font.
     fn bold(&self) -> Box<dyn Style>;
 }
@@ -48,4 +59,283 @@ pub trait Style {
 pub trait Stylesheet {
     /// Returns a `Style` implementer based on the requested `StyleClass` variant.
     fn get_style(&self, class: StyleClass) -> Box<dyn Style>;
+
+    /// Returns a `Style` implementer for an arbitrary `StyleSpec`, as used by
+    /// syntax highlighting. Stylesheets without colors may ignore the spec.
+    fn get_spec_style(&self, _spec: StyleSpec) -> Box<dyn Style> {
+        self.get_style(StyleClass::None)
+    }
+}
+
+/// A color which can be used by a `Stylesheet`.
+///
+/// Besides the 16 basic ANSI colors, a color may be an index into the
+/// 256-color palette (`Fixed`) or a 24-bit value (`Rgb`). Stylesheets are
+/// expected to degrade the latter two with `Color::degrade` when the
+/// terminal does not support them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+    /// An index into the 256-color palette.
+    Fixed(u8),
+    /// A 24-bit color.
+    Rgb(u8, u8, u8),
+}
+
+/// The xterm default values of the 16 basic colors.
+const BASIC_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Intensity levels of each channel in the 6x6x6 color cube of the 256-color palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl Color {
+    const BASIC: [Color; 16] = [
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::White,
+        Color::BrightBlack,
+        Color::BrightRed,
+        Color::BrightGreen,
+        Color::BrightYellow,
+        Color::BrightBlue,
+        Color::BrightMagenta,
+        Color::BrightCyan,
+        Color::BrightWhite,
+    ];
+
+    /// Returns the index of the color in the 256-color palette, if it has one.
+    pub fn to_fixed(self) -> Option<u8> {
+        match self {
+            Color::Fixed(n) => Some(n),
+            Color::Rgb(..) => None,
+            basic => Color::BASIC
+                .iter()
+                .position(|c| *c == basic)
+                .map(|i| i as u8),
+        }
+    }
+
+    /// Returns an approximation of the color as a 24-bit value.
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            Color::Rgb(r, g, b) => (r, g, b),
+            color => {
+                let n = color.to_fixed().unwrap_or_default();
+                match n {
+                    0..=15 => BASIC_PALETTE[n as usize],
+                    16..=231 => {
+                        let n = n - 16;
+                        (
+                            CUBE_LEVELS[(n / 36) as usize],
+                            CUBE_LEVELS[(n / 6 % 6) as usize],
+                            CUBE_LEVELS[(n % 6) as usize],
+                        )
+                    }
+                    _ => {
+                        let v = 8 + 10 * (n - 232);
+                        (v, v, v)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Converts the color to the closest one available at the given depth.
+    ///
+    /// The 16 basic colors are available at every depth and are returned unchanged.
+    pub fn degrade(self, depth: ColorDepth) -> Color {
+        match (self, depth) {
+            (_, ColorDepth::TrueColor) | (Color::Fixed(_), ColorDepth::Ansi256) => self,
+            (Color::Rgb(r, g, b), ColorDepth::Ansi256) => Color::Fixed(nearest_fixed(r, g, b)),
+            (Color::Fixed(n), ColorDepth::Ansi16) if n < 16 => Color::BASIC[n as usize],
+            (Color::Fixed(_), ColorDepth::Ansi16) | (Color::Rgb(..), ColorDepth::Ansi16) => {
+                let rgb = self.to_rgb();
+                let idx = (0..BASIC_PALETTE.len())
+                    .min_by_key(|i| distance(BASIC_PALETTE[*i], rgb))
+                    .unwrap_or_default();
+                Color::BASIC[idx]
+            }
+            (basic, _) => basic,
+        }
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (i32::from(x) - i32::from(y)).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+/// Finds the closest entry of the 6x6x6 color cube or the grayscale ramp of
+/// the 256-color palette.
+fn nearest_fixed(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|i| (i32::from(CUBE_LEVELS[*i]) - i32::from(v)).abs())
+            .unwrap_or_default() as u8
+    };
+    let cube = 16 + 36 * level(r) + 6 * level(g) + level(b);
+
+    let avg = ((u32::from(r) + u32::from(g) + u32::from(b)) / 3) as u8;
+    let gray = 232 + (avg.saturating_sub(3) / 10).min(23);
+
+    if distance(Color::Fixed(gray).to_rgb(), (r, g, b))
+        < distance(Color::Fixed(cube).to_rgb(), (r, g, b))
+    {
+        gray
+    } else {
+        cube
+    }
+}
+
+/// The number of colors a terminal is able to display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorDepth {
+    /// Only the 16 basic colors.
+    Ansi16,
+    /// The 256-color palette.
+    Ansi256,
+    /// 24-bit colors.
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Guesses the capabilities of the terminal from the `COLORTERM` and `TERM`
+    /// environment variables.
+    pub fn detect() -> ColorDepth {
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let term = std::env::var("TERM").unwrap_or_default();
+        Self::from_env_values(&colorterm, &term)
+    }
+
+    fn from_env_values(colorterm: &str, term: &str) -> ColorDepth {
+        if colorterm == "truecolor" || colorterm == "24bit" || term.ends_with("-direct") {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+}
+
+/// Colors and attributes applied to a single `StyleClass`.
+///
+/// ```
+/// use annotate_snippets::formatter::style::{Color, StyleSpec};
+///
+/// let spec = StyleSpec::new().fg(Color::Rgb(255, 135, 0)).bold();
+/// assert_eq!(spec.foreground, Some(Color::Rgb(255, 135, 0)));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StyleSpec {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub bold: bool,
+    pub dimmed: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+impl StyleSpec {
+    /// Creates a spec without any colors or attributes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn fg(mut self, color: Color) -> Self {
+        self.foreground = Some(color);
+        self
+    }
+
+    pub fn bg(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    pub fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    pub fn dimmed(mut self) -> Self {
+        self.dimmed = true;
+        self
+    }
+
+    pub fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+
+    pub fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+}
+
+/// Removes the ANSI escape sequences of the styles from `s`, giving the plain
+/// form of an output rendered with colors, as tests and log files want it.
+///
+/// ```
+/// use annotate_snippets::formatter::style::strip_styles;
+///
+/// assert_eq!(strip_styles("\x1b[1m\x1b[31merror\x1b[0m: x"), "error: x");
+/// ```
+pub fn strip_styles(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            result.push(c);
+            continue;
+        }
+        // A CSI sequence ends with a char in `@..=~`, other escapes are a
+        // single char long.
+        if let Some('[') = chars.next() {
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    result
 }
@@ -1,4 +1,5 @@
 #![deny(rust_2018_idioms)]
+#![cfg_attr(annotate_snippets_nightly, feature(proc_macro_diagnostic))]
 
 //! A library for formatting of text or programming code snippets.
 //!
@@ -46,9 +47,90 @@
 //! A user of the crate may also choose to provide their own formatter logic,
 //! to convert a `DisplayList` into a `String`, or just a `Stylesheet` to
 //! use the crate's formatting logic, but with a custom stylesheet.
+//!
+//! Users who only need the text can skip the `DisplayList` altogether with
+//! [format] or [render_to].
+//!
+//! ```
+//! use annotate_snippets::display_list::FormatOptions;
+//! use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+//!
+//! let snippet = Snippet::new().title(Annotation {
+//!     id: None,
+//!     label: Some("expected type, found `22`"),
+//!     annotation_type: AnnotationType::Error,
+//! });
+//! let text = annotate_snippets::format(&snippet, &FormatOptions::default());
+//! assert_eq!(text, "error: expected type, found `22`");
+//! ```
+//!
+//! # Panics
+//!
+//! Rendering a snippet accepted by
+//! [Snippet::validate](snippet::Snippet::validate) is not expected to panic,
+//! and `tests/fuzz.rs` checks it for generated sources, frames and options.
+//! The exceptions are deliberate or outside the crate: `assert_max_width`
+//! panics on a line wider than `max_width`, and the callbacks of
+//! `path_normalizer` and `gutter_badges`, like custom stylesheets, may panic
+//! on their own. Converting a snippet with invalid annotation ranges into a
+//! `DisplayList` panics, use `DisplayList::try_from_snippet` to get an error
+//! instead.
 // TODO: check documentation
 
+#[cfg(feature = "codespan-reporting")]
+pub mod codespan;
+#[cfg(feature = "serde")]
+pub mod description;
+#[cfg(feature = "diff")]
+pub mod diff;
 pub mod display_list;
+#[cfg(feature = "emitter")]
+pub mod emitter;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "fluent-bundle")]
+pub mod fluent;
 pub mod formatter;
+pub mod line_index;
+#[cfg(feature = "log")]
+pub mod log;
+#[cfg(feature = "lsp-types")]
+pub mod lsp;
+pub mod parse_error;
+#[cfg(feature = "proc-macro")]
+pub mod proc_macro;
 pub mod snippet;
+pub mod source_map;
 pub mod stylesheets;
+pub mod suggestion;
+pub mod summary;
+pub mod template;
+pub mod testing;
+#[cfg(feature = "tracing")]
+pub mod tracing;
+
+use std::io;
+
+use crate::display_list::{DisplayList, FormatOptions};
+use crate::snippet::Snippet;
+
+/// Renders `snippet` with `opt`, ignoring the options of the snippet.
+pub fn format(snippet: &Snippet<'_>, opt: &FormatOptions) -> String {
+    let lines = display_list::SnippetLines::with_options(
+        snippet,
+        opt,
+        display_list::LayoutBuffers::default(),
+    );
+    DisplayList::for_snippet(lines.collect(), snippet, opt.clone()).to_string()
+}
+
+/// Writes `snippet` rendered with `opt` into `w`, ignoring the options of the
+/// snippet, without building the whole output in memory.
+pub fn render_to<W: io::Write>(
+    snippet: &Snippet<'_>,
+    opt: &FormatOptions,
+    w: &mut W,
+) -> io::Result<()> {
+    formatter::stream::write_snippet_with(snippet, opt, w)
+}
@@ -0,0 +1,75 @@
+//! Conversions between offsets and positions in a text.
+//!
+//! A `LineIndex` stores where the lines of a text start, to convert the
+//! offsets the crate's ranges are made of from and to lines and columns.
+//! Like in the rest of the crate, offsets and columns count chars, and lines
+//! and columns start at 1.
+//!
+//! ```
+//! use annotate_snippets::line_index::LineIndex;
+//!
+//! let index = LineIndex::new("fn main() {\r\n    let é = 1;\n}");
+//! assert_eq!(index.offset_to_position(17), (2, 5));
+//! assert_eq!(index.position_to_offset(2, 5), Some(17));
+//! assert_eq!(index.position_to_offset(3, 3), None);
+//! ```
+
+/// The start offset of every line of a text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    /// Indexes the lines of `text`, which are ended by `\n`. A `\r` before it
+    /// belongs to its line.
+    pub fn new(text: &str) -> Self {
+        let mut len = 0;
+        let mut line_starts = vec![0];
+        for (i, c) in text.chars().enumerate() {
+            if c == '\n' {
+                line_starts.push(i + 1);
+            }
+            len = i + 1;
+        }
+        Self { line_starts, len }
+    }
+
+    /// Number of lines of the text, an empty text or a text ending with a
+    /// line break having an empty last line.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Offset of the first char of `line`, or `None` if the text has no such
+    /// line.
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line.checked_sub(1)?).copied()
+    }
+
+    /// Line and column of the char at `offset`. The line ending belongs to the
+    /// line it ends, and offsets past the end of the text are on the last
+    /// line.
+    pub fn offset_to_position(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        (line + 1, offset - self.line_starts[line] + 1)
+    }
+
+    /// Offset of the char at `line` and `col`, or `None` if the text has no
+    /// such position. The column after the last char of a line, where its
+    /// line ending or the end of the text is, is a position of the line.
+    pub fn position_to_offset(&self, line: usize, col: usize) -> Option<usize> {
+        let start = self.line_start(line)?;
+        let end = match self.line_starts.get(line) {
+            Some(next) => next - 1,
+            None => self.len,
+        };
+        let offset = start + col.checked_sub(1)?;
+        if offset <= end {
+            Some(offset)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Snippets logged through the `log` facade.
+//!
+//! With the `log` feature, a `Logger` renders snippets and logs them at the
+//! level of their title, either as a single record holding all of their
+//! lines, or as a record per line for loggers which expect single lines.
+//!
+//! ```
+//! use annotate_snippets::log::Logger;
+//! use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+//!
+//! let snippet = Snippet::new().title(Annotation::new(AnnotationType::Warning, "unused import"));
+//! Logger::new().target("lint").split_lines(true).log(&snippet);
+//! ```
+use log::Level;
+
+use crate::snippet::{AnnotationType, Snippet};
+
+/// The level of the records of snippets whose title is an `annotation_type`.
+///
+/// Notes and help are informational, like infos.
+pub fn level(annotation_type: AnnotationType) -> Level {
+    match annotation_type {
+        AnnotationType::Error => Level::Error,
+        AnnotationType::Warning => Level::Warn,
+        AnnotationType::Info | AnnotationType::Note | AnnotationType::Help => Level::Info,
+    }
+}
+
+/// Logs rendered snippets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Logger {
+    target: String,
+    split_lines: bool,
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self {
+            target: "annotate_snippets".to_string(),
+            split_lines: false,
+        }
+    }
+}
+
+impl Logger {
+    /// Creates a logger logging snippets as single records, with the
+    /// `annotate_snippets` target.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the target of the records.
+    pub fn target(mut self, target: impl Into<String>) -> Self {
+        self.target = target.into();
+        self
+    }
+
+    /// Sets whether every line of a rendered snippet is logged as a
+    /// record of its own.
+    pub fn split_lines(mut self, split_lines: bool) -> Self {
+        self.split_lines = split_lines;
+        self
+    }
+
+    /// Renders `snippet` with its options and logs it at the level of its
+    /// title, `Info` without one.
+    pub fn log(&self, snippet: &Snippet<'_>) {
+        let level = snippet
+            .title
+            .as_ref()
+            .map_or(Level::Info, |title| level(title.annotation_type));
+        if !log::log_enabled!(target: &self.target, level) {
+            return;
+        }
+        let rendered = crate::format(snippet, &snippet.opt);
+        if self.split_lines {
+            for line in rendered.lines() {
+                log::log!(target: &self.target, level, "{}", line);
+            }
+        } else {
+            log::log!(target: &self.target, level, "{}", rendered);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Snippets of the diagnostics of language servers.
+//!
+//! With the `lsp-types` feature, a command line frontend of a language server
+//! can display the `lsp_types::Diagnostic`s the server published for a
+//! document as snippets of the text of the document.
+//!
+//! The positions of the protocol count lines from 0, and UTF-16 code units in
+//! the lines; they are converted to the char offsets of the crate.
+//!
+//! ```
+//! use annotate_snippets::display_list::DisplayList;
+//! use lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+//!
+//! let document = "fn main() {\n    let x = 1;\n}\n";
+//! let diagnostic = Diagnostic {
+//!     range: Range::new(Position::new(1, 8), Position::new(1, 9)),
+//!     severity: Some(DiagnosticSeverity::WARNING),
+//!     message: "unused variable: `x`".to_string(),
+//!     ..Default::default()
+//! };
+//!
+//! let snippet = annotate_snippets::lsp::snippet(&diagnostic, document, Some("src/main.rs"), 0);
+//! assert_eq!(
+//!     DisplayList::from(snippet.to_snippet()).to_string(),
+//!     r#"warning: unused variable: `x`
+//!  --> src/main.rs:2:9
+//!   |
+//! 2 |     let x = 1;
+//!   |         -
+//!   |"#
+//! );
+//! ```
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position};
+
+use crate::display_list::{OwnedAnnotation, OwnedSlice, OwnedSnippet};
+use crate::snippet::{AnnotationType, Slice, SourceAnnotation};
+
+/// The annotation type of a diagnostic of `severity`.
+///
+/// Hints are help, and diagnostics without a severity errors.
+pub fn annotation_type(severity: Option<DiagnosticSeverity>) -> AnnotationType {
+    match severity {
+        Some(DiagnosticSeverity::WARNING) => AnnotationType::Warning,
+        Some(DiagnosticSeverity::INFORMATION) => AnnotationType::Info,
+        Some(DiagnosticSeverity::HINT) => AnnotationType::Help,
+        _ => AnnotationType::Error,
+    }
+}
+
+/// The char offset in `document` of a `position`, clamped to the end of its
+/// line, or of the document.
+pub fn offset(document: &str, position: Position) -> usize {
+    let mut offset = 0;
+    let mut lines = document.split_inclusive('\n');
+    for line in lines.by_ref().take(position.line as usize) {
+        offset += line.chars().count();
+    }
+    let line = lines.next().unwrap_or("");
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let mut units = 0;
+    for c in line.chars() {
+        if units >= position.character as usize {
+            break;
+        }
+        units += c.len_utf16();
+        offset += 1;
+    }
+    offset
+}
+
+/// Builds the snippet of a `diagnostic` of `document`, with `context` lines
+/// around the range of the diagnostic.
+///
+/// The message is the title, with the code as its id, and the range is
+/// marked without a label. The related information of the diagnostic are
+/// notes, with their locations.
+pub fn snippet(
+    diagnostic: &Diagnostic,
+    document: &str,
+    origin: Option<&str>,
+    context: usize,
+) -> OwnedSnippet {
+    let annotation_type = annotation_type(diagnostic.severity);
+    let start = offset(document, diagnostic.range.start);
+    let mut end = offset(document, diagnostic.range.end);
+    // An empty range marks the char after it.
+    if end <= start {
+        end = std::cmp::min(start + 1, document.chars().count());
+    }
+    let slice = Slice {
+        source: document,
+        line_start: 1,
+        origin,
+        annotations: vec![SourceAnnotation {
+            range: (start, end),
+            label: "",
+            annotation_type,
+            ..Default::default()
+        }],
+        fold: false,
+        line_highlights: vec![],
+        origin_note: None,
+    };
+    let footer = diagnostic
+        .related_information
+        .iter()
+        .flatten()
+        .map(|related| {
+            let position = related.location.range.start;
+            OwnedAnnotation {
+                id: None,
+                label: Some(format!(
+                    "{}:{}:{}: {}",
+                    related.location.uri,
+                    position.line + 1,
+                    position.character + 1,
+                    related.message
+                )),
+                annotation_type: AnnotationType::Note,
+            }
+        })
+        .collect();
+    OwnedSnippet {
+        title: Some(OwnedAnnotation {
+            id: diagnostic.code.as_ref().map(|code| match code {
+                NumberOrString::Number(number) => number.to_string(),
+                NumberOrString::String(code) => code.clone(),
+            }),
+            label: Some(diagnostic.message.clone()),
+            annotation_type,
+        }),
+        footer,
+        slices: slice
+            .extract(context)
+            .iter()
+            .map(OwnedSlice::from)
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Builds the snippets of the `diagnostics` of `document`, as `snippet`
+/// does.
+pub fn snippets(
+    diagnostics: &[Diagnostic],
+    document: &str,
+    origin: Option<&str>,
+    context: usize,
+) -> Vec<OwnedSnippet> {
+    diagnostics
+        .iter()
+        .map(|diagnostic| snippet(diagnostic, document, origin, context))
+        .collect()
+}
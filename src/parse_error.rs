@@ -0,0 +1,254 @@
+//! Snippets of the errors of the parsers of documents.
+//!
+//! A `ParseError` keeps the message of a parser and the position it failed
+//! at in a document, and displays them as a snippet of the document. With the
+//! `toml`, `serde_json`, `pest` and `nom` features, it can be made from the
+//! errors of these crates.
+//!
+//! ```
+//! use annotate_snippets::display_list::DisplayList;
+//! use annotate_snippets::parse_error::ParseError;
+//!
+//! let document = "[package]\nname = \"x\"\nversion = 1.0.0\n";
+//! let error = ParseError::new(document, "invalid TOML document", "invalid number")
+//!     .origin("Cargo.toml")
+//!     .at(31);
+//! assert_eq!(
+//!     DisplayList::from(error.snippet()).to_string(),
+//!     r#"error: invalid TOML document
+//!  --> Cargo.toml:3:11
+//!   |
+//! ...
+//! 3 | version = 1.0.0
+//!   |           ^ invalid number
+//!   |"#
+//! );
+//! ```
+use crate::snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation};
+
+/// The error of a parser on a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError<'a> {
+    pub document: &'a str,
+    pub origin: Option<&'a str>,
+    /// The label of the title of the snippet.
+    pub title: &'a str,
+    /// The message of the parser, labeling the position it failed at.
+    pub message: String,
+    /// The char offset in the document the parser failed at, if known.
+    pub offset: Option<usize>,
+    /// More messages of the parser, such as the tokens it expected, as the
+    /// footers of the snippet.
+    pub footer: Vec<(AnnotationType, String)>,
+}
+
+impl<'a> ParseError<'a> {
+    /// Creates the error of a parser which failed at an unknown position of
+    /// `document`.
+    pub fn new(document: &'a str, title: &'a str, message: impl Into<String>) -> Self {
+        Self {
+            document,
+            origin: None,
+            title,
+            message: message.into(),
+            offset: None,
+            footer: vec![],
+        }
+    }
+
+    pub fn origin(mut self, origin: &'a str) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+
+    /// Sets the char offset the parser failed at.
+    pub fn at(mut self, offset: usize) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Sets the byte offset in the document the parser failed at.
+    pub fn at_byte(self, byte: usize) -> Self {
+        let mut end = std::cmp::min(byte, self.document.len());
+        while !self.document.is_char_boundary(end) {
+            end -= 1;
+        }
+        let offset = self.document[..end].chars().count();
+        self.at(offset)
+    }
+
+    /// Sets the position the parser failed at, from its line and the byte
+    /// offset in the line, both starting at 0.
+    pub fn at_line_byte(self, line: usize, byte: usize) -> Self {
+        let mut line_start = 0;
+        for text in self.document.split_terminator('\n').take(line) {
+            line_start += text.len() + 1;
+        }
+        self.at_byte(line_start + byte)
+    }
+
+    /// Adds a footer to the snippet.
+    pub fn footer(mut self, annotation_type: AnnotationType, label: impl Into<String>) -> Self {
+        self.footer.push((annotation_type, label.into()));
+        self
+    }
+
+    /// Makes the error of the `toml` deserializer on `document`.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(document: &'a str, error: &toml::de::Error) -> Self {
+        let mut message = error.to_string();
+        match error.line_col() {
+            Some((line, col)) => {
+                let position = format!(" at line {} column {}", line + 1, col + 1);
+                if message.ends_with(&position) {
+                    message.truncate(message.len() - position.len());
+                }
+                Self::new(document, "invalid TOML document", message).at_line_byte(line, col)
+            }
+            None => Self::new(document, "invalid TOML document", message),
+        }
+    }
+
+    /// Makes the error of the `serde_json` deserializer on `document`.
+    #[cfg(feature = "serde_json")]
+    pub fn from_json(document: &'a str, error: &serde_json::Error) -> Self {
+        let mut message = error.to_string();
+        // Errors of deserializing values rather than text have no position,
+        // at line 0.
+        if error.line() == 0 {
+            return Self::new(document, "invalid JSON document", message);
+        }
+        let position = format!(" at line {} column {}", error.line(), error.column());
+        if message.ends_with(&position) {
+            message.truncate(message.len() - position.len());
+        }
+        Self::new(document, "invalid JSON document", message)
+            .at_line_byte(error.line() - 1, error.column().saturating_sub(1))
+    }
+
+    /// Makes the error of a `pest` parser on `document`. The rules it
+    /// expected are a help footer.
+    #[cfg(feature = "pest")]
+    pub fn from_pest<R: pest::RuleType>(document: &'a str, error: &pest::error::Error<R>) -> Self {
+        use pest::error::{ErrorVariant, InputLocation};
+
+        let byte = match error.location {
+            InputLocation::Pos(byte) | InputLocation::Span((byte, _)) => byte,
+        };
+        let error = match &error.variant {
+            ErrorVariant::ParsingError {
+                positives,
+                negatives,
+            } => {
+                let message = if negatives.is_empty() {
+                    "unexpected input".to_string()
+                } else {
+                    format!("unexpected {}", one_of(negatives))
+                };
+                let error = Self::new(document, "syntax error", message);
+                if positives.is_empty() {
+                    error
+                } else {
+                    error.footer(
+                        AnnotationType::Help,
+                        format!("expected {}", one_of(positives)),
+                    )
+                }
+            }
+            ErrorVariant::CustomError { message } => {
+                Self::new(document, "syntax error", message.as_str())
+            }
+        };
+        error.at_byte(byte)
+    }
+
+    /// Makes the error of a `nom` parser on `document`, from its trace. The
+    /// innermost error is the message, a char it expected is a help footer,
+    /// and the contexts around it are notes.
+    ///
+    /// The inputs of the trace are the remaining input of the parser, the
+    /// ends of `document`.
+    #[cfg(feature = "nom")]
+    pub fn from_nom(document: &'a str, error: &nom::error::VerboseError<&str>) -> Self {
+        use nom::error::VerboseErrorKind;
+
+        let (input, kind) = match error.errors.first() {
+            Some(first) => first,
+            None => return Self::new(document, "syntax error", "invalid input"),
+        };
+        let message = match kind {
+            VerboseErrorKind::Char(_) => match input.chars().next() {
+                Some(c) => format!("unexpected `{}`", c),
+                None => "unexpected end of input".to_string(),
+            },
+            VerboseErrorKind::Context(context) => format!("invalid {}", context),
+            VerboseErrorKind::Nom(kind) => kind.description().to_string(),
+        };
+        let mut parse_error = Self::new(document, "syntax error", message)
+            .at_byte(document.len().saturating_sub(input.len()));
+        if let VerboseErrorKind::Char(c) = kind {
+            parse_error = parse_error.footer(AnnotationType::Help, format!("expected `{}`", c));
+        }
+        for (_, kind) in &error.errors[1..] {
+            if let VerboseErrorKind::Context(context) = kind {
+                parse_error =
+                    parse_error.footer(AnnotationType::Note, format!("while parsing {}", context));
+            }
+        }
+        parse_error
+    }
+
+    /// Borrows the error as a snippet: an error titled `title`, with the
+    /// message at the position of the failure in the document, folded around
+    /// it. Without a position, the message is a footer.
+    pub fn snippet(&self) -> Snippet<'_> {
+        let mut snippet = Snippet::new().title(Annotation::new(AnnotationType::Error, self.title));
+        let offset = match self.offset {
+            Some(offset) => offset,
+            None => {
+                snippet =
+                    snippet.footer(Annotation::new(AnnotationType::Note, self.message.as_str()));
+                return self.add_footer(snippet);
+            }
+        };
+        // The failure can be at the end of the document, which the last line
+        // ending or char shows.
+        let len = self.document.chars().count();
+        let start = if offset >= len {
+            len.saturating_sub(1)
+        } else {
+            offset
+        };
+        let range = (start, std::cmp::min(start + 1, len));
+        let slice = Slice::new(self.document, 1)
+            .origin(self.origin)
+            .annotation(SourceAnnotation::new(
+                range,
+                AnnotationType::Error,
+                &self.message,
+            ))
+            .fold(true);
+        self.add_footer(snippet.slice(slice))
+    }
+
+    fn add_footer<'s>(&'s self, mut snippet: Snippet<'s>) -> Snippet<'s> {
+        for (annotation_type, label) in &self.footer {
+            snippet = snippet.footer(Annotation::new(*annotation_type, label.as_str()));
+        }
+        snippet
+    }
+}
+
+/// Lists pest rules as the tokens of a message.
+#[cfg(feature = "pest")]
+fn one_of<R: std::fmt::Debug>(rules: &[R]) -> String {
+    let rules = rules
+        .iter()
+        .map(|rule| format!("`{:?}`", rule))
+        .collect::<Vec<_>>();
+    match rules.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, [first])) => format!("{} or {}", first, last),
+        _ => format!("one of {}", rules.join(", ")),
+    }
+}
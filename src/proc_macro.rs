@@ -0,0 +1,132 @@
+//! Snippets reported by procedural macros.
+//!
+//! With the `proc-macro` feature, a procedural macro can report its errors as
+//! snippets with `emit`. On a nightly compiler, a snippet is forwarded to the
+//! compiler as a `proc_macro::Diagnostic`: its title at the span of the
+//! invocation, and its source annotations and footers as children at their
+//! own spans. On a stable compiler, a snippet titled as an error, or without
+//! a title, is rendered into the message of a `compile_error!` of the returned
+//! tokens. Stable compilers have no way for macros to report warnings, notes
+//! or help, so the other snippets are not reported there rather than fail the
+//! build.
+//!
+//! In both cases the returned tokens are to be added to the output of the
+//! macro.
+//!
+//! ```
+//! extern crate proc_macro;
+//!
+//! use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+//! use proc_macro::{Span, TokenStream};
+//!
+//! fn report() -> TokenStream {
+//!     let snippet = Snippet::new().title(Annotation::new(
+//!         AnnotationType::Error,
+//!         "expected a struct",
+//!     ));
+//!     annotate_snippets::proc_macro::emit(&snippet, Span::call_site())
+//! }
+//! ```
+extern crate proc_macro;
+
+#[cfg(not(annotate_snippets_nightly))]
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, TokenTree};
+#[cfg(annotate_snippets_nightly)]
+use proc_macro::{Diagnostic, Level};
+use proc_macro::{Span, TokenStream};
+
+#[cfg(not(annotate_snippets_nightly))]
+use crate::display_list::FormatOptions;
+use crate::snippet::{AnnotationType, Snippet, SourceAnnotation};
+
+/// Reports `snippet` at `span`, the source annotations of the snippet being
+/// at `span` too.
+pub fn emit(snippet: &Snippet<'_>, span: Span) -> TokenStream {
+    emit_with_spans(snippet, span, |_| None)
+}
+
+/// Reports `snippet` at `span`, the source annotations of the snippet being
+/// at the spans `span_of` finds for them, or at `span`.
+///
+/// The spans of the source annotations are only used on nightly compilers;
+/// on stable ones, the rendered snippet shows the annotations in its source.
+#[cfg(annotate_snippets_nightly)]
+pub fn emit_with_spans<F>(snippet: &Snippet<'_>, span: Span, mut span_of: F) -> TokenStream
+where
+    F: FnMut(&SourceAnnotation<'_>) -> Option<Span>,
+{
+    let (annotation_type, message) = match &snippet.title {
+        Some(title) => (title.annotation_type, title.label.unwrap_or_default()),
+        None => (AnnotationType::Error, ""),
+    };
+    let mut diagnostic = Diagnostic::spanned(span, level(annotation_type), message);
+    for annotation in snippet.slices.iter().flat_map(|slice| &slice.annotations) {
+        let span = span_of(annotation).unwrap_or(span);
+        diagnostic = match annotation.annotation_type {
+            AnnotationType::Error => diagnostic.span_error(span, annotation.label),
+            AnnotationType::Warning => diagnostic.span_warning(span, annotation.label),
+            AnnotationType::Info | AnnotationType::Note => {
+                diagnostic.span_note(span, annotation.label)
+            }
+            AnnotationType::Help => diagnostic.span_help(span, annotation.label),
+        };
+    }
+    for footer in &snippet.footer {
+        let label = footer.label.unwrap_or_default();
+        diagnostic = match footer.annotation_type {
+            AnnotationType::Error => diagnostic.error(label),
+            AnnotationType::Warning => diagnostic.warning(label),
+            AnnotationType::Info | AnnotationType::Note => diagnostic.note(label),
+            AnnotationType::Help => diagnostic.help(label),
+        };
+    }
+    diagnostic.emit();
+    TokenStream::new()
+}
+
+/// Reports `snippet` at `span`, the source annotations of the snippet being
+/// at the spans `span_of` finds for them, or at `span`.
+///
+/// The spans of the source annotations are only used on nightly compilers;
+/// on stable ones, the rendered snippet shows the annotations in its source.
+#[cfg(not(annotate_snippets_nightly))]
+pub fn emit_with_spans<F>(snippet: &Snippet<'_>, span: Span, _span_of: F) -> TokenStream
+where
+    F: FnMut(&SourceAnnotation<'_>) -> Option<Span>,
+{
+    if let Some(title) = &snippet.title {
+        if title.annotation_type != AnnotationType::Error {
+            return TokenStream::new();
+        }
+    }
+    // The compiler prints the message of `compile_error!` as is, without
+    // interpreting colors.
+    let opt = FormatOptions {
+        color: false,
+        ..snippet.opt.clone()
+    };
+    let mut message = Literal::string(&crate::format(snippet, &opt));
+    message.set_span(span);
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(span);
+    let mut group = Group::new(Delimiter::Brace, TokenTree::Literal(message).into());
+    group.set_span(span);
+    vec![
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(bang),
+        TokenTree::Group(group),
+    ]
+    .into_iter()
+    .collect()
+}
+
+/// The level of the diagnostics of an `annotation_type`.
+#[cfg(annotate_snippets_nightly)]
+fn level(annotation_type: AnnotationType) -> Level {
+    match annotation_type {
+        AnnotationType::Error => Level::Error,
+        AnnotationType::Warning => Level::Warning,
+        AnnotationType::Info | AnnotationType::Note => Level::Note,
+        AnnotationType::Help => Level::Help,
+    }
+}
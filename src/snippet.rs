@@ -5,49 +5,195 @@
 //! ```
 //! use annotate_snippets::snippet::*;
 //!
-//! Snippet {
-//!     title: Some(Annotation {
+//! Snippet::new()
+//!     .title(Annotation {
 //!         label: Some("mismatched types"),
 //!         id: None,
 //!         annotation_type: AnnotationType::Error,
-//!     }),
-//!     footer: vec![],
-//!     slices: vec![
-//!         Slice {
-//!             source: "Foo",
-//!             line_start: 51,
-//!             origin: Some("src/format.rs"),
-//!             fold: false,
-//!             annotations: vec![],
-//!         },
-//!         Slice {
-//!             source: "Faa",
-//!             line_start: 129,
-//!             origin: Some("src/display.rs"),
-//!             fold: false,
-//!             annotations: vec![],
-//!         },
-//!     ],
-//!     opt: Default::default(),
-//! };
+//!     })
+//!     .slice(Slice::new("Foo", 51).origin("src/format.rs"))
+//!     .slice(Slice::new("Faa", 129).origin("src/display.rs"));
 //! ```
+use std::error::Error;
+use std::fmt;
+
 use crate::display_list::FormatOptions;
+use crate::formatter::style::Color;
 
 /// Primary structure provided for formatting
-#[derive(Debug, Default)]
+///
+/// Snippets are built from `Snippet::new` with a method per field, so that
+/// adding fields doesn't break them.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct Snippet<'a> {
     pub title: Option<Annotation<'a>>,
     pub footer: Vec<Annotation<'a>>,
     pub slices: Vec<Slice<'a>>,
     pub opt: FormatOptions,
+    /// Frames of the macro expansions the snippet originates from, innermost
+    /// first, displayed as notes after the footer.
+    pub expansion: Vec<ExpansionFrame<'a>>,
+    /// Files including the file of the snippet, innermost first, displayed as
+    /// notes above the title.
+    pub includes: Vec<IncludeFrame<'a>>,
+    /// More title-level messages, like a warning about a feature gate along
+    /// with an error, displayed after the title as more header lines of the
+    /// same slices.
+    pub extra_titles: Vec<Annotation<'a>>,
+    /// The lint the snippet reports, whose level is explained by a note
+    /// after the footers, like `#[warn(unused_variables)]` on by default.
+    pub lint: Option<Lint<'a>>,
 }
 
+impl<'a> Snippet<'a> {
+    /// Creates a snippet without title, slices nor footers, to complete with
+    /// the following methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(mut self, title: Annotation<'a>) -> Self {
+        self.title = Some(title);
+        self
+    }
+
+    /// Appends a title displayed after the title.
+    pub fn extra_title(mut self, title: Annotation<'a>) -> Self {
+        self.extra_titles.push(title);
+        self
+    }
+
+    pub fn lint(mut self, lint: Lint<'a>) -> Self {
+        self.lint = Some(lint);
+        self
+    }
+
+    /// Appends a slice.
+    pub fn slice(mut self, slice: Slice<'a>) -> Self {
+        self.slices.push(slice);
+        self
+    }
+
+    /// Appends a footer.
+    pub fn footer(mut self, footer: Annotation<'a>) -> Self {
+        self.footer.push(footer);
+        self
+    }
+
+    pub fn opt(mut self, opt: FormatOptions) -> Self {
+        self.opt = opt;
+        self
+    }
+
+    /// Appends the frame of a macro expansion, outside the previous ones.
+    pub fn expansion(mut self, frame: ExpansionFrame<'a>) -> Self {
+        self.expansion.push(frame);
+        self
+    }
+
+    /// Appends a file including the file of the snippet, outside the
+    /// previous ones.
+    pub fn include(mut self, frame: IncludeFrame<'a>) -> Self {
+        self.includes.push(frame);
+        self
+    }
+
+    /// Checks that the snippet can be displayed: that the annotations of its
+    /// slices, and of the slices of its expansion frames, are within their
+    /// sources.
+    ///
+    /// Converting a snippet which is not valid into a `DisplayList` panics.
+    ///
+    /// Nothing else can make a snippet impossible to display. The titles of
+    /// a snippet are not referred to by index, and a fold is a flag of its
+    /// slice, so that folds can't overlap. Line highlights of lines outside
+    /// their slice don't highlight anything.
+    pub fn validate(&self) -> Result<(), SnippetError> {
+        let slices = self
+            .slices
+            .iter()
+            .enumerate()
+            .map(|(i, slice)| (None, i, slice));
+        let frames = self
+            .expansion
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| (Some(i), 0, &frame.slice));
+        for (frame, index, slice) in slices.chain(frames) {
+            let source_len = slice.source.chars().count();
+            for annotation in &slice.annotations {
+                let (start, end) = annotation.range;
+                if start > end || end > source_len {
+                    return Err(SnippetError::InvalidRange {
+                        frame,
+                        slice: index,
+                        range: annotation.range,
+                        source_len,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An error making a snippet impossible to display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnippetError {
+    /// An annotation of the slice at index `slice` whose range starts after
+    /// its end, or ends past the source of the slice.
+    ///
+    /// The slice is the one of the expansion frame at index `frame`, and
+    /// `slice` is then 0, or one of the slices of the snippet when `frame` is
+    /// `None`.
+    InvalidRange {
+        frame: Option<usize>,
+        slice: usize,
+        range: (usize, usize),
+        source_len: usize,
+    },
+}
+
+impl fmt::Display for SnippetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnippetError::InvalidRange {
+                frame: None,
+                slice,
+                range,
+                source_len,
+            } => write!(
+                f,
+                "invalid annotation range `{:?}` in slice {} of length `{}`",
+                range, slice, source_len
+            ),
+            SnippetError::InvalidRange {
+                frame: Some(frame),
+                range,
+                source_len,
+                ..
+            } => write!(
+                f,
+                "invalid annotation range `{:?}` in expansion frame {} of length `{}`",
+                range, frame, source_len
+            ),
+        }
+    }
+}
+
+impl Error for SnippetError {}
+
 /// Structure containing the slice of text to be annotated and
 /// basic information about the location of the slice.
 ///
 /// One `Slice` is meant to represent a single, continuous,
 /// slice of source code that you want to annotate.
-#[derive(Debug)]
+///
+/// The default slice is empty and starts at line 1. Slices are built from
+/// `Slice::new`, like snippets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct Slice<'a> {
     pub source: &'a str,
     pub line_start: usize,
@@ -56,10 +202,489 @@ pub struct Slice<'a> {
     /// If set explicitly to `true`, the snippet will fold
     /// parts of the slice that don't contain any annotations.
     pub fold: bool,
+    /// Whole lines highlighted as added, removed or changed.
+    pub line_highlights: Vec<LineHighlight>,
+    /// A short note about the origin, like `generated file`, displayed
+    /// under the header of the slice.
+    pub origin_note: Option<&'a str>,
+}
+
+impl Default for Slice<'_> {
+    fn default() -> Self {
+        Self {
+            source: "",
+            line_start: 1,
+            origin: None,
+            annotations: vec![],
+            fold: false,
+            line_highlights: vec![],
+            origin_note: None,
+        }
+    }
+}
+
+impl<'a> Slice<'a> {
+    /// Creates a slice of `source` without annotations, starting at line
+    /// `line_start`.
+    pub fn new(source: &'a str, line_start: usize) -> Self {
+        Self {
+            source,
+            line_start,
+            ..Default::default()
+        }
+    }
+
+    pub fn origin(mut self, origin: impl Into<Option<&'a str>>) -> Self {
+        self.origin = origin.into();
+        self
+    }
+
+    pub fn origin_note(mut self, origin_note: impl Into<Option<&'a str>>) -> Self {
+        self.origin_note = origin_note.into();
+        self
+    }
+
+    /// Appends an annotation.
+    pub fn annotation(mut self, annotation: SourceAnnotation<'a>) -> Self {
+        self.annotations.push(annotation);
+        self
+    }
+
+    pub fn fold(mut self, fold: bool) -> Self {
+        self.fold = fold;
+        self
+    }
+
+    /// Appends a highlight of a whole line.
+    pub fn highlight_line(mut self, highlight: LineHighlight) -> Self {
+        self.line_highlights.push(highlight);
+        self
+    }
+
+    /// Splits a slice of a whole file into the slices needed to display its
+    /// annotations.
+    ///
+    /// Only the lines covered by annotations, with `context` lines before and
+    /// after them, are kept. Groups of lines that are not adjacent become
+    /// separate slices, ordered by position in the file, with `line_start` and
+    /// the annotation ranges rebased on their first line. Annotations keep
+    /// their relative order.
+    ///
+    /// ```
+    /// use annotate_snippets::snippet::{AnnotationType, Slice, SourceAnnotation};
+    ///
+    /// let file = "fn a() {}\nfn b() {}\nfn c() {}\nfn d() {}\n";
+    /// let slices = Slice::new(file, 1)
+    ///     .origin("src/lib.rs")
+    ///     .annotation(SourceAnnotation {
+    ///         range: (23, 24),
+    ///         label: "here",
+    ///         annotation_type: AnnotationType::Error,
+    ///         ..Default::default()
+    ///     })
+    ///     .extract(0);
+    ///
+    /// assert_eq!(slices[0].source, "fn c() {}\n");
+    /// assert_eq!(slices[0].line_start, 3);
+    /// assert_eq!(slices[0].annotations[0].range, (3, 4));
+    /// ```
+    pub fn extract(self, context: usize) -> Vec<Slice<'a>> {
+        let Slice {
+            source,
+            line_start,
+            origin,
+            annotations,
+            fold,
+            line_highlights,
+            origin_note,
+        } = self;
+
+        // Byte and char offsets of the start of every line, and of the end.
+        let mut starts = vec![(0, 0)];
+        let mut char_index = 0;
+        for (byte_index, c) in source.char_indices() {
+            char_index += 1;
+            if c == '\n' {
+                starts.push((byte_index + 1, char_index));
+            }
+        }
+        let last_line = starts.len() - 1;
+        let line_of = |index: usize| {
+            starts
+                .partition_point(|&(_, start)| start <= index)
+                .saturating_sub(1)
+                .min(last_line)
+        };
+
+        // Lines needed by each annotation, and the groups they are merged in.
+        let needed = annotations
+            .iter()
+            .map(|annotation| {
+                let (start, end) = annotation.range;
+                let first = line_of(start);
+                let last = line_of(end.saturating_sub(1).max(start));
+                (
+                    first.saturating_sub(context),
+                    (last + context).min(last_line),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut groups = needed.clone();
+        groups.sort_unstable();
+        groups.dedup_by(|next, group| {
+            if next.0 <= group.1 + 1 {
+                group.1 = std::cmp::max(group.1, next.1);
+                true
+            } else {
+                false
+            }
+        });
+
+        let mut slices = groups
+            .iter()
+            .map(|&(first, last)| {
+                let end = starts.get(last + 1).map_or(source.len(), |&(end, _)| end);
+                Slice {
+                    source: &source[starts[first].0..end],
+                    line_start: line_start + first,
+                    origin,
+                    annotations: vec![],
+                    fold,
+                    line_highlights: line_highlights
+                        .iter()
+                        .filter(|highlight| {
+                            (line_start + first..=line_start + last).contains(&highlight.line)
+                        })
+                        .cloned()
+                        .collect(),
+                    origin_note,
+                }
+            })
+            .collect::<Vec<_>>();
+        for (annotation, (first, _)) in annotations.into_iter().zip(needed) {
+            let group = groups.partition_point(|&(start, _)| start <= first) - 1;
+            let offset = starts[groups[group].0].1;
+            slices[group].annotations.push(SourceAnnotation {
+                range: (annotation.range.0 - offset, annotation.range.1 - offset),
+                ..annotation
+            });
+        }
+        slices
+    }
+
+    /// Builds the slice displaying a single annotation of a whole file, with
+    /// `context` lines before and after the lines of its range.
+    ///
+    /// The range of `annotation` counts chars from the start of `file`, whose
+    /// first line is line 1. As with `extract`, `line_start` and the range are
+    /// rebased on the first displayed line.
+    ///
+    /// ```
+    /// use annotate_snippets::snippet::{AnnotationType, Slice, SourceAnnotation};
+    ///
+    /// let file = "fn a() {}\nfn b() {}\nfn c() {}\nfn d() {}\n";
+    /// let slice = Slice::around(
+    ///     file,
+    ///     SourceAnnotation {
+    ///         range: (23, 24),
+    ///         label: "here",
+    ///         annotation_type: AnnotationType::Error,
+    ///         ..Default::default()
+    ///     },
+    ///     1,
+    /// );
+    ///
+    /// assert_eq!(slice.source, "fn b() {}\nfn c() {}\nfn d() {}\n");
+    /// assert_eq!(slice.line_start, 2);
+    /// assert_eq!(slice.annotations[0].range, (13, 14));
+    /// ```
+    pub fn around(file: &'a str, annotation: SourceAnnotation<'a>, context: usize) -> Slice<'a> {
+        let slice = Slice {
+            source: file,
+            line_start: 1,
+            origin: None,
+            annotations: vec![annotation],
+            fold: false,
+            line_highlights: vec![],
+            origin_note: None,
+        };
+        // A single annotation needs a single group of lines.
+        slice.extract(context).pop().unwrap()
+    }
+
+    /// Merges the slices displaying the same lines of the same origin, so that
+    /// they are displayed once with all their annotations.
+    ///
+    /// A slice is merged into an earlier one of the same origin when the lines
+    /// of one of them are all lines of the other, with the same text. The
+    /// merged slice is shown at the place of the earlier one, from the larger
+    /// source, with the annotations of the earlier slice first. Partially
+    /// overlapping slices are kept apart, as their sources can't be joined.
+    ///
+    /// ```
+    /// use annotate_snippets::snippet::{AnnotationType, Slice, SourceAnnotation};
+    ///
+    /// let slice = |source, line_start, range| {
+    ///     Slice::new(source, line_start)
+    ///         .origin("src/lib.rs")
+    ///         .annotation(SourceAnnotation {
+    ///             range,
+    ///             label: "here",
+    ///             annotation_type: AnnotationType::Error,
+    ///             ..Default::default()
+    ///         })
+    /// };
+    /// let slices = Slice::merge(vec![
+    ///     slice("let b = a;\n", 2, (8, 9)),
+    ///     slice("let a = 1;\nlet b = a;\n", 1, (4, 5)),
+    /// ]);
+    ///
+    /// assert_eq!(slices.len(), 1);
+    /// assert_eq!(slices[0].line_start, 1);
+    /// assert_eq!(slices[0].annotations[0].range, (19, 20));
+    /// assert_eq!(slices[0].annotations[1].range, (4, 5));
+    /// ```
+    pub fn merge(slices: Vec<Slice<'a>>) -> Vec<Slice<'a>> {
+        let mut merged: Vec<Slice<'a>> = Vec::with_capacity(slices.len());
+        for slice in slices {
+            let target = merged.iter_mut().find_map(|earlier| {
+                if earlier.origin != slice.origin {
+                    return None;
+                }
+                if let Some(offset) = earlier.line_offset(&slice) {
+                    Some((earlier, offset, false))
+                } else {
+                    let offset = slice.line_offset(earlier)?;
+                    Some((earlier, offset, true))
+                }
+            });
+            match target {
+                // `slice` is within `earlier`.
+                Some((earlier, offset, false)) => {
+                    earlier.fold &= slice.fold;
+                    earlier
+                        .annotations
+                        .extend(
+                            slice
+                                .annotations
+                                .into_iter()
+                                .map(|annotation| SourceAnnotation {
+                                    range: (
+                                        annotation.range.0 + offset,
+                                        annotation.range.1 + offset,
+                                    ),
+                                    ..annotation
+                                }),
+                        );
+                    earlier.line_highlights.extend(slice.line_highlights);
+                    earlier.origin_note = earlier.origin_note.or(slice.origin_note);
+                }
+                // `earlier` is within `slice`.
+                Some((earlier, offset, true)) => {
+                    for annotation in &mut earlier.annotations {
+                        annotation.range =
+                            (annotation.range.0 + offset, annotation.range.1 + offset);
+                    }
+                    earlier.source = slice.source;
+                    earlier.line_start = slice.line_start;
+                    earlier.fold &= slice.fold;
+                    earlier.annotations.extend(slice.annotations);
+                    earlier.line_highlights.extend(slice.line_highlights);
+                    earlier.origin_note = earlier.origin_note.or(slice.origin_note);
+                }
+                None => merged.push(slice),
+            }
+        }
+        merged
+    }
+
+    /// Merges the annotations with the same label and type whose ranges
+    /// overlap or touch, so that they are displayed as a single underline.
+    ///
+    /// Each merged annotation takes the place of the first of the annotations
+    /// it replaces.
+    ///
+    /// ```
+    /// use annotate_snippets::snippet::{AnnotationType, Slice, SourceAnnotation};
+    ///
+    /// let annotation = |range| SourceAnnotation {
+    ///     range,
+    ///     label: "trailing whitespace",
+    ///     annotation_type: AnnotationType::Warning,
+    ///     ..Default::default()
+    /// };
+    /// let mut slice = Slice::new("let x = 1;\t  ", 1)
+    ///     .annotation(annotation((11, 12)))
+    ///     .annotation(annotation((10, 11)))
+    ///     .annotation(annotation((12, 13)));
+    /// slice.merge_annotations();
+    ///
+    /// assert_eq!(slice.annotations.len(), 1);
+    /// assert_eq!(slice.annotations[0].range, (10, 13));
+    /// ```
+    pub fn merge_annotations(&mut self) {
+        let mut annotations = std::mem::take(&mut self.annotations)
+            .into_iter()
+            .enumerate()
+            .collect::<Vec<_>>();
+        annotations.sort_by_key(|(_, annotation)| {
+            (
+                annotation.label,
+                annotation.annotation_type as u8,
+                annotation.range.0,
+            )
+        });
+        annotations.dedup_by(|(next_idx, next), (idx, annotation)| {
+            if next.label == annotation.label
+                && next.annotation_type == annotation.annotation_type
+                && next.range.0 <= annotation.range.1
+            {
+                annotation.range.1 = std::cmp::max(annotation.range.1, next.range.1);
+                *idx = std::cmp::min(*idx, *next_idx);
+                true
+            } else {
+                false
+            }
+        });
+        annotations.sort_unstable_by_key(|&(idx, _)| idx);
+        self.annotations = annotations
+            .into_iter()
+            .map(|(_, annotation)| annotation)
+            .collect();
+    }
+
+    /// Char offset in this slice of the start of `other`, if all lines of
+    /// `other` are lines of this slice.
+    fn line_offset(&self, other: &Slice<'_>) -> Option<usize> {
+        let skip = other.line_start.checked_sub(self.line_start)?;
+        let mut lines = self.source.split_terminator('\n');
+        let offset = lines
+            .by_ref()
+            .take(skip)
+            .map(|line| line.chars().count() + 1)
+            .sum();
+        let mut other_lines = other.source.split_terminator('\n');
+        if lines.zip(other_lines.by_ref()).any(|(a, b)| a != b) {
+            return None;
+        }
+        // All lines of `other` must have been compared.
+        if other_lines.next().is_some() {
+            return None;
+        }
+        Some(offset)
+    }
+}
+
+/// A frame of a macro expansion trace, such as "in this expansion of `foo!`",
+/// with the slice it points to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpansionFrame<'a> {
+    pub label: &'a str,
+    pub slice: Slice<'a>,
+}
+
+/// A file including the file of a snippet, at the given line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludeFrame<'a> {
+    pub path: &'a str,
+    pub line: usize,
+}
+
+/// A lint reported by a snippet, with the level it is reported at and how
+/// that level was set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lint<'a> {
+    /// Name of the lint, like `unused_variables`.
+    pub name: &'a str,
+    pub level: LintLevel,
+    pub source: LintSource<'a>,
+}
+
+impl<'a> Lint<'a> {
+    /// Creates a lint reported at its default level.
+    pub fn new(name: &'a str, level: LintLevel) -> Self {
+        Self {
+            name,
+            level,
+            source: LintSource::Default,
+        }
+    }
+
+    pub fn source(mut self, source: LintSource<'a>) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// The note explaining the level of the lint, as rustc words it.
+    ///
+    /// ```
+    /// use annotate_snippets::snippet::{Lint, LintLevel, LintSource};
+    ///
+    /// let lint = Lint::new("unused_variables", LintLevel::Warn);
+    /// assert_eq!(lint.note(), "`#[warn(unused_variables)]` on by default");
+    /// let lint = lint.source(LintSource::CommandLine);
+    /// assert_eq!(lint.note(), "requested on the command line with `-W unused-variables`");
+    /// ```
+    pub fn note(&self) -> String {
+        let attribute = format!("`#[{}({})]`", self.level.name(), self.name);
+        match self.source {
+            LintSource::Default => format!("{} on by default", attribute),
+            LintSource::Group(group) => format!(
+                "{} implied by `#[{}({})]`",
+                attribute,
+                self.level.name(),
+                group
+            ),
+            LintSource::CommandLine => format!(
+                "requested on the command line with `{} {}`",
+                self.level.flag(),
+                self.name.replace('_', "-")
+            ),
+        }
+    }
+}
+
+/// Levels lints are reported at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintLevel {
+    Warn,
+    Deny,
+    Forbid,
+}
+
+impl LintLevel {
+    /// The name of the level in attributes, like `warn`.
+    pub fn name(self) -> &'static str {
+        match self {
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny",
+            LintLevel::Forbid => "forbid",
+        }
+    }
+
+    /// The command line flag setting the level, like `-W`.
+    pub fn flag(self) -> &'static str {
+        match self {
+            LintLevel::Warn => "-W",
+            LintLevel::Deny => "-D",
+            LintLevel::Forbid => "-F",
+        }
+    }
+}
+
+/// How the level of a lint was set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintSource<'a> {
+    /// The lint is at its default level.
+    Default,
+    /// The level was set for the group of lints named so, like `unused`.
+    Group(&'a str),
+    /// The level was set on the command line.
+    CommandLine,
 }
 
 /// Types of annotations.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AnnotationType {
     /// Error annotations are displayed using red color and "^" character.
     Error,
@@ -70,19 +695,131 @@ pub enum AnnotationType {
     Help,
 }
 
+/// How much of the source around an annotation survives the folds of its
+/// slice.
+///
+/// Folding keeps a few lines of context on both sides of every annotation;
+/// high-priority annotations keep more of them, and low-priority ones only
+/// keep their own lines.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
 /// An annotation for a `Slice`.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SourceAnnotation<'a> {
     pub range: (usize, usize),
     pub label: &'a str,
     pub annotation_type: AnnotationType,
+    pub priority: Priority,
+}
+
+/// An empty error annotation at the start of the source, of normal
+/// priority, to build annotations with the fields that differ:
+///
+/// ```
+/// use annotate_snippets::snippet::{AnnotationType, SourceAnnotation};
+///
+/// let annotation = SourceAnnotation {
+///     range: (4, 7),
+///     label: "unused variable",
+///     annotation_type: AnnotationType::Warning,
+///     ..Default::default()
+/// };
+/// assert_eq!(annotation, SourceAnnotation::new((4, 7), AnnotationType::Warning, "unused variable"));
+/// ```
+impl Default for SourceAnnotation<'_> {
+    fn default() -> Self {
+        Self {
+            range: (0, 0),
+            label: "",
+            annotation_type: AnnotationType::Error,
+            priority: Priority::Normal,
+        }
+    }
+}
+
+/// How a line highlighted by a `LineHighlight` differs from another
+/// version of the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineChange {
+    /// The line is marked with `+`.
+    Added,
+    /// The line is marked with `-`.
+    Removed,
+    /// The line is marked with `>`.
+    Changed,
+}
+
+/// A highlight of a whole line of a `Slice`, marked in the gutter after its
+/// change and painted with an optional background color.
+///
+/// Highlighted lines are kept by folds, as annotated lines are.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LineHighlight {
+    /// Number of the line, as displayed.
+    pub line: usize,
+    pub change: LineChange,
+    pub background: Option<Color>,
+}
+
+impl LineHighlight {
+    pub fn new(line: usize, change: LineChange) -> Self {
+        Self {
+            line,
+            change,
+            background: None,
+        }
+    }
+
+    pub fn background(mut self, color: impl Into<Option<Color>>) -> Self {
+        self.background = color.into();
+        self
+    }
 }
 
 /// An annotation for a `Snippet`.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Annotation<'a> {
     /// Identifier of the annotation. Usually error code like "E0308".
     pub id: Option<&'a str>,
     pub label: Option<&'a str>,
     pub annotation_type: AnnotationType,
 }
+
+impl<'a> SourceAnnotation<'a> {
+    pub fn new(range: (usize, usize), annotation_type: AnnotationType, label: &'a str) -> Self {
+        Self {
+            range,
+            label,
+            annotation_type,
+            priority: Priority::Normal,
+        }
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+impl<'a> Annotation<'a> {
+    /// Creates an annotation without id.
+    pub fn new(annotation_type: AnnotationType, label: impl Into<Option<&'a str>>) -> Self {
+        Self {
+            id: None,
+            label: label.into(),
+            annotation_type,
+        }
+    }
+
+    pub fn id(mut self, id: impl Into<Option<&'a str>>) -> Self {
+        self.id = id.into();
+        self
+    }
+}
@@ -0,0 +1,227 @@
+//! Ownership of the files annotated by snippets.
+//!
+//! A `SourceMap` owns the contents of files, identified by a `FileId`.
+//! Annotations can then reference a file and a range in it, and the map builds
+//! the slices displaying them.
+//!
+//! Like in the rest of the crate, offsets and columns count chars.
+//! Contents which are not valid UTF-8 can be added with `SourceMap::add_bytes`,
+//! and the byte offsets into them converted with `SourceMap::offset_of_byte`.
+//!
+//! ```
+//! use annotate_snippets::snippet::AnnotationType;
+//! use annotate_snippets::source_map::{SourceMap, SpanAnnotation};
+//!
+//! let mut map = SourceMap::new();
+//! let file = map.add("src/main.rs", "fn main() {\n    let x = 1;\n}\n");
+//! assert_eq!(map.line_col(file, 20), (2, 9));
+//!
+//! let slices = map.slices(
+//!     vec![SpanAnnotation {
+//!         file,
+//!         range: (20, 21),
+//!         label: "unused variable",
+//!         annotation_type: AnnotationType::Warning,
+//!     }],
+//!     0,
+//! );
+//! assert_eq!(slices[0].source, "    let x = 1;\n");
+//! assert_eq!(slices[0].origin, Some("src/main.rs"));
+//! ```
+use crate::line_index::LineIndex;
+use crate::snippet::{AnnotationType, Slice, SourceAnnotation};
+
+/// Identifier of a file of a `SourceMap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId(usize);
+
+/// A file owned by a `SourceMap`.
+#[derive(Debug)]
+struct SourceFile {
+    name: String,
+    source: String,
+    lines: LineIndex,
+    /// Byte offset in the added contents and length of every invalid
+    /// sequence replaced by a `U+FFFD REPLACEMENT CHARACTER` in `source`.
+    replaced: Vec<(usize, usize)>,
+}
+
+/// A collection of files, with lookups between offsets and lines.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+/// An annotation of a range of a file of a `SourceMap`.
+#[derive(Debug)]
+pub struct SpanAnnotation<'a> {
+    pub file: FileId,
+    pub range: (usize, usize),
+    pub label: &'a str,
+    pub annotation_type: AnnotationType,
+}
+
+impl SourceMap {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, returning its identifier. `name` is used as the origin
+    /// of the slices of the file.
+    pub fn add(&mut self, name: impl Into<String>, source: impl Into<String>) -> FileId {
+        self.push(name.into(), source.into(), vec![])
+    }
+
+    /// Adds a file from contents which may not be valid UTF-8, returning its
+    /// identifier. Every invalid sequence is displayed as a
+    /// `U+FFFD REPLACEMENT CHARACTER`, as `String::from_utf8_lossy` does.
+    pub fn add_bytes(&mut self, name: impl Into<String>, bytes: &[u8]) -> FileId {
+        let mut source = String::with_capacity(bytes.len());
+        let mut replaced = vec![];
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    source.push_str(valid);
+                    break;
+                }
+                Err(error) => {
+                    let (valid, invalid) = rest.split_at(error.valid_up_to());
+                    // The bytes were just checked.
+                    source.push_str(std::str::from_utf8(valid).unwrap());
+                    source.push(char::REPLACEMENT_CHARACTER);
+                    let len = error.error_len().unwrap_or(invalid.len());
+                    replaced.push((bytes.len() - invalid.len(), len));
+                    rest = &invalid[len..];
+                }
+            }
+        }
+        self.push(name.into(), source, replaced)
+    }
+
+    fn push(&mut self, name: String, source: String, replaced: Vec<(usize, usize)>) -> FileId {
+        let lines = LineIndex::new(&source);
+        self.files.push(SourceFile {
+            name,
+            source,
+            lines,
+            replaced,
+        });
+        FileId(self.files.len() - 1)
+    }
+
+    fn file(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0]
+    }
+
+    /// Name of the file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not a file of this map.
+    pub fn name(&self, id: FileId) -> &str {
+        &self.file(id).name
+    }
+
+    /// Contents of the file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not a file of this map.
+    pub fn source(&self, id: FileId) -> &str {
+        &self.file(id).source
+    }
+
+    /// Offset of the char displaying the byte `byte` of the contents the file
+    /// was added with, the replacement character of an invalid sequence
+    /// displaying all of its bytes. Bytes past the end are after the last
+    /// char.
+    ///
+    /// This converts the byte ranges of tools working on raw contents into
+    /// the ranges of annotations.
+    pub fn offset_of_byte(&self, id: FileId, byte: usize) -> usize {
+        let file = self.file(id);
+        let mut replaced = file.replaced.iter().peekable();
+        let mut start = 0;
+        let mut chars = 0;
+        for c in file.source.chars() {
+            let len = match replaced.peek() {
+                Some(&&(at, len)) if at == start => {
+                    replaced.next();
+                    len
+                }
+                _ => c.len_utf8(),
+            };
+            if byte < start + len {
+                return chars;
+            }
+            start += len;
+            chars += 1;
+        }
+        chars
+    }
+
+    /// Number of lines of the file.
+    pub fn line_count(&self, id: FileId) -> usize {
+        self.file(id).lines.line_count()
+    }
+
+    /// Line and column, both starting at 1, of the char at `offset` in the
+    /// file. The line ending belongs to the line it ends, and offsets past the
+    /// end are on the last line.
+    pub fn line_col(&self, id: FileId, offset: usize) -> (usize, usize) {
+        self.file(id).lines.offset_to_position(offset)
+    }
+
+    /// Offset of the char at `line` and `col`, both starting at 1, or `None`
+    /// if the file has no such line.
+    ///
+    /// Columns past the end of the line are not checked.
+    pub fn offset(&self, id: FileId, line: usize, col: usize) -> Option<usize> {
+        let line_start = self.file(id).lines.line_start(line)?;
+        Some(line_start + col.checked_sub(1)?)
+    }
+
+    /// Builds the slices displaying `annotations`, with `context` lines
+    /// around the annotated lines, as `Slice::extract` does.
+    ///
+    /// The slices of each file are grouped, in the order the files are first
+    /// referenced by the annotations.
+    pub fn slices<'a>(
+        &'a self,
+        annotations: Vec<SpanAnnotation<'a>>,
+        context: usize,
+    ) -> Vec<Slice<'a>> {
+        let mut files: Vec<(FileId, Vec<SourceAnnotation<'a>>)> = vec![];
+        for annotation in annotations {
+            let annotation_source = SourceAnnotation {
+                range: annotation.range,
+                label: annotation.label,
+                annotation_type: annotation.annotation_type,
+                ..Default::default()
+            };
+            match files.iter_mut().find(|(id, _)| *id == annotation.file) {
+                Some((_, file_annotations)) => file_annotations.push(annotation_source),
+                None => files.push((annotation.file, vec![annotation_source])),
+            }
+        }
+
+        files
+            .into_iter()
+            .flat_map(|(id, annotations)| {
+                let file = self.file(id);
+                Slice {
+                    source: &file.source,
+                    line_start: 1,
+                    origin: Some(&file.name),
+                    annotations,
+                    fold: false,
+                    line_highlights: vec![],
+                    origin_note: None,
+                }
+                .extract(context)
+            })
+            .collect()
+    }
+}
@@ -1,8 +1,9 @@
 use std::fmt::{self, Display};
 
-use yansi_term::{Color::Fixed, Style as AnsiTermStyle};
+use yansi_term::{Color as AnsiTermColor, Style as AnsiTermStyle};
 
-use crate::formatter::style::{Style, StyleClass, Stylesheet};
+use crate::formatter::style::{Color, ColorDepth, Style, StyleClass, StyleSpec, Stylesheet};
+use crate::stylesheets::theme::{Theme, ThemeError};
 
 struct AnsiTermStyleWrapper {
     style: AnsiTermStyle,
@@ -21,30 +22,148 @@ impl Style for AnsiTermStyleWrapper {
         self.style.paint_fn(c).fmt(f)
     }
 
+    fn paint_with(
+        &self,
+        c: &mut dyn FnMut(&mut fmt::Formatter<'_>) -> fmt::Result,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        self.style.paint_fn(c).fmt(f)
+    }
+
     fn bold(&self) -> Box<dyn Style> {
         Box::new(AnsiTermStyleWrapper { style: self.style })
     }
 }
 
-pub struct AnsiTermStylesheet;
+/// A `Stylesheet` writing ANSI escape sequences.
+///
+/// Every `StyleClass` is described by a `StyleSpec`, whose colors are
+/// degraded to what the configured `ColorDepth` allows when painting.
+#[derive(Clone)]
+pub struct AnsiTermStylesheet {
+    depth: ColorDepth,
+    styles: [StyleSpec; 8],
+}
 
-impl Stylesheet for AnsiTermStylesheet {
-    fn get_style(&self, class: StyleClass) -> Box<dyn Style> {
-        let ansi_term_style = match class {
-            StyleClass::Error => Fixed(9).bold(),
-            StyleClass::Warning => Fixed(11).bold(),
-            StyleClass::Info => Fixed(12).bold(),
-            StyleClass::Note => AnsiTermStyle::new().bold(),
-            StyleClass::Help => Fixed(14).bold(),
+impl Default for AnsiTermStylesheet {
+    fn default() -> Self {
+        Self::with_depth(ColorDepth::detect())
+    }
+}
+
+impl AnsiTermStylesheet {
+    /// Creates a stylesheet with the default styles for the detected terminal.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a stylesheet for the detected terminal, with the theme the end
+    /// user set through the environment, as `Theme::from_env` reads it.
+    pub fn from_env() -> Result<Self, ThemeError> {
+        let mut stylesheet = Self::default();
+        if let Some(theme) = Theme::from_env()? {
+            stylesheet.apply_theme(&theme);
+        }
+        Ok(stylesheet)
+    }
+
+    /// Creates a stylesheet with the default styles and an explicit `ColorDepth`.
+    pub fn with_depth(depth: ColorDepth) -> Self {
+        let mut styles = [StyleSpec::new(); 8];
+        styles[index(StyleClass::Error)] = StyleSpec::new().fg(Color::Fixed(9)).bold();
+        styles[index(StyleClass::Warning)] = StyleSpec::new().fg(Color::Fixed(11)).bold();
+        styles[index(StyleClass::Info)] = StyleSpec::new().fg(Color::Fixed(12)).bold();
+        styles[index(StyleClass::Note)] = StyleSpec::new().bold();
+        styles[index(StyleClass::Help)] = StyleSpec::new().fg(Color::Fixed(14)).bold();
+        styles[index(StyleClass::LineNo)] = StyleSpec::new().fg(Color::Fixed(12)).bold();
+        styles[index(StyleClass::Emphasis)] = StyleSpec::new().bold();
+        Self { depth, styles }
+    }
+
+    pub fn depth(&self) -> ColorDepth {
+        self.depth
+    }
+
+    /// Returns the `StyleSpec` used for the given `StyleClass`.
+    pub fn style(&self, class: StyleClass) -> StyleSpec {
+        self.styles[index(class)]
+    }
+
+    /// Replaces the `StyleSpec` used for the given `StyleClass`.
+    pub fn set_style(&mut self, class: StyleClass, spec: StyleSpec) {
+        self.styles[index(class)] = spec;
+    }
+
+    /// Replaces the styles of all classes defined by the `Theme`.
+    pub fn apply_theme(&mut self, theme: &Theme) {
+        for (class, spec) in theme.iter() {
+            self.set_style(class, spec);
+        }
+    }
+}
 
-            StyleClass::LineNo => Fixed(12).bold(),
+fn index(class: StyleClass) -> usize {
+    match class {
+        StyleClass::Error => 0,
+        StyleClass::Warning => 1,
+        StyleClass::Info => 2,
+        StyleClass::Note => 3,
+        StyleClass::Help => 4,
+        StyleClass::LineNo => 5,
+        StyleClass::Emphasis => 6,
+        StyleClass::None => 7,
+    }
+}
+
+fn ansi_term_color(color: Color, depth: ColorDepth) -> AnsiTermColor {
+    match color.degrade(depth) {
+        Color::Black => AnsiTermColor::Black,
+        Color::Red => AnsiTermColor::Red,
+        Color::Green => AnsiTermColor::Green,
+        Color::Yellow => AnsiTermColor::Yellow,
+        Color::Blue => AnsiTermColor::Blue,
+        Color::Magenta => AnsiTermColor::Purple,
+        Color::Cyan => AnsiTermColor::Cyan,
+        Color::White => AnsiTermColor::White,
+        Color::Rgb(r, g, b) => AnsiTermColor::RGB(r, g, b),
+        // Bright colors have no dedicated escape codes in `yansi_term`.
+        other => AnsiTermColor::Fixed(other.to_fixed().unwrap_or_default()),
+    }
+}
+
+fn ansi_term_style(spec: StyleSpec, depth: ColorDepth) -> AnsiTermStyle {
+    let mut style = AnsiTermStyle::new();
+    if let Some(fg) = spec.foreground {
+        style = style.fg(ansi_term_color(fg, depth));
+    }
+    if let Some(bg) = spec.background {
+        style = style.on(ansi_term_color(bg, depth));
+    }
+    if spec.bold {
+        style = style.bold();
+    }
+    if spec.dimmed {
+        style = style.dimmed();
+    }
+    if spec.italic {
+        style = style.italic();
+    }
+    if spec.underline {
+        style = style.underline();
+    }
+    style
+}
 
-            StyleClass::Emphasis => AnsiTermStyle::new().bold(),
+impl Stylesheet for AnsiTermStylesheet {
+    fn get_style(&self, class: StyleClass) -> Box<dyn Style> {
+        Box::new(AnsiTermStyleWrapper {
+            style: ansi_term_style(self.style(class), self.depth),
+        })
+    }
 
-            StyleClass::None => AnsiTermStyle::new(),
-        };
+    fn get_spec_style(&self, spec: StyleSpec) -> Box<dyn Style> {
         Box::new(AnsiTermStyleWrapper {
-            style: ansi_term_style,
+            style: ansi_term_style(spec, self.depth),
         })
     }
 }
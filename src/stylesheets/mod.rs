@@ -5,7 +5,10 @@
 //!
 //! By default the `no_color` is available. If the crate gets compiled
 //! with `ansi_term`, the `color` stylesheet is added.
+//!
+//! The `theme` module allows customizing the colors of the `color` stylesheet.
 
 #[cfg(feature = "color")]
 pub mod color;
 pub mod no_color;
+pub mod theme;
@@ -17,6 +17,14 @@ impl Style for NoOpStyle {
         c(f)
     }
 
+    fn paint_with(
+        &self,
+        c: &mut dyn FnMut(&mut fmt::Formatter<'_>) -> fmt::Result,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        c(f)
+    }
+
     fn bold(&self) -> Box<dyn Style> {
         Box::new(NoOpStyle {})
     }
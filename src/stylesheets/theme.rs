@@ -0,0 +1,285 @@
+//! User-definable color themes.
+//!
+//! A `Theme` maps style classes to colors and attributes. It can be built in
+//! code, parsed from a compact `GCC_COLORS`-like string, or, with the `theme`
+//! feature, loaded from a TOML document:
+//!
+//! ```toml
+//! [error]
+//! fg = "#ff5f5f"
+//! bold = true
+//!
+//! [line-no]
+//! fg = 33
+//! ```
+//!
+//! End users can set a theme without the embedding tool doing anything:
+//! `ANNOTATE_SNIPPETS_COLORS` holds the compact form (for example
+//! `error=bold,#ff5f5f:warning=yellow:line-no=33`) and, with the `theme`
+//! feature, `ANNOTATE_SNIPPETS_THEME` holds the path of a TOML theme.
+//! The color stylesheet picks both up automatically, once per process.
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::formatter::style::{Color, StyleClass, StyleSpec};
+
+/// Environment variable holding a theme in the compact form.
+pub const COLORS_ENV: &str = "ANNOTATE_SNIPPETS_COLORS";
+/// Environment variable holding the path of a TOML theme.
+#[cfg(feature = "theme")]
+pub const THEME_ENV: &str = "ANNOTATE_SNIPPETS_THEME";
+
+/// A set of `StyleSpec` overrides for `StyleClass`es.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Theme {
+    styles: HashMap<StyleClass, StyleSpec>,
+}
+
+impl Hash for Theme {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // The order of the map is not the same for equal themes.
+        for class in [
+            StyleClass::Error,
+            StyleClass::Warning,
+            StyleClass::Info,
+            StyleClass::Note,
+            StyleClass::Help,
+            StyleClass::LineNo,
+            StyleClass::Emphasis,
+            StyleClass::None,
+        ] {
+            self.get(class).hash(state);
+        }
+    }
+}
+
+impl Theme {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, class: StyleClass, spec: StyleSpec) {
+        self.styles.insert(class, spec);
+    }
+
+    pub fn get(&self, class: StyleClass) -> Option<StyleSpec> {
+        self.styles.get(&class).copied()
+    }
+
+    /// Iterates over all styles defined by the theme.
+    pub fn iter(&self) -> impl Iterator<Item = (StyleClass, StyleSpec)> + '_ {
+        self.styles.iter().map(|(class, spec)| (*class, *spec))
+    }
+
+    /// Parses the compact form, a `:` separated list of `element=attributes`
+    /// entries where attributes are `,` separated.
+    ///
+    /// An attribute is either `bold`, `dimmed`, `italic`, `underline`, a color
+    /// for the foreground or `on-` followed by a color for the background.
+    ///
+    /// ```
+    /// use annotate_snippets::formatter::style::{Color, StyleClass, StyleSpec};
+    /// use annotate_snippets::stylesheets::theme::Theme;
+    ///
+    /// let theme = Theme::parse("error=bold,#ff0000:note=on-blue").unwrap();
+    /// assert_eq!(
+    ///     theme.get(StyleClass::Error),
+    ///     Some(StyleSpec::new().fg(Color::Rgb(255, 0, 0)).bold())
+    /// );
+    /// assert_eq!(theme.get(StyleClass::Note), Some(StyleSpec::new().bg(Color::Blue)));
+    /// ```
+    pub fn parse(src: &str) -> Result<Theme, ThemeError> {
+        let mut theme = Theme::new();
+        for entry in src.split(':').map(str::trim).filter(|e| !e.is_empty()) {
+            let (element, attributes) = match entry.find('=') {
+                Some(idx) => (&entry[..idx], &entry[idx + 1..]),
+                None => return Err(ThemeError::InvalidEntry(entry.to_string())),
+            };
+            let class = parse_class(element.trim())?;
+            let mut spec = StyleSpec::new();
+            for attribute in attributes.split(',').map(str::trim) {
+                spec = match attribute {
+                    "" => spec,
+                    "bold" => spec.bold(),
+                    "dimmed" => spec.dimmed(),
+                    "italic" => spec.italic(),
+                    "underline" => spec.underline(),
+                    _ if attribute.starts_with("on-") => spec.bg(parse_color(&attribute[3..])?),
+                    _ => spec.fg(parse_color(attribute)?),
+                };
+            }
+            theme.set(class, spec);
+        }
+        Ok(theme)
+    }
+
+    /// Reads the theme configured by the end user through the environment.
+    ///
+    /// Returns `Ok(None)` when no theme is configured. When both variables are
+    /// set, the entries of `ANNOTATE_SNIPPETS_COLORS` take precedence.
+    pub fn from_env() -> Result<Option<Theme>, ThemeError> {
+        #[cfg(feature = "theme")]
+        let mut theme = match std::env::var_os(THEME_ENV) {
+            Some(path) => Some(Theme::from_toml(&std::fs::read_to_string(path)?)?),
+            None => None,
+        };
+        #[cfg(not(feature = "theme"))]
+        let mut theme: Option<Theme> = None;
+
+        if let Ok(colors) = std::env::var(COLORS_ENV) {
+            let overrides = Theme::parse(&colors)?;
+            let theme = theme.get_or_insert_with(Theme::new);
+            for (class, spec) in overrides.iter() {
+                theme.set(class, spec);
+            }
+        }
+        Ok(theme)
+    }
+
+    /// Loads a theme from a TOML document with one table per element.
+    ///
+    /// Tables accept the `fg` and `bg` colors, either as a name, a `#rrggbb`
+    /// string or a palette index, and the `bold`, `dimmed`, `italic` and
+    /// `underline` booleans.
+    #[cfg(feature = "theme")]
+    pub fn from_toml(src: &str) -> Result<Theme, ThemeError> {
+        let value: toml::Value = src.parse()?;
+        let table = value
+            .as_table()
+            .ok_or_else(|| ThemeError::InvalidEntry(value.to_string()))?;
+
+        let mut theme = Theme::new();
+        for (element, entry) in table {
+            let class = parse_class(element)?;
+            let entry = entry
+                .as_table()
+                .ok_or_else(|| ThemeError::InvalidEntry(element.clone()))?;
+            let mut spec = StyleSpec::new();
+            for (key, value) in entry {
+                match (key.as_str(), value) {
+                    ("fg", value) => spec.foreground = Some(toml_color(value)?),
+                    ("bg", value) => spec.background = Some(toml_color(value)?),
+                    ("bold", toml::Value::Boolean(b)) => spec.bold = *b,
+                    ("dimmed", toml::Value::Boolean(b)) => spec.dimmed = *b,
+                    ("italic", toml::Value::Boolean(b)) => spec.italic = *b,
+                    ("underline", toml::Value::Boolean(b)) => spec.underline = *b,
+                    _ => return Err(ThemeError::InvalidAttribute(key.clone())),
+                }
+            }
+            theme.set(class, spec);
+        }
+        Ok(theme)
+    }
+}
+
+#[cfg(feature = "theme")]
+fn toml_color(value: &toml::Value) -> Result<Color, ThemeError> {
+    match value {
+        toml::Value::String(s) => parse_color(s),
+        toml::Value::Integer(n) if (0..=255).contains(n) => Ok(Color::Fixed(*n as u8)),
+        _ => Err(ThemeError::InvalidColor(value.to_string())),
+    }
+}
+
+fn parse_class(element: &str) -> Result<StyleClass, ThemeError> {
+    Ok(match element {
+        "error" => StyleClass::Error,
+        "warning" => StyleClass::Warning,
+        "info" => StyleClass::Info,
+        "note" => StyleClass::Note,
+        "help" => StyleClass::Help,
+        "line-no" => StyleClass::LineNo,
+        "emphasis" => StyleClass::Emphasis,
+        "none" => StyleClass::None,
+        _ => return Err(ThemeError::InvalidElement(element.to_string())),
+    })
+}
+
+/// Parses a color name (`red`, `bright-red`, ...), a `#rrggbb` value or a
+/// palette index.
+pub fn parse_color(src: &str) -> Result<Color, ThemeError> {
+    let invalid = || ThemeError::InvalidColor(src.to_string());
+    if let Some(hex) = src.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+        return Ok(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    if let Ok(n) = src.parse::<u8>() {
+        return Ok(Color::Fixed(n));
+    }
+    Ok(match src {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        "bright-black" => Color::BrightBlack,
+        "bright-red" => Color::BrightRed,
+        "bright-green" => Color::BrightGreen,
+        "bright-yellow" => Color::BrightYellow,
+        "bright-blue" => Color::BrightBlue,
+        "bright-magenta" => Color::BrightMagenta,
+        "bright-cyan" => Color::BrightCyan,
+        "bright-white" => Color::BrightWhite,
+        _ => return Err(invalid()),
+    })
+}
+
+/// An error encountered while loading a `Theme`.
+#[derive(Debug)]
+pub enum ThemeError {
+    /// An entry which is not of the `element=attributes` form.
+    InvalidEntry(String),
+    /// An element name which does not correspond to any `StyleClass`.
+    InvalidElement(String),
+    InvalidColor(String),
+    InvalidAttribute(String),
+    Io(std::io::Error),
+    #[cfg(feature = "theme")]
+    Toml(toml::de::Error),
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeError::InvalidEntry(entry) => write!(f, "invalid theme entry `{}`", entry),
+            ThemeError::InvalidElement(element) => write!(f, "unknown element `{}`", element),
+            ThemeError::InvalidColor(color) => write!(f, "invalid color `{}`", color),
+            ThemeError::InvalidAttribute(attr) => write!(f, "invalid attribute `{}`", attr),
+            ThemeError::Io(err) => write!(f, "failed to read theme: {}", err),
+            #[cfg(feature = "theme")]
+            ThemeError::Toml(err) => write!(f, "failed to parse theme: {}", err),
+        }
+    }
+}
+
+impl Error for ThemeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ThemeError::Io(err) => Some(err),
+            #[cfg(feature = "theme")]
+            ThemeError::Toml(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ThemeError {
+    fn from(err: std::io::Error) -> Self {
+        ThemeError::Io(err)
+    }
+}
+
+#[cfg(feature = "theme")]
+impl From<toml::de::Error> for ThemeError {
+    fn from(err: toml::de::Error) -> Self {
+        ThemeError::Toml(err)
+    }
+}
@@ -0,0 +1,211 @@
+//! Application of machine-applicable suggestions to a source, and lists of
+//! candidates for a span.
+//!
+//! ```
+//! use annotate_snippets::suggestion::{apply_suggestions, Suggestion};
+//!
+//! let fixed = apply_suggestions(
+//!     "let x = vec![1, 2];",
+//!     &[
+//!         Suggestion {
+//!             range: (4, 5),
+//!             replacement: "numbers",
+//!         },
+//!         Suggestion {
+//!             range: (4, 4),
+//!             replacement: "mut ",
+//!         },
+//!     ],
+//! )
+//! .unwrap();
+//! assert_eq!(fixed, "let mut numbers = vec![1, 2];");
+//! ```
+//!
+//! A `SuggestionList` gives several candidates for a span, like the names
+//! close to an unknown one:
+//!
+//! ```
+//! use annotate_snippets::suggestion::SuggestionList;
+//!
+//! let list = SuggestionList::new((8, 11), ["foo", "foa", "fob"]);
+//! assert_eq!(
+//!     list.message().unwrap(),
+//!     "did you mean one of: `foo`, `foa`, `fob`?"
+//! );
+//! ```
+use std::error::Error;
+use std::fmt;
+
+use crate::display_list::{DisplayList, FormatOptions};
+use crate::snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation};
+
+/// A replacement of a range of a source.
+///
+/// Like the ranges of annotations, `range` counts chars. An empty range
+/// inserts the replacement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion<'a> {
+    pub range: (usize, usize),
+    pub replacement: &'a str,
+}
+
+/// An error encountered while applying suggestions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SuggestionError {
+    /// A range whose start is after its end, or which ends past the source.
+    InvalidRange((usize, usize)),
+    /// Two suggestions replacing overlapping ranges.
+    Overlap((usize, usize), (usize, usize)),
+    /// A list of candidates without any.
+    NoCandidates,
+}
+
+impl fmt::Display for SuggestionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SuggestionError::InvalidRange(range) => {
+                write!(f, "invalid suggestion range `{:?}`", range)
+            }
+            SuggestionError::Overlap(a, b) => {
+                write!(f, "suggestion ranges `{:?}` and `{:?}` overlap", a, b)
+            }
+            SuggestionError::NoCandidates => write!(f, "no candidates to suggest"),
+        }
+    }
+}
+
+impl Error for SuggestionError {}
+
+/// Applies all suggestions to `source`, returning the fixed source.
+///
+/// Suggestions may be given in any order. Identical suggestions are applied
+/// once, and insertions at the same position are applied in the given order.
+/// Any other overlap is an error, as the suggestions can't be applied
+/// together.
+pub fn apply_suggestions(
+    source: &str,
+    suggestions: &[Suggestion<'_>],
+) -> Result<String, SuggestionError> {
+    let source_len = source.chars().count();
+    let mut sorted = suggestions.iter().collect::<Vec<_>>();
+    for suggestion in &sorted {
+        let (start, end) = suggestion.range;
+        if start > end || end > source_len {
+            return Err(SuggestionError::InvalidRange(suggestion.range));
+        }
+    }
+    // Insertions go before replacements starting at the same position.
+    sorted.sort_by_key(|suggestion| (suggestion.range.0, suggestion.range.1));
+    sorted.dedup();
+    for pair in sorted.windows(2) {
+        let (a, b) = (pair[0].range, pair[1].range);
+        if b.0 < a.1 || (a == b && a.0 != a.1) {
+            return Err(SuggestionError::Overlap(a, b));
+        }
+    }
+
+    // Byte offset of every char, and of the end of the source.
+    let offsets = source
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(source.len()))
+        .collect::<Vec<_>>();
+    let mut fixed = String::with_capacity(source.len());
+    let mut pos = 0;
+    for suggestion in sorted {
+        let (start, end) = suggestion.range;
+        fixed.push_str(&source[offsets[pos]..offsets[start]]);
+        fixed.push_str(suggestion.replacement);
+        pos = end;
+    }
+    fixed.push_str(&source[offsets[pos]..]);
+    Ok(fixed)
+}
+
+/// Candidate replacements of a span, of which one is probably meant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuggestionList<'a> {
+    pub range: (usize, usize),
+    pub candidates: Vec<&'a str>,
+}
+
+impl<'a> SuggestionList<'a> {
+    /// Creates the list of `candidates` for the span `range`.
+    pub fn new(range: (usize, usize), candidates: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            range,
+            candidates: candidates.into_iter().collect(),
+        }
+    }
+
+    /// The suggestions replacing the span with each candidate.
+    pub fn suggestions(&self) -> impl Iterator<Item = Suggestion<'a>> + '_ {
+        self.candidates.iter().map(move |candidate| Suggestion {
+            range: self.range,
+            replacement: candidate,
+        })
+    }
+
+    /// The label of the help about the candidates, like
+    /// `did you mean one of: `foo`, `foa`?`, or `did you mean `foo`?` with a
+    /// single candidate. `None` without candidates.
+    pub fn message(&self) -> Option<String> {
+        let candidates = self
+            .candidates
+            .iter()
+            .map(|candidate| format!("`{}`", candidate))
+            .collect::<Vec<_>>()
+            .join(", ");
+        match self.candidates.len() {
+            0 => None,
+            1 => Some(format!("did you mean {}?", candidates)),
+            _ => Some(format!("did you mean one of: {}?", candidates)),
+        }
+    }
+
+    /// Renders the help about the candidates as the title of a snippet with
+    /// the given options. `None` without candidates.
+    pub fn render(&self, opt: FormatOptions) -> Option<String> {
+        let label = self.message()?;
+        let snippet = Snippet {
+            title: Some(Annotation::new(AnnotationType::Help, label.as_str())),
+            opt,
+            ..Default::default()
+        };
+        Some(DisplayList::from(snippet).to_string())
+    }
+
+    /// Renders the help followed, for each candidate, by `slice` with the
+    /// span replaced by the candidate and underlined, from the origin of
+    /// `slice`. The range of the list is one of `slice`, and the list has
+    /// candidates.
+    pub fn render_candidates(
+        &self,
+        slice: &Slice<'_>,
+        opt: FormatOptions,
+    ) -> Result<String, SuggestionError> {
+        let fixed = self
+            .suggestions()
+            .map(|suggestion| apply_suggestions(slice.source, &[suggestion]))
+            .collect::<Result<Vec<_>, _>>()?;
+        let label = self.message().ok_or(SuggestionError::NoCandidates)?;
+        let slices = fixed
+            .iter()
+            .zip(&self.candidates)
+            .map(|(source, candidate)| {
+                let start = self.range.0;
+                let range = (start, start + candidate.chars().count());
+                Slice::new(source, slice.line_start)
+                    .origin(slice.origin)
+                    .annotation(SourceAnnotation::new(range, AnnotationType::Help, ""))
+            })
+            .collect();
+        let snippet = Snippet {
+            title: Some(Annotation::new(AnnotationType::Help, label.as_str())),
+            slices,
+            opt,
+            ..Default::default()
+        };
+        Ok(DisplayList::from(snippet).to_string())
+    }
+}
@@ -0,0 +1,172 @@
+//! The closing line of a batch of diagnostics.
+//!
+//! A `Summary` counts the snippets of each level, and renders the line
+//! compilers print after them. Its most severe level gives the exit code of
+//! a tool.
+//!
+//! ```
+//! use annotate_snippets::display_list::FormatOptions;
+//! use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+//! use annotate_snippets::summary::Summary;
+//!
+//! let snippets = [AnnotationType::Error, AnnotationType::Warning, AnnotationType::Error]
+//!     .iter()
+//!     .map(|annotation_type| {
+//!         Snippet::new().title(Annotation {
+//!             label: Some("unexpected token"),
+//!             id: None,
+//!             annotation_type: *annotation_type,
+//!         })
+//!     })
+//!     .collect::<Vec<_>>();
+//!
+//! let summary = snippets.iter().collect::<Summary>();
+//! assert_eq!(
+//!     summary.render(FormatOptions::default()).unwrap(),
+//!     "error: aborting due to 2 previous errors; 1 warning emitted"
+//! );
+//! assert_eq!(summary.max_severity(), Some(AnnotationType::Error));
+//! ```
+use std::iter::FromIterator;
+
+use crate::display_list::{DisplayList, FormatOptions};
+use crate::snippet::{Annotation, AnnotationType, Snippet};
+
+/// Counts of the levels of a batch of snippets, from their titles.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub errors: usize,
+    pub warnings: usize,
+    pub infos: usize,
+    pub notes: usize,
+    pub helps: usize,
+    /// Number of duplicate snippets which were not displayed, and are not
+    /// counted as errors or warnings.
+    pub duplicates: usize,
+}
+
+impl Summary {
+    /// Creates a summary of no snippets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts a snippet by the level of its title, if it has one.
+    pub fn add(&mut self, snippet: &Snippet<'_>) {
+        if let Some(title) = &snippet.title {
+            *self.count_mut(title.annotation_type) += 1;
+        }
+    }
+
+    /// Number of snippets counted with a title of `annotation_type`.
+    pub fn count(&self, annotation_type: AnnotationType) -> usize {
+        match annotation_type {
+            AnnotationType::Error => self.errors,
+            AnnotationType::Warning => self.warnings,
+            AnnotationType::Info => self.infos,
+            AnnotationType::Note => self.notes,
+            AnnotationType::Help => self.helps,
+        }
+    }
+
+    fn count_mut(&mut self, annotation_type: AnnotationType) -> &mut usize {
+        match annotation_type {
+            AnnotationType::Error => &mut self.errors,
+            AnnotationType::Warning => &mut self.warnings,
+            AnnotationType::Info => &mut self.infos,
+            AnnotationType::Note => &mut self.notes,
+            AnnotationType::Help => &mut self.helps,
+        }
+    }
+
+    /// The most severe level counted, errors first and helps last, or
+    /// `None` if no snippet was counted.
+    pub fn max_severity(&self) -> Option<AnnotationType> {
+        [
+            AnnotationType::Error,
+            AnnotationType::Warning,
+            AnnotationType::Info,
+            AnnotationType::Note,
+            AnnotationType::Help,
+        ]
+        .iter()
+        .copied()
+        .find(|&annotation_type| self.count(annotation_type) > 0)
+    }
+
+    /// Returns `true` if any error was counted, in which case a tool
+    /// usually exits with a failure.
+    pub fn has_errors(&self) -> bool {
+        self.errors > 0
+    }
+
+    /// The type and label of the closing line, or `None` without errors nor
+    /// warnings.
+    ///
+    /// Errors take precedence: with any, the line is an error mentioning the
+    /// warnings too. Suppressed duplicates are mentioned at the end.
+    pub fn message(&self) -> Option<(AnnotationType, String)> {
+        let (annotation_type, mut label) = self.counts_message()?;
+        if self.duplicates > 0 {
+            label.push_str(&format!(
+                " ({} suppressed)",
+                plural(self.duplicates, "duplicate diagnostic")
+            ));
+        }
+        Some((annotation_type, label))
+    }
+
+    fn counts_message(&self) -> Option<(AnnotationType, String)> {
+        let warnings = format!("{} emitted", plural(self.warnings, "warning"));
+        match (self.errors, self.warnings) {
+            (0, 0) => None,
+            (0, _) => Some((AnnotationType::Warning, warnings)),
+            (errors, 0) => Some((
+                AnnotationType::Error,
+                format!("aborting due to {}", plural(errors, "previous error")),
+            )),
+            (errors, _) => Some((
+                AnnotationType::Error,
+                format!(
+                    "aborting due to {}; {}",
+                    plural(errors, "previous error"),
+                    warnings
+                ),
+            )),
+        }
+    }
+
+    /// Renders the closing line as the title of a snippet with the given
+    /// options, or `None` without errors nor warnings.
+    pub fn render(&self, opt: FormatOptions) -> Option<String> {
+        let (annotation_type, label) = self.message()?;
+        let snippet = Snippet {
+            title: Some(Annotation {
+                id: None,
+                label: Some(&label),
+                annotation_type,
+            }),
+            opt,
+            ..Default::default()
+        };
+        Some(DisplayList::from(snippet).to_string())
+    }
+}
+
+impl<'a, 'b> FromIterator<&'b Snippet<'a>> for Summary {
+    fn from_iter<I: IntoIterator<Item = &'b Snippet<'a>>>(iter: I) -> Self {
+        let mut summary = Self::new();
+        for snippet in iter {
+            summary.add(snippet);
+        }
+        summary
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
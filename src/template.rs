@@ -0,0 +1,194 @@
+//! Messages instantiated from registered templates.
+//!
+//! A `TemplateRegistry` keeps the text of the messages of a tool in one
+//! place. Templates name their placeholders in braces, `{{` and `}}` being
+//! literal braces, and are instantiated into a `Message` from their id and the
+//! value of every placeholder. The id of a template is the id of the titles of
+//! its messages.
+//!
+//! ```
+//! use annotate_snippets::snippet::{AnnotationType, Snippet};
+//! use annotate_snippets::template::TemplateRegistry;
+//!
+//! let mut templates = TemplateRegistry::new();
+//! templates
+//!     .register(
+//!         "E0425",
+//!         AnnotationType::Error,
+//!         "cannot find value `{name}` in {scope}",
+//!     )
+//!     .unwrap();
+//!
+//! let message = templates
+//!     .instantiate("E0425", &[("name", "x"), ("scope", "this scope")])
+//!     .unwrap();
+//! assert_eq!(message.label, "cannot find value `x` in this scope");
+//!
+//! let snippet = Snippet::new().title(message.title());
+//! ```
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::snippet::{Annotation, AnnotationType};
+
+/// An error encountered while registering or instantiating a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A `{` without its `}`, or a `}` without its `{`, at the given byte
+    /// offset of the template.
+    Unbalanced(usize),
+    /// A template id which was not registered.
+    UnknownTemplate(String),
+    /// A placeholder of the template without a value.
+    MissingArgument(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Unbalanced(offset) => {
+                write!(f, "unbalanced brace at offset {} of template", offset)
+            }
+            TemplateError::UnknownTemplate(id) => write!(f, "unknown template `{}`", id),
+            TemplateError::MissingArgument(name) => {
+                write!(f, "missing value for placeholder `{}`", name)
+            }
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+/// A part of a parsed template.
+#[derive(Debug)]
+enum Part {
+    Text(String),
+    Placeholder(String),
+}
+
+#[derive(Debug)]
+struct Template {
+    annotation_type: AnnotationType,
+    parts: Vec<Part>,
+}
+
+/// A message instantiated from a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub id: String,
+    pub annotation_type: AnnotationType,
+    pub label: String,
+}
+
+impl Message {
+    /// Borrows the message as the title of a snippet.
+    pub fn title(&self) -> Annotation<'_> {
+        Annotation {
+            id: Some(&self.id),
+            label: Some(&self.label),
+            annotation_type: self.annotation_type,
+        }
+    }
+}
+
+/// A collection of message templates, by id.
+#[derive(Debug, Default)]
+pub struct TemplateRegistry {
+    templates: HashMap<String, Template>,
+}
+
+impl TemplateRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the template of the messages `id`, replacing any previous
+    /// one.
+    pub fn register(
+        &mut self,
+        id: impl Into<String>,
+        annotation_type: AnnotationType,
+        template: &str,
+    ) -> Result<(), TemplateError> {
+        let parts = parse(template)?;
+        self.templates.insert(
+            id.into(),
+            Template {
+                annotation_type,
+                parts,
+            },
+        );
+        Ok(())
+    }
+
+    /// Returns `true` if a template `id` is registered.
+    pub fn contains(&self, id: &str) -> bool {
+        self.templates.contains_key(id)
+    }
+
+    /// Instantiates the template `id`, replacing its placeholders by the
+    /// values of `args`, given as name and value.
+    ///
+    /// Arguments which are not placeholders of the template are ignored.
+    pub fn instantiate(&self, id: &str, args: &[(&str, &str)]) -> Result<Message, TemplateError> {
+        let template = self
+            .templates
+            .get(id)
+            .ok_or_else(|| TemplateError::UnknownTemplate(id.to_string()))?;
+        let mut label = String::new();
+        for part in &template.parts {
+            match part {
+                Part::Text(text) => label.push_str(text),
+                Part::Placeholder(name) => {
+                    let (_, value) = args
+                        .iter()
+                        .find(|(arg, _)| arg == name)
+                        .ok_or_else(|| TemplateError::MissingArgument(name.clone()))?;
+                    label.push_str(value);
+                }
+            }
+        }
+        Ok(Message {
+            id: id.to_string(),
+            annotation_type: template.annotation_type,
+            label,
+        })
+    }
+}
+
+fn parse(template: &str) -> Result<Vec<Part>, TemplateError> {
+    let mut parts = vec![];
+    let mut text = String::new();
+    let mut chars = template.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '{' | '}' if chars.peek().map(|&(_, next)| next) == Some(c) => {
+                chars.next();
+                text.push(c);
+            }
+            '{' => {
+                let end = template[i..]
+                    .find('}')
+                    .ok_or(TemplateError::Unbalanced(i))?
+                    + i;
+                let name = &template[i + 1..end];
+                if name.contains('{') {
+                    return Err(TemplateError::Unbalanced(i));
+                }
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                parts.push(Part::Placeholder(name.to_string()));
+                while chars.next_if(|&(j, _)| j <= end).is_some() {}
+            }
+            '}' => return Err(TemplateError::Unbalanced(i)),
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    Ok(parts)
+}
@@ -0,0 +1,457 @@
+//! Helpers for snapshot tests of rendered snippets.
+//!
+//! `render_plain` renders a snippet the same way regardless of its color
+//! option and of the environment, and the normalization functions remove the
+//! details of an output which change between machines or revisions.
+//!
+//! `diff_display_lists` compares lists line by line, as tests of passes and
+//! renderers want rather than comparing whole outputs.
+//!
+//! `assert_snippet_eq!` compares the plain output of a snippet with an
+//! expected string, and shows their lines side by side when they differ.
+//!
+//! With the `serde` and `toml` features, `check_fixtures` tests a renderer
+//! against golden files: TOML descriptions of snippets, as read by the
+//! `description` module, with their expected output in an `expected` string.
+//!
+//! ```toml
+//! expected = '''
+//! warning: unused variable
+//!   |
+//! 1 | let x = 1;
+//!   |     ^ never read
+//!   |'''
+//!
+//! [title]
+//! label = "unused variable"
+//! annotation_type = "Warning"
+//!
+//! [[slices]]
+//! source = "let x = 1;"
+//! line_start = 1
+//! [[slices.annotations]]
+//! label = "never read"
+//! annotation_type = "Warning"
+//! range = [4, 5]
+//! ```
+//!
+//! ```
+//! use annotate_snippets::testing::{anonymize_line_numbers, normalize_paths};
+//!
+//! let output = " --> C:\\work\\src\\lib.rs:12:5\n   |\n12 |     x\n   |     ^";
+//! let output = normalize_paths(output, "C:\\work", "$DIR");
+//! assert_eq!(
+//!     anonymize_line_numbers(&output),
+//!     " --> $DIR/src/lib.rs:12:5\n   |\nLL |     x\n   |     ^"
+//! );
+//! ```
+#[cfg(all(feature = "serde", feature = "toml"))]
+use std::io;
+#[cfg(all(feature = "serde", feature = "toml"))]
+use std::path::{Path, PathBuf};
+
+#[cfg(all(feature = "serde", feature = "toml"))]
+use serde::Deserialize;
+
+#[cfg(all(feature = "serde", feature = "toml"))]
+use crate::description::SnippetDesc;
+use crate::display_list::{
+    display_lines, DisplayLine, DisplayList, DisplayMark, DisplayRawLine, DisplaySourceLine,
+    DisplayTextFragment, FormatOptions,
+};
+use crate::formatter::style::strip_styles;
+use crate::snippet::Snippet;
+
+/// Renders the snippet without colors, whatever its options and the theme
+/// configured in the environment.
+pub fn render_plain(snippet: &Snippet<'_>) -> String {
+    let opt = FormatOptions {
+        color: false,
+        ..snippet.opt.clone()
+    };
+    DisplayList::for_snippet(display_lines(snippet).collect(), snippet, opt).to_string()
+}
+
+/// Asserts that a snippet renders as the expected string, with
+/// `render_plain` so that the output doesn't depend on colors.
+///
+/// The expected string may start with a newline, to be written as a raw
+/// literal with its first line under the macro, and trailing whitespace is
+/// ignored. On mismatch, it panics with a line by line diff of the outputs.
+///
+/// ```
+/// use annotate_snippets::assert_snippet_eq;
+/// use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+///
+/// let snippet = Snippet::new().title(Annotation::new(AnnotationType::Error, "oops"));
+/// assert_snippet_eq!(
+///     snippet,
+///     r#"
+/// error: oops
+/// "#
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_snippet_eq {
+    ($snippet:expr, $expected:expr $(,)?) => {
+        $crate::testing::assert_rendered_eq(&$snippet, $expected)
+    };
+}
+
+/// Panics with a line by line diff of the outputs if `snippet` doesn't
+/// render as `expected`, as `assert_snippet_eq!` does.
+pub fn assert_rendered_eq(snippet: &Snippet<'_>, expected: &str) {
+    let expected = expected.strip_prefix('\n').unwrap_or(expected).trim_end();
+    let actual = render_plain(snippet);
+    if let Some(diff) = diff_rendered(expected, actual.trim_end()) {
+        panic!(
+            "rendered snippet differs from the expected output:\n{}",
+            diff
+        );
+    }
+}
+
+/// The lines of `expected` and `actual`, the differing ones marked with `-`
+/// and `+` respectively, or `None` if the outputs are the same.
+///
+/// ```
+/// use annotate_snippets::testing::diff_rendered;
+///
+/// assert_eq!(
+///     diff_rendered("a\nb", "a\nc").unwrap(),
+///     "  a\n- b\n+ c"
+/// );
+/// ```
+pub fn diff_rendered(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let expected = expected.split('\n').collect::<Vec<_>>();
+    let actual = actual.split('\n').collect::<Vec<_>>();
+    let mut diff = vec![];
+    for i in 0..std::cmp::max(expected.len(), actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(expected), Some(actual)) if expected == actual => {
+                diff.push(format!("  {}", expected))
+            }
+            (expected, actual) => {
+                diff.extend(expected.map(|line| format!("- {}", line)));
+                diff.extend(actual.map(|line| format!("+ {}", line)));
+            }
+        }
+    }
+    Some(diff.join("\n"))
+}
+
+/// Removes the ANSI escape sequences from `s`, as
+/// `formatter::style::strip_styles` does.
+pub fn strip_ansi(s: &str) -> String {
+    strip_styles(s)
+}
+
+/// Replaces the line numbers in the gutter of source lines by `LL`, as
+/// `FormatOptions::anonymized_line_numbers` does, so that outputs don't
+/// change when lines are added above an annotation.
+///
+/// Line numbers in the `-->` headers are kept. The width of the gutter is
+/// kept too, apart from single digit line numbers which `LL` is wider than.
+pub fn anonymize_line_numbers(s: &str) -> String {
+    let lines = s.split('\n').map(|line| {
+        let digits_start = line.len() - line.trim_start_matches(' ').len();
+        let rest = &line[digits_start..];
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 || !rest[digits..].starts_with(" |") {
+            return line.to_string();
+        }
+        let width = digits_start + digits;
+        format!("{:<width$}{}", "LL", &rest[digits..], width = width.max(2))
+    });
+    lines.collect::<Vec<_>>().join("\n")
+}
+
+/// Replaces `base` by `placeholder`, and the backslashes of the paths of the
+/// `-->` and `:::` headers by slashes, so that outputs don't depend on the
+/// directory or the platform.
+pub fn normalize_paths(s: &str, base: &str, placeholder: &str) -> String {
+    let lines = s.split('\n').map(|line| {
+        let line = if base.is_empty() {
+            line.to_string()
+        } else {
+            line.replace(base, placeholder)
+        };
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("--> ") || trimmed.starts_with("::: ") {
+            line.replace('\\', "/")
+        } else {
+            line
+        }
+    });
+    lines.collect::<Vec<_>>().join("\n")
+}
+
+/// Describes the first line of `actual` which is not the one of `expected`:
+/// its index, what differs between the two lines, among their kind, line
+/// number, content and marks, and both lines. `None` if the lists have the
+/// same lines.
+///
+/// ```
+/// use annotate_snippets::display_list::{DisplayLine, DisplayList};
+/// use annotate_snippets::testing::diff_display_lists;
+///
+/// let expected = DisplayList::from(vec![DisplayLine::empty(), DisplayLine::empty()]);
+/// let actual = DisplayList::from(vec![DisplayLine::empty()]);
+/// assert_eq!(
+///     diff_display_lists(&expected, &actual).unwrap(),
+///     "line 1: the list ends early\n  expected: empty source line\n  actual:   nothing"
+/// );
+/// ```
+pub fn diff_display_lists(expected: &DisplayList<'_>, actual: &DisplayList<'_>) -> Option<String> {
+    let len = std::cmp::max(expected.body.len(), actual.body.len());
+    (0..len).find_map(|i| {
+        let (expected, actual) = (expected.body.get(i), actual.body.get(i));
+        if expected == actual {
+            return None;
+        }
+        let difference = match (expected, actual) {
+            (Some(_), None) => "the list ends early",
+            (None, Some(_)) => "the list has more lines",
+            (Some(expected), Some(actual)) => line_difference(expected, actual),
+            (None, None) => unreachable!(),
+        };
+        let describe = |line: Option<&DisplayLine<'_>>| match line {
+            Some(line) => describe_line(line),
+            None => "nothing".to_string(),
+        };
+        Some(format!(
+            "line {}: {}\n  expected: {}\n  actual:   {}",
+            i,
+            difference,
+            describe(expected),
+            describe(actual)
+        ))
+    })
+}
+
+/// Panics with the first difference of the lists, as `diff_display_lists`
+/// describes it, if they don't have the same lines.
+pub fn assert_display_lists_eq(expected: &DisplayList<'_>, actual: &DisplayList<'_>) {
+    if let Some(difference) = diff_display_lists(expected, actual) {
+        panic!("display lists differ at {}", difference);
+    }
+}
+
+/// What differs between two lines which are not equal.
+fn line_difference(expected: &DisplayLine<'_>, actual: &DisplayLine<'_>) -> &'static str {
+    match (expected, actual) {
+        (
+            DisplayLine::Source {
+                lineno,
+                inline_marks,
+                line,
+            },
+            DisplayLine::Source {
+                lineno: actual_lineno,
+                inline_marks: actual_marks,
+                line: actual_line,
+            },
+        ) => {
+            if std::mem::discriminant(line) != std::mem::discriminant(actual_line) {
+                "the kind differs"
+            } else if lineno != actual_lineno {
+                "the line number differs"
+            } else if inline_marks != actual_marks && line == actual_line {
+                "the marks differ"
+            } else {
+                "the content differs"
+            }
+        }
+        (DisplayLine::Fold { .. }, DisplayLine::Fold { .. }) => "the marks differ",
+        (
+            DisplayLine::Elided { count, .. },
+            DisplayLine::Elided {
+                count: actual_count,
+                ..
+            },
+        ) => {
+            if count != actual_count {
+                "the count differs"
+            } else {
+                "the marks differ"
+            }
+        }
+        (DisplayLine::Raw(line), DisplayLine::Raw(actual))
+            if std::mem::discriminant(line) == std::mem::discriminant(actual) =>
+        {
+            "the content differs"
+        }
+        _ => "the kind differs",
+    }
+}
+
+/// A line as it is described in differences.
+fn describe_line(line: &DisplayLine<'_>) -> String {
+    let marks = |inline_marks: &[DisplayMark]| {
+        if inline_marks.is_empty() {
+            String::new()
+        } else {
+            let marks = inline_marks
+                .iter()
+                .map(|mark| format!("{:?} {:?}", mark.mark_type, mark.annotation_type))
+                .collect::<Vec<_>>();
+            format!(", marks [{}]", marks.join(", "))
+        }
+    };
+    match line {
+        DisplayLine::Source {
+            lineno,
+            inline_marks,
+            line,
+        } => {
+            let lineno = match lineno {
+                Some(lineno) => format!("source line {}", lineno),
+                None => "source line without number".to_string(),
+            };
+            let line = match line {
+                DisplaySourceLine::Content { text, .. } => format!("{}: {:?}", lineno, text),
+                DisplaySourceLine::Compact {
+                    text, annotations, ..
+                } => format!(
+                    "{}: {:?}, with {}",
+                    lineno,
+                    text,
+                    annotations
+                        .iter()
+                        .map(|trailing| format!(
+                            "{:?} annotation of {:?}: {:?}",
+                            trailing.annotation_type,
+                            trailing.range,
+                            label(&trailing.annotation.label)
+                        ))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                DisplaySourceLine::Annotation {
+                    annotation,
+                    range,
+                    annotation_type,
+                    annotation_part,
+                    ..
+                } => format!(
+                    "{:?} annotation of {:?}, {:?}: {:?}",
+                    annotation_type,
+                    range,
+                    annotation_part,
+                    label(&annotation.label)
+                ),
+                DisplaySourceLine::Ruler { width, tens } => format!(
+                    "{} ruler of {} columns",
+                    if *tens { "tens" } else { "units" },
+                    width
+                ),
+                DisplaySourceLine::Empty => "empty source line".to_string(),
+            };
+            line + &marks(inline_marks)
+        }
+        DisplayLine::Fold { inline_marks } => "fold".to_string() + &marks(inline_marks),
+        DisplayLine::Elided {
+            inline_marks,
+            count,
+        } => format!("{} elided lines", count) + &marks(inline_marks),
+        DisplayLine::Raw(DisplayRawLine::Origin {
+            path, pos, offset, ..
+        }) => match (pos, offset) {
+            (Some((line, column)), _) => format!("origin {:?} at {}:{}", path, line, column),
+            (None, Some(offset)) => format!("origin {:?} at +0x{:X}", path, offset),
+            (None, None) => format!("origin {:?}", path),
+        },
+        DisplayLine::Raw(DisplayRawLine::Annotation {
+            annotation,
+            continuation,
+            ..
+        }) => format!(
+            "{:?} {}: {:?}",
+            annotation.annotation_type,
+            if *continuation {
+                "continuation"
+            } else {
+                "annotation"
+            },
+            label(&annotation.label)
+        ),
+        DisplayLine::Raw(DisplayRawLine::Include { path, line, .. }) => {
+            format!("include of {:?} at line {}", path, line)
+        }
+    }
+}
+
+fn label(fragments: &[DisplayTextFragment<'_>]) -> String {
+    fragments
+        .iter()
+        .map(|fragment| fragment.content.as_ref())
+        .collect()
+}
+
+/// A golden file: the description of a snippet, with its expected output.
+#[cfg(all(feature = "serde", feature = "toml"))]
+#[derive(Deserialize)]
+pub struct Fixture {
+    #[serde(flatten)]
+    pub snippet: SnippetDesc,
+    pub expected: String,
+}
+
+/// Reads the golden files `*.toml` of `dir`, sorted by path.
+#[cfg(all(feature = "serde", feature = "toml"))]
+pub fn read_fixtures(dir: impl AsRef<Path>) -> io::Result<Vec<(PathBuf, Fixture)>> {
+    let mut paths = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension() == Some("toml".as_ref()) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let fixture = toml::from_str(&std::fs::read_to_string(&path)?).map_err(|error| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {}", path.display(), error),
+                )
+            })?;
+            Ok((path, fixture))
+        })
+        .collect()
+}
+
+/// Renders the snippet of every golden file of `dir` with `render`, and
+/// panics listing the files whose output is not the expected one, or which
+/// can't be read.
+///
+/// Snippets are described without colors, and outputs are compared without
+/// their trailing whitespace.
+#[cfg(all(feature = "serde", feature = "toml"))]
+pub fn check_fixtures(dir: impl AsRef<Path>, mut render: impl FnMut(&Snippet<'_>) -> String) {
+    let fixtures = read_fixtures(dir).unwrap_or_else(|error| panic!("{}", error));
+    let mut failures = vec![];
+    for (path, fixture) in &fixtures {
+        let actual = render(&fixture.snippet.to_snippet(false));
+        if actual.trim_end() != fixture.expected.trim_end() {
+            failures.push(format!(
+                "{}\n--- expected\n{}\n--- actual\n{}",
+                path.display(),
+                fixture.expected.trim_end(),
+                actual.trim_end()
+            ));
+        }
+    }
+    if !failures.is_empty() {
+        panic!(
+            "{} of {} fixtures failed:\n\n{}",
+            failures.len(),
+            fixtures.len(),
+            failures.join("\n\n")
+        );
+    }
+}
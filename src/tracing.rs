@@ -0,0 +1,46 @@
+//! Snippets recorded as `tracing` events.
+//!
+//! With the `tracing` feature, an application using `tracing` for all of its
+//! output can route its diagnostics through its subscribers: `emit` renders a
+//! snippet and records it as an event at the level of its title.
+//!
+//! The message of the event is the label of the title, and its fields are
+//! the `code`, the id of the title if any, and the `diagnostic`, the rendered
+//! snippet with its lines.
+//!
+//! ```
+//! use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+//!
+//! let snippet = Snippet::new().title(Annotation::new(AnnotationType::Warning, "unused import"));
+//! annotate_snippets::tracing::emit(&snippet);
+//! ```
+use tracing::Level;
+
+use crate::snippet::{AnnotationType, Snippet};
+
+/// The level of the events of snippets whose title is an `annotation_type`.
+///
+/// Notes and help are informational, like infos.
+pub fn level(annotation_type: AnnotationType) -> Level {
+    match annotation_type {
+        AnnotationType::Error => Level::ERROR,
+        AnnotationType::Warning => Level::WARN,
+        AnnotationType::Info | AnnotationType::Note | AnnotationType::Help => Level::INFO,
+    }
+}
+
+/// Renders `snippet` with its options and records it as an event, at the
+/// level of its title, `INFO` without one.
+pub fn emit(snippet: &Snippet<'_>) {
+    let title = snippet.title.as_ref();
+    let level = title.map_or(Level::INFO, |title| level(title.annotation_type));
+    let code = title.and_then(|title| title.id);
+    let message = title.and_then(|title| title.label).unwrap_or_default();
+    let diagnostic = crate::format(snippet, &snippet.opt);
+    // The level of an event is part of its static metadata.
+    match level {
+        Level::ERROR => tracing::error!(code, %diagnostic, "{}", message),
+        Level::WARN => tracing::warn!(code, %diagnostic, "{}", message),
+        _ => tracing::info!(code, %diagnostic, "{}", message),
+    }
+}
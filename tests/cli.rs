@@ -0,0 +1,100 @@
+#![cfg(feature = "cli")]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run(input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_annotate-snippets"))
+        .args(["--color", "never"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(input: &str) -> String {
+    let output = run(input);
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_cli_toml() {
+    let input = r#"
+[title]
+label = "unused variable"
+annotation_type = "Warning"
+
+[[slices]]
+source = "let x = 1;"
+line_start = 3
+origin = "src/lib.rs"
+[[slices.annotations]]
+label = "never read"
+annotation_type = "Warning"
+range = [4, 5]
+"#;
+    let expected = r#"warning: unused variable
+ --> src/lib.rs:3:5
+  |
+3 | let x = 1;
+  |     - never read
+  |
+"#;
+    assert_eq!(stdout(input), expected);
+}
+
+#[test]
+fn test_cli_json_list() {
+    let input = r#"[
+        {"title": {"label": "first", "annotation_type": "Error"}},
+        {"title": {"label": "second", "annotation_type": "Note"}}
+    ]"#;
+    assert_eq!(stdout(input), "error: first\n\nnote: second\n");
+}
+
+#[test]
+fn test_cli_rustc() {
+    let input = concat!(
+        r#"{"$message_type":"artifact","artifact":"libbad.rmeta","emit":"metadata"}"#,
+        "\n",
+        r#"{"$message_type":"diagnostic","message":"mismatched types","#,
+        r#""code":{"code":"E0308","explanation":null},"level":"error","spans":["#,
+        r#"{"file_name":"bad.rs","line_start":2,"line_end":2,"is_primary":true,"#,
+        r#""text":[{"text":"    let x: u8 = \"a\";","highlight_start":17,"highlight_end":20}],"#,
+        r#""label":"expected `u8`"},"#,
+        r#"{"file_name":"bad.rs","line_start":2,"line_end":2,"is_primary":false,"#,
+        r#""text":[{"text":"    let x: u8 = \"a\";","highlight_start":12,"highlight_end":14}],"#,
+        r#""label":null}],"#,
+        r#""children":[{"message":"try a number","code":null,"level":"help","spans":[],"children":[]}],"#,
+        r#""rendered":"error[E0308]: mismatched types\n"}"#,
+        "\n",
+    );
+    let expected = r#"error[E0308]: mismatched types
+ --> bad.rs:2:17
+  |
+2 |     let x: u8 = "a";
+  |                 ^^^ expected `u8`
+  |            --
+  |
+  = help: try a number
+"#;
+    assert_eq!(stdout(input), expected);
+}
+
+#[test]
+fn test_cli_invalid_input() {
+    let output = run("{oops");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("error: key must be a string"));
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "codespan-reporting")]
+
+use annotate_snippets::codespan::{self, FileAnnotation};
+use annotate_snippets::display_list::DisplayList;
+use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+use codespan_reporting::files::SimpleFiles;
+
+#[test]
+fn test_codespan_slices() {
+    let mut files = SimpleFiles::new();
+    let lib = files.add("src/lib.rs", "mod a;\nconst É: u8 = 256;\n");
+    let a = files.add("src/a.rs", "pub fn f() {}\n");
+
+    let slices = codespan::slices(
+        &files,
+        vec![
+            FileAnnotation {
+                file: lib,
+                range: (22, 25),
+                label: "literal out of range for `u8`",
+                annotation_type: AnnotationType::Error,
+            },
+            FileAnnotation {
+                file: a,
+                range: (7, 8),
+                label: "unused function",
+                annotation_type: AnnotationType::Warning,
+            },
+            FileAnnotation {
+                file: lib,
+                range: (17, 19),
+                label: "of this type",
+                annotation_type: AnnotationType::Info,
+            },
+        ],
+        0,
+    )
+    .unwrap();
+    assert_eq!(slices.len(), 2);
+    assert_eq!(slices[0].source, "const É: u8 = 256;\n");
+    assert_eq!(slices[0].line_start, 2);
+
+    let snippet = slices.iter().fold(
+        Snippet::new().title(Annotation::new(AnnotationType::Error, "invalid constant")),
+        |snippet, slice| snippet.slice(slice.to_slice()),
+    );
+    let expected = [
+        "error: invalid constant",
+        " --> src/lib.rs:2:15",
+        "  |",
+        "2 | const É: u8 = 256;",
+        "  |               ^^^ literal out of range for `u8`",
+        "  |          -- info: of this type",
+        "  |",
+        " ::: src/a.rs:1:8",
+        "  |",
+        "1 | pub fn f() {}",
+        "  |        - unused function",
+        "  |",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet).to_string(), expected);
+}
+
+#[test]
+fn test_codespan_missing_file() {
+    let files = SimpleFiles::<&str, &str>::new();
+    let result = codespan::slices(
+        &files,
+        vec![FileAnnotation {
+            file: 0,
+            range: (0, 1),
+            label: "here",
+            annotation_type: AnnotationType::Error,
+        }],
+        0,
+    );
+    assert!(result.is_err());
+}
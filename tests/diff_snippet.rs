@@ -0,0 +1,139 @@
+#![cfg(feature = "diff")]
+
+use annotate_snippets::diff::{
+    diff_lines, DiffAnnotation, DiffSide, DiffSnippet, DiffTag, SideBySideSnippet,
+};
+use annotate_snippets::display_list::DisplayList;
+use annotate_snippets::snippet::{Annotation, AnnotationType};
+
+#[test]
+fn test_diff_lines() {
+    let diff = diff_lines("a\nb\nc\nd\ne", "a\nc\nx\nd\ne\nf");
+    let lines = diff
+        .iter()
+        .map(|line| {
+            (
+                line.tag,
+                line.text,
+                line.expected_lineno,
+                line.actual_lineno,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        lines,
+        vec![
+            (DiffTag::Equal, "a", Some(1), Some(1)),
+            (DiffTag::Delete, "b", Some(2), None),
+            (DiffTag::Equal, "c", Some(3), Some(2)),
+            (DiffTag::Insert, "x", None, Some(3)),
+            (DiffTag::Equal, "d", Some(4), Some(4)),
+            (DiffTag::Equal, "e", Some(5), Some(5)),
+            (DiffTag::Insert, "f", None, Some(6)),
+        ]
+    );
+}
+
+#[test]
+fn test_diff_snippet() {
+    let snippet = DiffSnippet {
+        title: Some(Annotation {
+            id: None,
+            label: Some("output mismatch"),
+            annotation_type: AnnotationType::Error,
+        }),
+        expected: "fn main() {\n    println!(\"a\");\n}\n",
+        actual: "fn main() {\n    print!(\"a\");\n}\n",
+        annotations: vec![
+            DiffAnnotation {
+                side: DiffSide::Expected,
+                line: 2,
+                range: (4, 12),
+                label: "expected",
+                annotation_type: AnnotationType::Info,
+            },
+            DiffAnnotation {
+                side: DiffSide::Actual,
+                line: 2,
+                range: (4, 10),
+                label: "found",
+                annotation_type: AnnotationType::Error,
+            },
+        ],
+        opt: Default::default(),
+    };
+
+    let expected = [
+        "error: output mismatch",
+        "  |",
+        "1 |   fn main() {",
+        "2 | -     println!(\"a\");",
+        "  |       -------- info: expected",
+        "2 | +     print!(\"a\");",
+        "  |       ^^^^^^ found",
+        "3 |   }",
+        "  |",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet).to_string(), expected);
+}
+
+#[test]
+fn test_side_by_side() {
+    let before = "fn main() {\n    let x = 1;\n    dbg!(x);\n}\n";
+    let after = "fn main() {\n    let x = 2;\n    let y = x;\n}\n";
+    let snippet = |width| SideBySideSnippet {
+        title: Some(Annotation {
+            id: None,
+            label: Some("suggested change"),
+            annotation_type: AnnotationType::Help,
+        }),
+        before,
+        after,
+        width,
+        ..Default::default()
+    };
+    let expected = [
+        "help: suggested change",
+        "  |",
+        "1 | fn main() {      fn main() {",
+        "2 |     let x = 1; |     let x = 2;",
+        "3 |     dbg!(x);   |     let y = x;",
+        "4 | }                }",
+        "  |",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet(40)).to_string(), expected);
+
+    // Too narrow for two columns.
+    let unified = DisplayList::from(snippet(20)).to_string();
+    assert!(unified.contains("2 | -     let x = 1;"), "{}", unified);
+
+    let snippet = SideBySideSnippet {
+        before: "a\nb\n",
+        after: "a\nc\nd\n",
+        width: 80,
+        ..Default::default()
+    };
+    assert_eq!(
+        DisplayList::from(snippet).to_string(),
+        "  |\n1 | a   a\n2 | b | c\n  |   > d\n  |"
+    );
+}
+
+#[cfg(feature = "color")]
+#[test]
+fn test_side_by_side_colors() {
+    let snippet = SideBySideSnippet {
+        before: "a\nb\n",
+        after: "a\nc\n",
+        width: 80,
+        opt: annotate_snippets::display_list::FormatOptions::new().color(true),
+        ..Default::default()
+    };
+    let rendered = DisplayList::from(snippet).to_string();
+    let line = rendered.lines().nth(2).unwrap();
+    assert!(line.contains("\x1b[31mb\x1b[0m"), "{:?}", line);
+    assert!(line.contains("\x1b[32mc\x1b[0m"), "{:?}", line);
+}
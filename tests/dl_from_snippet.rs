@@ -1,25 +1,22 @@
+use std::borrow::Cow;
+
 use annotate_snippets::display_list::DisplayList;
 use annotate_snippets::{display_list as dl, formatter::get_term_style, snippet};
 
 #[test]
 fn test_format_title() {
-    let input = snippet::Snippet {
-        title: Some(snippet::Annotation {
-            id: Some("E0001"),
-            label: Some("This is a title"),
-            annotation_type: snippet::AnnotationType::Error,
-        }),
-        footer: vec![],
-        slices: vec![],
-        opt: Default::default(),
-    };
+    let input = snippet::Snippet::new().title(snippet::Annotation {
+        id: Some("E0001"),
+        label: Some("This is a title"),
+        annotation_type: snippet::AnnotationType::Error,
+    });
     let output = dl::DisplayList {
         body: vec![dl::DisplayLine::Raw(dl::DisplayRawLine::Annotation {
             annotation: dl::Annotation {
                 annotation_type: dl::DisplayAnnotationType::Error,
                 id: Some("E0001"),
                 label: vec![dl::DisplayTextFragment {
-                    content: "This is a title",
+                    content: "This is a title".into(),
                     style: dl::DisplayTextStyle::Emphasis,
                 }],
             },
@@ -29,6 +26,7 @@ fn test_format_title() {
         stylesheet: get_term_style(input.opt.color),
         anonymized_line_numbers: input.opt.anonymized_line_numbers,
         margin: None,
+        ..dl::DisplayList::from(vec![])
     };
     assert_eq!(dl::DisplayList::from(input), output);
 }
@@ -37,51 +35,41 @@ fn test_format_title() {
 fn test_format_slice() {
     let line_1 = "This is line 1";
     let line_2 = "This is line 2";
-    let source = vec![line_1, line_2].join("\n");
-    let input = snippet::Snippet {
-        title: None,
-        footer: vec![],
-        slices: vec![snippet::Slice {
-            source: &source,
-            line_start: 5402,
-            origin: None,
-            annotations: vec![],
-            fold: false,
-        }],
-        opt: Default::default(),
-    };
+    let source = [line_1, line_2].join("\n");
+    let input = snippet::Snippet::new().slice(snippet::Slice::new(&source, 5402));
     let output = dl::DisplayList {
         body: vec![
             dl::DisplayLine::Source {
                 lineno: None,
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Empty,
             },
             dl::DisplayLine::Source {
                 lineno: Some(5402),
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Content {
-                    text: line_1,
+                    text: line_1.into(),
                     range: (0, line_1.len()),
                 },
             },
             dl::DisplayLine::Source {
                 lineno: Some(5403),
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Content {
                     range: (line_1.len() + 1, source.len()),
-                    text: line_2,
+                    text: line_2.into(),
                 },
             },
             dl::DisplayLine::Source {
                 lineno: None,
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Empty,
             },
         ],
         stylesheet: get_term_style(input.opt.color),
         anonymized_line_numbers: input.opt.anonymized_line_numbers,
         margin: None,
+        ..dl::DisplayList::from(vec![])
     };
     assert_eq!(dl::DisplayList::from(input), output);
 }
@@ -92,79 +80,64 @@ fn test_format_slices_continuation() {
     let src_0_len = src_0.len();
     let src_1 = "This is slice 2";
     let src_1_len = src_1.len();
-    let input = snippet::Snippet {
-        title: None,
-        footer: vec![],
-        slices: vec![
-            snippet::Slice {
-                source: src_0,
-                line_start: 5402,
-                origin: Some("file1.rs"),
-                annotations: vec![],
-                fold: false,
-            },
-            snippet::Slice {
-                source: src_1,
-                line_start: 2,
-                origin: Some("file2.rs"),
-                annotations: vec![],
-                fold: false,
-            },
-        ],
-        opt: Default::default(),
-    };
+    let input = snippet::Snippet::new()
+        .slice(snippet::Slice::new(src_0, 5402).origin("file1.rs"))
+        .slice(snippet::Slice::new(src_1, 2).origin("file2.rs"));
     let output = dl::DisplayList {
         body: vec![
             dl::DisplayLine::Raw(dl::DisplayRawLine::Origin {
-                path: "file1.rs",
+                path: "file1.rs".into(),
                 pos: None,
+                offset: None,
                 header_type: dl::DisplayHeaderType::Initial,
             }),
             dl::DisplayLine::Source {
                 lineno: None,
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Empty,
             },
             dl::DisplayLine::Source {
                 lineno: Some(5402),
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Content {
-                    text: src_0,
+                    text: src_0.into(),
                     range: (0, src_0_len),
                 },
             },
             dl::DisplayLine::Source {
                 lineno: None,
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Empty,
             },
             dl::DisplayLine::Raw(dl::DisplayRawLine::Origin {
-                path: "file2.rs",
+                path: "file2.rs".into(),
                 pos: None,
+                offset: None,
                 header_type: dl::DisplayHeaderType::Continuation,
             }),
             dl::DisplayLine::Source {
                 lineno: None,
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Empty,
             },
             dl::DisplayLine::Source {
                 lineno: Some(2),
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Content {
-                    text: src_1,
+                    text: src_1.into(),
                     range: (0, src_1_len),
                 },
             },
             dl::DisplayLine::Source {
                 lineno: None,
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Empty,
             },
         ],
         stylesheet: get_term_style(input.opt.color),
         anonymized_line_numbers: input.opt.anonymized_line_numbers,
         margin: None,
+        ..dl::DisplayList::from(vec![])
     };
     assert_eq!(dl::DisplayList::from(input), output);
 }
@@ -173,90 +146,79 @@ fn test_format_slices_continuation() {
 fn test_format_slice_annotation_standalone() {
     let line_1 = "This is line 1";
     let line_2 = "This is line 2";
-    let source = vec![line_1, line_2].join("\n");
+    let source = [line_1, line_2].join("\n");
     // In line 2
     let range = (22, 24);
-    let input = snippet::Snippet {
-        title: None,
-        footer: vec![],
-        slices: vec![snippet::Slice {
-            source: &source,
-            line_start: 5402,
-            origin: None,
-            annotations: vec![snippet::SourceAnnotation {
-                range,
-                label: "Test annotation",
-                annotation_type: snippet::AnnotationType::Info,
-            }],
-            fold: false,
-        }],
-        opt: Default::default(),
-    };
+    let input = snippet::Snippet::new().slice(snippet::Slice::new(&source, 5402).annotation(
+        snippet::SourceAnnotation {
+            range,
+            label: "Test annotation",
+            annotation_type: snippet::AnnotationType::Info,
+            ..Default::default()
+        },
+    ));
     let output = dl::DisplayList {
         body: vec![
             dl::DisplayLine::Source {
                 lineno: None,
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Empty,
             },
             dl::DisplayLine::Source {
                 lineno: Some(5402),
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Content {
                     range: (0, line_1.len()),
-                    text: line_1,
+                    text: line_1.into(),
                 },
             },
             dl::DisplayLine::Source {
                 lineno: Some(5403),
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Content {
                     range: (line_1.len() + 1, source.len()),
-                    text: line_2,
+                    text: line_2.into(),
                 },
             },
             dl::DisplayLine::Source {
                 lineno: None,
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Annotation {
                     annotation: dl::Annotation {
                         annotation_type: dl::DisplayAnnotationType::Info,
                         id: None,
                         label: vec![dl::DisplayTextFragment {
-                            content: "Test annotation",
+                            content: "Test annotation".into(),
                             style: dl::DisplayTextStyle::Regular,
                         }],
                     },
                     range: (range.0 - (line_1.len() + 1), range.1 - (line_1.len() + 1)),
                     annotation_type: dl::DisplayAnnotationType::Info,
                     annotation_part: dl::DisplayAnnotationPart::Standalone,
+                    wide_marks: false,
                 },
             },
             dl::DisplayLine::Source {
                 lineno: None,
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Empty,
             },
         ],
         stylesheet: get_term_style(input.opt.color),
         anonymized_line_numbers: input.opt.anonymized_line_numbers,
         margin: None,
+        ..dl::DisplayList::from(vec![])
     };
     assert_eq!(dl::DisplayList::from(input), output);
 }
 
 #[test]
 fn test_format_label() {
-    let input = snippet::Snippet {
-        title: None,
-        footer: vec![snippet::Annotation {
-            id: None,
-            label: Some("This __is__ a title"),
-            annotation_type: snippet::AnnotationType::Error,
-        }],
-        slices: vec![],
-        opt: Default::default(),
-    };
+    let input = snippet::Snippet::new().footer(snippet::Annotation {
+        id: None,
+        label: Some("This __is__ a title"),
+        annotation_type: snippet::AnnotationType::Error,
+    });
     let output = dl::DisplayList {
         body: vec![dl::DisplayLine::Raw(dl::DisplayRawLine::Annotation {
             annotation: dl::Annotation {
@@ -264,15 +226,15 @@ fn test_format_label() {
                 id: None,
                 label: vec![
                     dl::DisplayTextFragment {
-                        content: "This ",
+                        content: "This ".into(),
                         style: dl::DisplayTextStyle::Regular,
                     },
                     dl::DisplayTextFragment {
-                        content: "is",
+                        content: "is".into(),
                         style: dl::DisplayTextStyle::Emphasis,
                     },
                     dl::DisplayTextFragment {
-                        content: " a title",
+                        content: " a title".into(),
                         style: dl::DisplayTextStyle::Regular,
                     },
                 ],
@@ -283,6 +245,7 @@ fn test_format_label() {
         stylesheet: get_term_style(input.opt.color),
         anonymized_line_numbers: input.opt.anonymized_line_numbers,
         margin: None,
+        ..dl::DisplayList::from(vec![])
     };
     assert_eq!(dl::DisplayList::from(input), output);
 }
@@ -292,48 +255,37 @@ fn test_format_label() {
 fn test_i26() {
     let source = "short";
     let label = "label";
-    let input = snippet::Snippet {
-        title: None,
-        footer: vec![],
-        slices: vec![snippet::Slice {
-            annotations: vec![snippet::SourceAnnotation {
-                range: (0, source.len() + 1),
-                label,
-                annotation_type: snippet::AnnotationType::Error,
-            }],
-            source,
-            line_start: 0,
-            origin: None,
-            fold: false,
-        }],
-        opt: Default::default(),
-    };
+    let input = snippet::Snippet::new().slice(snippet::Slice::new(source, 0).annotation(
+        snippet::SourceAnnotation {
+            range: (0, source.len() + 1),
+            label,
+            annotation_type: snippet::AnnotationType::Error,
+            ..Default::default()
+        },
+    ));
 
     let _ = dl::DisplayList::from(input);
 }
 
 #[test]
 fn test_i_29() {
-    let snippets = snippet::Snippet {
-        title: Some(snippet::Annotation {
+    let snippets = snippet::Snippet::new()
+        .title(snippet::Annotation {
             id: None,
             label: Some("oops"),
             annotation_type: snippet::AnnotationType::Error,
-        }),
-        footer: vec![],
-        slices: vec![snippet::Slice {
-            source: "First line\r\nSecond oops line",
-            line_start: 1,
-            origin: Some("<current file>"),
-            annotations: vec![snippet::SourceAnnotation {
-                range: (19, 23),
-                label: "oops",
-                annotation_type: snippet::AnnotationType::Error,
-            }],
-            fold: true,
-        }],
-        opt: Default::default(),
-    };
+        })
+        .slice(
+            snippet::Slice::new("First line\r\nSecond oops line", 1)
+                .origin("<current file>")
+                .annotation(snippet::SourceAnnotation {
+                    range: (19, 23),
+                    label: "oops",
+                    annotation_type: snippet::AnnotationType::Error,
+                    ..Default::default()
+                })
+                .fold(true),
+        );
 
     let expected = DisplayList {
         body: vec![
@@ -342,7 +294,7 @@ fn test_i_29() {
                     annotation_type: dl::DisplayAnnotationType::Error,
                     id: None,
                     label: vec![dl::DisplayTextFragment {
-                        content: "oops",
+                        content: "oops".into(),
                         style: dl::DisplayTextStyle::Emphasis,
                     }],
                 },
@@ -350,58 +302,271 @@ fn test_i_29() {
                 continuation: false,
             }),
             dl::DisplayLine::Raw(dl::DisplayRawLine::Origin {
-                path: "<current file>",
+                path: "<current file>".into(),
                 pos: Some((2, 8)),
+                offset: None,
                 header_type: dl::DisplayHeaderType::Initial,
             }),
             dl::DisplayLine::Source {
                 lineno: None,
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Empty,
             },
             dl::DisplayLine::Source {
                 lineno: Some(1),
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Content {
-                    text: "First line",
+                    text: "First line".into(),
                     range: (0, 10),
                 },
             },
             dl::DisplayLine::Source {
                 lineno: Some(2),
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Content {
-                    text: "Second oops line",
+                    text: "Second oops line".into(),
                     range: (12, 28),
                 },
             },
             dl::DisplayLine::Source {
                 lineno: None,
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Annotation {
                     annotation: dl::Annotation {
                         annotation_type: dl::DisplayAnnotationType::None,
                         id: None,
                         label: vec![dl::DisplayTextFragment {
-                            content: "oops",
+                            content: "oops".into(),
                             style: dl::DisplayTextStyle::Regular,
                         }],
                     },
                     range: (7, 11),
                     annotation_type: dl::DisplayAnnotationType::Error,
                     annotation_part: dl::DisplayAnnotationPart::Standalone,
+                    wide_marks: false,
                 },
             },
             dl::DisplayLine::Source {
                 lineno: None,
-                inline_marks: vec![],
+                inline_marks: dl::InlineMarks::new(),
                 line: dl::DisplaySourceLine::Empty,
             },
         ],
+
         stylesheet: get_term_style(false),
+
         anonymized_line_numbers: false,
+
         margin: None,
+
+        ..dl::DisplayList::from(vec![])
     };
 
     assert_eq!(DisplayList::from(snippets), expected);
 }
+
+#[test]
+fn test_source_lines_are_borrowed() {
+    let source = "fn main() {\n    let x = 1;\n}";
+    let input = snippet::Snippet::new().slice(
+        snippet::Slice::new(source, 1)
+            .origin("src/main.rs")
+            .annotation(snippet::SourceAnnotation {
+                range: (20, 21),
+                label: "unused variable",
+                annotation_type: snippet::AnnotationType::Warning,
+                ..Default::default()
+            }),
+    );
+    let dl = DisplayList::from(input);
+
+    for line in &dl.body {
+        match line {
+            dl::DisplayLine::Source {
+                line: dl::DisplaySourceLine::Content { text, .. },
+                ..
+            } => assert!(matches!(text, Cow::Borrowed(_))),
+            dl::DisplayLine::Source {
+                line: dl::DisplaySourceLine::Annotation { annotation, .. },
+                ..
+            } => assert!(annotation
+                .label
+                .iter()
+                .all(|fragment| matches!(fragment.content, Cow::Borrowed(_)))),
+            dl::DisplayLine::Raw(dl::DisplayRawLine::Origin { path, .. }) => {
+                assert!(matches!(path, Cow::Borrowed(_)))
+            }
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn test_clone_and_compare() {
+    let snippet = snippet::Snippet::new()
+        .title(snippet::Annotation {
+            id: Some("E0001"),
+            label: Some("oops"),
+            annotation_type: snippet::AnnotationType::Error,
+        })
+        .slice(
+            snippet::Slice::new("let x = 1;", 1).annotation(snippet::SourceAnnotation {
+                range: (4, 5),
+                label: "here",
+                annotation_type: snippet::AnnotationType::Error,
+                ..Default::default()
+            }),
+        );
+    let copy = snippet.clone();
+    assert_eq!(copy, snippet);
+
+    let lines = DisplayList::from(copy).body;
+    assert_eq!(lines.clone(), lines);
+    assert_eq!(lines, DisplayList::from(snippet).body);
+}
+
+#[test]
+fn test_try_from_snippet() {
+    let annotated = |range| {
+        snippet::Snippet::new()
+            .slice(snippet::Slice::new("fn main() {}", 1))
+            .slice(
+                snippet::Slice::new("let x;", 1).annotation(snippet::SourceAnnotation {
+                    range,
+                    label: "here",
+                    annotation_type: snippet::AnnotationType::Error,
+                    ..Default::default()
+                }),
+            )
+    };
+
+    assert!(DisplayList::try_from_snippet(annotated((4, 5))).is_ok());
+    for range in [(4, 7), (5, 4)] {
+        let error = DisplayList::try_from_snippet(annotated(range)).unwrap_err();
+        assert_eq!(
+            error,
+            snippet::SnippetError::InvalidRange {
+                frame: None,
+                slice: 1,
+                range,
+                source_len: 6,
+            }
+        );
+    }
+    assert_eq!(
+        annotated((4, 7)).validate().unwrap_err().to_string(),
+        "invalid annotation range `(4, 7)` in slice 1 of length `6`"
+    );
+}
+
+#[test]
+fn test_try_from_snippet_expansion() {
+    let frame = |range| snippet::ExpansionFrame {
+        label: "in this macro invocation",
+        slice: snippet::Slice::new("ab", 1).annotation(snippet::SourceAnnotation {
+            range,
+            label: "here",
+            annotation_type: snippet::AnnotationType::Error,
+            ..Default::default()
+        }),
+    };
+    let snippet = |frames: Vec<_>| {
+        frames.into_iter().fold(
+            snippet::Snippet::new().slice(snippet::Slice::new("m!();", 1)),
+            snippet::Snippet::expansion,
+        )
+    };
+
+    assert!(DisplayList::try_from_snippet(snippet(vec![frame((0, 2))])).is_ok());
+    let invalid = snippet(vec![frame((0, 2)), frame((0, 50))]);
+    let error = DisplayList::try_from_snippet(invalid.clone()).unwrap_err();
+    assert_eq!(
+        error,
+        snippet::SnippetError::InvalidRange {
+            frame: Some(1),
+            slice: 0,
+            range: (0, 50),
+            source_len: 2,
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "invalid annotation range `(0, 50)` in expansion frame 1 of length `2`"
+    );
+}
+
+#[test]
+fn test_snippet_constructors() {
+    use snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation};
+
+    let built = Snippet::new()
+        .title(Annotation::new(AnnotationType::Error, "mismatched types").id("E0308"))
+        .slice(
+            Slice::new("let x: u8 = \"a\";", 3)
+                .origin("src/main.rs")
+                .annotation(SourceAnnotation::new(
+                    (12, 15),
+                    AnnotationType::Error,
+                    "expected `u8`",
+                ))
+                .fold(true),
+        )
+        .footer(Annotation::new(AnnotationType::Help, "use a number"))
+        .opt(dl::FormatOptions::new().color(true));
+    let literal = Snippet::new()
+        .title(Annotation {
+            id: Some("E0308"),
+            label: Some("mismatched types"),
+            annotation_type: AnnotationType::Error,
+        })
+        .footer(Annotation {
+            id: None,
+            label: Some("use a number"),
+            annotation_type: AnnotationType::Help,
+        })
+        .slice(
+            Slice::new("let x: u8 = \"a\";", 3)
+                .origin("src/main.rs")
+                .annotation(SourceAnnotation {
+                    range: (12, 15),
+                    label: "expected `u8`",
+                    annotation_type: AnnotationType::Error,
+                    ..Default::default()
+                })
+                .fold(true),
+        )
+        .opt(dl::FormatOptions::new().color(true));
+    assert_eq!(built, literal);
+    assert_eq!(Annotation::new(AnnotationType::Note, None).label, None);
+}
+
+#[test]
+fn test_to_owned_snippet() {
+    use snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation};
+
+    let snippet = Snippet::new()
+        .title(Annotation::new(AnnotationType::Error, "mismatched types").id("E0308"))
+        .slice(
+            Slice::new("fn f() -> u8 {\n    \"a\"\n}", 3)
+                .origin("src/main.rs")
+                .annotation(SourceAnnotation::new(
+                    (19, 22),
+                    AnnotationType::Error,
+                    "expected `u8`",
+                ))
+                .annotation(SourceAnnotation::new(
+                    (10, 12),
+                    AnnotationType::Warning,
+                    "because of this",
+                ))
+                .annotation(SourceAnnotation::new(
+                    (13, 24),
+                    AnnotationType::Info,
+                    "body",
+                )),
+        )
+        .footer(Annotation::new(AnnotationType::Note, "expected\nfound"));
+    let dl = DisplayList::from(snippet.clone());
+    let owned = dl.to_owned_snippet();
+    assert_eq!(owned.to_snippet(), snippet);
+}
@@ -0,0 +1,307 @@
+#![cfg(feature = "emitter")]
+
+use annotate_snippets::display_list::{ColumnUnit, FormatOptions};
+use annotate_snippets::emitter::{Backend, Emitter};
+use annotate_snippets::snippet::{
+    Annotation, AnnotationType, Lint, LintLevel, Slice, Snippet, SourceAnnotation,
+};
+
+fn snippet<'a>(
+    origin: &'a str,
+    line_start: usize,
+    label: &'a str,
+    annotation_type: AnnotationType,
+) -> Snippet<'a> {
+    Snippet::new()
+        .title(Annotation {
+            id: Some("E0001"),
+            label: Some(label),
+            annotation_type,
+        })
+        .slice(
+            Slice::new("x", line_start)
+                .origin(origin)
+                .annotation(SourceAnnotation {
+                    range: (0, 1),
+                    label: "here",
+                    annotation_type,
+                    ..Default::default()
+                }),
+        )
+}
+
+fn flush(emitter: &mut Emitter<'_>) -> String {
+    let mut out = Vec::new();
+    emitter.flush(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_tty_groups_and_orders() {
+    let mut emitter = Emitter::new(Backend::Tty);
+    emitter.emit(snippet("b.rs", 2, "second", AnnotationType::Error));
+    emitter.emit(snippet("a.rs", 1, "other file", AnnotationType::Error));
+    emitter.emit(snippet("b.rs", 1, "first", AnnotationType::Error));
+
+    let out = flush(&mut emitter);
+    let first = out.find("first").unwrap();
+    let second = out.find("second").unwrap();
+    let other = out.find("other file").unwrap();
+    assert!(first < second && second < other);
+    assert!(emitter.is_empty());
+}
+
+#[test]
+fn test_dedup_and_min_severity() {
+    let mut emitter = Emitter::new(Backend::Tty).min_severity(AnnotationType::Warning);
+    emitter.emit(snippet("a.rs", 1, "twice", AnnotationType::Error));
+    emitter.emit(snippet("a.rs", 1, "twice", AnnotationType::Error));
+    emitter.emit(snippet("a.rs", 1, "hidden", AnnotationType::Note));
+    assert_eq!(emitter.len(), 1);
+
+    let mut emitter = Emitter::new(Backend::Tty).dedup(false);
+    emitter.emit(snippet("a.rs", 1, "twice", AnnotationType::Error));
+    emitter.emit(snippet("a.rs", 1, "twice", AnnotationType::Error));
+    assert_eq!(emitter.len(), 2);
+
+    // Duplicates are only looked for within a flush.
+    let mut emitter = Emitter::new(Backend::Tty);
+    emitter.emit(snippet("a.rs", 1, "twice", AnnotationType::Error));
+    flush(&mut emitter);
+    emitter.emit(snippet("a.rs", 1, "twice", AnnotationType::Error));
+    emitter.emit(snippet("b.rs", 1, "twice", AnnotationType::Error));
+    emitter.emit(snippet("a.rs", 1, "twice", AnnotationType::Warning));
+    assert_eq!(emitter.len(), 3);
+
+    // Snippets differing in a later annotation are not duplicates.
+    let mut emitter = Emitter::new(Backend::Tty);
+    emitter.emit(snippet("a.rs", 1, "twice", AnnotationType::Error));
+    let mut other = snippet("a.rs", 1, "twice", AnnotationType::Error);
+    other.slices[0]
+        .annotations
+        .push(SourceAnnotation::new((1, 1), AnnotationType::Note, "there"));
+    emitter.emit(other.clone());
+    emitter.emit(other);
+    assert_eq!(emitter.len(), 2);
+}
+
+#[test]
+fn test_json() {
+    let mut emitter = Emitter::new(Backend::Json);
+    emitter.emit(snippet(
+        "a.rs",
+        1,
+        "a \"quoted\" word",
+        AnnotationType::Warning,
+    ));
+
+    let out = flush(&mut emitter);
+    assert!(out.starts_with(
+        r#"[{"level":"warning","code":"E0001","message":"a \"quoted\" word","location":{"origin":"a.rs","line":1,"column":1},"rendered":"warning[E0001]: a \"quoted\" word\n"#
+    ));
+    assert!(out.ends_with(
+        r#","lint":null}]
+"#
+    ));
+}
+
+#[test]
+fn test_json_lint() {
+    let mut emitter = Emitter::new(Backend::Json);
+    emitter.emit(
+        snippet("a.rs", 1, "unused", AnnotationType::Warning)
+            .lint(Lint::new("unused_variables", LintLevel::Warn)),
+    );
+
+    let out = flush(&mut emitter);
+    assert!(out.contains(r#"= note: `#[warn(unused_variables)]` on by default"#));
+    assert!(out.ends_with(
+        r#","lint":{"name":"unused_variables","level":"warn","note":"`#[warn(unused_variables)]` on by default"}}]
+"#
+    ));
+}
+
+#[test]
+fn test_sarif() {
+    let mut emitter = Emitter::new(Backend::Sarif);
+    emitter.emit(snippet("a.rs", 1, "note", AnnotationType::Help));
+
+    let out = flush(&mut emitter);
+    assert!(out.contains(
+        r#""results":[{"ruleId":"E0001","level":"note","message":{"text":"note"},"locations":[{"physicalLocation":{"artifactLocation":{"uri":"a.rs"},"region":{"startLine":1,"startColumn":1,"endLine":1,"endColumn":2}}}]}]"#
+    ));
+    assert!(out.contains(r#""columnKind":"utf16CodeUnits""#));
+
+    // Columns are counted in UTF-16 code units, not in the unit of the snippet.
+    let mut emitter = Emitter::new(Backend::Sarif);
+    emitter.emit(
+        Snippet::new()
+            .title(Annotation::new(AnnotationType::Error, "after emoji"))
+            .slice(
+                Slice::new("\"😀\" + x", 1)
+                    .origin("a.rs")
+                    .annotation(SourceAnnotation::new((6, 7), AnnotationType::Error, "x")),
+            ),
+    );
+    let out = flush(&mut emitter);
+    assert!(out.contains(r#""startLine":1,"startColumn":8,"endLine":1,"endColumn":9"#));
+}
+
+#[test]
+fn test_cargo() {
+    let mut emitter = Emitter::new(Backend::Cargo);
+    emitter.emit(snippet("a.rs", 3, "unused", AnnotationType::Warning));
+
+    let expected = [
+        "cargo:warning=warning[E0001]: unused",
+        "cargo:warning= --> a.rs:3:1",
+        "cargo:warning=  |",
+        "cargo:warning=3 | x",
+        "cargo:warning=  | - here",
+        "cargo:warning=  |",
+        "",
+    ]
+    .join("\n");
+    assert_eq!(flush(&mut emitter), expected);
+}
+
+#[test]
+fn test_cargo_condensed() {
+    let mut emitter = Emitter::new(Backend::CargoCondensed);
+    emitter.emit(snippet(
+        "a.rs",
+        3,
+        "unused\nvariable",
+        AnnotationType::Warning,
+    ));
+    emitter.emit(Snippet::new().title(Annotation::new(AnnotationType::Error, "no input")));
+
+    assert_eq!(
+        flush(&mut emitter),
+        "cargo:warning=a.rs:3:1: warning[E0001]: unused variable\ncargo:warning=error: no input\n"
+    );
+}
+
+#[test]
+fn test_summary_survives_flush() {
+    let mut emitter = Emitter::new(Backend::Tty).min_severity(AnnotationType::Warning);
+    emitter.emit(snippet("a.rs", 1, "twice", AnnotationType::Error));
+    emitter.emit(snippet("a.rs", 1, "twice", AnnotationType::Error));
+    emitter.emit(snippet("a.rs", 2, "hidden", AnnotationType::Note));
+    flush(&mut emitter);
+    emitter.emit(snippet("a.rs", 3, "unused", AnnotationType::Warning));
+
+    let summary = emitter.summary();
+    assert_eq!((summary.errors, summary.warnings), (1, 1));
+}
+
+#[test]
+fn test_report_duplicates() {
+    let mut emitter = Emitter::new(Backend::Tty).report_duplicates(true);
+    for _ in 0..3 {
+        emitter.emit(snippet("a.rs", 1, "twice", AnnotationType::Warning));
+    }
+    emitter.emit(snippet("a.rs", 2, "twice", AnnotationType::Warning));
+    assert_eq!(emitter.len(), 2);
+    assert_eq!(
+        emitter.summary().message().unwrap().1,
+        "2 warnings emitted (2 duplicate diagnostics suppressed)"
+    );
+
+    let mut emitter = Emitter::new(Backend::Tty);
+    emitter.emit(snippet("a.rs", 1, "twice", AnnotationType::Warning));
+    emitter.emit(snippet("a.rs", 1, "twice", AnnotationType::Warning));
+    assert_eq!(emitter.summary().duplicates, 0);
+}
+
+#[test]
+fn test_cap_severity() {
+    let mut emitter = Emitter::new(Backend::Tty)
+        .cap_severity(AnnotationType::Warning)
+        .min_severity(AnnotationType::Warning);
+    emitter.emit(snippet("a.rs", 1, "capped", AnnotationType::Error));
+    emitter.emit(snippet("a.rs", 2, "kept", AnnotationType::Warning));
+    emitter.emit(snippet("a.rs", 3, "hidden", AnnotationType::Note));
+
+    let summary = emitter.summary();
+    assert_eq!((summary.errors, summary.warnings), (0, 2));
+    assert!(!summary.has_errors());
+    let out = flush(&mut emitter);
+    assert!(out.starts_with("warning[E0001]: capped"));
+    assert!(!out.contains("error") && !out.contains("hidden"));
+}
+
+#[test]
+fn test_emit_with() {
+    let mut emitter = Emitter::new(Backend::Tty)
+        .min_severity(AnnotationType::Warning)
+        .cap_severity(AnnotationType::Warning);
+    let mut built = vec![];
+    for annotation_type in [AnnotationType::Error, AnnotationType::Note] {
+        emitter.emit_with(annotation_type, || {
+            built.push(annotation_type);
+            snippet("a.rs", 1, "lazy", annotation_type)
+        });
+    }
+    assert_eq!(built, [AnnotationType::Error]);
+    assert_eq!(emitter.len(), 1);
+    assert!(flush(&mut emitter).starts_with("warning[E0001]: lazy"));
+}
+
+#[test]
+fn test_column_unit() {
+    let mut emitter = Emitter::new(Backend::Json);
+    emitter.emit(
+        Snippet::new()
+            .title(Annotation::new(AnnotationType::Error, "after emoji"))
+            .slice(
+                Slice::new("\"😀\" + x", 1)
+                    .origin("a.rs")
+                    .annotation(SourceAnnotation::new((6, 7), AnnotationType::Error, "x")),
+            )
+            .opt(FormatOptions::new().column_unit(ColumnUnit::Utf16)),
+    );
+    let out = flush(&mut emitter);
+    assert!(out.contains(r#""line":1,"column":8}"#));
+    assert!(out.contains(" --> a.rs:1:8"));
+}
+
+#[test]
+fn test_path_normalizer() {
+    let opt = FormatOptions::new().path_normalizer(|path| format!("file:///work/{}", path));
+    for backend in [
+        Backend::Json,
+        Backend::Sarif,
+        Backend::CargoCondensed,
+        Backend::Tty,
+    ] {
+        let mut emitter = Emitter::new(backend);
+        let mut snippet = snippet("a.rs", 1, "first", AnnotationType::Error);
+        snippet.opt = opt.clone();
+        emitter.emit(snippet);
+        let out = flush(&mut emitter);
+        assert!(out.contains("file:///work/a.rs"), "{}", out);
+    }
+}
+
+#[test]
+fn test_emit_deferred() {
+    let formatted = std::cell::Cell::new(0);
+    let mut emitter = Emitter::new(Backend::Json).min_severity(AnnotationType::Warning);
+    for annotation_type in [
+        AnnotationType::Error,
+        AnnotationType::Error,
+        AnnotationType::Note,
+    ] {
+        emitter.emit_deferred(snippet("a.rs", 1, "", annotation_type), || {
+            formatted.set(formatted.get() + 1);
+            format!("deferred {}", 1)
+        });
+    }
+    assert_eq!(emitter.len(), 1);
+    assert_eq!(formatted.get(), 0);
+    let out = flush(&mut emitter);
+    assert_eq!(formatted.get(), 1);
+    assert!(out.contains(r#""message":"deferred 1""#));
+    assert!(out.contains("error[E0001]: deferred 1"));
+}
@@ -0,0 +1,98 @@
+use std::error::Error;
+
+use annotate_snippets::display_list::FormatOptions;
+use annotate_snippets::error::{DiagnosticError, RenderedDiagnostic};
+use annotate_snippets::snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation};
+
+fn snippet() -> Snippet<'static> {
+    Snippet::new()
+        .title(Annotation {
+            id: Some("E0001"),
+            label: Some("invalid key"),
+            annotation_type: AnnotationType::Error,
+        })
+        .slice(
+            Slice::new("key = 1", 1)
+                .origin("config.toml")
+                .annotation(SourceAnnotation {
+                    range: (0, 3),
+                    label: "unknown",
+                    annotation_type: AnnotationType::Error,
+                    ..Default::default()
+                }),
+        )
+}
+
+fn parse() -> Result<(), DiagnosticError<'static>> {
+    Err(snippet())?
+}
+
+#[test]
+fn test_diagnostic_error_display() {
+    let expected = [
+        "error[E0001]: invalid key",
+        " --> config.toml:1:1",
+        "  |",
+        "1 | key = 1",
+        "  | ^^^ unknown",
+        "  |",
+    ]
+    .join("\n");
+    let error = parse().unwrap_err();
+    assert_eq!(error.to_string(), expected);
+    assert_eq!(format!("{:?}", error), expected);
+}
+
+#[test]
+fn test_diagnostic_error_boxed() {
+    let error: Box<dyn Error> = Box::new(DiagnosticError::new(snippet()));
+    assert!(error.to_string().starts_with("error[E0001]: invalid key"));
+    assert!(error.source().is_none());
+}
+
+#[test]
+fn test_diagnostic_error_into_snippet() {
+    let error = DiagnosticError::from(snippet());
+    assert_eq!(error.snippet().slices.len(), 1);
+    let snippet = error.into_snippet();
+    assert_eq!(snippet.title.unwrap().id, Some("E0001"));
+}
+
+#[derive(Debug, thiserror::Error)]
+enum ConfigError {
+    #[error("cannot read config")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Invalid(#[from] RenderedDiagnostic),
+}
+
+fn load(source: &str) -> Result<(), ConfigError> {
+    let label = format!("invalid key `{}`", source);
+    let snippet = Snippet::new().title(Annotation::new(AnnotationType::Error, label.as_str()));
+    Err(RenderedDiagnostic::new(&snippet))?
+}
+
+#[test]
+fn test_rendered_diagnostic_in_enum() {
+    fn is_static_error<E: Error + Send + Sync + 'static>(_: &E) {}
+
+    let error = load("key").unwrap_err();
+    is_static_error(&error);
+    assert_eq!(error.to_string(), "error: invalid key `key`");
+    let error: Box<dyn Error> = Box::new(error);
+    assert!(error.source().is_none());
+}
+
+#[test]
+fn test_rendered_diagnostic_from_diagnostic_error() {
+    let error = RenderedDiagnostic::from(parse().unwrap_err());
+    assert_eq!(error.to_string(), parse().unwrap_err().to_string());
+    assert_eq!(format!("{:?}", error), error.to_string());
+    assert_eq!(
+        error.snippet().slices[0].origin.as_deref(),
+        Some("config.toml")
+    );
+
+    let error = error.options(FormatOptions::new().anonymized_line_numbers(true));
+    assert!(error.to_string().contains("LL | key = 1"));
+}
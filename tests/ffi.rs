@@ -0,0 +1,133 @@
+#![cfg(feature = "ffi")]
+
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::ptr;
+
+use annotate_snippets::ffi::*;
+
+#[test]
+fn test_ffi_render() {
+    let label = CString::new("mismatched types").unwrap();
+    let id = CString::new("E0308").unwrap();
+    let source = CString::new("let x: u8 = \"a\";").unwrap();
+    let origin = CString::new("src/main.rs").unwrap();
+    let expected_label = CString::new("expected `u8`").unwrap();
+    let help = CString::new("try a number").unwrap();
+
+    unsafe {
+        let snippet = annotate_snippets_snippet_new(0, id.as_ptr(), label.as_ptr());
+        assert!(!snippet.is_null());
+        let slice = annotate_snippets_snippet_add_slice(
+            snippet,
+            source.as_ptr(),
+            2,
+            origin.as_ptr(),
+            false,
+        );
+        assert_eq!(slice, 0);
+        assert_eq!(
+            annotate_snippets_snippet_add_annotation(
+                snippet,
+                0,
+                12,
+                15,
+                0,
+                expected_label.as_ptr()
+            ),
+            0
+        );
+        assert_eq!(
+            annotate_snippets_snippet_add_footer(snippet, 4, help.as_ptr()),
+            0
+        );
+
+        let len = annotate_snippets_snippet_render(snippet, false, ptr::null_mut(), 0);
+        let mut buffer = vec![0 as c_char; len + 1];
+        assert_eq!(
+            annotate_snippets_snippet_render(snippet, false, buffer.as_mut_ptr(), buffer.len()),
+            len
+        );
+        let rendered = std::ffi::CStr::from_ptr(buffer.as_ptr()).to_str().unwrap();
+        assert_eq!(
+            rendered,
+            [
+                "error[E0308]: mismatched types",
+                " --> src/main.rs:2:13",
+                "  |",
+                "2 | let x: u8 = \"a\";",
+                "  |             ^^^ expected `u8`",
+                "  |",
+                "  = help: try a number",
+            ]
+            .join("\n")
+        );
+
+        // A small buffer gets a truncated, terminated string.
+        let mut buffer = [1 as c_char; 6];
+        assert_eq!(
+            annotate_snippets_snippet_render(snippet, false, buffer.as_mut_ptr(), buffer.len()),
+            len
+        );
+        assert_eq!(
+            std::ffi::CStr::from_ptr(buffer.as_ptr()).to_str().unwrap(),
+            "error"
+        );
+        annotate_snippets_snippet_free(snippet);
+    }
+}
+
+#[test]
+fn test_ffi_invalid_arguments() {
+    let source = CString::new("x").unwrap();
+    unsafe {
+        assert!(annotate_snippets_snippet_new(7, ptr::null(), ptr::null()).is_null());
+
+        let snippet = annotate_snippets_snippet_new(-1, ptr::null(), ptr::null());
+        assert_eq!(
+            annotate_snippets_snippet_add_slice(snippet, ptr::null(), 1, ptr::null(), false),
+            -1
+        );
+        assert_eq!(
+            annotate_snippets_snippet_add_annotation(snippet, 0, 0, 1, 0, ptr::null()),
+            -1
+        );
+        annotate_snippets_snippet_add_slice(snippet, source.as_ptr(), 1, ptr::null(), false);
+        assert_eq!(
+            annotate_snippets_snippet_add_annotation(snippet, 0, 1, 0, 0, ptr::null()),
+            -1
+        );
+        assert_eq!(
+            annotate_snippets_snippet_add_annotation(snippet, 0, 0, 2, 0, ptr::null()),
+            -1
+        );
+        assert_eq!(
+            annotate_snippets_snippet_add_annotation(snippet, 0, 1, 1, 0, ptr::null()),
+            0
+        );
+        assert_eq!(
+            annotate_snippets_snippet_add_footer(snippet, 9, ptr::null()),
+            -1
+        );
+        annotate_snippets_snippet_free(snippet);
+        annotate_snippets_snippet_free(ptr::null_mut());
+    }
+}
+
+#[test]
+fn test_ffi_render_truncates_at_char_boundary() {
+    let label = CString::new("é").unwrap();
+    unsafe {
+        let snippet = annotate_snippets_snippet_new(0, ptr::null(), label.as_ptr());
+        let mut buffer = [1 as c_char; 9];
+        assert_eq!(
+            annotate_snippets_snippet_render(snippet, false, buffer.as_mut_ptr(), buffer.len()),
+            "error: é".len()
+        );
+        assert_eq!(
+            std::ffi::CStr::from_ptr(buffer.as_ptr()).to_str().unwrap(),
+            "error: "
+        );
+        annotate_snippets_snippet_free(snippet);
+    }
+}
@@ -2,7 +2,9 @@ mod diff;
 mod snippet;
 
 use crate::snippet::SnippetDef;
-use annotate_snippets::{display_list::DisplayList, snippet::Snippet};
+use annotate_snippets::{
+    display_list::DisplayList, formatter::stream::write_snippet, snippet::Snippet,
+};
 use glob::glob;
 use std::{error::Error, fs::File, io, io::prelude::*};
 
@@ -25,7 +27,7 @@ fn test_fixtures() {
         let path_in = p.to_str().expect("Can't print path");
         let path_out = path_in.replace(".toml", ".txt");
 
-        let src = read_file(&path_in).expect("Failed to read file");
+        let src = read_file(path_in).expect("Failed to read file");
         let snippet = read_fixture(&src).expect("Failed to read file");
         let expected_out = read_file(&path_out).expect("Failed to read file");
 
@@ -43,3 +45,62 @@ fn test_fixtures() {
         );
     }
 }
+
+#[test]
+fn test_fixtures_streamed() {
+    for entry in glob("./tests/fixtures/no-color/**/*.toml").expect("Failed to read glob pattern") {
+        let p = entry.expect("Error while getting an entry");
+
+        let path_in = p.to_str().expect("Can't print path");
+
+        let src = read_file(path_in).expect("Failed to read file");
+        let expected_out = DisplayList::from(read_fixture(&src).expect("Failed to read file"));
+        let snippet = read_fixture(&src).expect("Failed to read file");
+
+        let mut actual_out = Vec::new();
+        write_snippet(&snippet, &mut actual_out).expect("Failed to write snippet");
+
+        assert_eq!(
+            expected_out.to_string(),
+            String::from_utf8(actual_out).expect("Invalid UTF-8"),
+            "\n\n\nWhile streaming: {}\n",
+            path_in
+        );
+    }
+}
+
+#[test]
+fn test_fixtures_round_trip() {
+    for entry in glob("./tests/fixtures/no-color/**/*.toml").expect("Failed to read glob pattern") {
+        let p = entry.expect("Error while getting an entry");
+
+        let path_in = p.to_str().expect("Can't print path");
+
+        let src = read_file(path_in).expect("Failed to read file");
+        let snippet = read_fixture(&src).expect("Failed to read file");
+        if snippet.opt.footnotes {
+            // The labels replaced by footnotes are not recovered.
+            continue;
+        }
+        let expected_out = DisplayList::from(snippet);
+        let owned = expected_out.to_owned_snippet();
+        let actual_out = DisplayList::from(owned.to_snippet());
+
+        assert_eq!(
+            expected_out.to_string(),
+            actual_out.to_string(),
+            "\n\n\nWhile parsing: {}\nThe diff is:\n\n\n{}\n\n\n",
+            path_in,
+            diff::get_diff(&expected_out.to_string(), &actual_out.to_string())
+        );
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "toml"))]
+#[test]
+fn test_golden_fixtures() {
+    annotate_snippets::testing::check_fixtures(
+        "./tests/fixtures/golden",
+        annotate_snippets::testing::render_plain,
+    );
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "fluent-bundle")]
+
+use annotate_snippets::fluent::{LocalizeError, Localizer};
+use annotate_snippets::snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation};
+use fluent_bundle::{FluentArgs, FluentBundle, FluentResource};
+
+fn localizer(ftl: &str) -> Localizer<FluentResource> {
+    let mut bundle = FluentBundle::default();
+    bundle
+        .add_resource(FluentResource::try_new(ftl.to_string()).unwrap())
+        .unwrap();
+    Localizer::new(bundle)
+}
+
+#[test]
+fn test_localize() {
+    let localizer = localizer(
+        "mismatched-types = types de { $expected } et { $found } incompatibles
+    .label = { $found } trouvé ici
+    .note = attendu en raison de ce type
+",
+    );
+    let snippet = Snippet::new()
+        .title(Annotation::new(AnnotationType::Error, "mismatched-types").id("E0308"))
+        .footer(Annotation::new(
+            AnnotationType::Note,
+            "mismatched-types.note",
+        ))
+        .slice(
+            Slice::new("let x: u8 = \"a\";", 1)
+                .origin("src/main.rs")
+                .annotation(SourceAnnotation::new(
+                    (12, 15),
+                    AnnotationType::Error,
+                    "mismatched-types.label",
+                ))
+                .annotation(SourceAnnotation::new((7, 9), AnnotationType::Info, "")),
+        );
+    let mut args = FluentArgs::new();
+    args.set("expected", "`u8`");
+    args.set("found", "`&str`");
+
+    let owned = localizer.localize(&snippet, Some(&args)).unwrap();
+    let title = owned.title.as_ref().unwrap();
+    assert_eq!(title.id.as_deref(), Some("E0308"));
+    assert_eq!(
+        title.label.as_deref(),
+        Some("types de `u8` et `&str` incompatibles")
+    );
+    assert_eq!(owned.slices[0].annotations[1].label, "");
+
+    let expected = [
+        "error[E0308]: types de `u8` et `&str` incompatibles",
+        " --> src/main.rs:1:13",
+        "  |",
+        "1 | let x: u8 = \"a\";",
+        "  |             ^^^ `&str` trouvé ici",
+        "  |        --",
+        "  |",
+        "  = note: attendu en raison de ce type",
+    ]
+    .join("\n");
+    assert_eq!(localizer.render(&snippet, Some(&args)).unwrap(), expected);
+}
+
+#[test]
+fn test_localize_errors() {
+    let localizer = localizer("only-attributes =\n    .label = a label\nwith-arg = { $name }\n");
+
+    assert!(matches!(
+        localizer.message("unknown", None),
+        Err(LocalizeError::MissingMessage(id)) if id == "unknown"
+    ));
+    assert!(matches!(
+        localizer.message("only-attributes.help", None),
+        Err(LocalizeError::MissingMessage(id)) if id == "only-attributes.help"
+    ));
+    assert!(matches!(
+        localizer.message("only-attributes", None),
+        Err(LocalizeError::MissingValue(_))
+    ));
+    assert_eq!(
+        localizer.message("only-attributes.label", None).unwrap(),
+        "a label"
+    );
+
+    let error = localizer.message("with-arg", None).unwrap_err();
+    assert!(
+        matches!(&error, LocalizeError::Format(id, errors) if id == "with-arg" && !errors.is_empty())
+    );
+
+    let snippet = Snippet::new().title(Annotation::new(AnnotationType::Error, "unknown"));
+    assert!(localizer.render(&snippet, None).is_err());
+}
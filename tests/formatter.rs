@@ -1,11 +1,12 @@
 use annotate_snippets::display_list::*;
 use annotate_snippets::snippet::{self, Snippet};
+use annotate_snippets::stylesheets::theme::Theme;
 
 #[test]
 fn test_source_empty() {
     let dl = DisplayList::from(vec![DisplayLine::Source {
         lineno: None,
-        inline_marks: vec![],
+        inline_marks: InlineMarks::new(),
         line: DisplaySourceLine::Empty,
     }]);
 
@@ -17,17 +18,17 @@ fn test_source_content() {
     let dl = DisplayList::from(vec![
         DisplayLine::Source {
             lineno: Some(56),
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Content {
-                text: "This is an example",
+                text: "This is an example".into(),
                 range: (0, 19),
             },
         },
         DisplayLine::Source {
             lineno: Some(57),
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Content {
-                text: "of content lines",
+                text: "of content lines".into(),
                 range: (0, 19),
             },
         },
@@ -43,19 +44,20 @@ fn test_source_content() {
 fn test_source_annotation_standalone_singleline() {
     let dl = DisplayList::from(vec![DisplayLine::Source {
         lineno: None,
-        inline_marks: vec![],
+        inline_marks: InlineMarks::new(),
         line: DisplaySourceLine::Annotation {
             range: (0, 5),
             annotation: Annotation {
                 annotation_type: DisplayAnnotationType::None,
                 id: None,
                 label: vec![DisplayTextFragment {
-                    content: "Example string",
+                    content: "Example string".into(),
                     style: DisplayTextStyle::Regular,
                 }],
             },
             annotation_type: DisplayAnnotationType::Error,
             annotation_part: DisplayAnnotationPart::Standalone,
+            wide_marks: false,
         },
     }]);
 
@@ -67,36 +69,38 @@ fn test_source_annotation_standalone_multiline() {
     let dl = DisplayList::from(vec![
         DisplayLine::Source {
             lineno: None,
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Annotation {
                 range: (0, 5),
                 annotation: Annotation {
                     annotation_type: DisplayAnnotationType::Help,
                     id: None,
                     label: vec![DisplayTextFragment {
-                        content: "Example string",
+                        content: "Example string".into(),
                         style: DisplayTextStyle::Regular,
                     }],
                 },
                 annotation_type: DisplayAnnotationType::Warning,
                 annotation_part: DisplayAnnotationPart::Standalone,
+                wide_marks: false,
             },
         },
         DisplayLine::Source {
             lineno: None,
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Annotation {
                 range: (0, 5),
                 annotation: Annotation {
                     annotation_type: DisplayAnnotationType::Help,
                     id: None,
                     label: vec![DisplayTextFragment {
-                        content: "Second line",
+                        content: "Second line".into(),
                         style: DisplayTextStyle::Regular,
                     }],
                 },
                 annotation_type: DisplayAnnotationType::Warning,
                 annotation_part: DisplayAnnotationPart::LabelContinuation,
+                wide_marks: false,
             },
         },
     ]);
@@ -112,121 +116,128 @@ fn test_source_annotation_standalone_multi_annotation() {
     let dl = DisplayList::from(vec![
         DisplayLine::Source {
             lineno: None,
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Annotation {
                 range: (0, 5),
                 annotation: Annotation {
                     annotation_type: DisplayAnnotationType::Info,
                     id: None,
                     label: vec![DisplayTextFragment {
-                        content: "Example string",
+                        content: "Example string".into(),
                         style: DisplayTextStyle::Regular,
                     }],
                 },
                 annotation_type: DisplayAnnotationType::Note,
                 annotation_part: DisplayAnnotationPart::Standalone,
+                wide_marks: false,
             },
         },
         DisplayLine::Source {
             lineno: None,
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Annotation {
                 range: (0, 5),
                 annotation: Annotation {
                     annotation_type: DisplayAnnotationType::Info,
                     id: None,
                     label: vec![DisplayTextFragment {
-                        content: "Second line",
+                        content: "Second line".into(),
                         style: DisplayTextStyle::Regular,
                     }],
                 },
                 annotation_type: DisplayAnnotationType::Note,
                 annotation_part: DisplayAnnotationPart::LabelContinuation,
+                wide_marks: false,
             },
         },
         DisplayLine::Source {
             lineno: None,
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Annotation {
                 range: (0, 5),
                 annotation: Annotation {
                     annotation_type: DisplayAnnotationType::Warning,
                     id: None,
                     label: vec![DisplayTextFragment {
-                        content: "This is a note",
+                        content: "This is a note".into(),
                         style: DisplayTextStyle::Regular,
                     }],
                 },
                 annotation_type: DisplayAnnotationType::Note,
                 annotation_part: DisplayAnnotationPart::Consequitive,
+                wide_marks: false,
             },
         },
         DisplayLine::Source {
             lineno: None,
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Annotation {
                 range: (0, 5),
                 annotation: Annotation {
                     annotation_type: DisplayAnnotationType::Warning,
                     id: None,
                     label: vec![DisplayTextFragment {
-                        content: "Second line of the warning",
+                        content: "Second line of the warning".into(),
                         style: DisplayTextStyle::Regular,
                     }],
                 },
                 annotation_type: DisplayAnnotationType::Note,
                 annotation_part: DisplayAnnotationPart::LabelContinuation,
+                wide_marks: false,
             },
         },
         DisplayLine::Source {
             lineno: None,
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Annotation {
                 range: (0, 5),
                 annotation: Annotation {
                     annotation_type: DisplayAnnotationType::Info,
                     id: None,
                     label: vec![DisplayTextFragment {
-                        content: "This is an info",
+                        content: "This is an info".into(),
                         style: DisplayTextStyle::Regular,
                     }],
                 },
                 annotation_type: DisplayAnnotationType::Info,
                 annotation_part: DisplayAnnotationPart::Standalone,
+                wide_marks: false,
             },
         },
         DisplayLine::Source {
             lineno: None,
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Annotation {
                 range: (0, 5),
                 annotation: Annotation {
                     annotation_type: DisplayAnnotationType::Help,
                     id: None,
                     label: vec![DisplayTextFragment {
-                        content: "This is help",
+                        content: "This is help".into(),
                         style: DisplayTextStyle::Regular,
                     }],
                 },
                 annotation_type: DisplayAnnotationType::Help,
                 annotation_part: DisplayAnnotationPart::Standalone,
+                wide_marks: false,
             },
         },
         DisplayLine::Source {
             lineno: None,
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Annotation {
                 range: (0, 0),
                 annotation: Annotation {
                     annotation_type: DisplayAnnotationType::None,
                     id: None,
                     label: vec![DisplayTextFragment {
-                        content: "This is an annotation of type none",
+                        content: "This is an annotation of type none".into(),
                         style: DisplayTextStyle::Regular,
                     }],
                 },
                 annotation_type: DisplayAnnotationType::None,
                 annotation_part: DisplayAnnotationPart::Standalone,
+                wide_marks: false,
             },
         },
     ]);
@@ -239,20 +250,20 @@ fn test_fold_line() {
     let dl = DisplayList::from(vec![
         DisplayLine::Source {
             lineno: Some(5),
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Content {
-                text: "This is line 5",
+                text: "This is line 5".into(),
                 range: (0, 19),
             },
         },
         DisplayLine::Fold {
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
         },
         DisplayLine::Source {
             lineno: Some(10021),
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Content {
-                text: "... and now we're at line 10021",
+                text: "... and now we're at line 10021".into(),
                 range: (0, 19),
             },
         },
@@ -267,8 +278,9 @@ fn test_fold_line() {
 #[test]
 fn test_raw_origin_initial_nopos() {
     let dl = DisplayList::from(vec![DisplayLine::Raw(DisplayRawLine::Origin {
-        path: "src/test.rs",
+        path: "src/test.rs".into(),
         pos: None,
+        offset: None,
         header_type: DisplayHeaderType::Initial,
     })]);
 
@@ -278,8 +290,9 @@ fn test_raw_origin_initial_nopos() {
 #[test]
 fn test_raw_origin_initial_pos() {
     let dl = DisplayList::from(vec![DisplayLine::Raw(DisplayRawLine::Origin {
-        path: "src/test.rs",
+        path: "src/test.rs".into(),
         pos: Some((23, 15)),
+        offset: None,
         header_type: DisplayHeaderType::Initial,
     })]);
 
@@ -289,8 +302,9 @@ fn test_raw_origin_initial_pos() {
 #[test]
 fn test_raw_origin_continuation() {
     let dl = DisplayList::from(vec![DisplayLine::Raw(DisplayRawLine::Origin {
-        path: "src/test.rs",
+        path: "src/test.rs".into(),
         pos: Some((23, 15)),
+        offset: None,
         header_type: DisplayHeaderType::Continuation,
     })]);
 
@@ -304,7 +318,7 @@ fn test_raw_annotation_unaligned() {
             annotation_type: DisplayAnnotationType::Error,
             id: Some("E0001"),
             label: vec![DisplayTextFragment {
-                content: "This is an error",
+                content: "This is an error".into(),
                 style: DisplayTextStyle::Regular,
             }],
         },
@@ -323,7 +337,7 @@ fn test_raw_annotation_unaligned_multiline() {
                 annotation_type: DisplayAnnotationType::Warning,
                 id: Some("E0001"),
                 label: vec![DisplayTextFragment {
-                    content: "This is an error",
+                    content: "This is an error".into(),
                     style: DisplayTextStyle::Regular,
                 }],
             },
@@ -335,7 +349,7 @@ fn test_raw_annotation_unaligned_multiline() {
                 annotation_type: DisplayAnnotationType::Warning,
                 id: Some("E0001"),
                 label: vec![DisplayTextFragment {
-                    content: "Second line of the error",
+                    content: "Second line of the error".into(),
                     style: DisplayTextStyle::Regular,
                 }],
             },
@@ -357,7 +371,7 @@ fn test_raw_annotation_aligned() {
             annotation_type: DisplayAnnotationType::Error,
             id: Some("E0001"),
             label: vec![DisplayTextFragment {
-                content: "This is an error",
+                content: "This is an error".into(),
                 style: DisplayTextStyle::Regular,
             }],
         },
@@ -376,7 +390,7 @@ fn test_raw_annotation_aligned_multiline() {
                 annotation_type: DisplayAnnotationType::Warning,
                 id: Some("E0001"),
                 label: vec![DisplayTextFragment {
-                    content: "This is an error",
+                    content: "This is an error".into(),
                     style: DisplayTextStyle::Regular,
                 }],
             },
@@ -388,7 +402,7 @@ fn test_raw_annotation_aligned_multiline() {
                 annotation_type: DisplayAnnotationType::Warning,
                 id: Some("E0001"),
                 label: vec![DisplayTextFragment {
-                    content: "Second line of the error",
+                    content: "Second line of the error".into(),
                     style: DisplayTextStyle::Regular,
                 }],
             },
@@ -411,7 +425,7 @@ fn test_different_annotation_types() {
                 annotation_type: DisplayAnnotationType::Note,
                 id: None,
                 label: vec![DisplayTextFragment {
-                    content: "This is a note",
+                    content: "This is a note".into(),
                     style: DisplayTextStyle::Regular,
                 }],
             },
@@ -423,7 +437,7 @@ fn test_different_annotation_types() {
                 annotation_type: DisplayAnnotationType::None,
                 id: None,
                 label: vec![DisplayTextFragment {
-                    content: "This is just a string",
+                    content: "This is just a string".into(),
                     style: DisplayTextStyle::Regular,
                 }],
             },
@@ -435,7 +449,7 @@ fn test_different_annotation_types() {
                 annotation_type: DisplayAnnotationType::None,
                 id: None,
                 label: vec![DisplayTextFragment {
-                    content: "Second line of none type annotation",
+                    content: "Second line of none type annotation".into(),
                     style: DisplayTextStyle::Regular,
                 }],
             },
@@ -457,7 +471,8 @@ fn test_inline_marks_empty_line() {
         inline_marks: vec![DisplayMark {
             mark_type: DisplayMarkType::AnnotationThrough,
             annotation_type: DisplayAnnotationType::Error,
-        }],
+        }]
+        .into(),
         line: DisplaySourceLine::Empty,
     }]);
 
@@ -469,30 +484,30 @@ fn test_anon_lines() {
     let mut dl = DisplayList::from(vec![
         DisplayLine::Source {
             lineno: Some(56),
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Content {
-                text: "This is an example",
+                text: "This is an example".into(),
                 range: (0, 19),
             },
         },
         DisplayLine::Source {
             lineno: Some(57),
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Content {
-                text: "of content lines",
+                text: "of content lines".into(),
                 range: (0, 19),
             },
         },
         DisplayLine::Source {
             lineno: None,
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Empty,
         },
         DisplayLine::Source {
             lineno: None,
-            inline_marks: vec![],
+            inline_marks: InlineMarks::new(),
             line: DisplaySourceLine::Content {
-                text: "abc",
+                text: "abc".into(),
                 range: (0, 19),
             },
         },
@@ -508,8 +523,9 @@ fn test_anon_lines() {
 #[test]
 fn test_raw_origin_initial_pos_anon_lines() {
     let mut dl = DisplayList::from(vec![DisplayLine::Raw(DisplayRawLine::Origin {
-        path: "src/test.rs",
+        path: "src/test.rs".into(),
         pos: Some((23, 15)),
+        offset: None,
         header_type: DisplayHeaderType::Initial,
     })]);
 
@@ -520,26 +536,23 @@ fn test_raw_origin_initial_pos_anon_lines() {
 
 #[test]
 fn test_i_29() {
-    let snippets = Snippet {
-        title: Some(snippet::Annotation {
+    let snippets = Snippet::new()
+        .title(snippet::Annotation {
             id: None,
             label: Some("oops"),
             annotation_type: snippet::AnnotationType::Error,
-        }),
-        footer: vec![],
-        slices: vec![snippet::Slice {
-            source: "First line\r\nSecond oops line",
-            line_start: 1,
-            origin: Some("<current file>"),
-            annotations: vec![snippet::SourceAnnotation {
-                range: (19, 23),
-                label: "oops",
-                annotation_type: snippet::AnnotationType::Error,
-            }],
-            fold: true,
-        }],
-        opt: Default::default(),
-    };
+        })
+        .slice(
+            snippet::Slice::new("First line\r\nSecond oops line", 1)
+                .origin("<current file>")
+                .annotation(snippet::SourceAnnotation {
+                    range: (19, 23),
+                    label: "oops",
+                    annotation_type: snippet::AnnotationType::Error,
+                    ..Default::default()
+                })
+                .fold(true),
+        );
     let expected = r#"error: oops
  --> <current file>:2:8
   |
@@ -553,22 +566,16 @@ fn test_i_29() {
 
 #[test]
 fn test_point_to_double_width_characters() {
-    let snippets = Snippet {
-        slices: vec![snippet::Slice {
-            source: "こんにちは、世界",
-            line_start: 1,
-            origin: Some("<current file>"),
-            annotations: vec![snippet::SourceAnnotation {
+    let snippets = Snippet::new().slice(
+        snippet::Slice::new("こんにちは、世界", 1)
+            .origin("<current file>")
+            .annotation(snippet::SourceAnnotation {
                 range: (6, 8),
                 label: "world",
                 annotation_type: snippet::AnnotationType::Error,
-            }],
-            fold: false,
-        }],
-        title: None,
-        footer: vec![],
-        opt: Default::default(),
-    };
+                ..Default::default()
+            }),
+    );
 
     let expected = r#" --> <current file>:1:7
   |
@@ -581,22 +588,16 @@ fn test_point_to_double_width_characters() {
 
 #[test]
 fn test_point_to_double_width_characters_across_lines() {
-    let snippets = Snippet {
-        slices: vec![snippet::Slice {
-            source: "おはよう\nございます",
-            line_start: 1,
-            origin: Some("<current file>"),
-            annotations: vec![snippet::SourceAnnotation {
+    let snippets = Snippet::new().slice(
+        snippet::Slice::new("おはよう\nございます", 1)
+            .origin("<current file>")
+            .annotation(snippet::SourceAnnotation {
                 range: (2, 8),
                 label: "Good morning",
                 annotation_type: snippet::AnnotationType::Error,
-            }],
-            fold: false,
-        }],
-        title: None,
-        footer: vec![],
-        opt: Default::default(),
-    };
+                ..Default::default()
+            }),
+    );
 
     let expected = r#" --> <current file>:1:3
   |
@@ -611,29 +612,22 @@ fn test_point_to_double_width_characters_across_lines() {
 
 #[test]
 fn test_point_to_double_width_characters_multiple() {
-    let snippets = Snippet {
-        slices: vec![snippet::Slice {
-            source: "お寿司\n食べたい🍣",
-            line_start: 1,
-            origin: Some("<current file>"),
-            annotations: vec![
-                snippet::SourceAnnotation {
-                    range: (0, 3),
-                    label: "Sushi1",
-                    annotation_type: snippet::AnnotationType::Error,
-                },
-                snippet::SourceAnnotation {
-                    range: (6, 8),
-                    label: "Sushi2",
-                    annotation_type: snippet::AnnotationType::Note,
-                },
-            ],
-            fold: false,
-        }],
-        title: None,
-        footer: vec![],
-        opt: Default::default(),
-    };
+    let snippets = Snippet::new().slice(
+        snippet::Slice::new("お寿司\n食べたい🍣", 1)
+            .origin("<current file>")
+            .annotation(snippet::SourceAnnotation {
+                range: (0, 3),
+                label: "Sushi1",
+                annotation_type: snippet::AnnotationType::Error,
+                ..Default::default()
+            })
+            .annotation(snippet::SourceAnnotation {
+                range: (6, 8),
+                label: "Sushi2",
+                annotation_type: snippet::AnnotationType::Note,
+                ..Default::default()
+            }),
+    );
 
     let expected = r#" --> <current file>:1:1
   |
@@ -648,22 +642,16 @@ fn test_point_to_double_width_characters_multiple() {
 
 #[test]
 fn test_point_to_double_width_characters_mixed() {
-    let snippets = Snippet {
-        slices: vec![snippet::Slice {
-            source: "こんにちは、新しいWorld！",
-            line_start: 1,
-            origin: Some("<current file>"),
-            annotations: vec![snippet::SourceAnnotation {
+    let snippets = Snippet::new().slice(
+        snippet::Slice::new("こんにちは、新しいWorld！", 1)
+            .origin("<current file>")
+            .annotation(snippet::SourceAnnotation {
                 range: (6, 14),
                 label: "New world",
                 annotation_type: snippet::AnnotationType::Error,
-            }],
-            fold: false,
-        }],
-        title: None,
-        footer: vec![],
-        opt: Default::default(),
-    };
+                ..Default::default()
+            }),
+    );
 
     let expected = r#" --> <current file>:1:7
   |
@@ -673,3 +661,1884 @@ fn test_point_to_double_width_characters_mixed() {
 
     assert_eq!(DisplayList::from(snippets).to_string(), expected);
 }
+
+#[test]
+fn test_severity_icons() {
+    let snippet = |icons: Option<SeverityIcons>| {
+        Snippet::new()
+            .title(snippet::Annotation {
+                id: Some("E0425"),
+                label: Some("cannot find value `x`"),
+                annotation_type: snippet::AnnotationType::Error,
+            })
+            .footer(snippet::Annotation {
+                id: None,
+                label: Some("see the docs"),
+                annotation_type: snippet::AnnotationType::Note,
+            })
+            .opt(icons.map_or_else(FormatOptions::new, |icons| {
+                FormatOptions::new().icons(icons)
+            }))
+    };
+
+    assert_eq!(
+        DisplayList::from(snippet(None)).to_string(),
+        "error[E0425]: cannot find value `x`\n = note: see the docs"
+    );
+    assert_eq!(
+        DisplayList::from(snippet(Some(SeverityIcons::default()))).to_string(),
+        "✖ error[E0425]: cannot find value `x`\n = note: see the docs"
+    );
+    let icons = SeverityIcons {
+        error: "[E]".into(),
+        ..Default::default()
+    };
+    assert_eq!(
+        DisplayList::from(snippet(Some(icons))).to_string(),
+        "[E] error[E0425]: cannot find value `x`\n = note: see the docs"
+    );
+}
+
+#[test]
+fn test_translations() {
+    let source = "a\nb\nc\nd\ne\nf\ng\n";
+    let snippet = |translations: Option<Translations>| {
+        Snippet::new()
+            .title(snippet::Annotation {
+                id: Some("E0425"),
+                label: Some("valeur introuvable"),
+                annotation_type: snippet::AnnotationType::Error,
+            })
+            .footer(snippet::Annotation {
+                id: None,
+                label: Some("voir la\ndocumentation"),
+                annotation_type: snippet::AnnotationType::Note,
+            })
+            .slice(
+                snippet::Slice::new(source, 1)
+                    .annotation(snippet::SourceAnnotation {
+                        range: (0, 1),
+                        label: "ici",
+                        annotation_type: snippet::AnnotationType::Error,
+                        ..Default::default()
+                    })
+                    .annotation(snippet::SourceAnnotation {
+                        range: (12, 13),
+                        label: "essayer `x`",
+                        annotation_type: snippet::AnnotationType::Help,
+                        ..Default::default()
+                    })
+                    .fold(true),
+            )
+            .opt(
+                translations.map_or_else(FormatOptions::new, |translations| {
+                    FormatOptions::new().translations(translations)
+                }),
+            )
+    };
+
+    let english = DisplayList::from(snippet(None)).to_string();
+    assert_eq!(
+        DisplayList::from(snippet(Some(Translations::default()))).to_string(),
+        english
+    );
+    let translations = Translations {
+        error: "erreur".into(),
+        note: "remarque".into(),
+        help: "aide".into(),
+        fold: "…".into(),
+        ..Default::default()
+    };
+    let expected = r#"erreur[E0425]: valeur introuvable
+  |
+1 | a
+  | ^ ici
+…
+7 | g
+  | - aide: essayer `x`
+  |
+  = remarque: voir la
+              documentation"#;
+    assert_eq!(
+        DisplayList::from(snippet(Some(translations))).to_string(),
+        expected
+    );
+}
+
+#[test]
+fn test_write_snippet_folded() {
+    let source = (1..=20)
+        .map(|i| format!("line {}", i))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let snippet = || {
+        Snippet::new().slice(
+            snippet::Slice::new(&source, 1)
+                .origin("file.txt")
+                .annotation(snippet::SourceAnnotation {
+                    range: (0, 4),
+                    label: "first",
+                    annotation_type: snippet::AnnotationType::Info,
+                    ..Default::default()
+                })
+                .annotation(snippet::SourceAnnotation {
+                    range: (source.len() - 7, source.len()),
+                    label: "last",
+                    annotation_type: snippet::AnnotationType::Error,
+                    ..Default::default()
+                })
+                .fold(true),
+        )
+    };
+
+    let mut out = Vec::new();
+    annotate_snippets::formatter::stream::write_snippet(&snippet(), &mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        DisplayList::from(snippet()).to_string()
+    );
+}
+
+#[test]
+fn test_annotations_in_slice_order() {
+    let snippet = Snippet::new().slice(
+        snippet::Slice::new("foo bar\nbaz", 1)
+            .annotation(snippet::SourceAnnotation {
+                range: (8, 11),
+                label: "third",
+                annotation_type: snippet::AnnotationType::Error,
+                ..Default::default()
+            })
+            .annotation(snippet::SourceAnnotation {
+                range: (4, 7),
+                label: "second",
+                annotation_type: snippet::AnnotationType::Error,
+                ..Default::default()
+            })
+            .annotation(snippet::SourceAnnotation {
+                range: (0, 3),
+                label: "first",
+                annotation_type: snippet::AnnotationType::Warning,
+                ..Default::default()
+            }),
+    );
+
+    let expected = [
+        "  |",
+        "1 | foo bar",
+        "  |     ^^^ second",
+        "  | --- first",
+        "2 | baz",
+        "  | ^^^ third",
+        "  |",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet).to_string(), expected);
+}
+
+#[test]
+fn test_renderer_reuse() {
+    let source = "let x = 1;\nlet y = 2;";
+    let snippet = |label| {
+        Snippet::new()
+            .title(snippet::Annotation {
+                id: None,
+                label: Some(label),
+                annotation_type: snippet::AnnotationType::Error,
+            })
+            .slice(
+                snippet::Slice::new(source, 9)
+                    .origin("src/main.rs")
+                    .annotation(snippet::SourceAnnotation {
+                        range: (15, 16),
+                        label,
+                        annotation_type: snippet::AnnotationType::Error,
+                        ..Default::default()
+                    }),
+            )
+    };
+
+    let mut renderer = annotate_snippets::formatter::renderer::Renderer::new();
+    for label in &["first", "second"] {
+        assert_eq!(
+            renderer.render(&snippet(label)),
+            DisplayList::from(snippet(label)).to_string()
+        );
+    }
+}
+
+#[test]
+fn test_renderer_passes() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation {
+            id: None,
+            label: Some("oops"),
+            annotation_type: snippet::AnnotationType::Error,
+        })
+        .slice(snippet::Slice::new("let x = 1;", 9));
+
+    // Passes run in the order they are added.
+    let mut renderer = annotate_snippets::formatter::renderer::Renderer::new()
+        .pass(|dl: &mut DisplayList<'_>| {
+            dl.body
+                .retain(|line| matches!(line, DisplayLine::Source { .. }))
+        })
+        .pass(|dl: &mut DisplayList<'_>| dl.anonymized_line_numbers = true);
+    assert_eq!(renderer.render(&snippet), "   |\nLL | let x = 1;\n   |");
+}
+
+#[test]
+fn test_render_batch_order() {
+    let labels = (0..64).map(|i| format!("error {}", i)).collect::<Vec<_>>();
+    let snippet = |label| {
+        Snippet::new().title(snippet::Annotation {
+            id: None,
+            label: Some(label),
+            annotation_type: snippet::AnnotationType::Error,
+        })
+    };
+    let snippets = labels.iter().map(|l| snippet(l)).collect::<Vec<_>>();
+
+    let rendered = annotate_snippets::formatter::renderer::render_batch(&snippets);
+    let expected = labels
+        .iter()
+        .map(|label| DisplayList::from(snippet(label)).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn test_expansion_frames() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation {
+            id: None,
+            label: Some("mismatched types"),
+            annotation_type: snippet::AnnotationType::Error,
+        })
+        .slice(
+            snippet::Slice::new("    bad!();", 7)
+                .origin("src/main.rs")
+                .annotation(snippet::SourceAnnotation {
+                    range: (4, 10),
+                    label: "in this macro invocation",
+                    annotation_type: snippet::AnnotationType::Info,
+                    ..Default::default()
+                }),
+        )
+        .expansion(snippet::ExpansionFrame {
+            label: "in this expansion of `bad!`",
+            slice: snippet::Slice::new("    () => { 1 + \"\" };", 2)
+                .origin("src/macros.rs")
+                .annotation(snippet::SourceAnnotation {
+                    range: (16, 18),
+                    label: "expected integer",
+                    annotation_type: snippet::AnnotationType::Error,
+                    ..Default::default()
+                }),
+        });
+
+    let expected = [
+        "error: mismatched types",
+        " --> src/main.rs:7:5",
+        "  |",
+        "7 |     bad!();",
+        "  |     ------ info: in this macro invocation",
+        "  |",
+        "note: in this expansion of `bad!`",
+        " --> src/macros.rs:2:17",
+        "  |",
+        "2 |     () => { 1 + \"\" };",
+        "  |                 ^^ expected integer",
+        "  |",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet).to_string(), expected);
+}
+
+#[test]
+fn test_include_frames() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation {
+            id: None,
+            label: Some("unknown type name"),
+            annotation_type: snippet::AnnotationType::Error,
+        })
+        .include(snippet::IncludeFrame {
+            path: "src/util.h",
+            line: 3,
+        })
+        .include(snippet::IncludeFrame {
+            path: "src/main.c",
+            line: 1,
+        });
+
+    let expected = [
+        "note: included from src/util.h:3",
+        "  note: included from src/main.c:1",
+        "error: unknown type name",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet.clone()).to_string(), expected);
+
+    let translations = Translations {
+        note: "remarque".into(),
+        included_from: "inclus depuis {path}, ligne {line}".into(),
+        ..Default::default()
+    };
+    let snippet = snippet.opt(FormatOptions::new().translations(translations));
+    assert!(DisplayList::from(snippet)
+        .to_string()
+        .starts_with("remarque: inclus depuis src/util.h, ligne 3\n"));
+}
+
+#[test]
+fn test_format_and_render_to() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation {
+            id: None,
+            label: Some("unused variable"),
+            annotation_type: snippet::AnnotationType::Warning,
+        })
+        .slice(
+            snippet::Slice::new("let x = 1;", 3)
+                .origin("src/lib.rs")
+                .annotation(snippet::SourceAnnotation {
+                    range: (4, 5),
+                    label: "never read",
+                    annotation_type: snippet::AnnotationType::Warning,
+                    ..Default::default()
+                }),
+        );
+    let opt = FormatOptions::new().anonymized_line_numbers(true);
+    let expected = [
+        "warning: unused variable",
+        "  --> src/lib.rs:3:5",
+        "   |",
+        "LL | let x = 1;",
+        "   |     - never read",
+        "   |",
+    ]
+    .join("\n");
+    assert_eq!(annotate_snippets::format(&snippet, &opt), expected);
+
+    let mut out = vec![];
+    annotate_snippets::render_to(&snippet, &opt, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+}
+
+#[test]
+fn test_display_list_editing() {
+    let mut dl = DisplayList::from(Snippet::new().title(snippet::Annotation {
+        id: None,
+        label: Some("unused import"),
+        annotation_type: snippet::AnnotationType::Warning,
+    }));
+    dl.insert(
+        0,
+        DisplayLine::annotation(DisplayAnnotationType::Info, "in crate `app`"),
+    )
+    .push(DisplayLine::empty())
+    .extend(vec![DisplayLine::annotation(
+        DisplayAnnotationType::Help,
+        "remove it",
+    )]);
+
+    assert_eq!(dl.len(), 4);
+    assert_eq!(
+        dl.iter()
+            .filter(|line| **line == DisplayLine::empty())
+            .count(),
+        1
+    );
+    let expected = [
+        " = info: in crate `app`",
+        "warning: unused import",
+        " |",
+        " = help: remove it",
+    ]
+    .join("\n");
+    assert_eq!(dl.to_string(), expected);
+
+    let dl = dl.into_iter().skip(1).collect::<DisplayList<'_>>();
+    assert_eq!(dl.to_string(), expected.split_once('\n').unwrap().1);
+    assert!(DisplayList::new().is_empty());
+}
+
+#[test]
+fn test_rendered_lines() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation {
+            id: None,
+            label: Some("mismatched types"),
+            annotation_type: snippet::AnnotationType::Error,
+        })
+        .slice(
+            snippet::Slice::new("let x: u8 = \"a\";\nlet y = x;", 9)
+                .origin("src/main.rs")
+                .annotation(snippet::SourceAnnotation {
+                    range: (12, 15),
+                    label: "expected `u8`",
+                    annotation_type: snippet::AnnotationType::Error,
+                    ..Default::default()
+                }),
+        );
+    let dl = DisplayList::from(snippet);
+    let lines = dl.rendered_lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            "error: mismatched types",
+            "  --> src/main.rs:9:13",
+            "   |",
+            " 9 | let x: u8 = \"a\";",
+            "   |             ^^^ expected `u8`",
+            "10 | let y = x;",
+            "   |",
+        ]
+    );
+    assert_eq!(lines.join("\n"), dl.to_string());
+}
+
+#[test]
+fn test_format_options_builder() {
+    let margin = Margin::new(0, 4, 8, 20, 40, 60);
+    let markers = MarkerSet {
+        error: '~',
+        ..Default::default()
+    };
+    let theme = Theme::parse("error=bold").unwrap();
+    let opt = FormatOptions::new()
+        .color(true)
+        .anonymized_line_numbers(true)
+        .margin(margin)
+        .icons(SeverityIcons::default())
+        .indent(2)
+        .translations(Translations::default())
+        .footnotes(true)
+        .bidi(BidiMode::Isolate)
+        .escape(EscapeMode::Unicode)
+        .column_unit(ColumnUnit::Utf16)
+        .header_position(HeaderPosition::Offset)
+        .header_placement(HeaderPlacement::Below)
+        .full_width_marks(true)
+        .tab_width(4)
+        .max_width(80)
+        .assert_max_width(true)
+        .remap_path_prefix("/build", "/src")
+        .base_dir("/src")
+        .max_body_lines(10)
+        .compact_annotations(true)
+        .vertical_compact(true)
+        .max_label_length(40)
+        .screen_reader(true)
+        .column_ruler(true)
+        .markers(markers)
+        .theme(theme.clone())
+        .fold(true);
+    let mut expected = FormatOptions::new();
+    expected.color = true;
+    expected.anonymized_line_numbers = true;
+    expected.margin = Some(margin);
+    expected.icons = Some(SeverityIcons::default());
+    expected.indent = 2;
+    expected.translations = Some(Translations::default());
+    expected.footnotes = true;
+    expected.bidi = BidiMode::Isolate;
+    expected.escape = EscapeMode::Unicode;
+    expected.column_unit = ColumnUnit::Utf16;
+    expected.header_position = HeaderPosition::Offset;
+    expected.header_placement = HeaderPlacement::Below;
+    expected.full_width_marks = true;
+    expected.tab_width = Some(4);
+    expected.max_width = Some(80);
+    expected.assert_max_width = true;
+    expected.remap_path_prefix = vec![("/build".to_string(), "/src".to_string())];
+    expected.base_dir = Some("/src".into());
+    expected.max_body_lines = Some(10);
+    expected.compact_annotations = true;
+    expected.vertical_compact = true;
+    expected.max_label_length = Some(40);
+    expected.screen_reader = true;
+    expected.column_ruler = true;
+    expected.markers = markers;
+    expected.theme = Some(theme);
+    expected.fold = true;
+    assert_eq!(opt, expected);
+    assert_eq!(FormatOptions::new(), FormatOptions::default());
+}
+
+#[test]
+fn test_insert_raw() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Error,
+            "invalid key",
+        ))
+        .slice(
+            snippet::Slice::new("[package]\nnmae = \"x\"\nversion = \"1\"", 1)
+                .origin("Cargo.toml")
+                .annotation(snippet::SourceAnnotation::new(
+                    (10, 14),
+                    snippet::AnnotationType::Error,
+                    "unknown",
+                )),
+        )
+        .footer(snippet::Annotation::new(
+            snippet::AnnotationType::Help,
+            "did you mean `name`?",
+        ));
+    let note = |label| DisplayRawLine::Annotation {
+        annotation: Annotation {
+            annotation_type: DisplayAnnotationType::Note,
+            id: None,
+            label: vec![DisplayTextFragment {
+                content: std::borrow::Cow::Borrowed(label),
+                style: DisplayTextStyle::Regular,
+            }],
+        },
+        source_aligned: true,
+        continuation: false,
+    };
+    let mut dl = DisplayList::from(snippet);
+    assert!(dl.insert_raw(InsertPoint::BeforeHeader, note("in workspace member `x`")));
+    assert!(dl.insert_raw(
+        InsertPoint::AfterSourceLine(2),
+        note("set by `.cargo/config`")
+    ));
+    assert!(dl.insert_raw(InsertPoint::AfterBody, note("manifest format 1")));
+    assert!(!dl.insert_raw(InsertPoint::AfterSourceLine(9), note("missing")));
+
+    let expected = [
+        "error: invalid key",
+        "  = note: in workspace member `x`",
+        " --> Cargo.toml:2:1",
+        "  |",
+        "1 | [package]",
+        "2 | nmae = \"x\"",
+        "  | ^^^^ unknown",
+        "  = note: set by `.cargo/config`",
+        "3 | version = \"1\"",
+        "  |",
+        "  = note: manifest format 1",
+        "  = help: did you mean `name`?",
+    ]
+    .join("\n");
+    assert_eq!(dl.to_string(), expected);
+
+    let mut dl = DisplayList::new();
+    assert!(!dl.insert_raw(InsertPoint::AfterBody, note("missing")));
+}
+
+#[test]
+fn test_bidi_mode() {
+    let render = |bidi| {
+        let snippet = Snippet::new()
+            .slice(
+                snippet::Slice::new("let a = \"\u{202E}x\u{2066}\";", 1).annotation(
+                    snippet::SourceAnnotation::new((0, 3), snippet::AnnotationType::Error, "here"),
+                ),
+            )
+            .opt(FormatOptions::new().bidi(bidi));
+        DisplayList::from(snippet).to_string()
+    };
+    assert_eq!(
+        render(BidiMode::Preserve),
+        "  |\n1 | let a = \"\u{202E}x\u{2066}\";\n  | ^^^ here\n  |"
+    );
+    assert_eq!(
+        render(BidiMode::Strip),
+        "  |\n1 | let a = \"x\";\n  | ^^^ here\n  |"
+    );
+    assert_eq!(
+        render(BidiMode::Isolate),
+        "  |\n1 | \u{2068}let a = \"\u{202E}x\u{2066}\";\u{2069}\n  | ^^^ here\n  |"
+    );
+}
+
+#[test]
+fn test_escape_mode() {
+    let render = |escape| {
+        let snippet = Snippet::new()
+            .slice(
+                snippet::Slice::new("a\u{1b}[0m\u{200B}b = 1", 1).annotation(
+                    snippet::SourceAnnotation::new((6, 7), snippet::AnnotationType::Error, "b"),
+                ),
+            )
+            .opt(FormatOptions::new().escape(escape));
+        DisplayList::from(snippet).to_string()
+    };
+    assert_eq!(
+        render(EscapeMode::PassThrough),
+        "  |\n1 | a\u{1b}[0m\u{200B}b = 1\n  |     ^ b\n  |"
+    );
+    assert_eq!(
+        render(EscapeMode::Unicode),
+        "  |\n1 | a\\u{1b}[0m\\u{200b}b = 1\n  |                   ^ b\n  |"
+    );
+    assert_eq!(
+        render(EscapeMode::Replacement),
+        "  |\n1 | a\u{FFFD}[0m\u{FFFD}b = 1\n  |       ^ b\n  |"
+    );
+}
+
+#[test]
+fn test_column_unit() {
+    let header = |column_unit| {
+        let snippet = Snippet::new()
+            .slice(snippet::Slice::new("é😀 = x", 1).origin("a.rs").annotation(
+                snippet::SourceAnnotation::new((5, 6), snippet::AnnotationType::Error, "x"),
+            ))
+            .opt(FormatOptions::new().column_unit(column_unit));
+        DisplayList::from(snippet).rendered_lines().next().unwrap()
+    };
+    assert_eq!(header(ColumnUnit::Chars), " --> a.rs:1:6");
+    assert_eq!(header(ColumnUnit::Bytes), " --> a.rs:1:10");
+    assert_eq!(header(ColumnUnit::Utf16), " --> a.rs:1:7");
+    assert_eq!(header(ColumnUnit::Cells), " --> a.rs:1:7");
+}
+
+#[test]
+fn test_header_position() {
+    let snippet = Snippet::new()
+        .slice(
+            snippet::Slice::new("magic: é\nlength: 0xFFFF", 1)
+                .origin("file.bin")
+                .annotation(snippet::SourceAnnotation::new(
+                    (17, 23),
+                    snippet::AnnotationType::Error,
+                    "too long",
+                )),
+        )
+        .opt(FormatOptions::new().header_position(HeaderPosition::Offset));
+    let expected = [
+        " --> file.bin:+0x12",
+        "  |",
+        "1 | magic: é",
+        "2 | length: 0xFFFF",
+        "  |         ^^^^^^ too long",
+        "  |",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet.clone()).to_string(), expected);
+
+    let renderer = &mut annotate_snippets::formatter::renderer::Renderer::new();
+    assert_eq!(renderer.render(&snippet), expected);
+}
+
+#[test]
+fn test_max_width() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Error,
+            "the trait bound `Foo: Display` is not satisfied",
+        ))
+        .slice(
+            snippet::Slice::new("println!(\"{}\", Foo { field: 1, other: 2 });", 12)
+                .origin("src/main.rs")
+                .annotation(snippet::SourceAnnotation::new(
+                    (15, 41),
+                    snippet::AnnotationType::Error,
+                    "`Foo` cannot be formatted",
+                )),
+        )
+        .footer(snippet::Annotation::new(
+            snippet::AnnotationType::Help,
+            "the trait `Display` is not implemented for `Foo`",
+        ))
+        .opt(FormatOptions::new().max_width(30));
+    let expected = [
+        "error: the trait bound `Foo:",
+        "       Display` is not",
+        "       satisfied",
+        "  --> src/main.rs:12:16",
+        "   |",
+        "12 | ...\", Foo { field: 1, ...",
+        "   |       ^^^^^^^^^^^^^^^^...",
+        "   | `Foo` cannot be formatted",
+        "   |",
+        "   = help: the trait `Display`",
+        "           is not implemented",
+        "           for `Foo`",
+    ]
+    .join("\n");
+    let rendered = DisplayList::from(snippet).to_string();
+    assert_eq!(rendered, expected);
+    assert!(rendered.contains("`Foo` cannot be formatted"));
+    assert!(rendered.lines().all(|line| line.chars().count() <= 30));
+}
+
+#[test]
+fn test_max_width_wraps_labels() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Error,
+            "mismatched types",
+        ))
+        .slice(
+            snippet::Slice::new(
+                "    let total: u32 = items.iter().map(|item| item.weight).sum::<u64>();",
+                7,
+            )
+            .origin("crates/inventory/src/warehouse.rs")
+            .annotation(snippet::SourceAnnotation::new(
+                (54, 69),
+                snippet::AnnotationType::Error,
+                "expected `u32`, found `u64`",
+            )),
+        )
+        .opt(FormatOptions::new().max_width(32));
+    let expected = [
+        "error: mismatched types",
+        " --> crates/inventory/src/wareho",
+        "     use.rs:7:55",
+        "  |",
+        "7 | ...eight).sum::<u64>();",
+        "  |       ^^^^^^^^^^^^^^^",
+        "  | expected `u32`, found `u64`",
+        "  |",
+    ]
+    .join("\n");
+    let rendered = DisplayList::from(snippet).to_string();
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn test_max_width_unbreakable() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Note,
+            "abcdefghijklmnop",
+        ))
+        .opt(FormatOptions::new().max_width(8));
+    assert_eq!(
+        DisplayList::from(snippet).to_string(),
+        "note:\nabcdefgh\nijklmnop"
+    );
+}
+
+#[cfg(feature = "color")]
+#[test]
+fn test_max_width_colored() {
+    use annotate_snippets::testing::strip_ansi;
+
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Error,
+            "mismatched types",
+        ))
+        .slice(
+            snippet::Slice::new("let x: u8 = \"a long string\";", 1).annotation(
+                snippet::SourceAnnotation::new(
+                    (12, 27),
+                    snippet::AnnotationType::Error,
+                    "expected `u8`",
+                ),
+            ),
+        )
+        .opt(FormatOptions::new().color(true).max_width(16));
+    let rendered = DisplayList::from(snippet).to_string();
+    assert!(rendered.contains('\x1b'));
+    let plain = strip_ansi(&rendered);
+    assert!(
+        plain.lines().all(|line| line.chars().count() <= 16),
+        "{}",
+        plain
+    );
+    assert!(plain.contains("1 | ... = \"a ..."));
+    assert!(plain.contains("  | expected\n  | `u8`"));
+    // The styles opened before the cut are closed.
+    assert!(rendered.ends_with("\x1b[0m"));
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "more than the maximum of 10")]
+fn test_assert_max_width() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Error,
+            "a rather long title",
+        ))
+        .opt(FormatOptions::new().max_width(10).assert_max_width(true));
+    let _ = DisplayList::from(snippet).to_string();
+}
+
+#[test]
+fn test_path_normalizer() {
+    let mut snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Error,
+            "mismatched types",
+        ))
+        .slice(
+            snippet::Slice::new("let x: u32 = \"\";", 4)
+                .origin("/home/alice/project/src/main.rs")
+                .annotation(snippet::SourceAnnotation::new(
+                    (13, 15),
+                    snippet::AnnotationType::Error,
+                    "expected `u32`",
+                )),
+        )
+        .opt(FormatOptions::new().path_normalizer(|path| {
+            path.strip_prefix("/home/alice/project/")
+                .unwrap_or(path)
+                .to_string()
+        }));
+    snippet.includes.push(snippet::IncludeFrame {
+        path: "/home/alice/project/src/lib.rs",
+        line: 3,
+    });
+    let rendered = DisplayList::from(snippet).to_string();
+    assert!(
+        rendered.contains("included from src/lib.rs:3"),
+        "{}",
+        rendered
+    );
+    assert!(rendered.contains(" --> src/main.rs:4:14"), "{}", rendered);
+    assert!(!rendered.contains("alice"));
+}
+
+#[test]
+fn test_remap_path_prefix() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Error,
+            "mismatched types",
+        ))
+        .slice(
+            snippet::Slice::new("let x: u32 = \"\";", 4)
+                .origin("/tmp/sandbox-42/crates/core/src/main.rs")
+                .annotation(snippet::SourceAnnotation::new(
+                    (13, 15),
+                    snippet::AnnotationType::Error,
+                    "expected `u32`",
+                )),
+        )
+        .slice(snippet::Slice::new("fn f() {}", 1).origin("/tmp/sandbox-42/crates/core.rs"))
+        .opt(
+            FormatOptions::new()
+                .remap_path_prefix("/tmp/sandbox-42", "/workspace")
+                .remap_path_prefix("/tmp/sandbox-42/crates/core", "core")
+                .path_normalizer(|path| path.replace("core", "CORE")),
+        );
+    let rendered = DisplayList::from(snippet).to_string();
+    assert!(
+        rendered.contains(" --> CORE/src/main.rs:4:14"),
+        "{}",
+        rendered
+    );
+    assert!(
+        rendered.contains(" ::: /workspace/crates/CORE.rs"),
+        "{}",
+        rendered
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_base_dir() {
+    let opt = FormatOptions::new().base_dir("/repo/crates/app");
+    assert_eq!(
+        opt.normalize_path("/repo/crates/app/src/main.rs"),
+        "src/main.rs"
+    );
+    assert_eq!(
+        opt.normalize_path("/repo/crates/core/src/lib.rs"),
+        "../core/src/lib.rs"
+    );
+    assert_eq!(
+        opt.normalize_path("/repo/crates/app/./src/../build.rs"),
+        "build.rs"
+    );
+    assert_eq!(opt.normalize_path("/repo/crates/app"), ".");
+    assert_eq!(
+        opt.normalize_path("/usr/lib/rustlib/src/lib.rs"),
+        "/usr/lib/rustlib/src/lib.rs"
+    );
+    assert_eq!(opt.normalize_path("src/main.rs"), "src/main.rs");
+
+    let opt = opt.remap_path_prefix("/sandbox/1234", "/repo");
+    assert_eq!(
+        opt.normalize_path("/sandbox/1234/crates/app/src/main.rs"),
+        "src/main.rs"
+    );
+}
+
+#[test]
+fn test_render_cache() {
+    use annotate_snippets::formatter::cache::{self, RenderCache};
+
+    let snippet = |source, label| {
+        Snippet::new()
+            .title(snippet::Annotation::new(
+                snippet::AnnotationType::Error,
+                "mismatched types",
+            ))
+            .slice(
+                snippet::Slice::new(source, 1)
+                    .origin("src/main.rs")
+                    .annotation(snippet::SourceAnnotation::new(
+                        (13, 15),
+                        snippet::AnnotationType::Error,
+                        label,
+                    )),
+            )
+    };
+    let before = snippet("let x: u32 = \"\";", "expected `u32`");
+    let after = snippet("let y: u32 = \"\";", "expected `u32`");
+    assert_eq!(cache::key(&before), cache::key(&after));
+    assert_ne!(
+        cache::key(&before),
+        cache::key(&snippet("let x: u32 = \"\";", "expected `u64`"))
+    );
+    assert_ne!(
+        cache::key(&before),
+        cache::key(&before.clone().opt(FormatOptions::new().color(true)))
+    );
+    // A slice without an origin can't be invalidated, its source is hashed.
+    assert_ne!(
+        cache::key(&Snippet::new().slice(snippet::Slice::new("let x;", 1))),
+        cache::key(&Snippet::new().slice(snippet::Slice::new("let y;", 1)))
+    );
+
+    let mut cache = RenderCache::new();
+    let rendered = cache.render(&before).to_string();
+    assert!(rendered.contains("let x"));
+    // The source is not part of the key, the changed file is invalidated.
+    assert_eq!(cache.render(&after), rendered);
+    cache.invalidate_origin("src/lib.rs");
+    assert!(cache.contains(&after));
+    cache.invalidate_origin("src/main.rs");
+    assert!(!cache.contains(&after));
+    assert!(cache.render(&after).contains("let y"));
+
+    cache.invalidate(&after);
+    assert!(cache.is_empty());
+    let unnamed = |source| Snippet::new().slice(snippet::Slice::new(source, 1));
+    assert!(cache.render(&unnamed("let x;")).contains("let x;"));
+    assert!(cache.render(&unnamed("let y;")).contains("let y;"));
+    assert!(!cache.contains(&unnamed("let z;")));
+    cache.clear();
+    cache.render(&before);
+    cache.clear();
+    assert_eq!(cache.len(), 0);
+}
+
+#[test]
+fn test_metrics() {
+    use annotate_snippets::formatter::metrics::RenderMetrics;
+    use annotate_snippets::formatter::renderer::Renderer;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let source = (1..=12)
+        .map(|i| format!("line {}\n", i))
+        .collect::<String>();
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Error,
+            "a title longer than twenty columns",
+        ))
+        .slice(
+            snippet::Slice::new(&source, 1)
+                .origin("src/main.rs")
+                .fold(true)
+                .annotation(snippet::SourceAnnotation::new(
+                    (0, 4),
+                    snippet::AnnotationType::Error,
+                    "first",
+                ))
+                .annotation(snippet::SourceAnnotation::new(
+                    (source.len() - 8, source.len() - 6),
+                    snippet::AnnotationType::Error,
+                    "last",
+                )),
+        )
+        .opt(FormatOptions::new().max_width(20));
+    let dl = DisplayList::from(snippet.clone());
+    let rendered = dl.to_string();
+    let metrics = dl.metrics();
+    assert_eq!(metrics.folded_lines, 5, "{}", rendered);
+    // The title and the header of the slice.
+    assert_eq!(metrics.wrapped_lines, 2);
+    assert_eq!(metrics.truncated_lines, 0);
+    assert_eq!(metrics.height, rendered.lines().count());
+    assert_eq!(
+        metrics.width,
+        rendered
+            .lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap()
+    );
+    assert!(metrics.is_elided());
+    assert_eq!(dl.rendered_width(), metrics.width);
+
+    let reported = Rc::new(RefCell::new(vec![]));
+    let hook = reported.clone();
+    let mut renderer = Renderer::new().on_metrics(move |metrics: &RenderMetrics| {
+        hook.borrow_mut().push(*metrics);
+    });
+    assert_eq!(renderer.render(&snippet), rendered);
+    assert_eq!(*reported.borrow(), [metrics]);
+
+    let narrow = snippet.opt(FormatOptions::new().max_width(8));
+    assert!(DisplayList::from(narrow).metrics().truncated_lines > 0);
+}
+
+#[test]
+fn test_max_body_lines() {
+    let source = (1..=20)
+        .map(|i| format!("line {}\n", i))
+        .collect::<String>();
+    let offset = |line: usize| source.lines().take(line - 1).map(|l| l.len() + 1).sum();
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Error,
+            "too long",
+        ))
+        .slice(
+            snippet::Slice::new(&source, 1)
+                .annotation(snippet::SourceAnnotation::new(
+                    (offset(3), offset(3) + 4),
+                    snippet::AnnotationType::Error,
+                    "first",
+                ))
+                .annotation(snippet::SourceAnnotation::new(
+                    (offset(15), offset(15) + 4),
+                    snippet::AnnotationType::Warning,
+                    "second",
+                )),
+        )
+        .opt(FormatOptions::new().max_body_lines(8));
+    let expected = [
+        "error: too long",
+        "   |",
+        "   | … 1 line omitted",
+        " 2 | line 2",
+        " 3 | line 3",
+        "   | ^^^^ first",
+        " 4 | line 4",
+        "   | … 9 lines omitted",
+        "14 | line 14",
+        "15 | line 15",
+        "   | ---- second",
+        "16 | line 16",
+        "   | … 4 lines omitted",
+        "   |",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet.clone()).to_string(), expected);
+
+    let renderer = &mut annotate_snippets::formatter::renderer::Renderer::new();
+    assert_eq!(renderer.render(&snippet), expected);
+
+    // The markers are not source lines.
+    let dl = DisplayList::from(snippet.clone());
+    let metrics = dl.metrics();
+    assert_eq!(metrics.folded_lines, 14);
+    assert!(metrics.is_elided());
+    let owned = dl.to_owned_snippet();
+    assert!(!owned.slices[0].source.contains("omitted"));
+    assert_eq!(owned.slices[0].line_start, 2);
+    assert!(owned.slices[0].fold);
+
+    let translations = Translations {
+        omitted_line: "… 1 ligne omise".into(),
+        omitted_lines: "… {count} lignes omises".into(),
+        ..Default::default()
+    };
+    let translated = snippet.clone().opt(
+        FormatOptions::new()
+            .max_body_lines(8)
+            .translations(translations),
+    );
+    let rendered = DisplayList::from(translated).to_string();
+    assert!(rendered.contains("   | … 1 ligne omise\n"));
+    assert!(rendered.contains("   | … 9 lignes omises\n"));
+
+    let snippet = snippet.opt(FormatOptions::new().max_body_lines(100));
+    assert!(!DisplayList::from(snippet).to_string().contains("omitted"));
+}
+
+#[test]
+fn test_fold_priority() {
+    let source = (1..=30)
+        .map(|i| format!("line {}\n", i))
+        .collect::<String>();
+    let offset = |line: usize| source.lines().take(line - 1).map(|l| l.len() + 1).sum();
+    let render = |first: snippet::Priority, second: snippet::Priority| {
+        let slice = snippet::Slice::new(&source, 1)
+            .annotation(
+                snippet::SourceAnnotation::new(
+                    (offset(3), offset(3) + 4),
+                    snippet::AnnotationType::Error,
+                    "first",
+                )
+                .priority(first),
+            )
+            .annotation(
+                snippet::SourceAnnotation::new(
+                    (offset(20), offset(20) + 4),
+                    snippet::AnnotationType::Warning,
+                    "second",
+                )
+                .priority(second),
+            )
+            .fold(true);
+        DisplayList::from(Snippet::new().slice(slice)).to_string()
+    };
+    let lines = |lines: &[&str]| lines.join("\n");
+    let normal = lines(&[
+        "   |",
+        "...",
+        " 3 | line 3",
+        "   | ^^^^ first",
+        " 4 | line 4",
+        " 5 | line 5",
+        " 6 | line 6",
+        " 7 | line 7",
+        "...",
+        "19 | line 19",
+        "20 | line 20",
+        "   | ---- second",
+        "   |",
+    ]);
+    assert_eq!(
+        render(snippet::Priority::Normal, snippet::Priority::Normal),
+        normal
+    );
+
+    // High-priority annotations keep more context, low-priority ones only
+    // their own lines.
+    let high_first = lines(&[
+        "   |",
+        " 1 | line 1",
+        " 2 | line 2",
+        " 3 | line 3",
+        "   | ^^^^ first",
+        " 4 | line 4",
+        " 5 | line 5",
+        " 6 | line 6",
+        " 7 | line 7",
+        " 8 | line 8",
+        " 9 | line 9",
+        "10 | line 10",
+        "11 | line 11",
+        "...",
+        "20 | line 20",
+        "   | ---- second",
+        "   |",
+    ]);
+    assert_eq!(
+        render(snippet::Priority::High, snippet::Priority::Low),
+        high_first
+    );
+    let high_second = lines(&[
+        "   |",
+        "...",
+        " 3 | line 3",
+        "   | ^^^^ first",
+        "...",
+        "16 | line 16",
+        "17 | line 17",
+        "18 | line 18",
+        "19 | line 19",
+        "20 | line 20",
+        "   | ---- second",
+        "   |",
+    ]);
+    assert_eq!(
+        render(snippet::Priority::Low, snippet::Priority::High),
+        high_second
+    );
+}
+
+#[test]
+fn test_line_highlights() {
+    let source = (1..=12)
+        .map(|i| format!("line {}\n", i))
+        .collect::<String>();
+    let offset = |line: usize| source.lines().take(line - 1).map(|l| l.len() + 1).sum();
+    let slice = snippet::Slice::new(&source, 1)
+        .annotation(snippet::SourceAnnotation::new(
+            (offset(11), offset(11) + 4),
+            snippet::AnnotationType::Error,
+            "here",
+        ))
+        .highlight_line(snippet::LineHighlight::new(2, snippet::LineChange::Removed))
+        .highlight_line(snippet::LineHighlight::new(3, snippet::LineChange::Added))
+        .highlight_line(snippet::LineHighlight::new(
+            11,
+            snippet::LineChange::Changed,
+        ))
+        .fold(true);
+    let snippet = Snippet::new().slice(slice);
+    let expected = [
+        "   |",
+        " 1 |   line 1",
+        " 2 | - line 2",
+        " 3 | + line 3",
+        "...   ",
+        "11 | > line 11",
+        "   |   ^^^^ here",
+        "   |",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet.clone()).to_string(), expected);
+
+    #[cfg(feature = "color")]
+    {
+        use annotate_snippets::formatter::style::Color;
+
+        let mut snippet = snippet.opt(FormatOptions::new().color(true));
+        snippet.slices[0].line_highlights[1] =
+            snippet::LineHighlight::new(3, snippet::LineChange::Added).background(Color::Blue);
+        let rendered = DisplayList::from(snippet).to_string();
+        assert!(rendered.contains("\u{1b}[31m-\u{1b}[0m line 2"));
+        assert!(rendered.contains("\u{1b}[32m+\u{1b}[0m\u{1b}[44m line 3\u{1b}[0m"));
+    }
+}
+
+#[test]
+fn test_gutter_badges() {
+    let source = (1..=10)
+        .map(|i| format!("line {}\n", i))
+        .collect::<String>();
+    let snippet = Snippet::new()
+        .slice(
+            snippet::Slice::new(&source[..source.len() - 1], 1)
+                .origin("src/lib.rs")
+                .annotation(snippet::SourceAnnotation::new(
+                    (0, 4),
+                    snippet::AnnotationType::Error,
+                    "here",
+                )),
+        )
+        .opt(FormatOptions::new().gutter_badges(|origin, lineno| {
+            assert_eq!(origin, Some("src/lib.rs"));
+            match lineno {
+                1 => Some("100%".to_string()),
+                9 => Some("0%".to_string()),
+                _ => None,
+            }
+        }));
+    let expected = [
+        "       --> src/lib.rs:1:1",
+        "        |",
+        "100%  1 | line 1",
+        "        | ^^^^ here",
+        "      2 | line 2",
+        "      3 | line 3",
+        "      4 | line 4",
+        "      5 | line 5",
+        "      6 | line 6",
+        "      7 | line 7",
+        "      8 | line 8",
+        "0%    9 | line 9",
+        "     10 | line 10",
+        "        |",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet.clone()).to_string(), expected);
+
+    let renderer = &mut annotate_snippets::formatter::renderer::Renderer::new();
+    assert_eq!(renderer.render(&snippet), expected);
+    let mut streamed = vec![];
+    annotate_snippets::formatter::stream::write_snippet(&snippet, &mut streamed).unwrap();
+    assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+
+    // Streaming asks for the badge of every line once.
+    let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counted = calls.clone();
+    let snippet = snippet.opt(FormatOptions::new().gutter_badges(move |_, _| {
+        counted.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        None
+    }));
+    annotate_snippets::formatter::stream::write_snippet(&snippet, &mut vec![]).unwrap();
+    assert_eq!(calls.load(std::sync::atomic::Ordering::Relaxed), 10);
+}
+
+#[test]
+fn test_compact_annotations() {
+    let source = "let x = 1;\nlet y = x + z;\nfn f() {\n}";
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Error,
+            "lints",
+        ))
+        .slice(
+            snippet::Slice::new(source, 1)
+                .annotation(snippet::SourceAnnotation::new(
+                    (4, 5),
+                    snippet::AnnotationType::Warning,
+                    "unused",
+                ))
+                .annotation(snippet::SourceAnnotation::new(
+                    (23, 24),
+                    snippet::AnnotationType::Error,
+                    "not found",
+                ))
+                .annotation(snippet::SourceAnnotation::new(
+                    (15, 16),
+                    snippet::AnnotationType::Help,
+                    "",
+                ))
+                .annotation(snippet::SourceAnnotation::new(
+                    (26, 36),
+                    snippet::AnnotationType::Warning,
+                    "empty",
+                )),
+        )
+        .opt(FormatOptions::new().compact_annotations(true));
+    let expected = [
+        "error: lints",
+        "  |",
+        "1 |   let x = 1;  // - unused",
+        "2 |   let y = x + z;  // ^ not found, -",
+        "3 | / fn f() {",
+        "4 | | }",
+        "  | |_- empty",
+        "  |",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet.clone()).to_string(), expected);
+
+    let renderer = &mut annotate_snippets::formatter::renderer::Renderer::new();
+    assert_eq!(renderer.render(&snippet), expected);
+
+    let recovered = DisplayList::from(snippet.clone()).to_owned_snippet();
+    let ranges = recovered.slices[0]
+        .annotations
+        .iter()
+        .map(|annotation| annotation.range)
+        .collect::<Vec<_>>();
+    assert_eq!(ranges, [(4, 5), (23, 24), (15, 16), (26, 36)]);
+}
+
+#[test]
+fn test_compact_annotations_through_multiline() {
+    let snippet = Snippet::new()
+        .slice(
+            snippet::Slice::new("a\nbcd\ne", 1)
+                .annotation(snippet::SourceAnnotation::new(
+                    (3, 4),
+                    snippet::AnnotationType::Error,
+                    "x",
+                ))
+                .annotation(snippet::SourceAnnotation::new(
+                    (0, 7),
+                    snippet::AnnotationType::Warning,
+                    "w",
+                )),
+        )
+        .opt(FormatOptions::new().compact_annotations(true));
+    let expected = [
+        "  |",
+        "1 | / a",
+        "2 | | bcd  // ^ x",
+        "3 | | e",
+        "  | |_- w",
+        "  |",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet.clone()).to_string(), expected);
+    let renderer = &mut annotate_snippets::formatter::renderer::Renderer::new();
+    assert_eq!(renderer.render(&snippet), expected);
+}
+
+#[test]
+fn test_compact_annotations_crlf_fold() {
+    // The multiline annotation ends between the `\r` and the `\n` of line 4.
+    let snippet = Snippet::new()
+        .slice(
+            snippet::Slice::new("xxfooxbar\n\n\nbaz\r\nqux", 1)
+                .fold(true)
+                .annotation(snippet::SourceAnnotation::new(
+                    (12, 12),
+                    snippet::AnnotationType::Error,
+                    "a",
+                ))
+                .annotation(snippet::SourceAnnotation::new(
+                    (10, 16),
+                    snippet::AnnotationType::Warning,
+                    "b",
+                )),
+        )
+        .opt(FormatOptions::new().compact_annotations(true));
+    assert!(snippet.validate().is_ok());
+    let expected = [
+        "  |",
+        "... |",
+        "4 |   baz",
+        "  | |  a",
+        "  | |___- b",
+        "  |",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet.clone()).to_string(), expected);
+    let renderer = &mut annotate_snippets::formatter::renderer::Renderer::new();
+    assert_eq!(renderer.render(&snippet), expected);
+}
+
+#[test]
+fn test_vertical_compact() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Error,
+            "mismatched types",
+        ))
+        .footer(snippet::Annotation::new(
+            snippet::AnnotationType::Note,
+            "expected `u32`",
+        ))
+        .slice(
+            snippet::Slice::new("let x: u32 = \"\";", 7)
+                .origin("src/main.rs")
+                .annotation(snippet::SourceAnnotation::new(
+                    (13, 15),
+                    snippet::AnnotationType::Error,
+                    "expected `u32`",
+                )),
+        )
+        .opt(FormatOptions::new().vertical_compact(true));
+    let expected = [
+        "error: mismatched types",
+        " --> src/main.rs:7:14",
+        "7 | let x: u32 = \"\";",
+        "  |              ^^ expected `u32`",
+        "  = note: expected `u32`",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet.clone()).to_string(), expected);
+
+    let renderer = &mut annotate_snippets::formatter::renderer::Renderer::new();
+    assert_eq!(renderer.render(&snippet), expected);
+
+    let mut out = vec![];
+    annotate_snippets::formatter::stream::write_snippet(&snippet, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+}
+
+#[test]
+fn test_max_label_length() {
+    let snippet = Snippet::new()
+        .slice(
+            snippet::Slice::new("let v = f();", 1)
+                .annotation(snippet::SourceAnnotation::new(
+                    (4, 5),
+                    snippet::AnnotationType::Error,
+                    "`std::collections::HashMap<String, Vec<u8>>`",
+                ))
+                .annotation(snippet::SourceAnnotation::new(
+                    (8, 11),
+                    snippet::AnnotationType::Error,
+                    "short",
+                )),
+        )
+        .opt(FormatOptions::new().max_label_length(20));
+    let expected = [
+        "  |",
+        "1 | let v = f();",
+        "  |     ^ `std::collections::…",
+        "  |         ^^^ short",
+        "  |",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet).to_string(), expected);
+}
+
+#[test]
+fn test_header_placement() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Error,
+            "unused variable",
+        ))
+        .footer(snippet::Annotation::new(
+            snippet::AnnotationType::Help,
+            "prefix it with an underscore",
+        ))
+        .slice(
+            snippet::Slice::new("let x = 1;", 3)
+                .origin("src/main.rs")
+                .annotation(snippet::SourceAnnotation::new(
+                    (4, 5),
+                    snippet::AnnotationType::Error,
+                    "unused",
+                )),
+        )
+        .opt(FormatOptions::new().header_placement(HeaderPlacement::Below));
+    let expected = [
+        "  |",
+        "3 | let x = 1;",
+        "  |     ^ unused",
+        "  |",
+        " --> src/main.rs:3:5",
+        "error: unused variable",
+        "  = help: prefix it with an underscore",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet.clone()).to_string(), expected);
+
+    let renderer = &mut annotate_snippets::formatter::renderer::Renderer::new();
+    assert_eq!(renderer.render(&snippet), expected);
+}
+
+#[test]
+fn test_extra_titles() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Error,
+            "`let` expressions in this position are unstable",
+        ))
+        .extra_title(snippet::Annotation::new(
+            snippet::AnnotationType::Warning,
+            "the feature `let_chains` is incomplete",
+        ))
+        .slice(
+            snippet::Slice::new("if let Some(x) = y && x {}", 1)
+                .origin("src/main.rs")
+                .annotation(snippet::SourceAnnotation::new(
+                    (3, 18),
+                    snippet::AnnotationType::Error,
+                    "",
+                )),
+        );
+    let expected = [
+        "error: `let` expressions in this position are unstable",
+        "warning: the feature `let_chains` is incomplete",
+        " --> src/main.rs:1:4",
+        "  |",
+        "1 | if let Some(x) = y && x {}",
+        "  |    ^^^^^^^^^^^^^^^",
+        "  |",
+    ]
+    .join("\n");
+    let dl = DisplayList::from(snippet.clone());
+    assert_eq!(dl.to_string(), expected);
+
+    let renderer = &mut annotate_snippets::formatter::renderer::Renderer::new();
+    assert_eq!(renderer.render(&snippet), expected);
+
+    let recovered = dl.to_owned_snippet();
+    assert_eq!(
+        recovered.extra_titles,
+        [OwnedAnnotation::from(&snippet.extra_titles[0])]
+    );
+}
+
+#[test]
+fn test_screen_reader() {
+    let source = "let x = 1\nfn f() {\n}";
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Error,
+            "expected `;`",
+        ))
+        .slice(
+            snippet::Slice::new(source, 52)
+                .origin("src/main.rs")
+                .annotation(snippet::SourceAnnotation::new(
+                    (4, 9),
+                    snippet::AnnotationType::Error,
+                    "expected `;`",
+                ))
+                .annotation(snippet::SourceAnnotation::new(
+                    (10, 20),
+                    snippet::AnnotationType::Help,
+                    "in this function",
+                ))
+                .annotation(snippet::SourceAnnotation::new(
+                    (0, 3),
+                    snippet::AnnotationType::Warning,
+                    "",
+                )),
+        )
+        .opt(FormatOptions::new().screen_reader(true));
+    let expected = [
+        "error: expected `;`",
+        "  --> src/main.rs:52:5",
+        "   |",
+        "52 | let x = 1",
+        "   = error: line 52, columns 5-9: expected `;`",
+        "   = warning: line 52, columns 1-3",
+        "53 | fn f() {",
+        "54 | }",
+        "   = help: line 53, column 1 to line 54, column 1: in this function",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet.clone()).to_string(), expected);
+
+    let renderer = &mut annotate_snippets::formatter::renderer::Renderer::new();
+    assert_eq!(renderer.render(&snippet), expected);
+
+    let translations = Translations {
+        column_place: "ligne {line}, colonne {column}".into(),
+        columns_place: "ligne {line}, colonnes {start} à {end}".into(),
+        lines_place: "ligne {line}, colonne {column} à ligne {end_line}, colonne {end_column}"
+            .into(),
+        ..Default::default()
+    };
+    let snippet = snippet.opt(
+        FormatOptions::new()
+            .screen_reader(true)
+            .translations(translations),
+    );
+    let rendered = DisplayList::from(snippet).to_string();
+    assert!(rendered.contains("   = error: ligne 52, colonnes 5 à 9: expected `;`"));
+    assert!(rendered.contains("ligne 53, colonne 1 à ligne 54, colonne 1: in this function"));
+}
+
+#[test]
+fn test_translations_fill() {
+    assert_eq!(
+        Translations::fill("{a} and {b}, {c} {", &[("a", &1), ("b", &"two")]),
+        "1 and two, {c} {"
+    );
+}
+
+#[test]
+fn test_column_ruler() {
+    let snippet = Snippet::new()
+        .slice(
+            snippet::Slice::new("let message = \"hello world\";", 1)
+                .origin("src/main.rs")
+                .annotation(snippet::SourceAnnotation::new(
+                    (14, 27),
+                    snippet::AnnotationType::Error,
+                    "here",
+                )),
+        )
+        .opt(FormatOptions::new().column_ruler(true));
+    let expected = [
+        " --> src/main.rs:1:15",
+        "  |",
+        "  |          1         2",
+        "  | 1234567890123456789012345678",
+        "1 | let message = \"hello world\";",
+        "  |               ^^^^^^^^^^^^^ here",
+        "  |",
+    ]
+    .join("\n");
+    let dl = DisplayList::from(snippet.clone());
+    assert_eq!(dl.to_string(), expected);
+    assert_eq!(
+        dl.to_owned_snippet().slices[0].source,
+        snippet.slices[0].source
+    );
+
+    let renderer = &mut annotate_snippets::formatter::renderer::Renderer::new();
+    assert_eq!(renderer.render(&snippet), expected);
+}
+
+#[test]
+fn test_origin_note() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Error,
+            "mismatched types",
+        ))
+        .slice(
+            snippet::Slice::new("let x: u32 = \"\";", 3)
+                .origin("src/generated.rs")
+                .origin_note("generated file — edits will be overwritten")
+                .annotation(snippet::SourceAnnotation::new(
+                    (13, 15),
+                    snippet::AnnotationType::Error,
+                    "expected `u32`",
+                )),
+        );
+    let expected = [
+        "error: mismatched types",
+        " --> src/generated.rs:3:14",
+        "  = note: generated file — edits will be overwritten",
+        "  |",
+        "3 | let x: u32 = \"\";",
+        "  |              ^^ expected `u32`",
+        "  |",
+    ]
+    .join("\n");
+    let dl = DisplayList::from(snippet.clone());
+    assert_eq!(dl.to_string(), expected);
+
+    let renderer = &mut annotate_snippets::formatter::renderer::Renderer::new();
+    assert_eq!(renderer.render(&snippet), expected);
+
+    let recovered = dl.to_owned_snippet();
+    assert_eq!(recovered.slices[0], OwnedSlice::from(&snippet.slices[0]));
+    assert!(recovered.footer.is_empty());
+}
+
+#[test]
+fn test_lint() {
+    let snippet = Snippet::new()
+        .title(snippet::Annotation::new(
+            snippet::AnnotationType::Warning,
+            "unused variable: `x`",
+        ))
+        .footer(snippet::Annotation::new(
+            snippet::AnnotationType::Help,
+            "prefix it with an underscore: `_x`",
+        ))
+        .slice(
+            snippet::Slice::new("    let x = 1;", 2)
+                .origin("src/main.rs")
+                .annotation(snippet::SourceAnnotation::new(
+                    (8, 9),
+                    snippet::AnnotationType::Warning,
+                    "",
+                )),
+        )
+        .lint(
+            snippet::Lint::new("unused_variables", snippet::LintLevel::Warn)
+                .source(snippet::LintSource::Group("unused")),
+        );
+    let expected = [
+        "warning: unused variable: `x`",
+        " --> src/main.rs:2:9",
+        "  |",
+        "2 |     let x = 1;",
+        "  |         -",
+        "  |",
+        "  = help: prefix it with an underscore: `_x`",
+        "  = note: `#[warn(unused_variables)]` implied by `#[warn(unused)]`",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet.clone()).to_string(), expected);
+
+    let renderer = &mut annotate_snippets::formatter::renderer::Renderer::new();
+    assert_eq!(renderer.render(&snippet), expected);
+
+    let mut without_footer = snippet;
+    without_footer.footer.clear();
+    assert!(DisplayList::from(without_footer)
+        .to_string()
+        .ends_with("  |\n  = note: `#[warn(unused_variables)]` implied by `#[warn(unused)]`"));
+}
+
+#[cfg(all(feature = "serde", feature = "serde_json"))]
+#[test]
+fn test_serialized_display_list() {
+    use annotate_snippets::formatter::get_term_style;
+
+    let snippet = Snippet::new()
+        .title(
+            snippet::Annotation::new(snippet::AnnotationType::Error, "mismatched types")
+                .id("E0308"),
+        )
+        .footer(snippet::Annotation::new(
+            snippet::AnnotationType::Note,
+            "expected \"u8\"",
+        ))
+        .slice(
+            snippet::Slice::new("let x: u8 = \"a\";\nlet y = x;", 1)
+                .origin("src/main.rs")
+                .annotation(snippet::SourceAnnotation::new(
+                    (12, 15),
+                    snippet::AnnotationType::Error,
+                    "expected `u8`",
+                ))
+                .annotation(snippet::SourceAnnotation::new(
+                    (0, 26),
+                    snippet::AnnotationType::Info,
+                    "in this block",
+                )),
+        )
+        .opt(FormatOptions::new().margin(Margin::new(0, 0, 0, 0, 140, 0)));
+    let list = DisplayList::from(snippet.clone());
+    let json = serde_json::to_string(&list).unwrap();
+
+    let mut restored: DisplayList<'_> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, list);
+    assert_eq!(restored.margin, list.margin);
+    assert_eq!(restored.to_string(), list.to_string());
+
+    restored.stylesheet = get_term_style(true);
+    let colored = DisplayList::from(snippet.opt(FormatOptions::new().color(true))).to_string();
+    assert_eq!(restored.to_string(), colored);
+}
+
+#[test]
+fn test_markers() {
+    let snippet = Snippet::new()
+        .slice(
+            snippet::Slice::new("let 字 = x;", 1)
+                .annotation(snippet::SourceAnnotation::new(
+                    (4, 5),
+                    snippet::AnnotationType::Error,
+                    "wide",
+                ))
+                .annotation(snippet::SourceAnnotation::new(
+                    (8, 9),
+                    snippet::AnnotationType::Warning,
+                    "narrow",
+                )),
+        )
+        .opt(FormatOptions::new().markers(MarkerSet {
+            error: '~',
+            warning: '=',
+            ..Default::default()
+        }));
+    let expected = [
+        "  |",
+        "1 | let 字 = x;",
+        "  |     ~~ wide",
+        "  |          = narrow",
+        "  |",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet.clone()).to_string(), expected);
+
+    let mut opt = snippet.opt.clone().full_width_marks(true);
+    opt.markers.error = '→';
+    let rendered = DisplayList::from(snippet.opt(opt)).to_string();
+    assert!(rendered.contains("  |     →→ wide"), "{}", rendered);
+}
+
+#[test]
+fn test_fold_option() {
+    let source = (1..=20)
+        .map(|i| format!("line {}", i))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let slice = snippet::Slice::new(&source, 1).annotation(snippet::SourceAnnotation::new(
+        (0, 4),
+        snippet::AnnotationType::Error,
+        "here",
+    ));
+
+    let folded = Snippet::new().slice(slice.clone().fold(true));
+    let opted = Snippet::new()
+        .slice(slice)
+        .opt(FormatOptions::new().fold(true));
+    assert_eq!(
+        DisplayList::from(opted).to_string(),
+        DisplayList::from(folded).to_string()
+    );
+}
+
+#[test]
+fn test_theme() {
+    let snippet = Snippet::new().title(snippet::Annotation::new(
+        snippet::AnnotationType::Error,
+        "mismatched types",
+    ));
+    let theme = Theme::parse("error=blue").unwrap();
+
+    // Without colors, the theme changes nothing.
+    let plain = snippet
+        .clone()
+        .opt(FormatOptions::new().theme(theme.clone()));
+    assert_eq!(
+        DisplayList::from(plain).to_string(),
+        "error: mismatched types"
+    );
+
+    #[cfg(feature = "color")]
+    {
+        let colored = snippet.opt(FormatOptions::new().color(true).theme(theme));
+        let rendered = DisplayList::from(colored).to_string();
+        assert!(rendered.starts_with("\u{1b}[34merror"), "{:?}", rendered);
+    }
+}
@@ -0,0 +1,273 @@
+//! Renders many generated snippets, checking that no valid snippet panics.
+//!
+//! The test profile keeps overflow checks on, so arithmetic overflows panic
+//! here as well.
+
+use std::path::PathBuf;
+
+use annotate_snippets::display_list::{
+    BidiMode, ColumnUnit, DisplayList, EscapeMode, FormatOptions, GutterBadges, HeaderPlacement,
+    HeaderPosition, Margin, MarkerSet, PathNormalizer, SeverityIcons, Translations,
+};
+use annotate_snippets::snippet::{
+    Annotation, AnnotationType, ExpansionFrame, IncludeFrame, LineChange, LineHighlight, Lint,
+    LintLevel, LintSource, Priority, Slice, Snippet, SourceAnnotation,
+};
+use annotate_snippets::stylesheets::theme::Theme;
+
+/// A xorshift generator, keeping the generated snippets reproducible.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn flip(&mut self) -> bool {
+        self.next() & 1 == 0
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+
+    /// `Some` of a number below `n` half of the time.
+    fn maybe(&mut self, n: usize) -> Option<usize> {
+        if self.flip() {
+            Some(self.below(n))
+        } else {
+            None
+        }
+    }
+}
+
+const PIECES: &[&str] = &[
+    "", "a", "fn", " ", "\t", "\n", "\r\n", "é", "字", "🦀", "\u{301}", "{", "}", "  let x", "\n\n",
+];
+const LABELS: &[&str] = &["", "label", "multi\nline", "字字", "[1]"];
+const PRIORITIES: &[Priority] = &[Priority::Low, Priority::Normal, Priority::High];
+const CHANGES: &[LineChange] = &[LineChange::Added, LineChange::Removed, LineChange::Changed];
+const TYPES: &[AnnotationType] = &[
+    AnnotationType::Error,
+    AnnotationType::Warning,
+    AnnotationType::Info,
+    AnnotationType::Note,
+    AnnotationType::Help,
+];
+
+fn source(rng: &mut Rng) -> String {
+    (0..rng.below(12))
+        .map(|_| PIECES[rng.below(PIECES.len())])
+        .collect()
+}
+
+fn annotations(rng: &mut Rng, len: usize) -> Vec<SourceAnnotation<'static>> {
+    (0..rng.below(4))
+        .map(|_| {
+            let start = rng.below(len + 1);
+            let end = start + rng.below(len - start + 1);
+            SourceAnnotation {
+                range: (start, end),
+                label: rng.pick(LABELS),
+                annotation_type: rng.pick(TYPES),
+                priority: rng.pick(PRIORITIES),
+            }
+        })
+        .collect()
+}
+
+fn slice<'a>(rng: &mut Rng, source: &'a str) -> Slice<'a> {
+    let len = source.chars().count();
+    let mut slice = Slice::new(source, rng.below(1000));
+    slice.origin = if rng.flip() { Some("src/lib.rs") } else { None };
+    slice.annotations = annotations(rng, len);
+    slice.fold = rng.flip();
+    slice.line_highlights = (0..rng.below(3))
+        .map(|_| LineHighlight::new(rng.below(1010), rng.pick(CHANGES)))
+        .collect();
+    slice.origin_note = if rng.flip() { Some("generated") } else { None };
+    slice
+}
+
+fn label_annotation(rng: &mut Rng) -> Annotation<'static> {
+    Annotation {
+        id: if rng.flip() { Some("E0001") } else { None },
+        label: Some(rng.pick(LABELS)),
+        annotation_type: rng.pick(TYPES),
+    }
+}
+
+/// Options with every field set at random, except `assert_max_width`, which
+/// panics on purpose.
+fn options(rng: &mut Rng) -> FormatOptions {
+    let mut opt = FormatOptions::new();
+    opt.color = rng.flip();
+    opt.anonymized_line_numbers = rng.flip();
+    opt.margin = if rng.flip() {
+        Some(Margin::new(
+            rng.below(20),
+            rng.below(20),
+            rng.below(40),
+            rng.below(60),
+            rng.below(80),
+            rng.below(40),
+        ))
+    } else {
+        None
+    };
+    opt.icons = if rng.flip() {
+        Some(SeverityIcons::default())
+    } else {
+        None
+    };
+    opt.indent = rng.below(3);
+    opt.footnotes = rng.flip();
+    opt.bidi = rng.pick(&[BidiMode::Preserve, BidiMode::Strip, BidiMode::Isolate]);
+    opt.escape = rng.pick(&[
+        EscapeMode::PassThrough,
+        EscapeMode::Unicode,
+        EscapeMode::Replacement,
+    ]);
+    opt.translations = if rng.flip() {
+        Some(Translations {
+            error: "erreur".into(),
+            fold: "".into(),
+            columns_place: "{line}:{start}-{end} {".into(),
+            omitted_lines: "{count}字".into(),
+            ..Default::default()
+        })
+    } else {
+        None
+    };
+    opt.column_unit = rng.pick(&[
+        ColumnUnit::Bytes,
+        ColumnUnit::Chars,
+        ColumnUnit::Cells,
+        ColumnUnit::Utf16,
+    ]);
+    opt.header_position = rng.pick(&[HeaderPosition::LineColumn, HeaderPosition::Offset]);
+    opt.header_placement = rng.pick(&[HeaderPlacement::Above, HeaderPlacement::Below]);
+    opt.full_width_marks = rng.flip();
+    opt.tab_width = rng.maybe(9);
+    opt.max_width = rng.maybe(60);
+    opt.path_normalizer = if rng.flip() {
+        Some(PathNormalizer::new(|path| path.replace('/', "\\")))
+    } else {
+        None
+    };
+    opt.remap_path_prefix = if rng.flip() {
+        vec![("src".to_string(), "/work/字".to_string())]
+    } else {
+        vec![]
+    };
+    opt.base_dir = if rng.flip() {
+        Some(PathBuf::from("/work/sub"))
+    } else {
+        None
+    };
+    opt.max_body_lines = rng.maybe(8);
+    opt.gutter_badges = if rng.flip() {
+        Some(GutterBadges::new(|_, line| {
+            if line % 2 == 0 {
+                Some("字".to_string())
+            } else {
+                None
+            }
+        }))
+    } else {
+        None
+    };
+    opt.compact_annotations = rng.flip();
+    opt.vertical_compact = rng.flip();
+    opt.max_label_length = rng.maybe(12);
+    opt.screen_reader = rng.flip();
+    opt.column_ruler = rng.flip();
+    opt.markers = if rng.flip() {
+        MarkerSet {
+            error: '字',
+            warning: '~',
+            ..Default::default()
+        }
+    } else {
+        MarkerSet::default()
+    };
+    opt.theme = if rng.flip() {
+        Some(Theme::parse("error=bold,#ff0000:line-no=on-blue").unwrap())
+    } else {
+        None
+    };
+    opt.fold = rng.flip();
+    opt
+}
+
+#[test]
+fn test_fuzz_valid_snippets() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..20000 {
+        let sources = (0..rng.below(3))
+            .map(|_| source(&mut rng))
+            .collect::<Vec<_>>();
+        let frame_sources = (0..rng.below(3))
+            .map(|_| source(&mut rng))
+            .collect::<Vec<_>>();
+        let mut snippet = Snippet::new();
+        snippet.title = if rng.flip() {
+            Some(label_annotation(&mut rng))
+        } else {
+            None
+        };
+        snippet.footer = (0..rng.below(2))
+            .map(|_| label_annotation(&mut rng))
+            .collect();
+        snippet.slices = sources
+            .iter()
+            .map(|source| slice(&mut rng, source))
+            .collect();
+        snippet.opt = options(&mut rng);
+        snippet.expansion = frame_sources
+            .iter()
+            .map(|source| ExpansionFrame {
+                label: rng.pick(LABELS),
+                slice: slice(&mut rng, source),
+            })
+            .collect();
+        snippet.includes = (0..rng.below(3))
+            .map(|_| IncludeFrame {
+                path: "src/main.rs",
+                line: rng.below(100),
+            })
+            .collect();
+        snippet.extra_titles = (0..rng.below(2))
+            .map(|_| label_annotation(&mut rng))
+            .collect();
+        snippet.lint = if rng.flip() {
+            Some(
+                Lint::new(
+                    "dead_code",
+                    rng.pick(&[LintLevel::Warn, LintLevel::Deny, LintLevel::Forbid]),
+                )
+                .source(rng.pick(&[
+                    LintSource::Default,
+                    LintSource::Group("unused"),
+                    LintSource::CommandLine,
+                ])),
+            )
+        } else {
+            None
+        };
+        let description = format!("{:?}", snippet);
+        let result = std::panic::catch_unwind(|| {
+            let mut out = vec![];
+            annotate_snippets::render_to(&snippet, &snippet.opt, &mut out).unwrap();
+            DisplayList::from(snippet).to_string()
+        });
+        assert!(result.is_ok(), "rendering panicked for {}", description);
+    }
+}
@@ -0,0 +1,55 @@
+use annotate_snippets::display_list::{DisplayList, FormatOptions};
+use annotate_snippets::formatter::html::ansi_to_html;
+use annotate_snippets::snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation};
+
+#[test]
+fn test_ansi_to_html_colors() {
+    assert_eq!(
+        ansi_to_html("\x1b[38;5;12mblue\x1b[39m \x1b[38;2;255;135;0;48;5;0morange\x1b[0m"),
+        concat!(
+            "<span style=\"color:#5c5cff\">blue</span> ",
+            "<span style=\"color:#ff8700;background-color:#000000\">orange</span>"
+        )
+    );
+    assert_eq!(
+        ansi_to_html("\x1b[3;4mx\x1b[24my\x1b[0m\x1b[1m"),
+        concat!(
+            "<span style=\"font-style:italic;text-decoration:underline\">x</span>",
+            "<span style=\"font-style:italic\">y</span>"
+        )
+    );
+}
+
+#[test]
+fn test_ansi_to_html_escapes() {
+    assert_eq!(
+        ansi_to_html("a < b && \"c\" > d\x1b[K"),
+        "a &lt; b &amp;&amp; &quot;c&quot; &gt; d"
+    );
+}
+
+#[test]
+fn test_display_list_to_html() {
+    let snippet = Snippet::new()
+        .title(Annotation {
+            id: None,
+            label: Some("expected `<`"),
+            annotation_type: AnnotationType::Error,
+        })
+        .slice(Slice::new("Vec<u8>", 1).annotation(SourceAnnotation {
+            range: (3, 4),
+            label: "here",
+            annotation_type: AnnotationType::Error,
+            ..Default::default()
+        }))
+        .opt(FormatOptions::new());
+    let expected = [
+        "error: expected `&lt;`",
+        "  |",
+        "1 | Vec&lt;u8&gt;",
+        "  |    ^ here",
+        "  |",
+    ]
+    .join("\n");
+    assert_eq!(DisplayList::from(snippet).to_html(), expected);
+}
@@ -0,0 +1,46 @@
+use annotate_snippets::line_index::LineIndex;
+
+#[test]
+fn test_offset_to_position() {
+    let index = LineIndex::new("ab\ncd\n");
+    assert_eq!(index.line_count(), 3);
+    assert_eq!(index.offset_to_position(0), (1, 1));
+    assert_eq!(index.offset_to_position(2), (1, 3));
+    assert_eq!(index.offset_to_position(3), (2, 1));
+    assert_eq!(index.offset_to_position(6), (3, 1));
+    assert_eq!(index.offset_to_position(9), (3, 4));
+}
+
+#[test]
+fn test_position_to_offset() {
+    let index = LineIndex::new("ab\r\n字\n");
+    assert_eq!(index.position_to_offset(1, 1), Some(0));
+    assert_eq!(index.position_to_offset(1, 3), Some(2));
+    assert_eq!(index.position_to_offset(1, 4), Some(3));
+    assert_eq!(index.position_to_offset(1, 5), None);
+    assert_eq!(index.position_to_offset(2, 1), Some(4));
+    assert_eq!(index.position_to_offset(3, 1), Some(6));
+    assert_eq!(index.position_to_offset(3, 2), None);
+    assert_eq!(index.position_to_offset(4, 1), None);
+    assert_eq!(index.position_to_offset(0, 1), None);
+    assert_eq!(index.position_to_offset(1, 0), None);
+}
+
+#[test]
+fn test_round_trip() {
+    let text = "fn main() {\n    let x = \"é\";\n}";
+    let index = LineIndex::new(text);
+    for offset in 0..=text.chars().count() {
+        let (line, col) = index.offset_to_position(offset);
+        assert_eq!(index.position_to_offset(line, col), Some(offset));
+    }
+}
+
+#[test]
+fn test_empty_text() {
+    let index = LineIndex::new("");
+    assert_eq!(index.line_count(), 1);
+    assert_eq!(index.line_start(1), Some(0));
+    assert_eq!(index.position_to_offset(1, 1), Some(0));
+    assert_eq!(index.offset_to_position(0), (1, 1));
+}
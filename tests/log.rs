@@ -0,0 +1,69 @@
+#![cfg(feature = "log")]
+
+use std::sync::Mutex;
+
+use annotate_snippets::log::{level, Logger};
+use annotate_snippets::snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Keeps the target, level and message of every record.
+struct Capture(Mutex<Vec<(String, Level, String)>>);
+
+impl Log for Capture {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= Level::Warn
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        self.0.lock().unwrap().push((
+            record.target().to_string(),
+            record.level(),
+            record.args().to_string(),
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture(Mutex::new(vec![]));
+
+#[test]
+fn test_logger() {
+    log::set_logger(&CAPTURE).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let snippet = Snippet::new()
+        .title(Annotation::new(AnnotationType::Warning, "unused variable"))
+        .slice(
+            Slice::new("let x = 1;", 1).annotation(SourceAnnotation::new(
+                (4, 5),
+                AnnotationType::Warning,
+                "never read",
+            )),
+        );
+    Logger::new().log(&snippet);
+    Logger::new().target("lint").split_lines(true).log(&snippet);
+    // Disabled by the logger.
+    Logger::new().log(&Snippet::new().title(Annotation::new(AnnotationType::Note, "skipped")));
+
+    let rendered = "warning: unused variable\n  |\n1 | let x = 1;\n  |     - never read\n  |";
+    let records = CAPTURE.0.lock().unwrap();
+    assert_eq!(records.len(), 6);
+    assert_eq!(
+        records[0],
+        (
+            "annotate_snippets".to_string(),
+            Level::Warn,
+            rendered.to_string()
+        )
+    );
+    for (record, line) in records[1..].iter().zip(rendered.lines()) {
+        assert_eq!(record, &("lint".to_string(), Level::Warn, line.to_string()));
+    }
+}
+
+#[test]
+fn test_level() {
+    assert_eq!(level(AnnotationType::Error), Level::Error);
+    assert_eq!(level(AnnotationType::Help), Level::Info);
+}
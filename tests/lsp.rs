@@ -0,0 +1,77 @@
+#![cfg(feature = "lsp-types")]
+
+use annotate_snippets::display_list::DisplayList;
+use annotate_snippets::lsp;
+use annotate_snippets::snippet::AnnotationType;
+use lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, NumberOrString,
+    Position, Range, Url,
+};
+
+#[test]
+fn test_lsp_offset() {
+    // `😀` is two UTF-16 code units, and one char.
+    let document = "let s = \"😀\";\r\nlet t = s;\n";
+    assert_eq!(lsp::offset(document, Position::new(0, 9)), 9);
+    assert_eq!(lsp::offset(document, Position::new(0, 11)), 10);
+    assert_eq!(lsp::offset(document, Position::new(0, 100)), 12);
+    assert_eq!(lsp::offset(document, Position::new(1, 4)), 18);
+    assert_eq!(lsp::offset(document, Position::new(5, 0)), 25);
+}
+
+#[test]
+fn test_lsp_annotation_type() {
+    assert_eq!(lsp::annotation_type(None), AnnotationType::Error);
+    assert_eq!(
+        lsp::annotation_type(Some(DiagnosticSeverity::HINT)),
+        AnnotationType::Help
+    );
+}
+
+#[test]
+fn test_lsp_snippets() {
+    let document = "fn f(s: &str) {}\n\nfn main() {\n    f(\"😀\", 1);\n}\n";
+    let diagnostics = vec![
+        Diagnostic {
+            range: Range::new(Position::new(3, 12), Position::new(3, 13)),
+            severity: Some(DiagnosticSeverity::ERROR),
+            code: Some(NumberOrString::String("E0061".to_string())),
+            message: "this function takes 1 argument but 2 arguments were supplied".to_string(),
+            related_information: Some(vec![DiagnosticRelatedInformation {
+                location: Location::new(
+                    Url::parse("file:///src/main.rs").unwrap(),
+                    Range::new(Position::new(0, 3), Position::new(0, 4)),
+                ),
+                message: "function defined here".to_string(),
+            }]),
+            ..Default::default()
+        },
+        Diagnostic {
+            range: Range::new(Position::new(4, 1), Position::new(4, 1)),
+            severity: Some(DiagnosticSeverity::INFORMATION),
+            message: "end of `main`".to_string(),
+            ..Default::default()
+        },
+    ];
+
+    let snippets = lsp::snippets(&diagnostics, document, Some("src/main.rs"), 0);
+    let rendered = snippets
+        .iter()
+        .map(|snippet| DisplayList::from(snippet.to_snippet()).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rendered[0],
+        [
+            "error[E0061]: this function takes 1 argument but 2 arguments were supplied",
+            " --> src/main.rs:4:12",
+            "  |",
+            "4 |     f(\"😀\", 1);",
+            "  |             ^",
+            "  |",
+            "  = note: file:///src/main.rs:1:4: function defined here",
+        ]
+        .join("\n")
+    );
+    assert_eq!(snippets[1].slices[0].line_start, 5);
+    assert_eq!(snippets[1].slices[0].annotations[0].range, (1, 2));
+}
@@ -0,0 +1,170 @@
+use annotate_snippets::display_list::DisplayList;
+use annotate_snippets::parse_error::ParseError;
+
+#[test]
+fn test_at_line_byte() {
+    let document = "a = \"é\"\nb = ]\n";
+    let error = ParseError::new(document, "invalid", "unexpected").at_line_byte(1, 4);
+    assert_eq!(error.offset, Some(12));
+    let error = ParseError::new(document, "invalid", "unexpected").at_line_byte(0, 6);
+    assert_eq!(error.offset, Some(5));
+}
+
+#[test]
+fn test_snippet_at_end() {
+    let error = ParseError::new("a = [1,\n", "invalid", "unexpected eof")
+        .origin("a.toml")
+        .at(8);
+    assert_eq!(
+        DisplayList::from(error.snippet()).to_string(),
+        "error: invalid\n --> a.toml:1:8\n  |\n1 | a = [1,\n  |        ^ unexpected eof\n  |"
+    );
+}
+
+#[test]
+fn test_snippet_without_position() {
+    let error = ParseError::new("", "invalid", "missing field `name`");
+    assert_eq!(
+        DisplayList::from(error.snippet()).to_string(),
+        "error: invalid\n = note: missing field `name`"
+    );
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_from_toml() {
+    #[derive(Debug, serde::Deserialize)]
+    #[allow(dead_code)]
+    struct Config {
+        name: String,
+        build: Build,
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    #[allow(dead_code)]
+    struct Build {
+        jobs: u32,
+    }
+
+    let document = "name = \"x\"\n\n[build]\njobs = \"many\"\n";
+    let result: Result<toml::Value, _> = toml::from_str("name = \"x\"\njobs = ]\n");
+    let error = result.unwrap_err();
+    let error = ParseError::from_toml("name = \"x\"\njobs = ]\n", &error);
+    assert_eq!(
+        DisplayList::from(error.snippet()).to_string(),
+        "error: invalid TOML document\n  |\n1 | name = \"x\"\n2 | jobs = ]\n  |        ^ expected a value, found a right bracket\n  |"
+    );
+
+    let error = toml::from_str::<Config>(document).unwrap_err();
+    let error = ParseError::from_toml(document, &error).origin("config.toml");
+    assert_eq!(
+        DisplayList::from(error.snippet()).to_string(),
+        r#"error: invalid TOML document
+ --> config.toml:4:8
+  |
+...
+4 | jobs = "many"
+  |        ^ invalid type: string "many", expected u32 for key `build.jobs`
+  |"#
+    );
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_from_json() {
+    let document = "{\n  \"name\": \"x\",\n  \"jobs\": [1, 2,]\n}\n";
+    let error = serde_json::from_str::<serde_json::Value>(document).unwrap_err();
+    let error = ParseError::from_json(document, &error).origin("config.json");
+    assert_eq!(error.message, "trailing comma");
+    assert_eq!(
+        DisplayList::from(error.snippet()).to_string(),
+        r#"error: invalid JSON document
+ --> config.json:3:17
+  |
+...
+3 |   "jobs": [1, 2,]
+  |                 ^ trailing comma
+  |"#
+    );
+
+    let error = serde_json::from_str::<serde_json::Value>("[1, 2").unwrap_err();
+    let error = ParseError::from_json("[1, 2", &error);
+    assert_eq!(error.message, "EOF while parsing a list");
+    assert_eq!(error.offset, Some(4));
+}
+
+#[cfg(feature = "pest")]
+#[test]
+fn test_from_pest() {
+    use annotate_snippets::snippet::AnnotationType;
+    use pest::error::{Error, ErrorVariant};
+    use pest::Position;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    enum Rule {
+        Number,
+        String,
+        Comma,
+    }
+
+    let document = "[1, 2 3]";
+    let error = Error::new_from_pos(
+        ErrorVariant::<Rule>::ParsingError {
+            positives: vec![Rule::Comma],
+            negatives: vec![Rule::Number],
+        },
+        Position::new(document, 6).unwrap(),
+    );
+    let error = ParseError::from_pest(document, &error).origin("list");
+    assert_eq!(
+        DisplayList::from(error.snippet()).to_string(),
+        "error: syntax error\n --> list:1:7\n  |\n1 | [1, 2 3]\n  |       ^ unexpected `Number`\n  |\n  = help: expected `Comma`"
+    );
+
+    let error = Error::new_from_pos(
+        ErrorVariant::<Rule>::ParsingError {
+            positives: vec![Rule::Number, Rule::String, Rule::Comma],
+            negatives: vec![],
+        },
+        Position::new(document, 6).unwrap(),
+    );
+    let error = ParseError::from_pest(document, &error);
+    assert_eq!(error.message, "unexpected input");
+    assert_eq!(
+        error.footer,
+        vec![(
+            AnnotationType::Help,
+            "expected one of `Number`, `String`, `Comma`".to_string()
+        )]
+    );
+}
+
+#[cfg(feature = "nom")]
+#[test]
+fn test_from_nom() {
+    use nom::character::complete::{char, digit1};
+    use nom::error::{context, VerboseError};
+    use nom::multi::separated_list0;
+    use nom::sequence::delimited;
+    use nom::{Finish, IResult};
+
+    fn list(input: &str) -> IResult<&str, Vec<&str>, VerboseError<&str>> {
+        context(
+            "list",
+            delimited(char('['), separated_list0(char(','), digit1), char(']')),
+        )(input)
+    }
+
+    let document = "[1,2;3]";
+    let error = list(document).finish().unwrap_err();
+    let error = ParseError::from_nom(document, &error).origin("list");
+    assert_eq!(
+        DisplayList::from(error.snippet()).to_string(),
+        "error: syntax error\n --> list:1:5\n  |\n1 | [1,2;3]\n  |     ^ unexpected `;`\n  |\n  = help: expected `]`\n  = note: while parsing list"
+    );
+
+    let error = list("[1,2").finish().unwrap_err();
+    let error = ParseError::from_nom("[1,2", &error);
+    assert_eq!(error.message, "unexpected end of input");
+    assert_eq!(error.offset, Some(4));
+}
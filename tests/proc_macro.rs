@@ -0,0 +1,40 @@
+#![cfg(feature = "proc-macro")]
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Builds an example of the macro crate of `tests/ui/proc-macro`.
+fn build_example(name: &str) -> Output {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    Command::new(cargo)
+        .args([
+            "build",
+            "--quiet",
+            "--offline",
+            "--example",
+            name,
+            "--manifest-path",
+        ])
+        .arg(root.join("tests/ui/proc-macro/Cargo.toml"))
+        .env("CARGO_TARGET_DIR", root.join("target/ui"))
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_proc_macro_error() {
+    let output = build_example("error");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("error: unsupported input"), "{}", stderr);
+}
+
+#[test]
+fn test_proc_macro_warning() {
+    let output = build_example("warning");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    // Stable compilers have no warnings for macros, nightly ones show it.
+    assert!(!stderr.contains("error"), "{}", stderr);
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "ratatui")]
+
+use annotate_snippets::display_list::DisplayList;
+use annotate_snippets::formatter::ratatui::ansi_to_text;
+use annotate_snippets::snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation};
+use ratatui::style::{Color, Modifier, Style};
+
+#[test]
+fn test_ansi_to_text_styles() {
+    let text = ansi_to_text("\x1b[38;5;12mblue\x1b[39m \x1b[38;2;255;135;0;48;5;200mx\x1b[0m");
+    let spans = &text.lines[0].spans;
+    assert_eq!(spans.len(), 3);
+    assert_eq!(spans[0].style, Style::default().fg(Color::LightBlue));
+    assert_eq!(spans[1].content, " ");
+    assert_eq!(spans[1].style, Style::default());
+    assert_eq!(
+        spans[2].style,
+        Style::default()
+            .fg(Color::Rgb(255, 135, 0))
+            .bg(Color::Indexed(200))
+    );
+}
+
+#[test]
+fn test_ansi_to_text_lines() {
+    // Styles continue on the next lines, and empty lines are kept.
+    let text = ansi_to_text("\x1b[2;3ma\n\nb\x1b[0m\x1b[K");
+    assert_eq!(text.lines.len(), 3);
+    assert!(text.lines[1].spans.is_empty());
+    assert_eq!(text.lines[2].spans[0].content, "b");
+    assert_eq!(
+        text.lines[2].spans[0].style,
+        Style::default().add_modifier(Modifier::DIM | Modifier::ITALIC)
+    );
+}
+
+#[test]
+fn test_display_list_to_text() {
+    let snippet = Snippet::new()
+        .title(Annotation::new(AnnotationType::Error, "expected `<`"))
+        .slice(Slice::new("Vec<u8>", 1).annotation(SourceAnnotation::new(
+            (3, 4),
+            AnnotationType::Error,
+            "here",
+        )));
+    let text = DisplayList::from(snippet).to_text();
+    let lines = text
+        .lines
+        .iter()
+        .map(|line| {
+            line.spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect()
+        })
+        .collect::<Vec<String>>();
+    assert_eq!(
+        lines,
+        [
+            "error: expected `<`",
+            "  |",
+            "1 | Vec<u8>",
+            "  |    ^ here",
+            "  |"
+        ]
+    );
+}
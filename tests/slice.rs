@@ -0,0 +1,163 @@
+use annotate_snippets::snippet::{AnnotationType, Slice, SourceAnnotation};
+
+fn file_slice(annotations: Vec<SourceAnnotation<'_>>) -> Slice<'_> {
+    let source = "line 1\nline 2\nline 3\nline 4\nline 5\nline 6\nline 7\nline 8\n";
+    annotations
+        .into_iter()
+        .fold(Slice::new(source, 1).origin("file.txt"), Slice::annotation)
+}
+
+fn annotation(range: (usize, usize), label: &str) -> SourceAnnotation<'_> {
+    SourceAnnotation {
+        range,
+        label,
+        annotation_type: AnnotationType::Error,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_extract_with_context() {
+    let slices = file_slice(vec![annotation((21, 25), "four")]).extract(1);
+
+    assert_eq!(slices.len(), 1);
+    assert_eq!(slices[0].source, "line 3\nline 4\nline 5\n");
+    assert_eq!(slices[0].line_start, 3);
+    assert_eq!(slices[0].annotations[0].range, (7, 11));
+}
+
+#[test]
+fn test_extract_separate_groups() {
+    let slices = file_slice(vec![
+        annotation((49, 53), "eight"),
+        annotation((0, 4), "one"),
+        annotation((7, 11), "two"),
+    ])
+    .origin_note("generated")
+    .extract(0);
+
+    assert_eq!(slices.len(), 2);
+    assert!(slices.iter().all(|s| s.origin_note == Some("generated")));
+    assert_eq!(slices[0].source, "line 1\nline 2\n");
+    assert_eq!(slices[0].line_start, 1);
+    let labels = slices[0]
+        .annotations
+        .iter()
+        .map(|a| (a.label, a.range))
+        .collect::<Vec<_>>();
+    assert_eq!(labels, vec![("one", (0, 4)), ("two", (7, 11))]);
+    assert_eq!(slices[1].source, "line 8\n");
+    assert_eq!(slices[1].line_start, 8);
+    assert_eq!(slices[1].annotations[0].range, (0, 4));
+}
+
+#[test]
+fn test_extract_multiline() {
+    let slices = file_slice(vec![annotation((5, 26), "span")]).extract(0);
+
+    assert_eq!(slices.len(), 1);
+    assert_eq!(slices[0].source, "line 1\nline 2\nline 3\nline 4\n");
+    assert_eq!(slices[0].annotations[0].range, (5, 26));
+}
+
+#[test]
+fn test_merge_contained_slices() {
+    let file = "line 1\nline 2\nline 3\n";
+    let slice = |source, line_start, range, label| {
+        Slice::new(source, line_start)
+            .origin("file.txt")
+            .annotation(annotation(range, label))
+    };
+    let slices = Slice::merge(vec![
+        slice(&file[7..14], 2, (0, 4), "two"),
+        slice(&file[7..14], 2, (5, 6), "again"),
+        slice(file, 1, (14, 18), "three"),
+        slice(&file[7..14], 2, (0, 4), "other").origin("other.txt"),
+    ]);
+
+    assert_eq!(slices.len(), 2);
+    assert_eq!(slices[0].source, file);
+    assert_eq!(slices[0].line_start, 1);
+    let labels = slices[0]
+        .annotations
+        .iter()
+        .map(|a| (a.label, a.range))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        labels,
+        vec![("two", (7, 11)), ("again", (12, 13)), ("three", (14, 18))]
+    );
+    assert_eq!(slices[1].origin, Some("other.txt"));
+}
+
+#[test]
+fn test_merge_keeps_different_lines() {
+    let slice = |source, line_start| Slice::new(source, line_start).origin("file.txt");
+    // Overlapping, different text, and past the end of the earlier slice.
+    let slices = Slice::merge(vec![
+        slice("line 1\nline 2\n", 1),
+        slice("line 2\nline 3\n", 2),
+        slice("line 9\n", 2),
+        slice("line 3\n", 3),
+    ]);
+    assert_eq!(slices.len(), 3);
+}
+
+#[test]
+fn test_merge_annotations() {
+    let annotation = |range, label, annotation_type| SourceAnnotation {
+        range,
+        label,
+        annotation_type,
+        ..Default::default()
+    };
+    let mut slice = file_slice(vec![
+        annotation((7, 9), "dup", AnnotationType::Error),
+        annotation((0, 2), "dup", AnnotationType::Error),
+        annotation((1, 4), "dup", AnnotationType::Error),
+        annotation((2, 3), "dup", AnnotationType::Warning),
+        annotation((4, 5), "other", AnnotationType::Error),
+        annotation((9, 11), "dup", AnnotationType::Error),
+        annotation((5, 6), "dup", AnnotationType::Error),
+    ]);
+    slice.merge_annotations();
+
+    let annotations = slice
+        .annotations
+        .iter()
+        .map(|a| (a.label, a.range, a.annotation_type))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        annotations,
+        vec![
+            ("dup", (7, 11), AnnotationType::Error),
+            ("dup", (0, 4), AnnotationType::Error),
+            ("dup", (2, 3), AnnotationType::Warning),
+            ("other", (4, 5), AnnotationType::Error),
+            ("dup", (5, 6), AnnotationType::Error),
+        ]
+    );
+}
+
+#[test]
+fn test_around_clamps_context() {
+    let file = file_slice(vec![]).source;
+    let slice = Slice::around(file, annotation((3, 10), "one to two"), 3);
+    assert_eq!(slice.source, "line 1\nline 2\nline 3\nline 4\nline 5\n");
+    assert_eq!(slice.line_start, 1);
+    assert_eq!(slice.annotations[0].range, (3, 10));
+
+    let slice = Slice::around(file, annotation((52, 53), "end"), 1);
+    assert_eq!(slice.source, "line 7\nline 8\n");
+    assert_eq!(slice.line_start, 7);
+    assert_eq!(slice.annotations[0].range, (10, 11));
+}
+
+#[test]
+fn test_slice_default() {
+    let slice = Slice::new("let x = 1;", 1);
+    assert_eq!(slice.line_start, 1);
+    assert_eq!(slice.origin, None);
+    assert!(slice.annotations.is_empty());
+    assert!(!slice.fold);
+}
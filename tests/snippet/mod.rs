@@ -1,8 +1,15 @@
+use std::path::PathBuf;
+
 use serde::{Deserialize, Deserializer, Serialize};
 
 use annotate_snippets::{
-    display_list::{FormatOptions, Margin},
-    snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation},
+    display_list::{
+        BidiMode, ColumnUnit, EscapeMode, FormatOptions, GutterBadges, HeaderPlacement,
+        HeaderPosition, Margin, PathNormalizer, SeverityIcons, Translations,
+    },
+    snippet::{
+        Annotation, AnnotationType, LineHighlight, Priority, Slice, Snippet, SourceAnnotation,
+    },
 };
 
 #[derive(Deserialize)]
@@ -23,20 +30,20 @@ pub struct SnippetDef<'a> {
     pub slices: Vec<Slice<'a>>,
 }
 
-impl<'a> Into<Snippet<'a>> for SnippetDef<'a> {
-    fn into(self) -> Snippet<'a> {
+impl<'a> From<SnippetDef<'a>> for Snippet<'a> {
+    fn from(val: SnippetDef<'a>) -> Self {
         let SnippetDef {
             title,
             footer,
             opt,
             slices,
-        } = self;
-        Snippet {
-            title,
-            footer,
-            slices,
-            opt,
-        }
+        } = val;
+        let mut snippet = Snippet::new();
+        snippet.title = title;
+        snippet.footer = footer;
+        snippet.slices = slices;
+        snippet.opt = opt;
+        snippet
     }
 }
 
@@ -44,14 +51,10 @@ fn deserialize_opt<'de, D>(deserializer: D) -> Result<FormatOptions, D::Error>
 where
     D: Deserializer<'de>,
 {
-    #[derive(Deserialize)]
-    struct Wrapper(#[serde(with = "FormatOptionsDef")] FormatOptions);
-
-    Wrapper::deserialize(deserializer).map(|w| w.0)
+    FormatOptionsDef::deserialize(deserializer).map(FormatOptions::from)
 }
 
 #[derive(Deserialize)]
-#[serde(remote = "FormatOptions")]
 pub struct FormatOptionsDef {
     #[serde(default)]
     pub color: bool,
@@ -60,6 +63,85 @@ pub struct FormatOptionsDef {
     #[serde(deserialize_with = "deserialize_margin")]
     #[serde(default)]
     pub margin: Option<Margin>,
+    #[serde(skip)]
+    pub icons: Option<SeverityIcons>,
+    #[serde(default)]
+    pub indent: usize,
+    #[serde(skip)]
+    pub translations: Option<Translations>,
+    #[serde(default)]
+    pub footnotes: bool,
+    #[serde(skip)]
+    pub bidi: BidiMode,
+    #[serde(skip)]
+    pub escape: EscapeMode,
+    #[serde(skip)]
+    pub column_unit: ColumnUnit,
+    #[serde(skip)]
+    pub header_position: HeaderPosition,
+    #[serde(skip)]
+    pub header_placement: HeaderPlacement,
+    #[serde(default)]
+    pub full_width_marks: bool,
+    #[serde(default)]
+    pub tab_width: Option<usize>,
+    #[serde(default)]
+    pub max_width: Option<usize>,
+    #[serde(default)]
+    pub assert_max_width: bool,
+    #[serde(skip)]
+    pub path_normalizer: Option<PathNormalizer>,
+    #[serde(default)]
+    pub remap_path_prefix: Vec<(String, String)>,
+    #[serde(default)]
+    pub base_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub max_body_lines: Option<usize>,
+    #[serde(skip)]
+    pub gutter_badges: Option<GutterBadges>,
+    #[serde(default)]
+    pub compact_annotations: bool,
+    #[serde(default)]
+    pub vertical_compact: bool,
+    #[serde(default)]
+    pub max_label_length: Option<usize>,
+    #[serde(default)]
+    pub screen_reader: bool,
+    #[serde(default)]
+    pub column_ruler: bool,
+}
+
+impl From<FormatOptionsDef> for FormatOptions {
+    fn from(val: FormatOptionsDef) -> Self {
+        let mut opt = FormatOptions::new();
+        opt.color = val.color;
+        opt.anonymized_line_numbers = val.anonymized_line_numbers;
+        opt.margin = val.margin;
+        opt.icons = val.icons;
+        opt.indent = val.indent;
+        opt.translations = val.translations;
+        opt.footnotes = val.footnotes;
+        opt.bidi = val.bidi;
+        opt.escape = val.escape;
+        opt.column_unit = val.column_unit;
+        opt.header_position = val.header_position;
+        opt.header_placement = val.header_placement;
+        opt.full_width_marks = val.full_width_marks;
+        opt.tab_width = val.tab_width;
+        opt.max_width = val.max_width;
+        opt.assert_max_width = val.assert_max_width;
+        opt.path_normalizer = val.path_normalizer;
+        opt.remap_path_prefix = val.remap_path_prefix;
+        opt.base_dir = val.base_dir;
+        opt.max_body_lines = val.max_body_lines;
+        opt.gutter_badges = val.gutter_badges;
+        opt.compact_annotations = val.compact_annotations;
+        opt.vertical_compact = val.vertical_compact;
+        opt.max_label_length = val.max_label_length;
+        opt.screen_reader = val.screen_reader;
+        opt.column_ruler = val.column_ruler;
+        opt
+    }
 }
 
 fn deserialize_margin<'de, D>(deserializer: D) -> Result<Option<Margin>, D::Error>
@@ -102,15 +184,8 @@ fn deserialize_slices<'de, D>(deserializer: D) -> Result<Vec<Slice<'de>>, D::Err
 where
     D: Deserializer<'de>,
 {
-    #[derive(Deserialize)]
-    struct Wrapper<'a>(
-        #[serde(with = "SliceDef")]
-        #[serde(borrow)]
-        Slice<'a>,
-    );
-
-    let v = Vec::deserialize(deserializer)?;
-    Ok(v.into_iter().map(|Wrapper(a)| a).collect())
+    let v = Vec::<SliceDef<'de>>::deserialize(deserializer)?;
+    Ok(v.into_iter().map(Slice::from).collect())
 }
 
 fn deserialize_annotation<'de, D>(deserializer: D) -> Result<Option<Annotation<'de>>, D::Error>
@@ -144,7 +219,6 @@ where
 }
 
 #[derive(Deserialize)]
-#[serde(remote = "Slice")]
 pub struct SliceDef<'a> {
     #[serde(borrow)]
     pub source: &'a str,
@@ -156,6 +230,23 @@ pub struct SliceDef<'a> {
     pub annotations: Vec<SourceAnnotation<'a>>,
     #[serde(default)]
     pub fold: bool,
+    #[serde(skip)]
+    pub line_highlights: Vec<LineHighlight>,
+    #[serde(borrow)]
+    #[serde(default)]
+    pub origin_note: Option<&'a str>,
+}
+
+impl<'a> From<SliceDef<'a>> for Slice<'a> {
+    fn from(val: SliceDef<'a>) -> Self {
+        let mut slice = Slice::new(val.source, val.line_start);
+        slice.origin = val.origin;
+        slice.annotations = val.annotations;
+        slice.fold = val.fold;
+        slice.line_highlights = val.line_highlights;
+        slice.origin_note = val.origin_note;
+        slice
+    }
 }
 
 fn deserialize_source_annotations<'de, D>(
@@ -183,6 +274,9 @@ pub struct SourceAnnotationDef<'a> {
     pub label: &'a str,
     #[serde(with = "AnnotationTypeDef")]
     pub annotation_type: AnnotationType,
+    #[serde(with = "PriorityDef")]
+    #[serde(default)]
+    pub priority: Priority,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -206,3 +300,12 @@ enum AnnotationTypeDef {
     Note,
     Help,
 }
+
+#[allow(dead_code)]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Priority")]
+enum PriorityDef {
+    Low,
+    Normal,
+    High,
+}
@@ -0,0 +1,81 @@
+use annotate_snippets::snippet::AnnotationType;
+use annotate_snippets::source_map::{SourceMap, SpanAnnotation};
+
+#[test]
+fn test_line_col() {
+    let mut map = SourceMap::new();
+    let file = map.add("a.txt", "ab\ncd\n\nef");
+
+    assert_eq!(map.line_count(file), 4);
+    assert_eq!(map.line_col(file, 0), (1, 1));
+    assert_eq!(map.line_col(file, 2), (1, 3));
+    assert_eq!(map.line_col(file, 3), (2, 1));
+    assert_eq!(map.line_col(file, 6), (3, 1));
+    assert_eq!(map.line_col(file, 8), (4, 2));
+
+    assert_eq!(map.offset(file, 2, 2), Some(4));
+    assert_eq!(map.offset(file, 4, 1), Some(7));
+    assert_eq!(map.offset(file, 5, 1), None);
+    assert_eq!(map.offset(file, 0, 1), None);
+}
+
+#[test]
+fn test_slices_by_file() {
+    let mut map = SourceMap::new();
+    let a = map.add("a.txt", "one\ntwo\n");
+    let b = map.add("b.txt", "three\n");
+    let annotation = |file, range| SpanAnnotation {
+        file,
+        range,
+        label: "here",
+        annotation_type: AnnotationType::Error,
+    };
+
+    let slices = map.slices(
+        vec![
+            annotation(b, (0, 5)),
+            annotation(a, (4, 7)),
+            annotation(b, (1, 2)),
+        ],
+        0,
+    );
+
+    assert_eq!(slices.len(), 2);
+    assert_eq!(slices[0].origin, Some("b.txt"));
+    assert_eq!(slices[0].annotations.len(), 2);
+    assert_eq!(slices[1].origin, Some("a.txt"));
+    assert_eq!(slices[1].source, "two\n");
+    assert_eq!(slices[1].line_start, 2);
+    assert_eq!(slices[1].annotations[0].range, (0, 3));
+}
+
+#[test]
+fn test_add_bytes() {
+    let mut map = SourceMap::new();
+    let file = map.add_bytes("a.bin", b"a\xffb\n\xe2\x82x\xe2\x82");
+
+    assert_eq!(map.source(file), "a\u{FFFD}b\n\u{FFFD}x\u{FFFD}");
+    assert_eq!(map.offset_of_byte(file, 0), 0);
+    assert_eq!(map.offset_of_byte(file, 1), 1);
+    assert_eq!(map.offset_of_byte(file, 2), 2);
+    assert_eq!(map.offset_of_byte(file, 4), 4);
+    assert_eq!(map.offset_of_byte(file, 5), 4);
+    assert_eq!(map.offset_of_byte(file, 6), 5);
+    assert_eq!(map.offset_of_byte(file, 8), 6);
+    assert_eq!(map.offset_of_byte(file, 9), 7);
+
+    let valid = map.add("b.txt", "é = x");
+    assert_eq!(map.offset_of_byte(valid, 5), 4);
+
+    let slices = map.slices(
+        vec![SpanAnnotation {
+            file,
+            range: (map.offset_of_byte(file, 4), map.offset_of_byte(file, 6)),
+            label: "invalid",
+            annotation_type: AnnotationType::Error,
+        }],
+        0,
+    );
+    assert_eq!(slices[0].source, "\u{FFFD}x\u{FFFD}");
+    assert_eq!(slices[0].annotations[0].range, (0, 1));
+}
@@ -0,0 +1,200 @@
+use std::fmt;
+
+use annotate_snippets::formatter::style::{Color, ColorDepth, Style};
+
+#[test]
+fn test_degrade_truecolor() {
+    let color = Color::Rgb(250, 10, 10);
+
+    assert_eq!(color.degrade(ColorDepth::TrueColor), color);
+    assert_eq!(color.degrade(ColorDepth::Ansi256), Color::Fixed(196));
+    assert_eq!(color.degrade(ColorDepth::Ansi16), Color::BrightRed);
+}
+
+#[test]
+fn test_degrade_gray() {
+    assert_eq!(
+        Color::Rgb(128, 128, 128).degrade(ColorDepth::Ansi256),
+        Color::Fixed(244)
+    );
+    assert_eq!(
+        Color::Fixed(244).degrade(ColorDepth::Ansi16),
+        Color::BrightBlack
+    );
+}
+
+#[test]
+fn test_degrade_basic() {
+    assert_eq!(
+        Color::Fixed(9).degrade(ColorDepth::Ansi16),
+        Color::BrightRed
+    );
+    assert_eq!(Color::Cyan.degrade(ColorDepth::Ansi16), Color::Cyan);
+    assert_eq!(
+        Color::Fixed(21).degrade(ColorDepth::Ansi256),
+        Color::Fixed(21)
+    );
+}
+
+/// A style only implementing the required methods, wrapping text in brackets.
+struct Brackets;
+
+impl Style for Brackets {
+    fn paint(&self, text: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", text)
+    }
+
+    fn paint_fn<'a>(
+        &self,
+        c: Box<dyn FnOnce(&mut fmt::Formatter<'_>) -> fmt::Result + 'a>,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str("[")?;
+        c(f)?;
+        f.write_str("]")
+    }
+
+    fn bold(&self) -> Box<dyn Style> {
+        Box::new(Brackets)
+    }
+}
+
+#[test]
+fn test_paint_with_default() {
+    struct Painted;
+
+    impl fmt::Display for Painted {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            Brackets.paint_with(&mut |f| f.write_str("error"), f)
+        }
+    }
+
+    assert_eq!(Painted.to_string(), "[error]");
+}
+
+#[cfg(feature = "color")]
+mod color {
+    use std::fmt;
+
+    use annotate_snippets::{
+        display_list::DisplayList,
+        formatter::{
+            highlight::{HighlightSegment, Highlighter},
+            style::{Color, ColorDepth, Style, StyleClass, StyleSpec, Stylesheet},
+        },
+        snippet::{AnnotationType, Slice, Snippet, SourceAnnotation},
+        stylesheets::color::AnsiTermStylesheet,
+    };
+
+    struct Painted(Box<dyn Style>, &'static str);
+
+    impl fmt::Display for Painted {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.paint(self.1, f)
+        }
+    }
+
+    fn paint(depth: ColorDepth, spec: StyleSpec) -> String {
+        let mut stylesheet = AnsiTermStylesheet::with_depth(depth);
+        stylesheet.set_style(StyleClass::Error, spec);
+        Painted(stylesheet.get_style(StyleClass::Error), "error").to_string()
+    }
+
+    #[test]
+    fn test_default_styles() {
+        let stylesheet = AnsiTermStylesheet::with_depth(ColorDepth::Ansi16);
+        assert_eq!(
+            Painted(stylesheet.get_style(StyleClass::Error), "error").to_string(),
+            "\u{1b}[1;38;5;9merror\u{1b}[0m"
+        );
+    }
+
+    #[test]
+    fn test_truecolor_spec() {
+        let spec = StyleSpec::new().fg(Color::Rgb(250, 10, 10)).bold();
+
+        assert_eq!(
+            paint(ColorDepth::TrueColor, spec),
+            "\u{1b}[1;38;2;250;10;10merror\u{1b}[0m"
+        );
+        assert_eq!(
+            paint(ColorDepth::Ansi256, spec),
+            "\u{1b}[1;38;5;196merror\u{1b}[0m"
+        );
+        assert_eq!(
+            paint(ColorDepth::Ansi16, spec),
+            "\u{1b}[1;38;5;9merror\u{1b}[0m"
+        );
+    }
+
+    struct Keyword(&'static str);
+
+    impl Highlighter for Keyword {
+        fn highlight_line(
+            &self,
+            origin: Option<&str>,
+            _lineno: Option<usize>,
+            line: &str,
+        ) -> Vec<HighlightSegment> {
+            assert_eq!(origin, Some("src/lib.rs"));
+            line.match_indices(self.0)
+                .map(|(idx, kw)| HighlightSegment {
+                    range: idx..idx + kw.len(),
+                    style: StyleSpec::new().fg(Color::Magenta),
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_highlighter() {
+        let snippet =
+            Snippet::new().slice(Slice::new("let x = 1;", 1).origin("src/lib.rs").annotation(
+                SourceAnnotation {
+                    range: (4, 5),
+                    label: "unused",
+                    annotation_type: AnnotationType::Warning,
+                    ..Default::default()
+                },
+            ));
+        let mut dl = DisplayList::from(snippet);
+        let mut stylesheet = AnsiTermStylesheet::with_depth(ColorDepth::Ansi16);
+        stylesheet.set_style(StyleClass::LineNo, StyleSpec::new());
+        stylesheet.set_style(StyleClass::Warning, StyleSpec::new());
+        dl.stylesheet = Box::new(stylesheet);
+        dl.highlighter = Some(Box::new(Keyword("let")));
+
+        let expected = [
+            " --> src/lib.rs:1:5",
+            "  |",
+            "1 | \u{1b}[35mlet\u{1b}[0m x = 1;",
+            "  |     - unused",
+            "  |",
+        ]
+        .join("\n");
+        assert_eq!(dl.to_string(), expected);
+    }
+
+    #[test]
+    fn test_renderer_stylesheet() {
+        use annotate_snippets::display_list::FormatOptions;
+        use annotate_snippets::formatter::renderer::Renderer;
+        use annotate_snippets::snippet::Annotation;
+
+        let mut stylesheet = AnsiTermStylesheet::with_depth(ColorDepth::Ansi16);
+        stylesheet.set_style(StyleClass::Error, StyleSpec::new().fg(Color::Red));
+        let mut renderer = Renderer::new().stylesheet(Box::new(stylesheet));
+
+        let title = Annotation::new(AnnotationType::Error, "oops");
+        let colored = Snippet::new()
+            .title(title.clone())
+            .opt(FormatOptions::new().color(true));
+        for _ in 0..2 {
+            assert_eq!(
+                renderer.render(&colored),
+                "\u{1b}[31merror\u{1b}[0m: \u{1b}[1moops\u{1b}[0m"
+            );
+        }
+        assert_eq!(renderer.render(&Snippet::new().title(title)), "error: oops");
+    }
+}
@@ -0,0 +1,103 @@
+use annotate_snippets::display_list::FormatOptions;
+use annotate_snippets::snippet::Slice;
+use annotate_snippets::suggestion::{
+    apply_suggestions, Suggestion, SuggestionError, SuggestionList,
+};
+
+fn suggestion(range: (usize, usize), replacement: &str) -> Suggestion<'_> {
+    Suggestion { range, replacement }
+}
+
+#[test]
+fn test_apply_unordered() {
+    let fixed = apply_suggestions(
+        "föo(bar, baz)",
+        &[
+            suggestion((9, 12), "qux"),
+            suggestion((0, 3), "foo"),
+            suggestion((7, 7), " as u8"),
+        ],
+    );
+    assert_eq!(fixed, Ok("foo(bar as u8, qux)".to_string()));
+}
+
+#[test]
+fn test_apply_duplicates_and_insertions() {
+    let fixed = apply_suggestions(
+        "x",
+        &[
+            suggestion((0, 1), "y"),
+            suggestion((0, 0), "a"),
+            suggestion((0, 1), "y"),
+            suggestion((0, 0), "b"),
+        ],
+    );
+    assert_eq!(fixed, Ok("aby".to_string()));
+}
+
+#[test]
+fn test_apply_errors() {
+    assert_eq!(
+        apply_suggestions("abc", &[suggestion((0, 2), "x"), suggestion((1, 3), "y")]),
+        Err(SuggestionError::Overlap((0, 2), (1, 3)))
+    );
+    assert_eq!(
+        apply_suggestions("abc", &[suggestion((0, 2), "x"), suggestion((0, 2), "y")]),
+        Err(SuggestionError::Overlap((0, 2), (0, 2)))
+    );
+    assert_eq!(
+        apply_suggestions("abc", &[suggestion((2, 4), "x")]),
+        Err(SuggestionError::InvalidRange((2, 4)))
+    );
+}
+
+#[test]
+fn test_suggestion_list() {
+    let list = SuggestionList::new((8, 11), ["foo", "fob"]);
+    assert_eq!(
+        list.message().unwrap(),
+        "did you mean one of: `foo`, `fob`?"
+    );
+    assert_eq!(
+        SuggestionList::new((8, 11), ["foo"]).message().unwrap(),
+        "did you mean `foo`?"
+    );
+    assert_eq!(
+        list.render(FormatOptions::default()).unwrap(),
+        "help: did you mean one of: `foo`, `fob`?"
+    );
+    let empty = SuggestionList::new((8, 11), []);
+    assert_eq!(empty.message(), None);
+    assert_eq!(empty.render(FormatOptions::default()), None);
+
+    let slice = Slice::new("let x = fop;", 4);
+    let expected = [
+        "help: did you mean one of: `foo`, `fob`?",
+        "  |",
+        "4 | let x = foo;",
+        "  |         ---",
+        "  |",
+        "4 | let x = fob;",
+        "  |         ---",
+        "  |",
+    ]
+    .join("\n");
+    assert_eq!(
+        list.render_candidates(&slice, FormatOptions::default()),
+        Ok(expected)
+    );
+    assert_eq!(
+        SuggestionList::new((8, 20), ["foo"]).render_candidates(&slice, FormatOptions::default()),
+        Err(SuggestionError::InvalidRange((8, 20)))
+    );
+    assert_eq!(
+        empty.render_candidates(&slice, FormatOptions::default()),
+        Err(SuggestionError::NoCandidates)
+    );
+
+    let slice = Slice::new("let x = fop;", 4).origin("src/main.rs");
+    let rendered = SuggestionList::new((8, 11), ["foo"])
+        .render_candidates(&slice, FormatOptions::default())
+        .unwrap();
+    assert!(rendered.contains(" --> src/main.rs:4:9"));
+}
@@ -0,0 +1,97 @@
+use annotate_snippets::display_list::FormatOptions;
+use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+use annotate_snippets::summary::Summary;
+
+fn summary(types: &[AnnotationType]) -> Summary {
+    let snippets = types
+        .iter()
+        .map(|annotation_type| {
+            Snippet::new().title(Annotation {
+                id: None,
+                label: Some("oops"),
+                annotation_type: *annotation_type,
+            })
+        })
+        .collect::<Vec<_>>();
+    snippets.iter().collect()
+}
+
+fn render(types: &[AnnotationType]) -> Option<String> {
+    summary(types).render(FormatOptions::default())
+}
+
+#[test]
+fn test_summary_counts() {
+    let summary = summary(&[
+        AnnotationType::Warning,
+        AnnotationType::Note,
+        AnnotationType::Help,
+        AnnotationType::Error,
+    ]);
+    assert_eq!(
+        summary,
+        Summary {
+            errors: 1,
+            warnings: 1,
+            infos: 0,
+            notes: 1,
+            helps: 1,
+            duplicates: 0,
+        }
+    );
+    assert_eq!(Summary::new().message(), None);
+}
+
+#[test]
+fn test_summary_max_severity() {
+    assert_eq!(Summary::new().max_severity(), None);
+    let notes = summary(&[AnnotationType::Help, AnnotationType::Note]);
+    assert_eq!(notes.max_severity(), Some(AnnotationType::Note));
+    assert_eq!(notes.count(AnnotationType::Help), 1);
+    assert_eq!(notes.message(), None);
+    let warnings = summary(&[AnnotationType::Info, AnnotationType::Warning]);
+    assert_eq!(warnings.max_severity(), Some(AnnotationType::Warning));
+    assert_eq!(warnings.count(AnnotationType::Info), 1);
+}
+
+#[test]
+fn test_summary_plurals() {
+    assert_eq!(render(&[AnnotationType::Note]), None);
+    assert_eq!(
+        render(&[AnnotationType::Warning]).unwrap(),
+        "warning: 1 warning emitted"
+    );
+    assert_eq!(
+        render(&[AnnotationType::Warning; 4]).unwrap(),
+        "warning: 4 warnings emitted"
+    );
+    assert_eq!(
+        render(&[AnnotationType::Error]).unwrap(),
+        "error: aborting due to 1 previous error"
+    );
+    assert_eq!(
+        render(&[
+            AnnotationType::Error,
+            AnnotationType::Warning,
+            AnnotationType::Error,
+            AnnotationType::Warning
+        ])
+        .unwrap(),
+        "error: aborting due to 2 previous errors; 2 warnings emitted"
+    );
+}
+
+#[test]
+fn test_summary_duplicates() {
+    let mut summary = summary(&[AnnotationType::Error]);
+    summary.duplicates = 1;
+    assert_eq!(
+        summary.render(FormatOptions::default()).unwrap(),
+        "error: aborting due to 1 previous error (1 duplicate diagnostic suppressed)"
+    );
+    summary.duplicates = 3;
+    assert!(summary
+        .render(FormatOptions::default())
+        .unwrap()
+        .ends_with("(3 duplicate diagnostics suppressed)"));
+}
@@ -0,0 +1,36 @@
+#![cfg(feature = "syntect")]
+
+use annotate_snippets::formatter::highlight::{Highlighter, SyntectHighlighter};
+
+#[test]
+fn test_highlight_by_extension() {
+    let highlighter = SyntectHighlighter::new();
+    let line = "fn main() {}";
+
+    let segments = highlighter.highlight_line(Some("src/main.rs"), Some(1), line);
+    assert!(segments.iter().any(|s| &line[s.range.clone()] == "fn"));
+
+    let segments = highlighter.highlight_line(Some("README"), Some(1), line);
+    assert!(segments.is_empty());
+    let segments = highlighter.highlight_line(None, Some(1), line);
+    assert!(segments.is_empty());
+}
+
+#[test]
+fn test_highlight_keeps_state_between_lines() {
+    let highlighter = SyntectHighlighter::new();
+    let origin = Some("src/lib.rs");
+
+    highlighter.highlight_line(origin, Some(1), "/* fn");
+    let in_comment = highlighter.highlight_line(origin, Some(2), "fn */");
+    highlighter.highlight_line(origin, Some(1), "let x;");
+    let in_code = highlighter.highlight_line(origin, Some(2), "fn */");
+
+    let style_of_fn = |segments: &[annotate_snippets::formatter::highlight::HighlightSegment]| {
+        segments
+            .iter()
+            .find(|s| s.range.start == 0)
+            .map(|s| s.style)
+    };
+    assert_ne!(style_of_fn(&in_comment), style_of_fn(&in_code));
+}
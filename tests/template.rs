@@ -0,0 +1,56 @@
+use annotate_snippets::snippet::AnnotationType;
+use annotate_snippets::template::{Message, TemplateError, TemplateRegistry};
+
+fn registry() -> TemplateRegistry {
+    let mut templates = TemplateRegistry::new();
+    templates
+        .register(
+            "unused",
+            AnnotationType::Warning,
+            "unused {kind} `{name}`: {{{name}}}",
+        )
+        .unwrap();
+    templates
+}
+
+#[test]
+fn test_instantiate() {
+    let templates = registry();
+    assert!(templates.contains("unused"));
+    assert_eq!(
+        templates.instantiate(
+            "unused",
+            &[("name", "x"), ("kind", "variable"), ("extra", "ignored")]
+        ),
+        Ok(Message {
+            id: "unused".to_string(),
+            annotation_type: AnnotationType::Warning,
+            label: "unused variable `x`: {x}".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_instantiate_errors() {
+    let templates = registry();
+    assert_eq!(
+        templates.instantiate("unknown", &[]),
+        Err(TemplateError::UnknownTemplate("unknown".to_string()))
+    );
+    assert_eq!(
+        templates.instantiate("unused", &[("kind", "variable")]),
+        Err(TemplateError::MissingArgument("name".to_string()))
+    );
+}
+
+#[test]
+fn test_register_unbalanced() {
+    let mut templates = TemplateRegistry::new();
+    for (template, offset) in &[("a {b", 2), ("a } b", 2), ("{a{b}", 0)] {
+        assert_eq!(
+            templates.register("id", AnnotationType::Error, template),
+            Err(TemplateError::Unbalanced(*offset))
+        );
+    }
+    assert!(!templates.contains("id"));
+}
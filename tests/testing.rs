@@ -0,0 +1,198 @@
+use annotate_snippets::assert_snippet_eq;
+use annotate_snippets::display_list::{DisplayLine, DisplayList, DisplaySourceLine, FormatOptions};
+#[cfg(feature = "color")]
+use annotate_snippets::formatter::{renderer::Renderer, style::strip_styles};
+use annotate_snippets::snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation};
+use annotate_snippets::testing::{
+    anonymize_line_numbers, assert_display_lists_eq, diff_display_lists, diff_rendered,
+    normalize_paths, render_plain, strip_ansi,
+};
+
+fn snippet(color: bool) -> Snippet<'static> {
+    Snippet::new()
+        .title(Annotation {
+            label: Some("mismatched types"),
+            id: Some("E0308"),
+            annotation_type: AnnotationType::Error,
+        })
+        .slice(
+            Slice::new("let x: u8 = \"a\";", 99)
+                .origin("src\\main.rs")
+                .annotation(SourceAnnotation {
+                    range: (12, 15),
+                    label: "expected `u8`",
+                    annotation_type: AnnotationType::Error,
+                    ..Default::default()
+                }),
+        )
+        .opt(FormatOptions::new().color(color))
+}
+
+#[test]
+fn test_render_plain_ignores_color() {
+    let plain = DisplayList::from(snippet(false)).to_string();
+    assert_eq!(render_plain(&snippet(true)), plain);
+    assert!(!plain.contains('\x1b'));
+}
+
+#[test]
+fn test_strip_ansi() {
+    assert_eq!(
+        strip_ansi("\x1b[1m\x1b[38;5;9merror\x1b[0m: x\x1b7"),
+        "error: x"
+    );
+    assert_eq!(strip_ansi("plain"), "plain");
+}
+
+#[cfg(feature = "color")]
+#[test]
+fn test_strip_ansi_rendered() {
+    let colored = DisplayList::from(snippet(true)).to_string();
+    assert_ne!(colored, render_plain(&snippet(false)));
+    assert_eq!(strip_ansi(&colored), render_plain(&snippet(false)));
+}
+
+#[cfg(feature = "color")]
+#[test]
+fn test_render_with_plain() {
+    let mut renderer = Renderer::new();
+    let (styled, plain) = renderer.render_with_plain(&snippet(true));
+    assert_eq!(styled, DisplayList::from(snippet(true)).to_string());
+    assert_eq!(plain, render_plain(&snippet(false)));
+    assert_eq!(strip_styles(styled), plain);
+}
+
+#[test]
+fn test_normalized_snapshot() {
+    let output = render_plain(&snippet(false));
+    let output = anonymize_line_numbers(&normalize_paths(&output, "src", "$DIR"));
+    let expected = r#"error[E0308]: mismatched types
+  --> $DIR/main.rs:99:13
+   |
+LL | let x: u8 = "a";
+   |             ^^^ expected `u8`
+   |"#;
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_anonymize_line_numbers_widths() {
+    assert_eq!(anonymize_line_numbers("9 | a\n  |"), "LL | a\n  |");
+    assert_eq!(anonymize_line_numbers("100 | a"), "LL  | a");
+    assert_eq!(anonymize_line_numbers("12 x |"), "12 x |");
+}
+
+#[cfg(all(feature = "serde", feature = "toml"))]
+#[test]
+fn test_check_fixtures_failure() {
+    use annotate_snippets::testing::{check_fixtures, read_fixtures};
+
+    let dir = std::env::temp_dir().join(format!("annotate-snippets-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("a.toml"),
+        "expected = 'error: first'\n[title]\nlabel = \"first\"\nannotation_type = \"Error\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("b.toml"),
+        "expected = 'error: other'\n[title]\nlabel = \"second\"\nannotation_type = \"Error\"\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("notes.txt"), "not a fixture").unwrap();
+
+    let fixtures = read_fixtures(&dir).unwrap();
+    assert_eq!(fixtures.len(), 2);
+    assert!(fixtures[0].0.ends_with("a.toml"));
+
+    let result = std::panic::catch_unwind(|| check_fixtures(&dir, render_plain));
+    std::fs::remove_dir_all(&dir).unwrap();
+    let message = *result.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.starts_with("1 of 2 fixtures failed:"));
+    assert!(message.contains("b.toml\n--- expected\nerror: other\n--- actual\nerror: second"));
+}
+
+#[test]
+fn test_diff_display_lists() {
+    let expected = DisplayList::from(snippet(false));
+    assert_eq!(
+        diff_display_lists(&expected, &DisplayList::from(snippet(false))),
+        None
+    );
+    assert_display_lists_eq(&expected, &DisplayList::from(snippet(false)));
+
+    let mut actual = DisplayList::from(snippet(false));
+    for line in &mut actual.body {
+        if let DisplayLine::Source {
+            line: DisplaySourceLine::Content { text, .. },
+            ..
+        } = line
+        {
+            *text = "let x: u8 = 'a';".into();
+        }
+    }
+    assert_eq!(
+        diff_display_lists(&expected, &actual).unwrap(),
+        [
+            "line 3: the content differs",
+            "  expected: source line 99: \"let x: u8 = \\\"a\\\";\"",
+            "  actual:   source line 99: \"let x: u8 = 'a';\"",
+        ]
+        .join("\n")
+    );
+
+    let mut actual = DisplayList::from(snippet(false));
+    actual.body.remove(0);
+    let difference = diff_display_lists(&expected, &actual).unwrap();
+    assert!(
+        difference.starts_with(
+            "line 0: the kind differs\n  expected: Error annotation: \"mismatched types\"\n"
+        ),
+        "{}",
+        difference
+    );
+}
+
+#[test]
+#[should_panic(expected = "display lists differ at line 4: the kind differs")]
+fn test_assert_display_lists_eq() {
+    let expected = DisplayList::from(snippet(false));
+    let mut actual = DisplayList::from(snippet(false));
+    actual.body[4] = DisplayLine::empty();
+    assert_display_lists_eq(&expected, &actual);
+}
+
+#[test]
+fn test_assert_snippet_eq() {
+    assert_snippet_eq!(
+        snippet(true),
+        r#"
+error[E0308]: mismatched types
+  --> src\main.rs:99:13
+   |
+99 | let x: u8 = "a";
+   |             ^^^ expected `u8`
+   |
+"#
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "differs from the expected output:\n  error[E0308]: mismatched types\n- "
+)]
+fn test_assert_snippet_eq_mismatch() {
+    assert_snippet_eq!(
+        snippet(false),
+        "error[E0308]: mismatched types\n --> src/main.rs"
+    );
+}
+
+#[test]
+fn test_diff_rendered() {
+    assert_eq!(diff_rendered("a\nb", "a\nb"), None);
+    assert_eq!(
+        diff_rendered("a\nb\nc", "a\nx").unwrap(),
+        "  a\n- b\n+ x\n- c"
+    );
+}
@@ -0,0 +1,78 @@
+use annotate_snippets::formatter::style::{Color, StyleClass, StyleSpec};
+use annotate_snippets::stylesheets::theme::{parse_color, Theme, ThemeError};
+
+#[test]
+fn test_parse_compact() {
+    let theme =
+        Theme::parse("error=bold,196:warning=#ffaf00,underline:line-no=bright-blue").unwrap();
+
+    assert_eq!(
+        theme.get(StyleClass::Error),
+        Some(StyleSpec::new().fg(Color::Fixed(196)).bold())
+    );
+    assert_eq!(
+        theme.get(StyleClass::Warning),
+        Some(StyleSpec::new().fg(Color::Rgb(255, 175, 0)).underline())
+    );
+    assert_eq!(
+        theme.get(StyleClass::LineNo),
+        Some(StyleSpec::new().fg(Color::BrightBlue))
+    );
+    assert_eq!(theme.get(StyleClass::Help), None);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(matches!(
+        Theme::parse("errors=red"),
+        Err(ThemeError::InvalidElement(e)) if e == "errors"
+    ));
+    assert!(matches!(
+        Theme::parse("error"),
+        Err(ThemeError::InvalidEntry(_))
+    ));
+    assert!(matches!(
+        parse_color("#ff00"),
+        Err(ThemeError::InvalidColor(_))
+    ));
+    assert!(matches!(
+        parse_color("purple"),
+        Err(ThemeError::InvalidColor(_))
+    ));
+}
+
+#[cfg(feature = "theme")]
+#[test]
+fn test_from_toml() {
+    let theme = Theme::from_toml(
+        r##"
+[error]
+fg = "#ff5f5f"
+bold = true
+
+[note]
+fg = 33
+bg = "black"
+italic = true
+"##,
+    )
+    .unwrap();
+
+    assert_eq!(
+        theme.get(StyleClass::Error),
+        Some(StyleSpec::new().fg(Color::Rgb(255, 95, 95)).bold())
+    );
+    assert_eq!(
+        theme.get(StyleClass::Note),
+        Some(
+            StyleSpec::new()
+                .fg(Color::Fixed(33))
+                .bg(Color::Black)
+                .italic()
+        )
+    );
+    assert!(matches!(
+        Theme::from_toml("[error]\nblink = true"),
+        Err(ThemeError::InvalidAttribute(a)) if a == "blink"
+    ));
+}
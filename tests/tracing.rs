@@ -0,0 +1,103 @@
+#![cfg(feature = "tracing")]
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use annotate_snippets::snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+/// The level and the names and values of the fields of an event.
+struct Recorded {
+    level: Level,
+    fields: Vec<(String, String)>,
+}
+
+/// Records every event.
+#[derive(Clone, Default)]
+struct Events(Arc<Mutex<Vec<Recorded>>>);
+
+struct Fields(Vec<(String, String)>);
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name().to_string(), value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+impl Subscriber for Events {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields(vec![]);
+        event.record(&mut fields);
+        let level = *event.metadata().level();
+        self.0.lock().unwrap().push(Recorded {
+            level,
+            fields: fields.0,
+        });
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[test]
+fn test_emit() {
+    let events = Events::default();
+    let snippet = Snippet::new()
+        .title(Annotation::new(AnnotationType::Warning, "unused variable").id("W1"))
+        .slice(
+            Slice::new("let x = 1;", 1).annotation(SourceAnnotation::new(
+                (4, 5),
+                AnnotationType::Warning,
+                "never read",
+            )),
+        );
+    tracing::subscriber::with_default(events.clone(), || {
+        annotate_snippets::tracing::emit(&snippet);
+        annotate_snippets::tracing::emit(&Snippet::new());
+    });
+
+    let events = events.0.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].level, Level::WARN);
+    let field = |name| {
+        events[0]
+            .fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    };
+    assert_eq!(field("message"), Some("unused variable"));
+    assert_eq!(field("code"), Some("W1"));
+    assert_eq!(
+        field("diagnostic"),
+        Some("warning[W1]: unused variable\n  |\n1 | let x = 1;\n  |     - never read\n  |")
+    );
+    assert_eq!(events[1].level, Level::INFO);
+}
+
+#[test]
+fn test_level() {
+    use annotate_snippets::tracing::level;
+    assert_eq!(level(AnnotationType::Error), Level::ERROR);
+    assert_eq!(level(AnnotationType::Note), Level::INFO);
+}
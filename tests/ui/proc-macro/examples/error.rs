@@ -0,0 +1,3 @@
+annotate_snippets_ui_proc_macro::report!(error);
+
+fn main() {}
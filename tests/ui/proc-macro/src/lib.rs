@@ -0,0 +1,17 @@
+//! A procedural macro reporting a snippet, built by `tests/proc_macro.rs`.
+extern crate proc_macro;
+
+use annotate_snippets::snippet::{Annotation, AnnotationType, Snippet};
+use proc_macro::{Span, TokenStream};
+
+/// Reports `unsupported input` with the level named by the input, `error` or
+/// `warning`, and expands to nothing else.
+#[proc_macro]
+pub fn report(input: TokenStream) -> TokenStream {
+    let annotation_type = match input.to_string().as_str() {
+        "warning" => AnnotationType::Warning,
+        _ => AnnotationType::Error,
+    };
+    let snippet = Snippet::new().title(Annotation::new(annotation_type, "unsupported input"));
+    annotate_snippets::proc_macro::emit(&snippet, Span::call_site())
+}